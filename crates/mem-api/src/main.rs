@@ -1,11 +1,17 @@
 //! MemOS REST API server: /product/add, /product/search, /product/scheduler/status, /health.
 
 use mem_api::server;
-use mem_cube::NaiveMemCube;
-use mem_embed::OpenAiEmbedder;
-use mem_graph::InMemoryGraphStore;
+use mem_cube::{
+    AutoLinkConfig, EntityAwareMemCube, EntityCubeConfig, FileIntentLog, InMemoryFeedbackStore,
+    InMemoryIntentLog, NaiveMemCube,
+};
+use mem_embed::{
+    CompositeExtractor, Embedder, EntityExtractor, LLMClient, OpenAiEmbedder,
+    OpenAiEntityExtractor, OpenAiLLMClient, RuleBasedExtractor,
+};
+use mem_graph::{EntityKnowledgeGraph, GraphStore, InMemoryGraphStore};
 use mem_scheduler::InMemoryScheduler;
-use mem_vec::{InMemoryVecStore, QdrantVecStore};
+use mem_vec::{InMemoryVecStore, QdrantVecStore, VecStore};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -19,24 +25,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    let (pii_policy, pii_key) = pii_config_from_env()?;
+    let auto_link = auto_link_from_env()?;
+    let intent_log = intent_log_from_env()?;
     let cube: Arc<dyn mem_types::MemCube + Send + Sync> = if let Ok(url) =
         std::env::var("QDRANT_URL")
     {
         let store = QdrantVecStore::new(&url, std::env::var("QDRANT_COLLECTION").ok().as_deref())
             .map_err(|e| format!("QdrantVecStore: {}", e))?;
         tracing::info!("Using Qdrant vector store at {}", url);
-        Arc::new(NaiveMemCube::new(
-            InMemoryGraphStore::new(),
-            store,
-            OpenAiEmbedder::from_env(),
-        ))
+        with_entities(
+            recover_writes(
+                NaiveMemCube::new(InMemoryGraphStore::new(), store, OpenAiEmbedder::from_env())
+                    .with_feedback_store(Some(Arc::new(InMemoryFeedbackStore::new())))
+                    .with_default_pii_policy(pii_policy.clone())
+                    .with_pii_key(pii_key)
+                    .with_llm_client(llm_client_from_env())
+                    .with_auto_link(auto_link.clone())
+                    .with_intent_log(Some(intent_log.clone())),
+            )
+            .await,
+        )
+        .await?
     } else {
         tracing::info!("Using in-memory vector store (set QDRANT_URL for Qdrant)");
-        Arc::new(NaiveMemCube::new(
-            InMemoryGraphStore::new(),
-            InMemoryVecStore::new(None),
-            OpenAiEmbedder::from_env(),
-        ))
+        with_entities(
+            recover_writes(
+                NaiveMemCube::new(
+                    InMemoryGraphStore::new(),
+                    InMemoryVecStore::new(None),
+                    OpenAiEmbedder::from_env(),
+                )
+                .with_feedback_store(Some(Arc::new(InMemoryFeedbackStore::new())))
+                .with_default_pii_policy(pii_policy.clone())
+                .with_pii_key(pii_key)
+                .with_llm_client(llm_client_from_env())
+                .with_auto_link(auto_link.clone())
+                .with_intent_log(Some(intent_log.clone())),
+            )
+            .await,
+        )
+        .await?
     };
 
     let audit_store: Arc<dyn mem_types::AuditStore + Send + Sync> =
@@ -47,10 +76,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             tracing::info!("Using in-memory audit log (set AUDIT_LOG_PATH for persistence)");
             Arc::new(server::InMemoryAuditStore::new())
         };
-    let scheduler = Arc::new(InMemoryScheduler::new(
-        Arc::clone(&cube),
-        Some(Arc::clone(&audit_store)),
-    ));
+    let scheduler = match std::env::var("MEMOS_ERASURE_SIGNING_KEY") {
+        Ok(key) if !key.is_empty() => Arc::new(InMemoryScheduler::with_signing_key(
+            Arc::clone(&cube),
+            Some(Arc::clone(&audit_store)),
+            key.into_bytes(),
+        )),
+        _ => {
+            tracing::info!(
+                "Erasure reports signed with an ephemeral key (set MEMOS_ERASURE_SIGNING_KEY)"
+            );
+            Arc::new(InMemoryScheduler::new(
+                Arc::clone(&cube),
+                Some(Arc::clone(&audit_store)),
+            ))
+        }
+    };
     let auth_token =
         std::env::var("MEMOS_AUTH_TOKEN")
             .ok()
@@ -76,3 +117,205 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     .await?;
     Ok(())
 }
+
+/// Write intent log: JSONL at `MEMOS_INTENT_LOG` (survives restarts), else in memory.
+fn intent_log_from_env(
+) -> Result<Arc<dyn mem_types::IntentLog + Send + Sync>, Box<dyn std::error::Error + Send + Sync>> {
+    match std::env::var("MEMOS_INTENT_LOG") {
+        Ok(path) if !path.is_empty() => {
+            let log = FileIntentLog::open(&path).map_err(|e| format!("MEMOS_INTENT_LOG: {}", e))?;
+            tracing::info!("Using write intent log at {}", path);
+            Ok(Arc::new(log))
+        }
+        _ => {
+            tracing::info!("Using in-memory write intent log (set MEMOS_INTENT_LOG to recover writes after a crash)");
+            Ok(Arc::new(InMemoryIntentLog::new()))
+        }
+    }
+}
+
+/// Replay writes interrupted by the previous run before serving requests.
+async fn recover_writes<G, V, E>(cube: NaiveMemCube<G, V, E>) -> NaiveMemCube<G, V, E>
+where
+    G: GraphStore + Send + Sync + 'static,
+    V: VecStore + Send + Sync,
+    E: Embedder + Send + Sync,
+{
+    match cube.recover_pending_writes().await {
+        Ok(0) => {}
+        Ok(n) => tracing::info!("Recovered {} interrupted writes", n),
+        Err(e) => tracing::warn!(error = %e, "write recovery failed"),
+    }
+    cube
+}
+
+/// LLM used for summaries and entity profiles; configured when `LLM_API_KEY` is set.
+fn llm_client_from_env() -> Option<Arc<dyn LLMClient + Send + Sync>> {
+    let client = OpenAiLLMClient::from_env()?;
+    tracing::info!("LLM client configured (/product/summarize, /product/entity/profile)");
+    Some(Arc::new(client))
+}
+
+/// Wrap the cube with entity extraction when `MEMOS_ENTITY_EXTRACTION=1`. Entities are persisted
+/// to `MEMOS_ENTITY_DB` (SQLite, `sqlite` feature) and loaded back at startup; a JSON snapshot is
+/// exported to `MEMOS_ENTITY_SNAPSHOT_PATH` every `MEMOS_ENTITY_SNAPSHOT_INTERVAL_SECS` (default 300).
+/// `MEMOS_ENTITY_PROFILE_REFRESH=1` makes entity profile reads refresh stale descriptions.
+async fn with_entities<V>(
+    cube: NaiveMemCube<InMemoryGraphStore, V, OpenAiEmbedder>,
+) -> Result<Arc<dyn mem_types::MemCube + Send + Sync>, Box<dyn std::error::Error + Send + Sync>>
+where
+    V: VecStore + Send + Sync + 'static,
+{
+    let enabled = std::env::var("MEMOS_ENTITY_EXTRACTION")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if !enabled {
+        return Ok(Arc::new(cube));
+    }
+    tracing::info!("Entity extraction enabled (/product/entity/*)");
+    let mut config = EntityCubeConfig::default();
+    config.extraction_config.generate_summary = std::env::var("MEMOS_ENTITY_PROFILE_REFRESH")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    let cube = EntityAwareMemCube::with_extractor(
+        cube,
+        entity_extractor_from_env()?,
+        EntityKnowledgeGraph::new(),
+        Some(config),
+    )
+    .with_entity_store(entity_store_from_env()?);
+    let loaded = cube.load_entities().await?;
+    if loaded > 0 {
+        tracing::info!("Loaded {} entities from the entity store", loaded);
+    }
+    if let Ok(path) = std::env::var("MEMOS_ENTITY_SNAPSHOT_PATH") {
+        let secs = std::env::var("MEMOS_ENTITY_SNAPSHOT_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(300);
+        tracing::info!("Exporting entity snapshots to {} every {}s", path, secs);
+        cube.spawn_snapshot_export(path.into(), std::time::Duration::from_secs(secs));
+    }
+    Ok(Arc::new(cube))
+}
+
+/// Extractor chain from `MEMOS_ENTITY_EXTRACTOR`: comma-separated `openai` / `rules`, tried in
+/// order (default `openai`). `rules` loads an optional gazetteer from `MEMOS_ENTITY_GAZETTEER`.
+fn entity_extractor_from_env(
+) -> Result<Arc<dyn EntityExtractor>, Box<dyn std::error::Error + Send + Sync>> {
+    let spec = std::env::var("MEMOS_ENTITY_EXTRACTOR").unwrap_or_else(|_| "openai".to_string());
+    let mut extractors: Vec<Box<dyn EntityExtractor>> = Vec::new();
+    for name in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        match name {
+            "openai" => extractors.push(Box::new(OpenAiEntityExtractor::from_env())),
+            "rules" => {
+                let mut rules = RuleBasedExtractor::new();
+                if let Ok(path) = std::env::var("MEMOS_ENTITY_GAZETTEER") {
+                    rules = rules.with_gazetteer_file(&path)?;
+                }
+                extractors.push(Box::new(rules));
+            }
+            other => {
+                return Err(format!("MEMOS_ENTITY_EXTRACTOR: unknown extractor {}", other).into())
+            }
+        }
+    }
+    tracing::info!("Entity extractors: {}", spec);
+    match extractors.len() {
+        0 => Err("MEMOS_ENTITY_EXTRACTOR is empty".into()),
+        1 => Ok(Arc::from(extractors.remove(0))),
+        _ => Ok(Arc::new(CompositeExtractor::new(extractors))),
+    }
+}
+
+type EntityStoreHandle = Option<Arc<dyn mem_types::EntityStore + Send + Sync>>;
+
+#[cfg(feature = "sqlite")]
+fn entity_store_from_env() -> Result<EntityStoreHandle, Box<dyn std::error::Error + Send + Sync>> {
+    match std::env::var("MEMOS_ENTITY_DB") {
+        Ok(path) if !path.is_empty() => {
+            tracing::info!("Using SQLite entity store at {}", path);
+            let store = mem_graph::SqliteEntityStore::new(&path)
+                .map_err(|e| format!("SqliteEntityStore: {}", e))?;
+            Ok(Some(Arc::new(store)))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn entity_store_from_env() -> Result<EntityStoreHandle, Box<dyn std::error::Error + Send + Sync>> {
+    if std::env::var("MEMOS_ENTITY_DB").is_ok() {
+        tracing::warn!("MEMOS_ENTITY_DB is set but mem-api was built without the sqlite feature");
+    }
+    Ok(None)
+}
+
+/// Auto-linking from `MEMOS_AUTO_LINK=1`, tuned by `MEMOS_AUTO_LINK_TOP_N` (default 3),
+/// `MEMOS_AUTO_LINK_THRESHOLD` (default 0.8) and `MEMOS_AUTO_LINK_SESSIONS` (default 1).
+fn auto_link_from_env() -> Result<Option<AutoLinkConfig>, Box<dyn std::error::Error + Send + Sync>>
+{
+    let flag = |name: &str, default: bool| {
+        std::env::var(name)
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(default)
+    };
+    if !flag("MEMOS_AUTO_LINK", false) {
+        return Ok(None);
+    }
+    let mut config = AutoLinkConfig::default();
+    if let Ok(v) = std::env::var("MEMOS_AUTO_LINK_TOP_N") {
+        config.similar_top_n = v
+            .parse()
+            .map_err(|e| format!("MEMOS_AUTO_LINK_TOP_N: {}", e))?;
+    }
+    if let Ok(v) = std::env::var("MEMOS_AUTO_LINK_THRESHOLD") {
+        config.similar_threshold = v
+            .parse()
+            .map_err(|e| format!("MEMOS_AUTO_LINK_THRESHOLD: {}", e))?;
+    }
+    config.link_sessions = flag("MEMOS_AUTO_LINK_SESSIONS", true);
+    tracing::info!(?config, "auto-linking enabled");
+    Ok(Some(config))
+}
+
+type PiiConfig = (Option<mem_types::PiiPolicy>, Option<[u8; 32]>);
+
+/// Default PII policy from `MEMOS_PII_POLICY` (`detect|mask|hash|reject`), with
+/// `MEMOS_PII_ENCRYPT_ORIGINAL=1` and a 64-hex-char `MEMOS_PII_KEY`.
+fn pii_config_from_env() -> Result<PiiConfig, Box<dyn std::error::Error + Send + Sync>> {
+    let key = match std::env::var("MEMOS_PII_KEY") {
+        Ok(hex_key) if !hex_key.is_empty() => {
+            let bytes = hex::decode(hex_key.trim()).map_err(|e| format!("MEMOS_PII_KEY: {}", e))?;
+            let key: [u8; 32] = bytes
+                .try_into()
+                .map_err(|_| "MEMOS_PII_KEY must be 32 bytes (64 hex chars)")?;
+            Some(key)
+        }
+        _ => None,
+    };
+    let policy = match std::env::var("MEMOS_PII_POLICY") {
+        Ok(action) if !action.is_empty() => {
+            let action: mem_types::PiiAction =
+                serde_json::from_value(serde_json::Value::String(action.to_lowercase()))
+                    .map_err(|e| format!("MEMOS_PII_POLICY: {}", e))?;
+            let encrypt_original = std::env::var("MEMOS_PII_ENCRYPT_ORIGINAL")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false);
+            if encrypt_original && key.is_none() {
+                return Err("MEMOS_PII_ENCRYPT_ORIGINAL requires MEMOS_PII_KEY".into());
+            }
+            if action == mem_types::PiiAction::Hash && key.is_none() {
+                return Err("MEMOS_PII_POLICY=hash requires MEMOS_PII_KEY".into());
+            }
+            tracing::info!(?action, "PII policy enabled");
+            Some(mem_types::PiiPolicy {
+                action,
+                encrypt_original,
+            })
+        }
+        _ => None,
+    };
+    Ok((policy, key))
+}
@@ -15,10 +15,12 @@ use mem_types::MemCube;
 use mem_types::{
     ApiAddRequest, ApiHybridSearchRequest, ApiSearchRequest, AuditEvent, AuditEventKind,
     AuditListOptions, AuditStore, Entity, EntityRelationType, EntityType, ForgetMemoryRequest,
-    ForgetMemoryResponse, GetMemoryRequest, GetMemoryResponse, GraphNeighborsRequest,
-    GraphNeighborsResponse, GraphPathRequest, GraphPathResponse, GraphPathsRequest,
-    GraphPathsResponse, HybridSearchResponse, MemCubeError, MemoryResponse,
-    SchedulerStatusResponse, SearchResponse, UpdateMemoryRequest, UpdateMemoryResponse,
+    ForgetMemoryResponse, GetMemoryRequest, GetMemoryResponse, GraphEdgeAddRequest,
+    GraphEdgeListRequest, GraphEdgeListResponse, GraphEdgeRequest, GraphEdgeResponse,
+    GraphEdgeUpdateRequest, GraphNeighborsRequest, GraphNeighborsResponse, GraphPathRequest,
+    GraphPathResponse, GraphPathsRequest, GraphPathsResponse, HybridSearchResponse, MemCubeError,
+    MemoryResponse, SchedulerStatusResponse, SearchResponse, UpdateMemoryRequest,
+    UpdateMemoryResponse, Validity,
 };
 use serde::Deserialize;
 use std::collections::HashMap;
@@ -69,6 +71,15 @@ impl AuditStore for InMemoryAuditStore {
         apply_audit_list_opts(&mut out, opts);
         Ok(out)
     }
+
+    async fn redact(
+        &self,
+        user_id: Option<&str>,
+        cube_id: Option<&str>,
+    ) -> Result<u64, mem_types::AuditStoreError> {
+        let mut guard = self.events.write().await;
+        Ok(redact_audit_events(&mut guard, user_id, cube_id))
+    }
 }
 
 /// JSONL file-backed AuditStore (persists across restarts).
@@ -126,8 +137,69 @@ impl AuditStore for JsonlAuditStore {
         apply_audit_list_opts(&mut out, opts);
         Ok(out)
     }
+
+    async fn redact(
+        &self,
+        user_id: Option<&str>,
+        cube_id: Option<&str>,
+    ) -> Result<u64, mem_types::AuditStoreError> {
+        let _guard = self.append_lock.lock().await;
+        let content = match tokio::fs::read_to_string(&self.path).await {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(mem_types::AuditStoreError::Other(e.to_string())),
+        };
+        let mut events: Vec<AuditEvent> = content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .filter_map(|l| serde_json::from_str(l.trim()).ok())
+            .collect();
+        let redacted = redact_audit_events(&mut events, user_id, cube_id);
+        if redacted == 0 {
+            return Ok(0);
+        }
+        let mut out = String::new();
+        for ev in &events {
+            let line = serde_json::to_string(ev)
+                .map_err(|e| mem_types::AuditStoreError::Other(e.to_string()))?;
+            out.push_str(&line);
+            out.push('\n');
+        }
+        // Write to a sibling file and rename so a crash never leaves a half-written log.
+        let tmp = self.path.with_extension("jsonl.tmp");
+        tokio::fs::write(&tmp, out)
+            .await
+            .map_err(|e| mem_types::AuditStoreError::Other(e.to_string()))?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .map_err(|e| mem_types::AuditStoreError::Other(e.to_string()))?;
+        Ok(redacted)
+    }
+}
+
+/// Replace `input_summary` of the events of the user and/or cube. Returns how many changed.
+fn redact_audit_events(
+    events: &mut [AuditEvent],
+    user_id: Option<&str>,
+    cube_id: Option<&str>,
+) -> u64 {
+    if user_id.is_none() && cube_id.is_none() {
+        return 0;
+    }
+    let mut n = 0u64;
+    for ev in events.iter_mut().filter(|e| {
+        user_id.is_none_or(|u| e.user_id == u) && cube_id.is_none_or(|c| e.cube_id == c)
+    }) {
+        if ev.input_summary.as_deref().is_some_and(|s| s != REDACTED) {
+            ev.input_summary = Some(REDACTED.to_string());
+            n += 1;
+        }
+    }
+    n
 }
 
+const REDACTED: &str = "[redacted]";
+
 fn apply_audit_list_opts(out: &mut Vec<AuditEvent>, opts: &AuditListOptions) {
     if let Some(ref uid) = opts.user_id {
         out.retain(|e| &e.user_id == uid);
@@ -354,6 +426,15 @@ pub fn router(state: Arc<AppState>) -> Router {
         .route("/product/graph/neighbors", post(handle_graph_neighbors))
         .route("/product/graph/path", post(handle_graph_path))
         .route("/product/graph/paths", post(handle_graph_paths))
+        .route("/product/graph/edge/add", post(handle_graph_edge_add))
+        .route("/product/graph/edge/get", post(handle_graph_edge_get))
+        .route("/product/graph/edge/update", post(handle_graph_edge_update))
+        .route("/product/graph/edge/delete", post(handle_graph_edge_delete))
+        .route("/product/graph/edge/list", post(handle_graph_edge_list))
+        .route("/product/graph/analytics", post(handle_graph_analytics))
+        .route("/product/graph/export", post(handle_graph_export))
+        .route("/product/graph/query", post(handle_graph_query))
+        .route("/product/consistency/check", post(handle_consistency_check))
         .route("/product/audit/list", get(handle_audit_list))
         // P1-2: Batch operations
         .route("/product/batch/add", post(handle_batch_add))
@@ -367,6 +448,42 @@ pub fn router(state: Arc<AppState>) -> Router {
         .route("/product/session/timeline", post(handle_session_timeline))
         // P1-1: Memory summary
         .route("/product/summarize", post(handle_summarize))
+        // Feedback
+        .route("/product/feedback", post(handle_feedback))
+        .route("/product/feedback/export", get(handle_feedback_export))
+        // Erasure (right to be forgotten)
+        .route("/product/erase", post(handle_erase))
+        // Entities
+        .route("/product/entity/search", post(handle_entity_search))
+        .route("/product/entity/get", post(handle_entity_get))
+        .route(
+            "/product/entity/list_by_type",
+            post(handle_entity_list_by_type),
+        )
+        .route("/product/entity/relations", post(handle_entity_relations))
+        .route(
+            "/product/entity/memory_entities",
+            post(handle_memory_entities),
+        )
+        .route("/product/entity/stats", get(handle_entity_stats))
+        .route(
+            "/product/entity/merge_candidates",
+            post(handle_entity_merge_candidates),
+        )
+        .route("/product/entity/merge", post(handle_entity_merge))
+        .route("/product/entity/split", post(handle_entity_split))
+        .route(
+            "/product/entity/relation/upsert",
+            post(handle_entity_relation_upsert),
+        )
+        .route("/product/entity/attributes", post(handle_entity_attributes))
+        .route("/product/entity/path", post(handle_entity_path))
+        .route("/product/entity/subgraph", post(handle_entity_subgraph))
+        .route("/product/entity/profile", post(handle_entity_profile))
+        .route(
+            "/product/entity/search_memories",
+            post(handle_entity_search_memories),
+        )
         .route_layer(middleware::from_fn_with_state(
             Arc::clone(&state),
             require_auth,
@@ -524,6 +641,16 @@ async fn handle_add(
                 .await;
                 Json(res)
             }
+            Err(MemCubeError::BadRequest(msg)) => Json(MemoryResponse {
+                code: 400,
+                message: msg,
+                data: None,
+            }),
+            Err(MemCubeError::NotFound(msg)) => Json(MemoryResponse {
+                code: 404,
+                message: msg,
+                data: None,
+            }),
             Err(e) => Json(MemoryResponse {
                 code: 500,
                 message: e.to_string(),
@@ -648,6 +775,11 @@ async fn handle_update_memory(
             .await;
             Json(res)
         }
+        Err(MemCubeError::BadRequest(msg)) => Json(UpdateMemoryResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
         Err(MemCubeError::NotFound(msg)) => Json(UpdateMemoryResponse {
             code: 404,
             message: msg,
@@ -834,6 +966,236 @@ async fn handle_graph_paths(
     response
 }
 
+/// Map an edge operation result to its response (400/404/500 codes in the body).
+fn graph_edge_response(result: Result<GraphEdgeResponse, MemCubeError>) -> GraphEdgeResponse {
+    match result {
+        Ok(res) => res,
+        Err(MemCubeError::BadRequest(msg)) => GraphEdgeResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        },
+        Err(MemCubeError::NotFound(msg)) => GraphEdgeResponse {
+            code: 404,
+            message: msg,
+            data: None,
+        },
+        Err(e) => GraphEdgeResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        },
+    }
+}
+
+/// Audit a successful edge write; `memory_id` is the edge's source memory.
+async fn push_edge_audit(
+    state: &AppState,
+    kind: AuditEventKind,
+    req_meta: &RequestMeta,
+    user_id: &str,
+    mem_cube_id: &Option<String>,
+    res: &GraphEdgeResponse,
+) {
+    let Some(ref edge) = res.data else {
+        return;
+    };
+    push_audit(
+        state,
+        AuditEvent {
+            event_id: Uuid::new_v4().to_string(),
+            kind,
+            memory_id: Some(edge.from.clone()),
+            user_id: user_id.to_string(),
+            cube_id: mem_cube_id.clone().unwrap_or_else(|| user_id.to_string()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            input_summary: Some(format!(
+                "request_id={} edge_id={}",
+                req_meta.request_id, edge.id
+            )),
+            outcome: Some(format!("code={}", res.code)),
+        },
+    )
+    .await;
+}
+
+async fn handle_graph_edge_add(
+    State(state): State<Arc<AppState>>,
+    Extension(req_meta): Extension<RequestMeta>,
+    Json(req): Json<GraphEdgeAddRequest>,
+) -> Json<GraphEdgeResponse> {
+    let res = graph_edge_response(state.cube.add_edge(&req).await);
+    push_edge_audit(
+        &state,
+        AuditEventKind::EdgeAdd,
+        &req_meta,
+        &req.user_id,
+        &req.mem_cube_id,
+        &res,
+    )
+    .await;
+    Json(res)
+}
+
+async fn handle_graph_edge_get(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<GraphEdgeRequest>,
+) -> Json<GraphEdgeResponse> {
+    Json(graph_edge_response(state.cube.get_edge(&req).await))
+}
+
+async fn handle_graph_edge_update(
+    State(state): State<Arc<AppState>>,
+    Extension(req_meta): Extension<RequestMeta>,
+    Json(req): Json<GraphEdgeUpdateRequest>,
+) -> Json<GraphEdgeResponse> {
+    let res = graph_edge_response(state.cube.update_edge(&req).await);
+    push_edge_audit(
+        &state,
+        AuditEventKind::EdgeUpdate,
+        &req_meta,
+        &req.user_id,
+        &req.mem_cube_id,
+        &res,
+    )
+    .await;
+    Json(res)
+}
+
+async fn handle_graph_edge_delete(
+    State(state): State<Arc<AppState>>,
+    Extension(req_meta): Extension<RequestMeta>,
+    Json(req): Json<GraphEdgeRequest>,
+) -> Json<GraphEdgeResponse> {
+    let res = graph_edge_response(state.cube.delete_edge(&req).await);
+    push_edge_audit(
+        &state,
+        AuditEventKind::EdgeDelete,
+        &req_meta,
+        &req.user_id,
+        &req.mem_cube_id,
+        &res,
+    )
+    .await;
+    Json(res)
+}
+
+async fn handle_graph_edge_list(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<GraphEdgeListRequest>,
+) -> Json<GraphEdgeListResponse> {
+    match state.cube.list_edges(&req).await {
+        Ok(res) => Json(res),
+        Err(MemCubeError::BadRequest(msg)) => Json(GraphEdgeListResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
+        Err(MemCubeError::NotFound(msg)) => Json(GraphEdgeListResponse {
+            code: 404,
+            message: msg,
+            data: None,
+        }),
+        Err(e) => Json(GraphEdgeListResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+async fn handle_graph_export(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<mem_types::GraphExportRequest>,
+) -> Json<mem_types::GraphExportResponse> {
+    match state.cube.export_graph(&req).await {
+        Ok(res) => Json(res),
+        Err(MemCubeError::BadRequest(msg)) => Json(mem_types::GraphExportResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
+        Err(MemCubeError::NotFound(msg)) => Json(mem_types::GraphExportResponse {
+            code: 404,
+            message: msg,
+            data: None,
+        }),
+        Err(e) => Json(mem_types::GraphExportResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+async fn handle_graph_query(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<mem_types::GraphQueryRequest>,
+) -> Json<mem_types::GraphQueryResponse> {
+    match state.cube.graph_query(&req).await {
+        Ok(res) => Json(res),
+        Err(MemCubeError::BadRequest(msg)) => Json(mem_types::GraphQueryResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
+        Err(e) => Json(mem_types::GraphQueryResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+/// Compare the cube's graph, vector and keyword stores; `repair` re-derives divergent entries.
+async fn handle_consistency_check(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<mem_types::ConsistencyCheckRequest>,
+) -> Json<mem_types::ConsistencyCheckResponse> {
+    match state.cube.check_consistency(&req).await {
+        Ok(res) => Json(res),
+        Err(MemCubeError::BadRequest(msg)) => Json(mem_types::ConsistencyCheckResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
+        Err(e) => Json(mem_types::ConsistencyCheckResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+/// Queue a graph analytics run. Poll `/product/scheduler/status` for the report.
+async fn handle_graph_analytics(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<mem_types::GraphAnalyticsRequest>,
+) -> Json<MemoryResponse> {
+    if let Err(message) = req.validate() {
+        return Json(MemoryResponse {
+            code: 400,
+            message,
+            data: None,
+        });
+    }
+    match state.scheduler.submit_graph_analytics(req).await {
+        Ok(task_id) => {
+            tracing::info!(task_id = %task_id, "graph analytics job submitted");
+            Json(MemoryResponse {
+                code: 200,
+                message: "Graph analytics job submitted".to_string(),
+                data: Some(vec![serde_json::json!({ "task_id": task_id })]),
+            })
+        }
+        Err(e) => Json(MemoryResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct AuditListQuery {
     #[serde(default)]
@@ -944,15 +1306,34 @@ async fn handle_export(
         .cloned()
         .unwrap_or_else(|| "json".to_string());
 
+    let limit = match params.get("limit").map(|l| l.parse::<u32>()) {
+        Some(Ok(limit)) if limit > 0 => Some(limit),
+        None => None,
+        Some(_) => {
+            return Json(mem_types::ExportResponse {
+                code: 400,
+                message: "limit must be a positive integer".to_string(),
+                data: None,
+            })
+        }
+    };
+
     let req = mem_types::ExportRequest {
         user_id,
         mem_cube_id,
         scope,
         format,
+        limit,
+        cursor: params.get("cursor").cloned(),
     };
 
     match state.cube.export_memories(&req).await {
         Ok(resp) => Json(resp),
+        Err(MemCubeError::BadRequest(msg)) => Json(mem_types::ExportResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
         Err(e) => Json(mem_types::ExportResponse {
             code: 500,
             message: e.to_string(),
@@ -961,6 +1342,135 @@ async fn handle_export(
     }
 }
 
+// ============================================================================
+// Feedback Handlers
+// ============================================================================
+
+async fn handle_feedback(
+    State(state): State<Arc<AppState>>,
+    Extension(req_meta): Extension<RequestMeta>,
+    Json(req): Json<mem_types::FeedbackRequest>,
+) -> Json<mem_types::FeedbackResponse> {
+    let user_id = req.user_id.clone();
+    let cube_id = req
+        .mem_cube_id
+        .clone()
+        .unwrap_or_else(|| req.user_id.clone());
+    match state.cube.submit_feedback(&req).await {
+        Ok(res) => {
+            for memory_id in &req.memory_ids {
+                push_audit(
+                    &state,
+                    AuditEvent {
+                        event_id: Uuid::new_v4().to_string(),
+                        kind: AuditEventKind::Feedback,
+                        memory_id: Some(memory_id.clone()),
+                        user_id: user_id.clone(),
+                        cube_id: cube_id.clone(),
+                        timestamp: chrono::Utc::now().to_rfc3339(),
+                        input_summary: Some(format!("request_id={}", req_meta.request_id)),
+                        outcome: Some(format!("code={}", res.code)),
+                    },
+                )
+                .await;
+            }
+            Json(res)
+        }
+        Err(MemCubeError::BadRequest(msg)) => Json(mem_types::FeedbackResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
+        Err(MemCubeError::NotFound(msg)) => Json(mem_types::FeedbackResponse {
+            code: 404,
+            message: msg,
+            data: None,
+        }),
+        Err(e) => Json(mem_types::FeedbackResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+async fn handle_feedback_export(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Json<mem_types::FeedbackExportResponse> {
+    let req = mem_types::FeedbackExportRequest {
+        user_id: params.get("user_id").cloned().unwrap_or_default(),
+        mem_cube_id: params.get("mem_cube_id").cloned(),
+        since: params.get("since").cloned(),
+        limit: params.get("limit").and_then(|s| s.parse().ok()),
+        format: params
+            .get("format")
+            .cloned()
+            .unwrap_or_else(|| "json".to_string()),
+    };
+
+    match state.cube.export_feedback(&req).await {
+        Ok(resp) => Json(resp),
+        Err(e) => Json(mem_types::FeedbackExportResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
+// ============================================================================
+// Erasure Handlers
+// ============================================================================
+
+/// Queue a right-to-be-forgotten erasure. Poll `/product/scheduler/status` for the signed report.
+async fn handle_erase(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<mem_types::EraseUserDataRequest>,
+) -> Json<MemoryResponse> {
+    if let Err(message) = req.validate() {
+        return Json(MemoryResponse {
+            code: 400,
+            message,
+            data: None,
+        });
+    }
+    let user_id = req.user_id.clone();
+    let cube_id = req
+        .mem_cube_id
+        .clone()
+        .unwrap_or_else(|| req.user_id.clone());
+    match state.scheduler.submit_erasure(req).await {
+        Ok(task_id) => {
+            tracing::info!(task_id = %task_id, "erasure job submitted");
+            push_audit(
+                &state,
+                AuditEvent {
+                    event_id: Uuid::new_v4().to_string(),
+                    kind: AuditEventKind::Erase,
+                    memory_id: None,
+                    user_id,
+                    cube_id,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                    input_summary: None,
+                    outcome: Some(format!("task_id={}", task_id)),
+                },
+            )
+            .await;
+            Json(MemoryResponse {
+                code: 200,
+                message: "Erasure job submitted".to_string(),
+                data: Some(vec![serde_json::json!({ "task_id": task_id })]),
+            })
+        }
+        Err(e) => Json(MemoryResponse {
+            code: 500,
+            message: e.to_string(),
+            data: None,
+        }),
+    }
+}
+
 // ============================================================================
 // Session Management Handlers (P1-3)
 // ============================================================================
@@ -1050,6 +1560,11 @@ async fn handle_session_timeline(
 ) -> Json<mem_types::SessionTimelineResponse> {
     match state.cube.session_timeline(&req).await {
         Ok(resp) => Json(resp),
+        Err(MemCubeError::BadRequest(msg)) => Json(mem_types::SessionTimelineResponse {
+            code: 400,
+            message: msg,
+            data: None,
+        }),
         Err(e) => Json(mem_types::SessionTimelineResponse {
             code: 500,
             message: e.to_string(),
@@ -1084,24 +1599,39 @@ async fn handle_summarize(
 #[derive(Debug, Deserialize)]
 pub struct GetEntityRequest {
     pub entity_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Return attribute values as of this time instead of the current ones.
+    #[serde(default)]
+    pub as_of: Option<String>,
 }
 
 /// Get entity by name request.
 #[derive(Debug, Deserialize)]
 pub struct GetEntityByNameRequest {
     pub name: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
 }
 
 /// Search entities request.
 #[derive(Debug, Deserialize)]
 pub struct SearchEntitiesRequest {
     pub query: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
     #[serde(default)]
     pub entity_type: Option<EntityType>,
     #[serde(default = "default_limit")]
     pub limit: u32,
     #[serde(default)]
     pub fuzzy: bool,
+    /// Minimum fuzzy match score (0.0 - 1.0); only used with `fuzzy`.
+    #[serde(default)]
+    pub min_score: Option<f64>,
 }
 
 fn default_limit() -> u32 {
@@ -1112,6 +1642,9 @@ fn default_limit() -> u32 {
 #[derive(Debug, Deserialize)]
 pub struct ListEntitiesByTypeRequest {
     pub entity_type: EntityType,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: u32,
     #[serde(default)]
@@ -1122,8 +1655,14 @@ pub struct ListEntitiesByTypeRequest {
 #[derive(Debug, Deserialize)]
 pub struct GetEntityRelationsRequest {
     pub entity_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
     #[serde(default)]
     pub relation_type: Option<EntityRelationType>,
+    /// Only relations that held at this time (RFC 3339 or `YYYY-MM-DD`).
+    #[serde(default)]
+    pub as_of: Option<String>,
     #[serde(default = "default_related_limit")]
     pub limit: u32,
 }
@@ -1136,6 +1675,130 @@ fn default_related_limit() -> u32 {
 #[derive(Debug, Deserialize)]
 pub struct GetMemoryEntitiesRequest {
     pub memory_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+}
+
+/// Entity merge proposals request.
+#[derive(Debug, Deserialize)]
+pub struct EntityMergeCandidatesRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Minimum confidence (defaults to the resolver's, 0.6).
+    #[serde(default)]
+    pub min_confidence: Option<f64>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+/// Merge two entities request.
+#[derive(Debug, Deserialize)]
+pub struct MergeEntitiesRequest {
+    pub keep_id: String,
+    pub merge_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+}
+
+/// Split an entity request.
+#[derive(Debug, Deserialize)]
+pub struct SplitEntityRequest {
+    pub entity_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    #[serde(flatten)]
+    pub split: mem_types::EntitySplit,
+}
+
+/// Shortest entity path request.
+#[derive(Debug, Deserialize)]
+pub struct EntityPathRequest {
+    pub source_entity_id: String,
+    pub target_entity_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    #[serde(default)]
+    pub direction: mem_types::GraphDirection,
+    #[serde(default)]
+    pub relation_types: Option<Vec<EntityRelationType>>,
+    #[serde(default = "default_entity_path_depth")]
+    pub max_depth: u32,
+    #[serde(default)]
+    pub as_of: Option<String>,
+}
+
+fn default_entity_path_depth() -> u32 {
+    6
+}
+
+/// Upper bound on the entities a path search visits.
+const ENTITY_PATH_MAX_VISITED: u32 = 10_000;
+
+/// Entity-centric subgraph request.
+#[derive(Debug, Deserialize)]
+pub struct EntitySubgraphRequest {
+    pub entity_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Memories attached to each entity (0 for none).
+    #[serde(default = "default_memories_per_entity")]
+    pub memories_per_entity: u32,
+    #[serde(flatten)]
+    pub traversal: mem_types::EntityTraversal,
+}
+
+fn default_memories_per_entity() -> u32 {
+    3
+}
+
+/// Entity profile request.
+#[derive(Debug, Deserialize)]
+pub struct EntityProfileRequest {
+    pub entity_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Fold newly linked memories into the description first (default: the server's setting).
+    #[serde(default)]
+    pub refresh: Option<bool>,
+    /// Linked memories returned, most recently linked first (0 for none).
+    #[serde(default = "default_profile_memory_limit")]
+    pub memory_limit: u32,
+}
+
+fn default_profile_memory_limit() -> u32 {
+    20
+}
+
+/// Assert a relation between two entities request.
+#[derive(Debug, Deserialize)]
+pub struct UpsertEntityRelationRequest {
+    pub source_entity_id: String,
+    pub target_entity_id: String,
+    pub relation_type: EntityRelationType,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    #[serde(flatten)]
+    pub validity: Validity,
+}
+
+/// Record entity attribute values request.
+#[derive(Debug, Deserialize)]
+pub struct SetEntityAttributesRequest {
+    pub entity_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    pub attributes: HashMap<String, serde_json::Value>,
+    #[serde(flatten)]
+    pub validity: Validity,
 }
 
 /// Entity response.
@@ -1172,6 +1835,8 @@ pub struct EntityRelationsApiResponse {
 pub struct RelationItem {
     pub relation_type: String,
     pub entity: EntityItem,
+    #[serde(flatten)]
+    pub validity: Validity,
 }
 
 #[derive(Debug, Clone, serde::Serialize, Deserialize)]
@@ -1205,14 +1870,15 @@ pub struct EntityStatsData {
 }
 
 /// Convert internal Entity to API format.
-#[allow(dead_code)]
 fn entity_to_api(entity: &Entity) -> serde_json::Value {
     serde_json::json!({
         "id": entity.id,
         "name": entity.name,
         "entity_type": entity.entity_type.to_string(),
+        "cube_id": entity.cube_id,
         "description": entity.description,
         "attributes": entity.attributes,
+        "attribute_history": entity.attribute_history,
         "memory_ids": entity.memory_ids,
         "name_variants": entity.name_variants,
         "occurrence_count": entity.metadata.occurrence_count,
@@ -1222,3 +1888,578 @@ fn entity_to_api(entity: &Entity) -> serde_json::Value {
         "version": entity.version,
     })
 }
+
+fn relation_to_api(relation: &mem_types::StoredRelation) -> serde_json::Value {
+    let mut value = serde_json::json!({
+        "source_id": relation.source_id,
+        "target_id": relation.target_id,
+        "relation_type": relation.relation_type.to_string(),
+    });
+    if let (Some(map), Ok(serde_json::Value::Object(validity))) = (
+        value.as_object_mut(),
+        serde_json::to_value(&relation.validity),
+    ) {
+        map.extend(validity);
+    }
+    value
+}
+
+/// Parse an optional `as_of` (RFC 3339 or `YYYY-MM-DD`); malformed values are a bad request.
+fn parse_as_of(raw: Option<&str>) -> Result<Option<chrono::DateTime<chrono::Utc>>, MemCubeError> {
+    match raw {
+        Some(raw) => mem_types::parse_timestamp(raw)
+            .map(Some)
+            .ok_or_else(|| MemCubeError::BadRequest(format!("invalid as_of: {}", raw))),
+        None => Ok(None),
+    }
+}
+
+fn entity_to_item(entity: &Entity) -> EntityItem {
+    EntityItem {
+        id: entity.id.clone(),
+        name: entity.name.clone(),
+        entity_type: entity.entity_type.to_string(),
+        description: entity.description.clone(),
+        attributes: (!entity.attributes.is_empty()).then(|| serde_json::json!(entity.attributes)),
+        occurrence_count: entity.metadata.occurrence_count,
+        confidence: entity.metadata.confidence,
+    }
+}
+
+/// Cube an entity request is scoped to (`mem_cube_id`, defaulting to `user_id`).
+fn entity_cube_id(user_id: &str, mem_cube_id: &Option<String>) -> Result<String, MemCubeError> {
+    if user_id.trim().is_empty() {
+        return Err(MemCubeError::BadRequest("user_id is required".to_string()));
+    }
+    Ok(mem_cube_id.clone().unwrap_or_else(|| user_id.to_string()))
+}
+
+/// Map a cube error to the response `code` and message.
+fn entity_error(e: MemCubeError) -> (i32, String) {
+    match e {
+        MemCubeError::BadRequest(msg) => (400, msg),
+        MemCubeError::NotFound(msg) => (404, msg),
+        e => (500, e.to_string()),
+    }
+}
+
+fn entity_list_response(
+    result: Result<(Vec<Entity>, Option<String>), MemCubeError>,
+) -> Json<EntityListApiResponse> {
+    match result {
+        Ok((entities, next_cursor)) => Json(EntityListApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            total_count: entities.len() as u32,
+            entities: entities.iter().map(entity_to_api).collect(),
+            next_cursor,
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityListApiResponse {
+                code,
+                message,
+                entities: Vec::new(),
+                total_count: 0,
+                next_cursor: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_search(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SearchEntitiesRequest>,
+) -> Json<EntityListApiResponse> {
+    let cube_id = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => cube_id,
+        Err(e) => return entity_list_response(Err(e)),
+    };
+    if !req.fuzzy {
+        let result = state
+            .cube
+            .search_entities(&cube_id, &req.query, req.entity_type, req.limit)
+            .await
+            .map(|entities| (entities, None));
+        return entity_list_response(result);
+    }
+    if let Some(min_score) = req.min_score {
+        if !(0.0..=1.0).contains(&min_score) {
+            return entity_list_response(Err(MemCubeError::BadRequest(
+                "min_score must be between 0 and 1".to_string(),
+            )));
+        }
+    }
+    match state
+        .cube
+        .fuzzy_search_entities(
+            &cube_id,
+            &req.query,
+            req.entity_type,
+            req.min_score,
+            req.limit,
+        )
+        .await
+    {
+        Ok(hits) => Json(EntityListApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            total_count: hits.len() as u32,
+            entities: hits
+                .iter()
+                .map(|(entity, score)| {
+                    let mut value = entity_to_api(entity);
+                    value["score"] = serde_json::json!(score);
+                    value
+                })
+                .collect(),
+            next_cursor: None,
+        }),
+        Err(e) => entity_list_response(Err(e)),
+    }
+}
+
+async fn handle_entity_get(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<GetEntityRequest>,
+) -> Json<EntityApiResponse> {
+    let result = match (
+        entity_cube_id(&req.user_id, &req.mem_cube_id),
+        parse_as_of(req.as_of.as_deref()),
+    ) {
+        (Ok(cube_id), Ok(as_of)) => {
+            state
+                .cube
+                .get_entity(&cube_id, &req.entity_id)
+                .await
+                .map(|entity| {
+                    entity.map(|mut entity| {
+                        if let Some(at) = as_of {
+                            entity.attributes = entity.attributes_as_of(&at);
+                        }
+                        entity
+                    })
+                })
+        }
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    };
+    match result {
+        Ok(Some(entity)) => Json(EntityApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(entity_to_api(&entity)),
+        }),
+        Ok(None) => Json(EntityApiResponse {
+            code: 404,
+            message: format!("entity not found: {}", req.entity_id),
+            data: None,
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_list_by_type(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ListEntitiesByTypeRequest>,
+) -> Json<EntityListApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => {
+            state
+                .cube
+                .list_entities_by_type(&cube_id, req.entity_type, req.limit, req.cursor.as_deref())
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    entity_list_response(result)
+}
+
+async fn handle_entity_relations(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<GetEntityRelationsRequest>,
+) -> Json<EntityRelationsApiResponse> {
+    let result = match (
+        entity_cube_id(&req.user_id, &req.mem_cube_id),
+        parse_as_of(req.as_of.as_deref()),
+    ) {
+        (Ok(cube_id), Ok(as_of)) => {
+            state
+                .cube
+                .get_entity_relations(
+                    &cube_id,
+                    &req.entity_id,
+                    req.relation_type,
+                    as_of.as_ref(),
+                    req.limit,
+                )
+                .await
+        }
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    };
+    match result {
+        Ok(related) => Json(EntityRelationsApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            relations: related
+                .iter()
+                .map(|(relation, entity)| RelationItem {
+                    relation_type: relation.relation_type.to_string(),
+                    entity: entity_to_item(entity),
+                    validity: relation.validity.clone(),
+                })
+                .collect(),
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityRelationsApiResponse {
+                code,
+                message,
+                relations: Vec::new(),
+            })
+        }
+    }
+}
+
+async fn handle_memory_entities(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<GetMemoryEntitiesRequest>,
+) -> Json<EntityListApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => state
+            .cube
+            .get_memory_entities(&cube_id, &req.memory_id)
+            .await
+            .map(|entities| (entities, None)),
+        Err(e) => Err(e),
+    };
+    entity_list_response(result)
+}
+
+async fn handle_entity_stats(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> Json<EntityStatsApiResponse> {
+    let user_id = params.get("user_id").cloned().unwrap_or_default();
+    let result = match entity_cube_id(&user_id, &params.get("mem_cube_id").cloned()) {
+        Ok(cube_id) => state.cube.entity_stats(&cube_id).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(stats) => Json(EntityStatsApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(EntityStatsData {
+                total_entities: stats.total_entities as u32,
+                total_relations: stats.total_relations as u32,
+                type_counts: serde_json::json!(stats.type_counts),
+            }),
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityStatsApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_merge_candidates(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<EntityMergeCandidatesRequest>,
+) -> Json<EntityApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => {
+            state
+                .cube
+                .propose_entity_merges(&cube_id, req.min_confidence, req.limit as usize)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(candidates) => Json(EntityApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(serde_json::json!({ "candidates": candidates })),
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+fn entity_response(result: Result<Entity, MemCubeError>) -> Json<EntityApiResponse> {
+    match result {
+        Ok(entity) => Json(EntityApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(entity_to_api(&entity)),
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_merge(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<MergeEntitiesRequest>,
+) -> Json<EntityApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => {
+            state
+                .cube
+                .merge_entities(&cube_id, &req.keep_id, &req.merge_id)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    entity_response(result)
+}
+
+async fn handle_entity_split(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SplitEntityRequest>,
+) -> Json<EntityApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => {
+            state
+                .cube
+                .split_entity(&cube_id, &req.entity_id, &req.split)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    entity_response(result)
+}
+
+async fn handle_entity_relation_upsert(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<UpsertEntityRelationRequest>,
+) -> Json<EntityApiResponse> {
+    let relation = mem_types::StoredRelation {
+        source_id: req.source_entity_id,
+        target_id: req.target_entity_id,
+        relation_type: req.relation_type,
+        validity: req.validity,
+    };
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => state.cube.upsert_entity_relation(&cube_id, &relation).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(stored) => Json(EntityApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(relation_to_api(&stored)),
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_attributes(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SetEntityAttributesRequest>,
+) -> Json<EntityApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => {
+            state
+                .cube
+                .set_entity_attributes(&cube_id, &req.entity_id, &req.attributes, &req.validity)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    entity_response(result)
+}
+
+async fn handle_entity_path(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<EntityPathRequest>,
+) -> Json<EntityApiResponse> {
+    let traversal = mem_types::EntityTraversal {
+        direction: req.direction,
+        relation_types: req.relation_types,
+        max_depth: req.max_depth,
+        max_entities: ENTITY_PATH_MAX_VISITED,
+        as_of: req.as_of.clone(),
+    };
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id)
+        .and_then(|cube_id| parse_as_of(req.as_of.as_deref()).map(|_| cube_id))
+    {
+        Ok(cube_id) => state
+            .cube
+            .entity_path(
+                &cube_id,
+                &req.source_entity_id,
+                &req.target_entity_id,
+                &traversal,
+            )
+            .await
+            .and_then(|path| {
+                path.ok_or_else(|| {
+                    MemCubeError::NotFound(format!(
+                        "path not found: {} -> {}",
+                        req.source_entity_id, req.target_entity_id
+                    ))
+                })
+            }),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(path) => Json(EntityApiResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(serde_json::json!({
+                "hops": path.relations.len(),
+                "entities": path.entities.iter().map(entity_to_api).collect::<Vec<_>>(),
+                "relations": path.relations.iter().map(relation_to_api).collect::<Vec<_>>(),
+            })),
+        }),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_subgraph(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<EntitySubgraphRequest>,
+) -> Json<EntityApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id)
+        .and_then(|cube_id| parse_as_of(req.traversal.as_of.as_deref()).map(|_| cube_id))
+    {
+        Ok(cube_id) => {
+            state
+                .cube
+                .entity_subgraph(
+                    &cube_id,
+                    &req.entity_id,
+                    &req.traversal,
+                    req.memories_per_entity,
+                )
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(subgraph) => {
+            let nodes: Vec<serde_json::Value> = subgraph
+                .nodes
+                .iter()
+                .map(|node| {
+                    let mut value = entity_to_api(&node.entity);
+                    value["depth"] = serde_json::json!(node.depth);
+                    value["memories"] = serde_json::json!(node.memories);
+                    value
+                })
+                .collect();
+            Json(EntityApiResponse {
+                code: 200,
+                message: "Success".to_string(),
+                data: Some(serde_json::json!({
+                    "center_id": req.entity_id,
+                    "entities": nodes,
+                    "relations": subgraph.relations.iter().map(relation_to_api).collect::<Vec<_>>(),
+                    "truncated": subgraph.truncated,
+                })),
+            })
+        }
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_profile(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<EntityProfileRequest>,
+) -> Json<EntityApiResponse> {
+    let result = match entity_cube_id(&req.user_id, &req.mem_cube_id) {
+        Ok(cube_id) => {
+            state
+                .cube
+                .entity_profile(&cube_id, &req.entity_id, req.refresh, req.memory_limit)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(profile) => {
+            let mut value = entity_to_api(&profile.entity);
+            value["description_memory_ids"] =
+                serde_json::json!(profile.entity.metadata.description_memory_ids);
+            value["description_updated_at"] =
+                serde_json::json!(profile.entity.metadata.description_updated_at);
+            value["pending_memory_ids"] = serde_json::json!(profile.pending_memory_ids);
+            value["refreshed"] = serde_json::json!(profile.refreshed);
+            value["memories"] = serde_json::json!(profile.memories);
+            Json(EntityApiResponse {
+                code: 200,
+                message: "Success".to_string(),
+                data: Some(value),
+            })
+        }
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(EntityApiResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
+
+async fn handle_entity_search_memories(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<mem_types::EntityAwareSearchRequest>,
+) -> Json<SearchResponse> {
+    match state.cube.search_by_entity(&req).await {
+        Ok(res) => Json(res),
+        Err(e) => {
+            let (code, message) = entity_error(e);
+            Json(SearchResponse {
+                code,
+                message,
+                data: None,
+            })
+        }
+    }
+}
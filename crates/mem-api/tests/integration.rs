@@ -4,7 +4,7 @@ use axum::body::Body;
 use axum::http::{header, Request, StatusCode};
 use http_body_util::BodyExt;
 use mem_api::server::{self, AppState, InMemoryAuditStore};
-use mem_cube::NaiveMemCube;
+use mem_cube::{InMemoryFeedbackStore, NaiveMemCube};
 use mem_embed::MockEmbedder;
 use mem_graph::InMemoryGraphStore;
 use mem_scheduler::InMemoryScheduler;
@@ -23,8 +23,10 @@ fn test_app_with_auth(auth_token: Option<&str>) -> axum::Router {
     let graph = InMemoryGraphStore::new();
     let vec_store = InMemoryVecStore::new(None);
     let embedder = MockEmbedder::new();
-    let cube: Arc<dyn mem_types::MemCube + Send + Sync> =
-        Arc::new(NaiveMemCube::new(graph, vec_store, embedder));
+    let cube: Arc<dyn mem_types::MemCube + Send + Sync> = Arc::new(
+        NaiveMemCube::new(graph, vec_store, embedder)
+            .with_feedback_store(Some(Arc::new(InMemoryFeedbackStore::new()))),
+    );
     let audit_store: Arc<dyn mem_types::AuditStore + Send + Sync> =
         Arc::new(InMemoryAuditStore::new());
     let scheduler = Arc::new(InMemoryScheduler::new(
@@ -1226,6 +1228,968 @@ async fn graph_paths_invalid_top_k_returns_400() {
     assert_eq!(j["code"], 400);
 }
 
+#[tokio::test]
+async fn graph_edges_can_be_created_updated_listed_and_deleted() {
+    let app = test_app();
+    let a = add_memory(&app, "edge_u1", "edge node a").await;
+    let b = add_memory(&app, "edge_u1", "edge node b").await;
+    let c = add_memory(&app, "edge_u1", "edge node c").await;
+    let foreign = add_memory(&app, "edge_u2", "edge node of another cube").await;
+
+    // Endpoints must be memories of the caller's cube.
+    let j = post_json(
+        &app,
+        "/product/graph/edge/add",
+        json!({ "from": a, "to": foreign, "relation": "knows", "user_id": "edge_u1" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+    let j = post_json(
+        &app,
+        "/product/graph/edge/add",
+        json!({ "from": a, "to": b, "relation": " ", "user_id": "edge_u1" }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+
+    let j = post_json(
+        &app,
+        "/product/graph/edge/add",
+        json!({
+            "from": a, "to": b, "relation": "knows", "user_id": "edge_u1",
+            "metadata": { "weight": 2, "user_name": "edge_u2" }
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["metadata"]["user_name"], "edge_u1");
+    let ab = j["data"]["id"].as_str().unwrap().to_string();
+    let j = post_json(
+        &app,
+        "/product/graph/edge/add",
+        json!({ "from": c, "to": a, "relation": "cites", "user_id": "edge_u1" }),
+    )
+    .await;
+    let ca = j["data"]["id"].as_str().unwrap().to_string();
+
+    // The new edge is traversable like relations written at add time.
+    let j = post_json(
+        &app,
+        "/product/graph/neighbors",
+        json!({ "memory_id": a, "user_id": "edge_u1" }),
+    )
+    .await;
+    assert_eq!(j["data"]["items"][0]["memory"]["id"], b.as_str());
+
+    let j = post_json(
+        &app,
+        "/product/graph/edge/get",
+        json!({ "edge_id": ab, "user_id": "edge_u2" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    let j = post_json(
+        &app,
+        "/product/graph/edge/update",
+        json!({
+            "edge_id": ab, "user_id": "edge_u1", "relation": "likes",
+            "metadata": { "weight": null, "note": "updated" }
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["relation"], "likes");
+    assert!(j["data"]["metadata"].get("weight").is_none());
+    assert_eq!(j["data"]["metadata"]["note"], "updated");
+    let j = post_json(
+        &app,
+        "/product/graph/edge/update",
+        json!({ "edge_id": ab, "user_id": "edge_u1" }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+    let j = post_json(
+        &app,
+        "/product/graph/edge/update",
+        json!({ "edge_id": ab, "user_id": "edge_u2", "relation": "stolen" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    // Listing pages through the cube's edges by id.
+    let mut expected = [ab.clone(), ca.clone()];
+    expected.sort();
+    let j = post_json(
+        &app,
+        "/product/graph/edge/list",
+        json!({ "user_id": "edge_u1", "limit": 1 }),
+    )
+    .await;
+    assert_eq!(j["data"]["items"][0]["id"], expected[0].as_str());
+    let cursor = j["data"]["next_cursor"].as_str().unwrap().to_string();
+    let j = post_json(
+        &app,
+        "/product/graph/edge/list",
+        json!({ "user_id": "edge_u1", "limit": 1, "cursor": cursor }),
+    )
+    .await;
+    assert_eq!(j["data"]["items"][0]["id"], expected[1].as_str());
+    assert!(j["data"]["next_cursor"].is_null());
+    let j = post_json(
+        &app,
+        "/product/graph/edge/list",
+        json!({ "user_id": "edge_u1", "memory_id": a, "direction": "inbound" }),
+    )
+    .await;
+    assert_eq!(j["data"]["items"].as_array().unwrap().len(), 1);
+    assert_eq!(j["data"]["items"][0]["id"], ca.as_str());
+    let j = post_json(
+        &app,
+        "/product/graph/edge/list",
+        json!({ "user_id": "edge_u2" }),
+    )
+    .await;
+    assert!(j["data"]["items"].as_array().unwrap().is_empty());
+
+    let j = post_json(
+        &app,
+        "/product/graph/edge/delete",
+        json!({ "edge_id": ab, "user_id": "edge_u1" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let j = post_json(
+        &app,
+        "/product/graph/edge/get",
+        json!({ "edge_id": ab, "user_id": "edge_u1" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    let j = get_json(&app, "/product/audit/list?user_id=edge_u1&cube_id=edge_u1").await;
+    let kinds: Vec<&str> = j["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|e| e["kind"].as_str())
+        .filter(|k| k.starts_with("edge_"))
+        .collect();
+    assert_eq!(kinds.iter().filter(|k| **k == "edge_add").count(), 2);
+    assert!(kinds.contains(&"edge_update") && kinds.contains(&"edge_delete"));
+}
+
+#[tokio::test]
+async fn graph_path_ranks_by_edge_cost_field() {
+    let app = test_app();
+    let s = add_memory(&app, "cost_u1", "cost source").await;
+    let a = add_memory(&app, "cost_u1", "cost detour a").await;
+    let b = add_memory(&app, "cost_u1", "cost detour b").await;
+    let t = add_memory(&app, "cost_u1", "cost target").await;
+    for (from, to, weight) in [(&s, &t, 10.0), (&s, &a, 1.0), (&a, &b, 1.0), (&b, &t, 1.0)] {
+        let j = post_json(
+            &app,
+            "/product/graph/edge/add",
+            json!({
+                "from": from, "to": to, "relation": "next", "user_id": "cost_u1",
+                "mem_cube_id": "cost_u1", "metadata": { "weight": weight }
+            }),
+        )
+        .await;
+        assert_eq!(j["code"], 200);
+    }
+    let base = json!({
+        "source_memory_id": s, "target_memory_id": t,
+        "user_id": "cost_u1", "mem_cube_id": "cost_u1", "direction": "outbound"
+    });
+    let with = |extra: serde_json::Value| {
+        let mut body = base.clone();
+        body.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        body
+    };
+
+    // Hop count prefers the direct edge; cost prefers the three-hop detour.
+    let j = post_json(&app, "/product/graph/path", base.clone()).await;
+    assert_eq!(j["data"]["hops"], 1);
+    assert!(j["data"].get("cost").is_none());
+    let j = post_json(
+        &app,
+        "/product/graph/path",
+        with(json!({ "cost_field": "weight" })),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["hops"], 3);
+    assert_eq!(j["data"]["cost"], 3.0);
+    assert_eq!(j["data"]["nodes"][1]["id"], a.as_str());
+
+    let j = post_json(
+        &app,
+        "/product/graph/paths",
+        with(json!({ "cost_field": "weight", "top_k_paths": 5 })),
+    )
+    .await;
+    let costs: Vec<f64> = j["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["cost"].as_f64().unwrap())
+        .collect();
+    assert_eq!(costs, vec![3.0, 10.0]);
+
+    // max_cost bounds the total; nothing within budget is a 404.
+    let j = post_json(
+        &app,
+        "/product/graph/paths",
+        with(json!({ "cost_field": "weight", "max_cost": 5 })),
+    )
+    .await;
+    assert_eq!(j["data"].as_array().unwrap().len(), 1);
+    let j = post_json(
+        &app,
+        "/product/graph/path",
+        with(json!({ "cost_field": "weight", "max_cost": 2 })),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    // inverse turns large weights into cheap edges.
+    let j = post_json(
+        &app,
+        "/product/graph/path",
+        with(json!({ "cost_field": "weight", "cost_transform": "inverse" })),
+    )
+    .await;
+    assert_eq!(j["data"]["hops"], 1);
+    assert_eq!(j["data"]["cost"], 0.1);
+
+    for bad in [
+        json!({ "max_cost": 5 }),
+        json!({ "cost_field": "weight", "default_cost": -1 }),
+        json!({ "cost_field": "" }),
+    ] {
+        let j = post_json(&app, "/product/graph/path", with(bad)).await;
+        assert_eq!(j["code"], 400);
+    }
+}
+
+#[tokio::test]
+async fn graph_analytics_job_writes_centrality_and_communities() {
+    let app = test_app();
+    let hub = add_memory(&app, "ga_u1", "analytics hub").await;
+    let mut spokes = Vec::new();
+    for i in 0..3 {
+        let spoke = add_memory(&app, "ga_u1", &format!("analytics spoke {i}")).await;
+        let j = post_json(
+            &app,
+            "/product/graph/edge/add",
+            json!({ "from": spoke, "to": hub, "relation": "mentions", "user_id": "ga_u1" }),
+        )
+        .await;
+        assert_eq!(j["code"], 200);
+        spokes.push(spoke);
+    }
+    let lonely = add_memory(&app, "ga_u1", "analytics lonely").await;
+
+    let j = post_json(
+        &app,
+        "/product/graph/analytics",
+        json!({ "user_id": "ga_u1", "top_k": 2 }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let task_id = j["data"][0]["task_id"].as_str().unwrap().to_string();
+    let mut status = json!(null);
+    for _ in 0..50 {
+        status = get_json(
+            &app,
+            &format!(
+                "/product/scheduler/status?user_id=ga_u1&task_id={}",
+                task_id
+            ),
+        )
+        .await;
+        if status["data"]["status"] == "done" || status["data"]["status"] == "failed" {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(status["data"]["status"], "done", "{}", status);
+    let report: mem_types::GraphAnalyticsReport =
+        serde_json::from_value(status["data"]["result_summary"].clone()).unwrap();
+    assert_eq!(report.nodes, 5);
+    assert_eq!(report.edges, 3);
+    assert_eq!(report.component_sizes, vec![4, 1]);
+    assert_eq!(report.communities, 2);
+    assert_eq!(report.top_nodes.len(), 2);
+    assert_eq!(report.top_nodes[0].memory_id, hub);
+
+    // Results are written back to node metadata.
+    let j = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": hub, "user_id": "ga_u1" }),
+    )
+    .await;
+    let metadata = &j["data"]["metadata"];
+    assert_eq!(metadata["centrality"], 1.0);
+    assert_eq!(metadata["degree"], 3);
+    assert_eq!(metadata["component_id"], 0);
+    assert_eq!(metadata["community_id"], 0);
+    assert!(metadata["pagerank"].as_f64().unwrap() > 0.25);
+    assert!(metadata["analytics_updated_at"].is_string());
+    let j = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": spokes[0], "user_id": "ga_u1" }),
+    )
+    .await;
+    assert_eq!(j["data"]["metadata"]["community_id"], 0);
+    assert!(j["data"]["metadata"]["centrality"].as_f64().unwrap() < 1.0);
+    let j = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": lonely, "user_id": "ga_u1" }),
+    )
+    .await;
+    assert_eq!(j["data"]["metadata"]["component_id"], 1);
+    assert_eq!(j["data"]["metadata"]["degree"], 0);
+
+    for bad in [
+        json!({ "user_id": "ga_u1", "damping": 1.0 }),
+        json!({ "user_id": "ga_u1", "max_iterations": 0 }),
+        json!({ "user_id": "" }),
+    ] {
+        let j = post_json(&app, "/product/graph/analytics", bad).await;
+        assert_eq!(j["code"], 400);
+    }
+}
+
+#[tokio::test]
+async fn graph_export_writes_filtered_graphs_in_each_format() {
+    let app = test_app();
+    let mut ids = Vec::new();
+    for (content, session, scope) in [
+        ("export root", "s1", "WorkingMemory"),
+        ("export child", "s1", "LongTermMemory"),
+        ("export grandchild", "s2", "LongTermMemory"),
+        ("export side", "s2", "LongTermMemory"),
+    ] {
+        let j = post_json(
+            &app,
+            "/product/add",
+            json!({
+                "user_id": "gx_u1", "memory_content": content, "session_id": session,
+                "info": { "scope": scope }, "async_mode": "sync"
+            }),
+        )
+        .await;
+        assert_eq!(j["code"], 200);
+        ids.push(j["data"][0]["id"].as_str().unwrap().to_string());
+    }
+    for (from, to, relation) in [(0, 1, "next"), (1, 2, "next"), (0, 3, "aside")] {
+        let j = post_json(
+            &app,
+            "/product/graph/edge/add",
+            json!({ "from": ids[from], "to": ids[to], "relation": relation, "user_id": "gx_u1" }),
+        )
+        .await;
+        assert_eq!(j["code"], 200);
+    }
+    add_memory(&app, "gx_u2", "other tenant").await;
+    let export = |extra: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            let mut body = json!({ "user_id": "gx_u1" });
+            body.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            post_json(&app, "/product/graph/export", body).await
+        }
+    };
+
+    // JSON Graph Format is the default.
+    let j = export(json!({})).await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["format"], "json");
+    assert_eq!(j["data"]["total_nodes"], 4);
+    assert_eq!(j["data"]["total_edges"], 3);
+    let doc: serde_json::Value = serde_json::from_str(j["data"]["data"].as_str().unwrap()).unwrap();
+    assert_eq!(doc["graph"]["nodes"][&ids[0]]["label"], "export root");
+    assert_eq!(doc["graph"]["nodes"][&ids[0]]["metadata"]["kind"], "memory");
+    assert_eq!(doc["graph"]["edges"].as_array().unwrap().len(), 3);
+
+    let counts = |j: &serde_json::Value| {
+        (
+            j["data"]["total_nodes"].as_u64().unwrap(),
+            j["data"]["total_edges"].as_u64().unwrap(),
+        )
+    };
+    let j = export(json!({ "root_memory_id": ids[0], "depth": 1, "direction": "outbound" })).await;
+    assert_eq!(counts(&j), (3, 2));
+    let j = export(json!({ "root_memory_id": ids[0], "relation": "next" })).await;
+    assert_eq!(counts(&j), (3, 2));
+    let j = export(json!({ "root_memory_id": ids[2], "depth": 1, "direction": "outbound" })).await;
+    assert_eq!(counts(&j), (1, 0));
+    let j = export(json!({ "session_id": "s1" })).await;
+    assert_eq!(counts(&j), (2, 1));
+    let j = export(json!({ "scope": "WorkingMemory" })).await;
+    assert_eq!(counts(&j), (1, 0));
+
+    let j = export(json!({ "format": "graphml", "session_id": "s1" })).await;
+    let xml = j["data"]["data"].as_str().unwrap();
+    assert!(xml.starts_with("<?xml"));
+    assert!(xml.contains(&format!("<node id=\"{}\">", ids[0])));
+    assert!(xml.contains("attr.name=\"relation\""));
+    let j = export(json!({ "format": "dot", "session_id": "s1" })).await;
+    let dot = j["data"]["data"].as_str().unwrap();
+    assert!(dot.contains(&format!("\"{}\" -> \"{}\"", ids[0], ids[1])));
+
+    // The flat export covers every scope; `scope` accepts the scope names.
+    let j = get_json(&app, "/product/export?user_id=gx_u1").await;
+    assert_eq!(j["data"]["total_memories"], 4);
+    let j = get_json(&app, "/product/export?user_id=gx_u1&scope=WorkingMemory").await;
+    assert_eq!(j["data"]["total_memories"], 1);
+
+    let j = export(json!({ "root_memory_id": "missing" })).await;
+    assert_eq!(j["code"], 404);
+    let j = export(json!({ "include_entities": true })).await;
+    assert_eq!(j["code"], 400);
+
+    // Entity-aware cubes add entity nodes with `mentions` edges.
+    let app = entity_app();
+    let memory_id = add_memory(&app, "gx_ent", "Alice works at Acme").await;
+    let j = post_json(
+        &app,
+        "/product/graph/export",
+        json!({ "user_id": "gx_ent", "include_entities": true }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let doc: serde_json::Value = serde_json::from_str(j["data"]["data"].as_str().unwrap()).unwrap();
+    let nodes = doc["graph"]["nodes"].as_object().unwrap();
+    let entity = |name: &str| {
+        nodes
+            .iter()
+            .find(|(_, n)| n["label"] == name)
+            .map(|(id, _)| id.clone())
+            .expect(name)
+    };
+    let (alice, acme) = (entity("alice"), entity("acme"));
+    assert_eq!(nodes[&alice]["metadata"]["kind"], "entity");
+    let edges = doc["graph"]["edges"].as_array().unwrap();
+    assert!(edges.iter().any(|e| {
+        e["source"] == memory_id.as_str()
+            && e["target"] == alice.as_str()
+            && e["relation"] == "mentions"
+    }));
+    assert!(edges.iter().any(|e| {
+        e["source"] == alice.as_str() && e["target"] == acme.as_str() && e["relation"] == "works_at"
+    }));
+}
+
+#[tokio::test]
+async fn graph_query_matches_patterns_within_the_cube() {
+    let app = test_app();
+    let mut ids = Vec::new();
+    for (content, scope) in [
+        ("query outage", "UserMemory"),
+        ("query deploy", "LongTermMemory"),
+        ("query config change", "LongTermMemory"),
+    ] {
+        let j = post_json(
+            &app,
+            "/product/add",
+            json!({
+                "user_id": "gq_u1", "memory_content": content,
+                "info": { "scope": scope }, "async_mode": "sync"
+            }),
+        )
+        .await;
+        assert_eq!(j["code"], 200);
+        ids.push(j["data"][0]["id"].as_str().unwrap().to_string());
+    }
+    for (from, to) in [(0, 1), (1, 2)] {
+        let j = post_json(
+            &app,
+            "/product/graph/edge/add",
+            json!({ "from": ids[from], "to": ids[to], "relation": "caused_by", "user_id": "gq_u1" }),
+        )
+        .await;
+        assert_eq!(j["code"], 200);
+    }
+    let other = add_memory(&app, "gq_u2", "query outage elsewhere").await;
+
+    let j = post_json(
+        &app,
+        "/product/graph/query",
+        json!({
+            "user_id": "gq_u1",
+            "query": "MATCH (a)-[p:caused_by*1..3]->(b) WHERE a.scope = 'UserMemory' \
+                      RETURN b, b.memory AS text, p"
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    assert_eq!(j["data"]["columns"], json!(["b", "text", "p"]));
+    let rows = j["data"]["rows"].as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    let texts: Vec<&str> = rows.iter().map(|r| r[1].as_str().unwrap()).collect();
+    assert!(texts.contains(&"query deploy") && texts.contains(&"query config change"));
+    let deepest = rows.iter().find(|r| r[0]["id"] == json!(ids[2])).unwrap();
+    assert_eq!(deepest[2].as_array().unwrap().len(), 2);
+    assert_eq!(deepest[2][0]["relation"], "caused_by");
+
+    // Other cubes are invisible, even by id.
+    let j = post_json(
+        &app,
+        "/product/graph/query",
+        json!({
+            "user_id": "gq_u1",
+            "query": format!("MATCH (n {{id: '{}'}}) RETURN n", other)
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["rows"], json!([]));
+    let j = post_json(
+        &app,
+        "/product/graph/query",
+        json!({ "user_id": "gq_u1", "query": "MATCH (n) RETURN n", "limit": 1 }),
+    )
+    .await;
+    assert_eq!(j["data"]["rows"].as_array().unwrap().len(), 1);
+
+    let j = post_json(
+        &app,
+        "/product/graph/query",
+        json!({ "user_id": "gq_u1", "query": "MATCH (a)-[*1..20]->(b) RETURN b" }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+    assert!(j["message"].as_str().unwrap().contains("hops"));
+}
+
+#[tokio::test]
+async fn keyset_cursors_stay_stable_under_concurrent_writes() {
+    let app = test_app();
+    let root = add_memory(&app, "kc_u1", "cursor root").await;
+    let link = |id: String| {
+        let app = app.clone();
+        let root = root.clone();
+        async move {
+            let j = post_json(
+                &app,
+                "/product/graph/edge/add",
+                json!({ "from": id, "to": root, "relation": "mentions", "user_id": "kc_u1" }),
+            )
+            .await;
+            assert_eq!(j["code"], 200);
+        }
+    };
+    let mut leaves = Vec::new();
+    for i in 0..3 {
+        let id = add_memory(&app, "kc_u1", &format!("cursor leaf {}", i)).await;
+        link(id.clone()).await;
+        leaves.push(id);
+    }
+    let neighbors = |cursor: Option<String>| {
+        let app = app.clone();
+        let root = root.clone();
+        async move {
+            let mut body = json!({
+                "memory_id": root, "user_id": "kc_u1", "direction": "inbound", "limit": 2
+            });
+            if let Some(cursor) = cursor {
+                body["cursor"] = json!(cursor);
+            }
+            post_json(&app, "/product/graph/neighbors", body).await
+        }
+    };
+    let page1 = neighbors(None).await;
+    let ids = |j: &serde_json::Value| -> Vec<String> {
+        j["data"]["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["memory"]["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(ids(&page1), leaves[..2].to_vec(), "oldest edges first");
+    let cursor = page1["data"]["next_cursor"].as_str().unwrap().to_string();
+    assert!(cursor.parse::<usize>().is_err(), "cursors are opaque");
+
+    // An edge added between pages shows up after the cursor instead of shifting the page.
+    let late = add_memory(&app, "kc_u1", "cursor late leaf").await;
+    link(late.clone()).await;
+    let page2 = neighbors(Some(cursor)).await;
+    assert_eq!(ids(&page2), vec![leaves[2].clone(), late]);
+    assert!(page2["data"]["next_cursor"].is_null());
+
+    // Session timelines page oldest first with their own cursors.
+    for i in 0..3 {
+        let j = post_json(
+            &app,
+            "/product/add",
+            json!({
+                "user_id": "kc_u1", "memory_content": format!("timeline {}", i),
+                "session_id": "kc_s1", "async_mode": "sync"
+            }),
+        )
+        .await;
+        assert_eq!(j["code"], 200);
+    }
+    let timeline = |cursor: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            post_json(
+                &app,
+                "/product/session/timeline",
+                json!({ "session_id": "kc_s1", "user_id": "kc_u1", "limit": 2, "cursor": cursor }),
+            )
+            .await
+        }
+    };
+    let t1 = timeline(json!(null)).await;
+    assert_eq!(t1["data"]["total"], 3);
+    assert_eq!(t1["data"]["memories"][0]["memory"], "timeline 0");
+    let t_cursor = t1["data"]["next_cursor"].clone();
+    let t2 = timeline(t_cursor.clone()).await;
+    assert_eq!(t2["data"]["memories"].as_array().unwrap().len(), 1);
+    assert_eq!(t2["data"]["memories"][0]["memory"], "timeline 2");
+    assert!(t2["data"]["next_cursor"].is_null());
+
+    // Exports page with limit/cursor; cursors of another listing are rejected.
+    let e1 = get_json(&app, "/product/export?user_id=kc_u1&limit=5").await;
+    assert_eq!(e1["data"]["total_memories"], 5);
+    let e_cursor = e1["data"]["next_cursor"].as_str().unwrap().to_string();
+    let e2 = get_json(
+        &app,
+        &format!("/product/export?user_id=kc_u1&limit=5&cursor={}", e_cursor),
+    )
+    .await;
+    assert_eq!(e2["data"]["total_memories"], 3);
+    assert!(e2["data"]["next_cursor"].is_null());
+    let first: Vec<serde_json::Value> =
+        serde_json::from_str(e1["data"]["data"].as_str().unwrap()).unwrap();
+    let second: Vec<serde_json::Value> =
+        serde_json::from_str(e2["data"]["data"].as_str().unwrap()).unwrap();
+    assert!(second.iter().all(|m| !first.contains(m)));
+    let j = get_json(
+        &app,
+        &format!("/product/export?user_id=kc_u1&cursor={}", e_cursor),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let j = timeline(json!(e_cursor)).await;
+    assert_eq!(j["code"], 400);
+    let j = get_json(&app, "/product/export?user_id=kc_u1&limit=0").await;
+    assert_eq!(j["code"], 400);
+}
+
+#[tokio::test]
+async fn consistency_check_repairs_divergent_stores_and_recovery_replays_intents() {
+    use mem_types::{GraphStore, IntentLog, IntentOp, KeywordStore, VecStore, WriteIntent};
+
+    let log_path = std::env::temp_dir().join(format!("consistency-{}.jsonl", uuid::Uuid::new_v4()));
+    let log = Arc::new(mem_cube::FileIntentLog::open(&log_path).unwrap());
+    let keywords = Arc::new(mem_vec::InMemoryKeywordStore::new());
+    let cube = Arc::new(
+        NaiveMemCube::new(
+            InMemoryGraphStore::new(),
+            InMemoryVecStore::new(None),
+            MockEmbedder::new(),
+        )
+        .with_keyword_store(Some(keywords.clone()))
+        .with_intent_log(Some(log.clone())),
+    );
+    let app = entity_router(cube.clone());
+    let check = |repair: bool| {
+        let app = app.clone();
+        async move {
+            let j = post_json(
+                &app,
+                "/product/consistency/check",
+                json!({ "user_id": "cc_u1", "repair": repair }),
+            )
+            .await;
+            assert_eq!(j["code"], 200);
+            j["data"].clone()
+        }
+    };
+    let sorted = |v: &serde_json::Value| -> Vec<String> {
+        let mut ids: Vec<String> = v
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i.as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+    let divergent = [
+        "missing_vectors",
+        "orphan_vectors",
+        "stale_vectors",
+        "missing_keywords",
+        "orphan_keywords",
+        "in_flight",
+        "unchecked",
+    ];
+
+    let a = add_memory(&app, "cc_u1", "consistency alpha").await;
+    let b = add_memory(&app, "cc_u1", "consistency beta").await;
+    let c = add_memory(&app, "cc_u1", "consistency gamma").await;
+    let other = add_memory(&app, "cc_u2", "another cube").await;
+    let report = check(false).await;
+    assert_eq!(report["memories"], 3);
+    for field in divergent {
+        assert!(report[field].as_array().unwrap().is_empty(), "{}", field);
+    }
+    assert!(
+        log.pending().await.unwrap().is_empty(),
+        "writes complete their intents"
+    );
+
+    // Diverge the stores behind the cube's back.
+    cube.vec_store
+        .delete(std::slice::from_ref(&a), None)
+        .await
+        .unwrap();
+    keywords.remove(&b, Some("cc_u1")).await.unwrap();
+    let mut stale = cube
+        .vec_store
+        .get_by_ids(std::slice::from_ref(&c), None)
+        .await
+        .unwrap();
+    stale[0]
+        .payload
+        .insert("scope".to_string(), json!("WorkingMemory"));
+    cube.vec_store.upsert(&stale, None).await.unwrap();
+    let ghost = mem_types::VecStoreItem {
+        id: "ghost".to_string(),
+        vector: vec![0.1; 4],
+        payload: HashMap::from([("mem_cube_id".to_string(), json!("cc_u1"))]),
+    };
+    cube.vec_store.upsert(&[ghost], None).await.unwrap();
+    keywords
+        .index("ghost-kw", "nothing here", Some("cc_u1"))
+        .await
+        .unwrap();
+    let raw = mem_types::MemoryNode {
+        id: "raw".to_string(),
+        memory: "written to the graph only".to_string(),
+        metadata: HashMap::from([("scope".to_string(), json!("LongTermMemory"))]),
+        embedding: None,
+    };
+    cube.graph
+        .add_nodes_batch(&[raw], Some("cc_u1"))
+        .await
+        .unwrap();
+
+    let report = check(false).await;
+    assert_eq!(report["memories"], 4);
+    let mut missing = vec![a.clone(), "raw".to_string()];
+    missing.sort();
+    assert_eq!(sorted(&report["missing_vectors"]), missing);
+    assert_eq!(sorted(&report["orphan_vectors"]), vec!["ghost"]);
+    assert_eq!(sorted(&report["stale_vectors"]), vec![c.clone()]);
+    let mut missing = vec![b.clone(), "raw".to_string()];
+    missing.sort();
+    assert_eq!(sorted(&report["missing_keywords"]), missing);
+    assert_eq!(sorted(&report["orphan_keywords"]), vec!["ghost-kw"]);
+    assert_eq!(report["repaired"], 0);
+
+    let report = check(true).await;
+    assert_eq!(report["repaired"], 6);
+    let report = check(false).await;
+    assert_eq!(report["memories"], 4);
+    for field in divergent {
+        assert!(report[field].as_array().unwrap().is_empty(), "{}", field);
+    }
+    let j = post_json(
+        &app,
+        "/product/search",
+        json!({ "query": "written to the graph only", "user_id": "cc_u1", "top_k": 1 }),
+    )
+    .await;
+    assert_eq!(j["data"]["text_mem"][0]["memories"][0]["id"], "raw");
+
+    // Intents left by a crash: an unfinished add is rolled back, an update rolled forward.
+    keywords.remove(&a, Some("cc_u1")).await.unwrap();
+    cube.vec_store
+        .delete(std::slice::from_ref(&b), None)
+        .await
+        .unwrap();
+    for (op, memory_id, cube_id) in [
+        (IntentOp::Add, &a, "cc_u1"),
+        (IntentOp::Update, &b, "cc_u1"),
+        (IntentOp::Update, &other, "cc_u2"),
+    ] {
+        log.begin(&WriteIntent {
+            intent_id: uuid::Uuid::new_v4().to_string(),
+            op,
+            memory_id: memory_id.clone(),
+            cube_id: cube_id.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        })
+        .await
+        .unwrap();
+    }
+    // The report and its repair only cover the checked cube's intents.
+    let report = check(false).await;
+    let mut in_flight = vec![a.clone(), b.clone()];
+    in_flight.sort();
+    assert_eq!(sorted(&report["in_flight"]), in_flight);
+    assert!(report["missing_vectors"].as_array().unwrap().is_empty());
+
+    let report = check(true).await;
+    assert!(report["in_flight"].as_array().unwrap().is_empty());
+    let pending = log.pending().await.unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].memory_id, other);
+    assert!(cube.graph.get_node(&a, false).await.unwrap().is_none());
+    let report = check(false).await;
+    assert_eq!(report["memories"], 3);
+    for field in divergent {
+        assert!(report[field].as_array().unwrap().is_empty(), "{}", field);
+    }
+
+    assert_eq!(cube.recover_pending_writes().await.unwrap(), 1);
+    assert!(log.pending().await.unwrap().is_empty());
+
+    std::fs::remove_file(&log_path).unwrap();
+}
+
+#[tokio::test]
+async fn recovery_keeps_an_add_whose_completion_was_lost() {
+    use mem_types::{GraphStore, IntentLog, IntentOp, MemCube, WriteIntent};
+
+    let log_path =
+        std::env::temp_dir().join(format!("lost-complete-{}.jsonl", uuid::Uuid::new_v4()));
+    let log = Arc::new(mem_cube::FileIntentLog::open(&log_path).unwrap());
+    let cube = NaiveMemCube::new(
+        InMemoryGraphStore::new(),
+        InMemoryVecStore::new(None),
+        MockEmbedder::new(),
+    )
+    .with_keyword_store(Some(Arc::new(mem_vec::InMemoryKeywordStore::new())))
+    .with_intent_log(Some(log.clone()));
+    let req: mem_types::ApiAddRequest = serde_json::from_value(json!({
+        "user_id": "lc_u1", "memory_content": "fully written memory"
+    }))
+    .unwrap();
+    let res = cube.add_memories(&req).await.unwrap();
+    let id = res.data.unwrap()[0]["id"].as_str().unwrap().to_string();
+    assert!(log.pending().await.unwrap().is_empty());
+
+    // Power loss after the stores were written but before the complete line hit the disk.
+    let cube = cube.with_intent_log(None);
+    drop(log);
+    let begin = json!({ "begin": WriteIntent {
+        intent_id: uuid::Uuid::new_v4().to_string(),
+        op: IntentOp::Add,
+        memory_id: id.clone(),
+        cube_id: "lc_u1".to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    }});
+    std::fs::write(&log_path, format!("{}\n", begin)).unwrap();
+
+    let log = Arc::new(mem_cube::FileIntentLog::open(&log_path).unwrap());
+    assert_eq!(log.pending().await.unwrap().len(), 1);
+    let cube = cube.with_intent_log(Some(log.clone()));
+    assert_eq!(cube.recover_pending_writes().await.unwrap(), 1);
+    assert!(log.pending().await.unwrap().is_empty());
+    let node = cube.graph.get_node(&id, false).await.unwrap().unwrap();
+    assert_eq!(node.memory, "fully written memory");
+    std::fs::remove_file(&log_path).unwrap();
+}
+
+#[tokio::test]
+async fn auto_link_connects_similar_and_session_memories() {
+    let cube = NaiveMemCube::new(
+        InMemoryGraphStore::new(),
+        InMemoryVecStore::new(None),
+        MockEmbedder::new(),
+    )
+    .with_auto_link(Some(mem_cube::AutoLinkConfig {
+        similar_top_n: 2,
+        similar_threshold: 0.9,
+        link_sessions: true,
+    }));
+    let app = entity_router(Arc::new(cube));
+    let add = |content: &str, session: Option<&str>| {
+        let mut body = json!({
+            "user_id": "link_u1", "mem_cube_id": "link_u1",
+            "memory_content": content, "async_mode": "sync"
+        });
+        if let Some(session) = session {
+            body["session_id"] = json!(session);
+        }
+        body
+    };
+    let added = |j: serde_json::Value| j["data"][0]["id"].as_str().unwrap().to_string();
+    let neighbors = |id: &str, relation: &str| {
+        json!({
+            "memory_id": id, "user_id": "link_u1", "mem_cube_id": "link_u1",
+            "relation": relation, "direction": "outbound"
+        })
+    };
+
+    // The mock embedder maps equal text to equal vectors and anything else far apart.
+    let first = added(post_json(&app, "/product/add", add("the same fact", None)).await);
+    add_memory(&app, "link_u2", "the same fact").await;
+    let other = added(post_json(&app, "/product/add", add("something else", None)).await);
+    let second = added(post_json(&app, "/product/add", add("the same fact", None)).await);
+    let j = post_json(
+        &app,
+        "/product/graph/neighbors",
+        neighbors(&second, "similar_to"),
+    )
+    .await;
+    let items = j["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["memory"]["id"], first.as_str());
+    assert!((items[0]["edge"]["metadata"]["score"].as_f64().unwrap() - 1.0).abs() < 1e-6);
+    assert_eq!(items[0]["edge"]["metadata"]["auto_linked"], true);
+    let j = post_json(
+        &app,
+        "/product/graph/neighbors",
+        neighbors(&other, "similar_to"),
+    )
+    .await;
+    assert!(j["data"]["items"].as_array().unwrap().is_empty());
+
+    // Consecutive memories of a session are chained; deleted ones are skipped.
+    let one = added(post_json(&app, "/product/add", add("step one", Some("s1"))).await);
+    let two = added(post_json(&app, "/product/add", add("step two", Some("s1"))).await);
+    let j = post_json(&app, "/product/graph/neighbors", neighbors(&two, "follows")).await;
+    assert_eq!(j["data"]["items"][0]["memory"]["id"], one.as_str());
+    assert_eq!(
+        j["data"]["items"][0]["edge"]["metadata"]["session_id"],
+        "s1"
+    );
+    let j = post_json(
+        &app,
+        "/product/delete_memory",
+        json!({ "memory_id": two, "user_id": "link_u1", "mem_cube_id": "link_u1", "soft": true }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let three = added(post_json(&app, "/product/add", add("step three", Some("s1"))).await);
+    let j = post_json(
+        &app,
+        "/product/graph/neighbors",
+        neighbors(&three, "follows"),
+    )
+    .await;
+    let items = j["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["memory"]["id"], one.as_str());
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct ComplexMemoryFixture {
     user_id: String,
@@ -1597,3 +2561,2014 @@ async fn hybrid_search_fusion_returns_channel_results() {
         assert!(hits[0].get("fused_score").is_some());
     }
 }
+
+async fn post_json(app: &axum::Router, uri: &str, body: serde_json::Value) -> serde_json::Value {
+    let req = Request::builder()
+        .method("POST")
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+async fn add_memory(app: &axum::Router, user: &str, content: &str) -> String {
+    let j = post_json(
+        app,
+        "/product/add",
+        json!({
+            "user_id": user,
+            "mem_cube_id": user,
+            "memory_content": content,
+            "async_mode": "sync"
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    j["data"][0]["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn access_tracking_and_importance_are_visible_in_metadata() {
+    let app = test_app();
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({
+            "user_id": "sig_user",
+            "memory_content": "My passport number expires next March",
+            "info": { "importance": 0.9 }
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let memory_id = j["data"][0]["id"].as_str().unwrap().to_string();
+
+    let search = post_json(
+        &app,
+        "/product/search",
+        json!({ "user_id": "sig_user", "query": "passport expiry", "top_k": 5 }),
+    )
+    .await;
+    let hit = &search["data"]["text_mem"][0]["memories"][0];
+    assert_eq!(hit["id"], memory_id.as_str());
+    assert_eq!(hit["metadata"]["importance"], 0.9);
+
+    // Access updates are written in the background; poll until visible.
+    let mut metadata = serde_json::Value::Null;
+    for _ in 0..50 {
+        let got = post_json(
+            &app,
+            "/product/get_memory",
+            json!({ "memory_id": memory_id, "user_id": "sig_user" }),
+        )
+        .await;
+        metadata = got["data"]["metadata"].clone();
+        if metadata["access_count"].as_u64().unwrap_or(0) >= 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert!(metadata["access_count"].as_u64().unwrap() >= 1);
+    assert!(metadata["last_accessed_at"].is_string());
+
+    let updated = post_json(
+        &app,
+        "/product/update_memory",
+        json!({
+            "memory_id": memory_id,
+            "user_id": "sig_user",
+            "metadata": { "importance": 0.2 }
+        }),
+    )
+    .await;
+    assert_eq!(updated["code"], 200);
+    let got = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": memory_id, "user_id": "sig_user" }),
+    )
+    .await;
+    assert_eq!(got["data"]["metadata"]["importance"], 0.2);
+}
+
+#[tokio::test]
+async fn summaries_lead_with_retained_memories_and_keep_their_signals() {
+    let llm = Arc::new(RecordingLLM::default());
+    let app = entity_router(Arc::new(
+        NaiveMemCube::new(
+            InMemoryGraphStore::new(),
+            InMemoryVecStore::new(None),
+            MockEmbedder::new(),
+        )
+        .with_llm_client(Some(llm.clone())),
+    ));
+    let add = |content: &str, importance: f64| json!({ "user_id": "sum_user", "memory_content": content, "info": { "importance": importance } });
+    let minor = post_json(&app, "/product/add", add("Likes green tea", 0.1)).await;
+    let minor = minor["data"][0]["id"].as_str().unwrap().to_string();
+    let major = post_json(&app, "/product/add", add("Allergic to peanuts", 0.9)).await;
+    let major = major["data"][0]["id"].as_str().unwrap().to_string();
+    // Use the minor memory so its access count is carried over.
+    let mut accessed = 0;
+    for _ in 0..50 {
+        let got = post_json(
+            &app,
+            "/product/get_memory",
+            json!({ "memory_id": minor, "user_id": "sum_user" }),
+        )
+        .await;
+        accessed = got["data"]["metadata"]["access_count"]
+            .as_u64()
+            .unwrap_or(0);
+        if accessed >= 1 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    assert!(accessed >= 1);
+
+    let j = post_json(
+        &app,
+        "/product/summarize",
+        json!({ "user_id": "sum_user", "memory_ids": [minor, major] }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let prompt = llm.prompts.lock().unwrap()[0].clone();
+    assert!(prompt.find("Allergic to peanuts").unwrap() < prompt.find("Likes green tea").unwrap());
+
+    let summary = j["data"]["summary_memory_id"].as_str().unwrap();
+    let got = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": summary, "user_id": "sum_user" }),
+    )
+    .await;
+    assert_eq!(got["data"]["metadata"]["importance"], 0.9);
+    assert!(got["data"]["metadata"]["access_count"].as_u64().unwrap() >= accessed);
+}
+
+#[tokio::test]
+async fn update_with_invalid_importance_returns_400() {
+    let app = test_app();
+    let memory_id = add_memory(&app, "sig_user2", "Coffee order: flat white").await;
+    let j = post_json(
+        &app,
+        "/product/update_memory",
+        json!({
+            "memory_id": memory_id,
+            "user_id": "sig_user2",
+            "metadata": { "importance": 3 }
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+}
+
+#[tokio::test]
+async fn relevance_feedback_updates_counts_and_exports() {
+    let app = test_app();
+    let memory_id = add_memory(&app, "fb_user", "The office wifi password is on the fridge").await;
+
+    let j = post_json(
+        &app,
+        "/product/feedback",
+        json!({
+            "user_id": "fb_user",
+            "query": "wifi password",
+            "memory_ids": [memory_id],
+            "label": "helpful"
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"][0]["feedback_helpful"], 1);
+    assert_eq!(j["data"][0]["feedback_wrong"], 0);
+
+    // Other tenants cannot judge this memory.
+    let j = post_json(
+        &app,
+        "/product/feedback",
+        json!({ "user_id": "intruder", "memory_ids": [memory_id], "label": "wrong" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    let req = Request::builder()
+        .method("GET")
+        .uri("/product/feedback/export?user_id=fb_user&format=jsonl")
+        .body(Body::empty())
+        .unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    let j: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["total_events"], 1);
+    let event: serde_json::Value =
+        serde_json::from_str(j["data"]["data"].as_str().unwrap()).unwrap();
+    assert_eq!(event["memory_id"], memory_id.as_str());
+    assert_eq!(event["label"], "helpful");
+    assert_eq!(event["query"], "wifi password");
+}
+
+#[tokio::test]
+async fn feedback_add_corrects_referenced_memory() {
+    let app = test_app();
+    let original = add_memory(&app, "fb_user2", "My dentist appointment is on Monday").await;
+
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({
+            "user_id": "fb_user2",
+            "memory_content": "Correction: the dentist appointment is on Tuesday",
+            "is_feedback": true,
+            "info": { "feedback_type": "correction" },
+            "relations": [{ "memory_id": original, "relation": "corrects" }]
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let feedback_id = j["data"][0]["id"].as_str().unwrap().to_string();
+
+    let got = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": original, "user_id": "fb_user2" }),
+    )
+    .await;
+    let meta = &got["data"]["metadata"];
+    assert_eq!(meta["corrected_by"], feedback_id.as_str());
+    assert_eq!(meta["feedback_wrong"], 1);
+    assert_eq!(meta["feedback_notes"][0]["type"], "correction");
+
+    let got = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": feedback_id, "user_id": "fb_user2" }),
+    )
+    .await;
+    assert_eq!(got["data"]["metadata"]["is_feedback"], true);
+
+    // Feedback may not reference memories of another cube or deleted ones.
+    let foreign = add_memory(&app, "fb_other", "Someone else's memory").await;
+    let deleted = add_memory(&app, "fb_user2", "A memory that was deleted").await;
+    let j = post_json(
+        &app,
+        "/product/delete_memory",
+        json!({ "memory_id": deleted, "user_id": "fb_user2", "soft": true }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    for target in [&foreign, &deleted] {
+        let j = post_json(
+            &app,
+            "/product/add",
+            json!({
+                "user_id": "fb_user2",
+                "memory_content": "Correction of a memory that is not mine",
+                "is_feedback": true,
+                "info": { "feedback_type": "correction" },
+                "relations": [{ "memory_id": target, "relation": "corrects" }]
+            }),
+        )
+        .await;
+        assert_eq!(j["code"], 404);
+    }
+    let got = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": foreign, "user_id": "fb_other" }),
+    )
+    .await;
+    assert!(got["data"]["metadata"].get("corrected_by").is_none());
+
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({
+            "user_id": "fb_user2",
+            "memory_content": "whatever",
+            "is_feedback": true,
+            "info": { "feedback_type": "nonsense" }
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+}
+
+async fn get_json(app: &axum::Router, uri: &str) -> serde_json::Value {
+    let req = Request::builder()
+        .method("GET")
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    let res = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// App whose scheduler signs erasure reports with `key`.
+fn erasure_app(key: &[u8]) -> axum::Router {
+    let cube: Arc<dyn mem_types::MemCube + Send + Sync> = Arc::new(
+        NaiveMemCube::new(
+            InMemoryGraphStore::new(),
+            InMemoryVecStore::new(None),
+            MockEmbedder::new(),
+        )
+        .with_feedback_store(Some(Arc::new(InMemoryFeedbackStore::new()))),
+    );
+    let audit_store: Arc<dyn mem_types::AuditStore + Send + Sync> =
+        Arc::new(InMemoryAuditStore::new());
+    let scheduler = Arc::new(InMemoryScheduler::with_signing_key(
+        Arc::clone(&cube),
+        Some(Arc::clone(&audit_store)),
+        key.to_vec(),
+    ));
+    server::router(Arc::new(AppState {
+        cube,
+        scheduler,
+        audit_log: audit_store,
+        auth_token: None,
+    }))
+}
+
+async fn add_memory_to(app: &axum::Router, user: &str, cube: &str, content: &str) -> String {
+    let j = post_json(
+        app,
+        "/product/add",
+        json!({
+            "user_id": user,
+            "mem_cube_id": cube,
+            "memory_content": content,
+            "async_mode": "sync"
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    j["data"][0]["id"].as_str().unwrap().to_string()
+}
+
+/// Submit an erasure and wait for its signed report.
+async fn erase(app: &axum::Router, body: serde_json::Value) -> mem_types::ErasureReport {
+    let user = body["user_id"].as_str().unwrap().to_string();
+    let j = post_json(app, "/product/erase", body).await;
+    assert_eq!(j["code"], 200, "{}", j);
+    let task_id = j["data"][0]["task_id"].as_str().unwrap().to_string();
+
+    let mut status = json!(null);
+    for _ in 0..50 {
+        status = get_json(
+            app,
+            &format!(
+                "/product/scheduler/status?user_id={}&task_id={}",
+                user, task_id
+            ),
+        )
+        .await;
+        if status["data"]["status"] == "done" || status["data"]["status"] == "failed" {
+            break;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(20)).await;
+    }
+    assert_eq!(status["data"]["status"], "done", "{}", status);
+    let report: mem_types::ErasureReport =
+        serde_json::from_value(status["data"]["result_summary"].clone()).unwrap();
+    assert_eq!(report.erasure_id, task_id);
+    report
+}
+
+async fn memory_code(app: &axum::Router, user: &str, cube: &str, id: &str) -> serde_json::Value {
+    let j = post_json(
+        app,
+        "/product/get_memory",
+        json!({ "memory_id": id, "user_id": user, "mem_cube_id": cube }),
+    )
+    .await;
+    j["code"].clone()
+}
+
+#[tokio::test]
+async fn erase_user_data_removes_memories_and_returns_signed_report() {
+    let key = b"erasure-test-key".to_vec();
+    let app = erasure_app(&key);
+
+    let first = add_memory(&app, "erase_me", "My home address is 12 Elm Street").await;
+    add_memory(&app, "erase_me", "My phone number is 555-0100").await;
+    let shared = add_memory_to(&app, "erase_me", "team", "My badge is 4411").await;
+    let kept = add_memory(&app, "keep_me", "I keep my data").await;
+    let team_a = add_memory_to(&app, "keep_me", "team", "Standup is at nine").await;
+    let team_b = add_memory_to(&app, "keep_me", "team", "Retro is on Friday").await;
+    // An edge erase_me drew between keep_me's memories is erase_me's data.
+    let j = post_json(
+        &app,
+        "/product/graph/edge/add",
+        json!({ "from": team_a, "to": team_b, "relation": "related",
+                "user_id": "erase_me", "mem_cube_id": "team" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let edge_id = j["data"]["id"].as_str().unwrap().to_string();
+    let j = post_json(
+        &app,
+        "/product/feedback",
+        json!({ "user_id": "erase_me", "memory_ids": [first], "label": "helpful" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+
+    // No cube named: every cube erase_me wrote to, found by the server.
+    let report = erase(&app, json!({ "user_id": "erase_me" })).await;
+    assert_eq!(report.cube_id, None);
+    assert!(!report.whole_cube);
+    assert_eq!(
+        report.cubes,
+        vec!["erase_me".to_string(), "team".to_string()]
+    );
+    assert_eq!(report.counts.memories, 3);
+    assert_eq!(report.counts.vectors, 3);
+    assert_eq!(report.counts.feedback_events, 1);
+    assert!(report.counts.audit_events_redacted >= 4);
+    assert_eq!(report.algorithm, "HMAC-SHA256");
+    assert!(mem_scheduler::verify_erasure_report(&report, &key));
+    let mut tampered = report.clone();
+    tampered.counts.memories = 0;
+    assert!(!mem_scheduler::verify_erasure_report(&tampered, &key));
+
+    let j = post_json(
+        &app,
+        "/product/search",
+        json!({ "query": "address phone", "user_id": "erase_me" }),
+    )
+    .await;
+    let memories = j["data"]["text_mem"][0]["memories"].as_array().unwrap();
+    assert!(memories.is_empty());
+    assert_eq!(memory_code(&app, "erase_me", "erase_me", &first).await, 404);
+    assert_eq!(memory_code(&app, "keep_me", "team", &shared).await, 404);
+
+    let j = get_json(&app, "/product/audit/list?user_id=erase_me").await;
+    let events = j["data"].as_array().unwrap();
+    assert!(events.iter().any(|e| e["kind"] == "erase"));
+    for e in events {
+        if let Some(summary) = e.get("input_summary") {
+            assert_eq!(summary, "[redacted]");
+        }
+    }
+
+    // Other users' data, in their own cube and in the shared one, is untouched.
+    assert_eq!(memory_code(&app, "keep_me", "keep_me", &kept).await, 200);
+    assert_eq!(memory_code(&app, "keep_me", "team", &team_a).await, 200);
+    assert_eq!(memory_code(&app, "keep_me", "team", &team_b).await, 200);
+    let j = post_json(
+        &app,
+        "/product/graph/edge/get",
+        json!({ "edge_id": edge_id, "user_id": "keep_me", "mem_cube_id": "team" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+    let j = get_json(&app, "/product/audit/list?user_id=keep_me").await;
+    let events = j["data"].as_array().unwrap();
+    assert!(events
+        .iter()
+        .any(|e| e.get("input_summary").is_some_and(|s| s != "[redacted]")));
+}
+
+#[tokio::test]
+async fn erase_whole_cube_removes_every_users_data() {
+    let app = erasure_app(b"erasure-test-key");
+    let mine = add_memory_to(&app, "owner", "team", "Standup is at nine").await;
+    let theirs = add_memory_to(&app, "other", "team", "Retro is on Friday").await;
+    let elsewhere = add_memory(&app, "other", "Lunch at noon").await;
+
+    let j = post_json(
+        &app,
+        "/product/erase",
+        json!({ "user_id": "owner", "whole_cube": true }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+
+    // Only the named cube, and only what owner wrote there.
+    let report = erase(&app, json!({ "user_id": "owner", "mem_cube_id": "team" })).await;
+    assert_eq!(report.cubes, vec!["team".to_string()]);
+    assert_eq!(report.counts.memories, 1);
+    assert_eq!(memory_code(&app, "owner", "team", &mine).await, 404);
+    assert_eq!(memory_code(&app, "other", "team", &theirs).await, 200);
+
+    let report = erase(
+        &app,
+        json!({ "user_id": "owner", "mem_cube_id": "team", "whole_cube": true }),
+    )
+    .await;
+    assert!(report.whole_cube);
+    assert_eq!(report.counts.memories, 1);
+    assert_eq!(memory_code(&app, "other", "team", &theirs).await, 404);
+    assert_eq!(memory_code(&app, "other", "other", &elsewhere).await, 200);
+}
+
+#[tokio::test]
+async fn pii_policy_masks_or_rejects_on_ingest() {
+    use mem_types::GraphStore;
+
+    let key = [9u8; 32];
+    let naive = NaiveMemCube::new(
+        InMemoryGraphStore::new(),
+        InMemoryVecStore::new(None),
+        MockEmbedder::new(),
+    )
+    .with_pii_policy(
+        "pii_mask",
+        mem_types::PiiPolicy {
+            action: mem_types::PiiAction::Mask,
+            encrypt_original: true,
+        },
+    )
+    .with_pii_policy(
+        "pii_reject",
+        mem_types::PiiPolicy {
+            action: mem_types::PiiAction::Reject,
+            encrypt_original: false,
+        },
+    )
+    .with_pii_key(Some(key));
+    let graph = Arc::clone(&naive.graph);
+    let cube: Arc<dyn mem_types::MemCube + Send + Sync> = Arc::new(naive);
+    let audit_store: Arc<dyn mem_types::AuditStore + Send + Sync> =
+        Arc::new(InMemoryAuditStore::new());
+    let scheduler = Arc::new(InMemoryScheduler::new(
+        Arc::clone(&cube),
+        Some(Arc::clone(&audit_store)),
+    ));
+    let app = server::router(Arc::new(AppState {
+        cube,
+        scheduler,
+        audit_log: audit_store,
+        auth_token: None,
+    }));
+
+    let text = "Reach me at jane@example.com or 555-0100";
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({ "user_id": "pii_mask", "memory_content": text }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"][0]["memory"], "Reach me at [EMAIL] or [PHONE]");
+    let id = j["data"][0]["id"].as_str().unwrap().to_string();
+
+    let j = post_json(
+        &app,
+        "/product/search",
+        json!({ "query": "reach me", "user_id": "pii_mask" }),
+    )
+    .await;
+    let hit = &j["data"]["text_mem"][0]["memories"][0];
+    assert_eq!(hit["memory"], "Reach me at [EMAIL] or [PHONE]");
+    assert_eq!(hit["metadata"]["pii_kinds"], json!(["email", "phone"]));
+    assert!(hit["metadata"].get("pii_original").is_none());
+
+    // The encrypted original stays in the store; no read path returns it.
+    let j = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": id, "user_id": "pii_mask" }),
+    )
+    .await;
+    assert_eq!(j["data"]["memory"], "Reach me at [EMAIL] or [PHONE]");
+    assert!(j["data"]["metadata"].get("pii_original").is_none());
+    let stored = graph.get_node(&id, false).await.unwrap().unwrap();
+    let encrypted = stored.metadata["pii_original"].as_str().unwrap();
+    assert!(!encrypted.contains("jane"));
+    assert_eq!(
+        mem_cube::decrypt_pii_original(encrypted, &key).unwrap(),
+        text
+    );
+
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({ "user_id": "pii_reject", "memory_content": text }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+
+    let j = post_json(
+        &app,
+        "/product/batch/add",
+        json!({
+            "user_id": "pii_reject",
+            "memories": [
+                { "memory": "I like tea" },
+                { "memory": "Email me: bob@example.org" }
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(j["data"]["successful"].as_array().unwrap().len(), 1);
+    assert_eq!(j["data"]["failed"][0]["index"], 1);
+
+    // Caller-supplied metadata gets the same policy as the text.
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({
+            "user_id": "pii_mask",
+            "memory_content": "Tagged memory",
+            "custom_tags": ["owner jane@example.com"],
+            "info": { "contact": { "phone": "555-0100" } }
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let tagged = j["data"][0]["id"].as_str().unwrap().to_string();
+    let j = post_json(
+        &app,
+        "/product/update_memory",
+        json!({ "memory_id": tagged, "user_id": "pii_mask",
+                "metadata": { "note": "or mail bob@example.org" } }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let j = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": tagged, "user_id": "pii_mask" }),
+    )
+    .await;
+    let metadata = &j["data"]["metadata"];
+    assert_eq!(metadata["custom_tags"], json!(["owner [EMAIL]"]));
+    assert_eq!(metadata["contact"]["phone"], "[PHONE]");
+    assert_eq!(metadata["note"], "or mail [EMAIL]");
+    assert_eq!(metadata["pii_kinds"], json!(["email", "phone"]));
+
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({ "user_id": "pii_reject", "memory_content": "I like tea",
+                "chat_history": [{ "role": "user", "content": "call 555-0100" }] }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+    let j = post_json(
+        &app,
+        "/product/batch/add",
+        json!({
+            "user_id": "pii_reject",
+            "memories": [{ "memory": "I like tea", "metadata": { "email": "bob@example.org" } }]
+        }),
+    )
+    .await;
+    assert_eq!(j["data"]["failed"][0]["index"], 0);
+
+    // Text stored before the policy was set is redacted on every read path.
+    let legacy = HashMap::from([
+        ("memory".to_string(), json!(text)),
+        ("note".to_string(), json!("bob@example.org")),
+    ]);
+    graph
+        .update_node(&tagged, &legacy, Some("pii_mask"))
+        .await
+        .unwrap();
+    let j = post_json(
+        &app,
+        "/product/get_memory",
+        json!({ "memory_id": tagged, "user_id": "pii_mask" }),
+    )
+    .await;
+    assert_eq!(j["data"]["memory"], "Reach me at [EMAIL] or [PHONE]");
+    assert_eq!(j["data"]["metadata"]["note"], "[EMAIL]");
+    let j = get_json(&app, "/product/export?user_id=pii_mask").await;
+    let exported = j.to_string();
+    assert!(exported.contains("[EMAIL]"));
+    assert!(!exported.contains("jane@example.com"));
+    assert!(!exported.contains("bob@example.org"));
+    assert!(!exported.contains("pii_original"));
+
+    // Cubes without a policy keep text verbatim.
+    let j = post_json(
+        &app,
+        "/product/add",
+        json!({ "user_id": "no_policy", "memory_content": text }),
+    )
+    .await;
+    assert_eq!(j["data"][0]["memory"], text);
+}
+
+/// Test extractor: every capitalized word is a person; "X works at Y" yields a relation.
+struct CapitalizedExtractor;
+
+#[async_trait::async_trait]
+impl mem_embed::EntityExtractor for CapitalizedExtractor {
+    async fn extract(
+        &self,
+        text: &str,
+        _config: mem_types::ExtractionConfig,
+    ) -> Result<mem_types::ExtractionResult, mem_embed::ExtractorError> {
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        let entities = words
+            .iter()
+            .filter(|w| w.chars().next().is_some_and(|c| c.is_uppercase()))
+            .map(|w| {
+                let start = text.find(w).unwrap_or(0);
+                mem_types::ExtractedEntity::new(
+                    w.to_string(),
+                    mem_types::EntityType::Person,
+                    mem_types::TextPosition::new(start, start + w.len()),
+                    0.9,
+                )
+            })
+            .collect();
+        let relations = words
+            .windows(4)
+            .filter(|w| w[1] == "works" && w[2] == "at")
+            .map(|w| mem_types::ExtractedRelation {
+                source_text: w[0].to_string(),
+                target_text: w[3].to_string(),
+                relation_type: mem_types::EntityRelationType::WorksAt,
+                confidence: 0.9,
+            })
+            .collect();
+        Ok(mem_types::ExtractionResult {
+            entities,
+            relations,
+            summary: None,
+            processing_time_ms: 0,
+        })
+    }
+
+    async fn extract_batch(
+        &self,
+        texts: &[String],
+        config: mem_types::ExtractionConfig,
+    ) -> Result<Vec<mem_types::ExtractionResult>, mem_embed::ExtractorError> {
+        let mut out = Vec::new();
+        for text in texts {
+            out.push(self.extract(text, config.clone()).await?);
+        }
+        Ok(out)
+    }
+
+    fn supported_types(&self) -> Vec<mem_types::EntityType> {
+        vec![mem_types::EntityType::Person]
+    }
+
+    fn name(&self) -> &str {
+        "capitalized"
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+}
+
+type TestEntityCube =
+    mem_cube::EntityAwareMemCube<InMemoryGraphStore, InMemoryVecStore, MockEmbedder>;
+
+fn entity_cube(store: Option<Arc<dyn mem_types::EntityStore + Send + Sync>>) -> TestEntityCube {
+    entity_cube_with(Arc::new(CapitalizedExtractor), store)
+}
+
+fn entity_cube_with(
+    extractor: Arc<dyn mem_embed::EntityExtractor>,
+    store: Option<Arc<dyn mem_types::EntityStore + Send + Sync>>,
+) -> TestEntityCube {
+    let inner = NaiveMemCube::new(
+        InMemoryGraphStore::new(),
+        InMemoryVecStore::new(None),
+        MockEmbedder::new(),
+    );
+    mem_cube::EntityAwareMemCube::with_extractor(
+        inner,
+        extractor,
+        mem_graph::EntityKnowledgeGraph::new(),
+        Some(mem_cube::EntityCubeConfig {
+            async_extraction: false,
+            ..Default::default()
+        }),
+    )
+    .with_entity_store(store)
+}
+
+fn entity_app() -> axum::Router {
+    entity_router(Arc::new(entity_cube(None)))
+}
+
+fn entity_router(cube: Arc<dyn mem_types::MemCube + Send + Sync>) -> axum::Router {
+    let audit_store: Arc<dyn mem_types::AuditStore + Send + Sync> =
+        Arc::new(InMemoryAuditStore::new());
+    let scheduler = Arc::new(InMemoryScheduler::new(
+        Arc::clone(&cube),
+        Some(Arc::clone(&audit_store)),
+    ));
+    server::router(Arc::new(AppState {
+        cube,
+        scheduler,
+        audit_log: audit_store,
+        auth_token: None,
+    }))
+}
+
+#[tokio::test]
+async fn entity_endpoints_expose_extracted_entities() {
+    let app = entity_app();
+    let memory_id = add_memory(&app, "ent_user", "Alice works at Acme").await;
+    add_memory(&app, "ent_user", "Alice met Bob").await;
+
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "ali", "user_id": "ent_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    assert_eq!(j["total_count"], 1);
+    let alice_id = j["entities"][0]["id"].as_str().unwrap().to_string();
+    assert_eq!(j["entities"][0]["name"], "alice");
+
+    let j = post_json(
+        &app,
+        "/product/entity/get",
+        json!({ "entity_id": alice_id, "user_id": "ent_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["memory_ids"].as_array().unwrap().len(), 2);
+    let j = post_json(
+        &app,
+        "/product/entity/get",
+        json!({ "entity_id": "missing", "user_id": "ent_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    let j = post_json(
+        &app,
+        "/product/entity/list_by_type",
+        json!({ "entity_type": "person", "user_id": "ent_user", "limit": 2 }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["entities"].as_array().unwrap().len(), 2);
+    let cursor = j["next_cursor"].as_str().unwrap().to_string();
+    let j = post_json(
+        &app,
+        "/product/entity/list_by_type",
+        json!({ "entity_type": "person", "user_id": "ent_user", "limit": 2, "cursor": cursor }),
+    )
+    .await;
+    assert_eq!(j["entities"].as_array().unwrap().len(), 1);
+    assert_eq!(j["entities"][0]["name"], "bob");
+    assert!(j["next_cursor"].is_null());
+    let j = post_json(
+        &app,
+        "/product/entity/list_by_type",
+        json!({ "entity_type": "person", "user_id": "ent_user", "cursor": "bogus" }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": alice_id, "user_id": "ent_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["relations"][0]["relation_type"], "works_at");
+    assert_eq!(j["relations"][0]["entity"]["name"], "acme");
+
+    let j = post_json(
+        &app,
+        "/product/entity/memory_entities",
+        json!({ "memory_id": memory_id, "user_id": "ent_user" }),
+    )
+    .await;
+    assert_eq!(j["total_count"], 2);
+
+    let j = get_json(&app, "/product/entity/stats?user_id=ent_user").await;
+    assert_eq!(j["data"]["total_entities"], 3);
+    assert_eq!(j["data"]["total_relations"], 1);
+
+    let j = post_json(
+        &app,
+        "/product/entity/search_memories",
+        json!({ "entity_name": "alice", "user_id": "ent_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let memories = j["data"]["text_mem"][0]["memories"].as_array().unwrap();
+    assert_eq!(memories.len(), 2);
+    assert_eq!(memories[0]["metadata"]["matched_entities"][0], "alice");
+
+    // Memories of other cubes are never returned.
+    let j = post_json(
+        &app,
+        "/product/entity/search_memories",
+        json!({ "entity_name": "alice", "user_id": "someone_else" }),
+    )
+    .await;
+    assert!(j["data"]["text_mem"][0]["memories"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn entity_endpoints_without_entity_cube_report_unsupported() {
+    let app = test_app();
+    let j = get_json(&app, "/product/entity/stats?user_id=u1").await;
+    assert_eq!(j["code"], 500);
+    assert!(j["message"]
+        .as_str()
+        .unwrap()
+        .contains("entities not supported"));
+}
+
+#[tokio::test]
+async fn entity_graph_is_partitioned_per_cube() {
+    let app = entity_app();
+    add_memory(&app, "tenant_a", "Alice works at Acme").await;
+    add_memory(&app, "tenant_b", "Alice works at Globex").await;
+
+    let a = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alice", "user_id": "tenant_a" }),
+    )
+    .await;
+    let b = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alice", "user_id": "tenant_b" }),
+    )
+    .await;
+    assert_eq!(a["total_count"], 1);
+    assert_eq!(b["total_count"], 1);
+    let alice_a = a["entities"][0]["id"].as_str().unwrap().to_string();
+    let alice_b = b["entities"][0]["id"].as_str().unwrap().to_string();
+    assert_ne!(alice_a, alice_b);
+    assert_eq!(a["entities"][0]["memory_ids"].as_array().unwrap().len(), 1);
+
+    // Each tenant only sees its own employer.
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": alice_a, "user_id": "tenant_a" }),
+    )
+    .await;
+    assert_eq!(j["relations"].as_array().unwrap().len(), 1);
+    assert_eq!(j["relations"][0]["entity"]["name"], "acme");
+
+    // Entity ids of another cube are invisible.
+    let j = post_json(
+        &app,
+        "/product/entity/get",
+        json!({ "entity_id": alice_b, "user_id": "tenant_a" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": alice_b, "user_id": "tenant_a" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    let j = get_json(&app, "/product/entity/stats?user_id=tenant_a").await;
+    assert_eq!(j["data"]["total_entities"], 2);
+    assert_eq!(j["data"]["total_relations"], 1);
+    let j = get_json(&app, "/product/entity/stats").await;
+    assert_eq!(j["code"], 400);
+}
+
+#[tokio::test]
+async fn entity_store_survives_restart() {
+    let store: Arc<dyn mem_types::EntityStore + Send + Sync> =
+        Arc::new(mem_graph::InMemoryEntityStore::new());
+    let app = entity_router(Arc::new(entity_cube(Some(Arc::clone(&store)))));
+    add_memory(&app, "persist_user", "Alice works at Acme").await;
+    let memory_id = add_memory(&app, "persist_user", "Alice met Bob").await;
+
+    let j = post_json(
+        &app,
+        "/product/delete_memory",
+        json!({ "memory_id": memory_id, "user_id": "persist_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+
+    // A fresh cube over the same store sees the same entities and relations.
+    let cube = entity_cube(Some(Arc::clone(&store)));
+    assert_eq!(cube.load_entities().await.unwrap(), 2);
+    let alice = cube
+        .get_entity_by_name("persist_user", "alice")
+        .await
+        .expect("alice reloaded");
+    assert_eq!(alice.memory_ids.len(), 1);
+    assert!(cube
+        .get_entity_by_name("persist_user", "bob")
+        .await
+        .is_none());
+    let app = entity_router(Arc::new(cube));
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": alice.id, "user_id": "persist_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["relations"][0]["entity"]["name"], "acme");
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "acme", "user_id": "persist_user" }),
+    )
+    .await;
+    assert_eq!(j["total_count"], 1);
+
+    let path = std::env::temp_dir().join(format!("entities-{}.json", uuid::Uuid::new_v4()));
+    entity_cube(Some(store))
+        .export_snapshot(&path)
+        .await
+        .unwrap();
+    let snapshot: mem_types::EntityKgSnapshot =
+        serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert!(snapshot.entities.is_empty());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn entity_graph_follows_memory_update_and_forget() {
+    let app = entity_app();
+    let first = add_memory(&app, "kg_user", "Alice works at Acme").await;
+    let second = add_memory(&app, "kg_user", "Alice met Bob").await;
+    let third = add_memory(&app, "kg_user", "Dave met Bob").await;
+
+    let entity = |j: &serde_json::Value, name: &str| {
+        j["entities"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["name"] == name)
+            .cloned()
+    };
+    let search = |q: &'static str| {
+        let app = app.clone();
+        async move {
+            post_json(
+                &app,
+                "/product/entity/search",
+                json!({ "query": q, "user_id": "kg_user" }),
+            )
+            .await
+        }
+    };
+    let alice = entity(&search("alice").await, "alice").unwrap();
+    assert_eq!(alice["occurrence_count"], 2);
+
+    // Rewriting the text relinks the memory: Acme is gone, Globex appears.
+    let j = post_json(
+        &app,
+        "/product/update_memory",
+        json!({ "memory_id": first, "user_id": "kg_user", "memory": "Alice works at Globex" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert!(entity(&search("acme").await, "acme").is_none());
+    let globex = entity(&search("globex").await, "globex").unwrap();
+    assert_eq!(globex["memory_ids"], json!([first]));
+    let alice = entity(&search("alice").await, "alice").unwrap();
+    assert_eq!(alice["occurrence_count"], 2);
+
+    // Forgetting drops the association and the counts follow.
+    let j = post_json(
+        &app,
+        "/product/delete_memory",
+        json!({ "memory_id": second, "user_id": "kg_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let alice = entity(&search("alice").await, "alice").unwrap();
+    assert_eq!(alice["occurrence_count"], 1);
+    assert_eq!(alice["memory_ids"], json!([first]));
+    let bob = entity(&search("bob").await, "bob").unwrap();
+    assert_eq!(bob["memory_ids"], json!([third]));
+
+    // Batch delete garbage-collects entities left without memories.
+    let j = post_json(
+        &app,
+        "/product/batch/delete",
+        json!({ "memory_ids": [first, third], "user_id": "kg_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    for name in ["alice", "bob", "dave", "globex"] {
+        assert!(search(name).await["entities"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+}
+
+#[tokio::test]
+async fn rule_based_extractor_feeds_entity_endpoints() {
+    use mem_embed::{GazetteerEntry, RuleBasedExtractor};
+    use mem_types::EntityType;
+
+    let extractor = RuleBasedExtractor::new()
+        .with_gazetteer(vec![
+            GazetteerEntry::new("Robert Paulson", EntityType::Person).with_alias("Bob"),
+            GazetteerEntry::new("Initech", EntityType::Organization),
+        ])
+        .unwrap();
+    let app = entity_router(Arc::new(entity_cube_with(Arc::new(extractor), None)));
+    add_memory(
+        &app,
+        "rules_user",
+        "Bob works for Initech, mail bob@initech.com",
+    )
+    .await;
+    add_memory(&app, "rules_user", "Robert Paulson called on 2025-01-31").await;
+
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "robert", "user_id": "rules_user" }),
+    )
+    .await;
+    assert_eq!(j["total_count"], 1);
+    assert_eq!(j["entities"][0]["occurrence_count"], 2);
+    let bob = j["entities"][0]["id"].as_str().unwrap().to_string();
+
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": bob, "user_id": "rules_user" }),
+    )
+    .await;
+    assert_eq!(j["relations"][0]["relation_type"], "works_at");
+    assert_eq!(j["relations"][0]["entity"]["name"], "initech");
+
+    let j = post_json(
+        &app,
+        "/product/entity/list_by_type",
+        json!({ "entity_type": "email", "user_id": "rules_user" }),
+    )
+    .await;
+    assert_eq!(j["entities"][0]["name"], "bob@initech.com");
+}
+
+#[tokio::test]
+async fn entity_list_pages_do_not_shift_when_counts_change() {
+    let app = entity_app();
+    for text in ["Carol met Dave", "Erin met Frank"] {
+        add_memory(&app, "page_user", text).await;
+    }
+    let page = |cursor: Option<String>| {
+        let app = app.clone();
+        async move {
+            let j = post_json(
+                &app,
+                "/product/entity/list_by_type",
+                json!({
+                    "entity_type": "person", "user_id": "page_user", "limit": 2,
+                    "cursor": cursor
+                }),
+            )
+            .await;
+            assert_eq!(j["code"], 200, "{}", j);
+            let names: Vec<String> = j["entities"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["name"].as_str().unwrap().to_string())
+                .collect();
+            (names, j["next_cursor"].as_str().map(str::to_string))
+        }
+    };
+    let (mut seen, cursor) = page(None).await;
+    assert_eq!(seen.len(), 2);
+
+    // Entities not listed yet become the most frequent, and a new one appears.
+    for text in ["Erin met Frank again", "Frank and Erin met Gina"] {
+        add_memory(&app, "page_user", text).await;
+    }
+    let mut cursor = cursor;
+    while let Some(c) = cursor {
+        let (names, next) = page(Some(c)).await;
+        seen.extend(names);
+        cursor = next;
+    }
+    let mut sorted = seen.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted.len(), seen.len(), "duplicates in {:?}", seen);
+    for name in ["carol", "dave", "erin", "frank", "gina"] {
+        assert!(
+            seen.contains(&name.to_string()),
+            "{} skipped in {:?}",
+            name,
+            seen
+        );
+    }
+}
+
+#[tokio::test]
+async fn entities_are_extracted_from_redacted_text() {
+    use mem_embed::RuleBasedExtractor;
+
+    let inner = NaiveMemCube::new(
+        InMemoryGraphStore::new(),
+        InMemoryVecStore::new(None),
+        MockEmbedder::new(),
+    )
+    .with_pii_policy(
+        "pii_entities",
+        mem_types::PiiPolicy {
+            action: mem_types::PiiAction::Mask,
+            encrypt_original: false,
+        },
+    );
+    let cube = mem_cube::EntityAwareMemCube::with_extractor(
+        inner,
+        Arc::new(RuleBasedExtractor::new()),
+        mem_graph::EntityKnowledgeGraph::new(),
+        Some(mem_cube::EntityCubeConfig {
+            async_extraction: false,
+            ..Default::default()
+        }),
+    );
+    let app = entity_router(Arc::new(cube));
+    let id = add_memory(
+        &app,
+        "pii_entities",
+        "Mail jane@example.com or call +49 30 1234567",
+    )
+    .await;
+    let j = post_json(
+        &app,
+        "/product/update_memory",
+        json!({
+            "memory_id": id, "user_id": "pii_entities",
+            "memory": "Now mail bob@example.org or call +1 415 5550100"
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+
+    for entity_type in ["email", "phone"] {
+        let j = post_json(
+            &app,
+            "/product/entity/list_by_type",
+            json!({ "entity_type": entity_type, "user_id": "pii_entities" }),
+        )
+        .await;
+        for entity in j["entities"].as_array().unwrap() {
+            let name = entity["name"].as_str().unwrap();
+            assert!(
+                !name.contains('@') && !name.chars().any(|c| c.is_ascii_digit()),
+                "{} entity holds a raw value: {}",
+                entity_type,
+                name
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn entity_merge_candidates_merge_and_split() {
+    use mem_embed::{GazetteerEntry, RuleBasedExtractor};
+    use mem_types::EntityType;
+
+    let extractor = RuleBasedExtractor::new()
+        .with_gazetteer(vec![
+            GazetteerEntry::new("Bob Smith", EntityType::Person),
+            GazetteerEntry::new("B. Smith", EntityType::Person),
+            GazetteerEntry::new("Robert Smith", EntityType::Person),
+            GazetteerEntry::new("Acme", EntityType::Organization),
+        ])
+        .unwrap();
+    let app = entity_router(Arc::new(entity_cube_with(Arc::new(extractor), None)));
+    add_memory(&app, "er_user", "Bob Smith works at Acme").await;
+    let second = add_memory(&app, "er_user", "B. Smith works at Acme").await;
+    add_memory(&app, "er_user", "Robert Smith called").await;
+
+    let j = post_json(
+        &app,
+        "/product/entity/merge_candidates",
+        json!({ "user_id": "er_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let candidates = j["data"]["candidates"].as_array().unwrap();
+    assert_eq!(candidates.len(), 1, "{}", j);
+    let mut names = [
+        candidates[0]["keep_name"].as_str().unwrap(),
+        candidates[0]["merge_name"].as_str().unwrap(),
+    ];
+    names.sort();
+    assert_eq!(names, ["b. smith", "bob smith"]);
+    assert!(candidates[0]["confidence"].as_f64().unwrap() > 0.9);
+    assert_eq!(candidates[0]["context_similarity"], 1.0);
+    let keep_id = candidates[0]["keep_id"].as_str().unwrap().to_string();
+    let merge_id = candidates[0]["merge_id"].as_str().unwrap().to_string();
+
+    // Merging is scoped to the cube.
+    let j = post_json(
+        &app,
+        "/product/entity/merge",
+        json!({ "keep_id": keep_id, "merge_id": merge_id, "user_id": "other_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    let j = post_json(
+        &app,
+        "/product/entity/merge",
+        json!({ "keep_id": keep_id, "merge_id": merge_id, "user_id": "er_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    assert_eq!(j["data"]["id"], keep_id);
+    assert_eq!(j["data"]["memory_ids"].as_array().unwrap().len(), 2);
+    assert_eq!(j["data"]["occurrence_count"], 2);
+
+    let j = post_json(
+        &app,
+        "/product/entity/get",
+        json!({ "entity_id": merge_id, "user_id": "er_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": keep_id, "user_id": "er_user" }),
+    )
+    .await;
+    assert_eq!(j["relations"].as_array().unwrap().len(), 1);
+    let acme_id = j["relations"][0]["entity"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let j = post_json(
+        &app,
+        "/product/entity/memory_entities",
+        json!({ "memory_id": second, "user_id": "er_user" }),
+    )
+    .await;
+    assert!(j["entities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|e| e["id"] == keep_id));
+
+    // Split the second mention back out, taking the Acme relation with it.
+    let j = post_json(
+        &app,
+        "/product/entity/split",
+        json!({
+            "entity_id": keep_id,
+            "user_id": "er_user",
+            "name": "Barbara Smith",
+            "memory_ids": [second],
+            "relation_targets": [acme_id],
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    let split_id = j["data"]["id"].as_str().unwrap().to_string();
+    assert_eq!(j["data"]["name"], "barbara smith");
+    assert_eq!(j["data"]["memory_ids"], json!([second]));
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": split_id, "user_id": "er_user" }),
+    )
+    .await;
+    assert_eq!(j["relations"][0]["entity"]["id"], acme_id);
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": keep_id, "user_id": "er_user" }),
+    )
+    .await;
+    assert!(j["relations"].as_array().unwrap().is_empty());
+
+    // The source must keep at least one memory, and names stay unique per cube.
+    let j = post_json(
+        &app,
+        "/product/entity/split",
+        json!({ "entity_id": split_id, "user_id": "er_user", "name": "X", "memory_ids": [second] }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+    let j = post_json(
+        &app,
+        "/product/entity/split",
+        json!({ "entity_id": keep_id, "user_id": "er_user", "name": "Acme", "memory_ids": [] }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+}
+
+#[tokio::test]
+async fn entity_fuzzy_search_scores_and_orders_hits() {
+    use mem_embed::{GazetteerEntry, RuleBasedExtractor};
+    use mem_types::EntityType;
+
+    let extractor = RuleBasedExtractor::new()
+        .with_gazetteer(vec![
+            GazetteerEntry::new("Alice Johnson", EntityType::Person),
+            GazetteerEntry::new("Ally", EntityType::Person),
+            GazetteerEntry::new("Alicia Keys", EntityType::Person),
+            GazetteerEntry::new("Acme", EntityType::Organization),
+        ])
+        .unwrap();
+    let app = entity_router(Arc::new(entity_cube_with(Arc::new(extractor), None)));
+    add_memory(&app, "fz_user", "Alice Johnson joined Acme").await;
+    add_memory(&app, "fz_user", "Alicia Keys played a concert").await;
+
+    // Plain substring search misses the typo.
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alcie", "user_id": "fz_user" }),
+    )
+    .await;
+    assert_eq!(j["total_count"], 0);
+
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alcie", "user_id": "fz_user", "fuzzy": true }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    let hits = j["entities"].as_array().unwrap();
+    assert!(!hits.is_empty(), "{}", j);
+    assert_eq!(hits[0]["name"], "alice johnson");
+    let scores: Vec<f64> = hits.iter().map(|h| h["score"].as_f64().unwrap()).collect();
+    assert!(scores.windows(2).all(|w| w[0] >= w[1]), "{:?}", scores);
+    assert!(hits.iter().all(|h| h["name"] != "acme"));
+
+    // Sorted before truncation, so the limit keeps the best hit.
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alicia", "user_id": "fz_user", "fuzzy": true, "limit": 1 }),
+    )
+    .await;
+    assert_eq!(j["total_count"], 1);
+    assert_eq!(j["entities"][0]["name"], "alicia keys");
+    assert_eq!(j["entities"][0]["score"], 0.9 + 0.1 * 6.0 / 11.0);
+
+    // Names merged away live on as variants and are matched too; the type filter applies.
+    let find = |j: &serde_json::Value| j["entities"][0]["id"].as_str().unwrap().to_string();
+    add_memory(&app, "fz_user", "Ally sent the slides").await;
+    let alice = find(
+        &post_json(
+            &app,
+            "/product/entity/search",
+            json!({ "query": "alice johnson", "user_id": "fz_user" }),
+        )
+        .await,
+    );
+    let ally = find(
+        &post_json(
+            &app,
+            "/product/entity/search",
+            json!({ "query": "ally", "user_id": "fz_user" }),
+        )
+        .await,
+    );
+    let j = post_json(
+        &app,
+        "/product/entity/merge",
+        json!({ "keep_id": alice, "merge_id": ally, "user_id": "fz_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "ally", "user_id": "fz_user", "fuzzy": true, "entity_type": "person" }),
+    )
+    .await;
+    assert_eq!(j["entities"][0]["id"], alice);
+    assert_eq!(j["entities"][0]["score"], 1.0);
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "ally", "user_id": "fz_user", "fuzzy": true, "entity_type": "organization" }),
+    )
+    .await;
+    assert_eq!(j["total_count"], 0);
+
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alcie", "user_id": "fz_user", "fuzzy": true, "min_score": 1.5 }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+}
+
+#[tokio::test]
+async fn entity_path_and_subgraph_traverse_relations() {
+    use mem_embed::{GazetteerEntry, RuleBasedExtractor};
+    use mem_types::EntityType;
+
+    let extractor = RuleBasedExtractor::new()
+        .with_gazetteer(vec![
+            GazetteerEntry::new("Alice", EntityType::Person),
+            GazetteerEntry::new("Bob", EntityType::Person),
+            GazetteerEntry::new("Acme", EntityType::Organization),
+            GazetteerEntry::new("Berlin", EntityType::Location),
+        ])
+        .unwrap();
+    let app = entity_router(Arc::new(entity_cube_with(Arc::new(extractor), None)));
+    add_memory(&app, "tr_user", "Alice works at Acme").await;
+    add_memory(&app, "tr_user", "Bob works at Acme").await;
+    add_memory(&app, "tr_user", "Acme is located in Berlin").await;
+
+    let mut ids = HashMap::new();
+    for name in ["alice", "bob", "acme", "berlin"] {
+        let j = post_json(
+            &app,
+            "/product/entity/search",
+            json!({ "query": name, "user_id": "tr_user" }),
+        )
+        .await;
+        ids.insert(name, j["entities"][0]["id"].as_str().unwrap().to_string());
+    }
+
+    // alice -works_at-> acme -located_in-> berlin
+    let j = post_json(
+        &app,
+        "/product/entity/path",
+        json!({ "source_entity_id": ids["alice"], "target_entity_id": ids["berlin"], "user_id": "tr_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    assert_eq!(j["data"]["hops"], 2);
+    let names: Vec<&str> = j["data"]["entities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["alice", "acme", "berlin"]);
+    assert_eq!(j["data"]["relations"][1]["relation_type"], "located_in");
+
+    // alice and bob only meet at acme, against the direction of bob's relation.
+    let j = post_json(
+        &app,
+        "/product/entity/path",
+        json!({ "source_entity_id": ids["alice"], "target_entity_id": ids["bob"], "user_id": "tr_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+    let j = post_json(
+        &app,
+        "/product/entity/path",
+        json!({
+            "source_entity_id": ids["alice"],
+            "target_entity_id": ids["bob"],
+            "user_id": "tr_user",
+            "direction": "both",
+        }),
+    )
+    .await;
+    assert_eq!(j["data"]["hops"], 2);
+    assert_eq!(j["data"]["relations"][1]["source_id"], ids["bob"]);
+    let j = post_json(
+        &app,
+        "/product/entity/path",
+        json!({
+            "source_entity_id": ids["alice"],
+            "target_entity_id": ids["berlin"],
+            "user_id": "tr_user",
+            "relation_types": ["located_in"],
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+    let j = post_json(
+        &app,
+        "/product/entity/path",
+        json!({ "source_entity_id": ids["alice"], "target_entity_id": ids["berlin"], "user_id": "other_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    let j = post_json(
+        &app,
+        "/product/entity/subgraph",
+        json!({ "entity_id": ids["acme"], "user_id": "tr_user", "direction": "both", "max_depth": 1 }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    let entities = j["data"]["entities"].as_array().unwrap();
+    assert_eq!(entities.len(), 4);
+    assert_eq!(entities[0]["id"], ids["acme"]);
+    assert_eq!(entities[0]["depth"], 0);
+    assert!(entities[1..].iter().all(|e| e["depth"] == 1));
+    assert_eq!(entities[0]["memories"].as_array().unwrap().len(), 3);
+    assert!(entities[0]["memories"][0]["memory"].is_string());
+    assert_eq!(j["data"]["relations"].as_array().unwrap().len(), 3);
+    assert_eq!(j["data"]["truncated"], false);
+
+    // Outbound only, limited to works_at: acme has no outgoing works_at relation.
+    let j = post_json(
+        &app,
+        "/product/entity/subgraph",
+        json!({ "entity_id": ids["acme"], "user_id": "tr_user", "relation_types": ["works_at"], "memories_per_entity": 0 }),
+    )
+    .await;
+    assert_eq!(j["data"]["entities"].as_array().unwrap().len(), 1);
+    assert!(j["data"]["entities"][0]["memories"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+
+    let j = post_json(
+        &app,
+        "/product/entity/subgraph",
+        json!({ "entity_id": ids["alice"], "user_id": "tr_user", "direction": "both", "max_entities": 2 }),
+    )
+    .await;
+    assert_eq!(j["data"]["entities"].as_array().unwrap().len(), 2);
+    assert_eq!(j["data"]["truncated"], true);
+
+    let j = post_json(
+        &app,
+        "/product/entity/subgraph",
+        json!({ "entity_id": ids["alice"], "user_id": "other_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+}
+
+#[tokio::test]
+async fn entity_relations_and_attributes_keep_temporal_history() {
+    use mem_embed::{GazetteerEntry, RuleBasedExtractor};
+    use mem_types::EntityType;
+
+    let extractor = RuleBasedExtractor::new()
+        .with_gazetteer(vec![
+            GazetteerEntry::new("Alice", EntityType::Person),
+            GazetteerEntry::new("Acme", EntityType::Organization),
+            GazetteerEntry::new("Globex", EntityType::Organization),
+        ])
+        .unwrap();
+    let app = entity_router(Arc::new(entity_cube_with(Arc::new(extractor), None)));
+    let first = add_memory(&app, "tv_user", "Alice works at Acme").await;
+    add_memory(&app, "tv_user", "Globex opened an office").await;
+    let mut ids = HashMap::new();
+    for name in ["alice", "acme", "globex"] {
+        let j = post_json(
+            &app,
+            "/product/entity/search",
+            json!({ "query": name, "user_id": "tv_user" }),
+        )
+        .await;
+        ids.insert(name, j["entities"][0]["id"].as_str().unwrap().to_string());
+    }
+
+    // Extracted relations carry their source memory.
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user" }),
+    )
+    .await;
+    assert_eq!(j["relations"][0]["source_memory_ids"], json!([first]));
+    assert!(j["relations"][0]["valid_to"].is_null());
+
+    // Alice worked at Acme until the end of 2023, and at Globex since.
+    let j = post_json(
+        &app,
+        "/product/entity/relation/upsert",
+        json!({
+            "source_entity_id": ids["alice"],
+            "target_entity_id": ids["acme"],
+            "relation_type": "works_at",
+            "valid_to": "2024-01-01",
+            "user_id": "tv_user",
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+    assert_eq!(j["data"]["valid_to"], "2024-01-01");
+    assert_eq!(j["data"]["source_memory_ids"], json!([first]));
+    let j = post_json(
+        &app,
+        "/product/entity/relation/upsert",
+        json!({
+            "source_entity_id": ids["alice"],
+            "target_entity_id": ids["globex"],
+            "relation_type": "works_at",
+            "valid_from": "2024-01-01",
+            "confidence": 0.9,
+            "user_id": "tv_user",
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 200, "{}", j);
+
+    let employers = |j: &serde_json::Value| -> Vec<String> {
+        j["relations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["entity"]["name"].as_str().unwrap().to_string())
+            .collect()
+    };
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user", "as_of": "2023-06-01" }),
+    )
+    .await;
+    assert_eq!(employers(&j), ["acme"]);
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user", "as_of": "2024-06-01T12:00:00Z" }),
+    )
+    .await;
+    assert_eq!(employers(&j), ["globex"]);
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user" }),
+    )
+    .await;
+    assert_eq!(employers(&j), ["acme", "globex"]);
+    let j = post_json(
+        &app,
+        "/product/entity/relations",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user", "as_of": "last year" }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+
+    // Traversal honours as_of too.
+    let j = post_json(
+        &app,
+        "/product/entity/path",
+        json!({
+            "source_entity_id": ids["alice"],
+            "target_entity_id": ids["acme"],
+            "user_id": "tv_user",
+            "as_of": "2024-06-01",
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    // Attribute changes close the previous value instead of overwriting it.
+    for (title, from) in [("engineer", "2020-01-01"), ("manager", "2024-01-01")] {
+        let j = post_json(
+            &app,
+            "/product/entity/attributes",
+            json!({
+                "entity_id": ids["alice"],
+                "user_id": "tv_user",
+                "attributes": { "title": title },
+                "valid_from": from,
+            }),
+        )
+        .await;
+        assert_eq!(j["code"], 200, "{}", j);
+    }
+    let j = post_json(
+        &app,
+        "/product/entity/get",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user" }),
+    )
+    .await;
+    assert_eq!(j["data"]["attributes"]["title"], "manager");
+    let history = j["data"]["attribute_history"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0]["value"], "engineer");
+    assert_eq!(history[0]["valid_to"], "2024-01-01");
+    let j = post_json(
+        &app,
+        "/product/entity/get",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user", "as_of": "2022-03-01" }),
+    )
+    .await;
+    assert_eq!(j["data"]["attributes"]["title"], "engineer");
+    let j = post_json(
+        &app,
+        "/product/entity/get",
+        json!({ "entity_id": ids["alice"], "user_id": "tv_user", "as_of": "2019-01-01" }),
+    )
+    .await;
+    assert!(j["data"]["attributes"].get("title").is_none());
+
+    let j = post_json(
+        &app,
+        "/product/entity/attributes",
+        json!({
+            "entity_id": ids["alice"],
+            "user_id": "tv_user",
+            "attributes": { "title": "cto" },
+            "valid_from": "2025-01-01",
+            "valid_to": "2024-01-01",
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 400);
+    let j = post_json(
+        &app,
+        "/product/entity/relation/upsert",
+        json!({
+            "source_entity_id": ids["alice"],
+            "target_entity_id": ids["acme"],
+            "relation_type": "works_at",
+            "user_id": "other_user",
+        }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+}
+
+/// Test LLM: records prompts and answers "profile vN".
+#[derive(Default)]
+struct RecordingLLM {
+    prompts: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl mem_embed::LLMClient for RecordingLLM {
+    async fn complete(&self, prompt: &str) -> Result<String, mem_embed::LLMError> {
+        let mut prompts = self.prompts.lock().unwrap();
+        prompts.push(prompt.to_string());
+        Ok(format!("profile v{}", prompts.len()))
+    }
+
+    async fn complete_with_messages(
+        &self,
+        messages: &[mem_embed::Message],
+    ) -> Result<String, mem_embed::LLMError> {
+        let prompt: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        self.complete(&prompt.join("\n")).await
+    }
+}
+
+#[tokio::test]
+async fn entity_profile_summarizes_linked_memories_incrementally() {
+    let llm = Arc::new(RecordingLLM::default());
+    let inner = NaiveMemCube::new(
+        InMemoryGraphStore::new(),
+        InMemoryVecStore::new(None),
+        MockEmbedder::new(),
+    )
+    .with_llm_client(Some(llm.clone()));
+    let cube = mem_cube::EntityAwareMemCube::with_extractor(
+        inner,
+        Arc::new(CapitalizedExtractor),
+        mem_graph::EntityKnowledgeGraph::new(),
+        Some(mem_cube::EntityCubeConfig {
+            async_extraction: false,
+            ..Default::default()
+        }),
+    );
+    let app = entity_router(Arc::new(cube));
+    let first = add_memory(&app, "prof_user", "Alice works at Acme").await;
+    let second = add_memory(&app, "prof_user", "Alice met Bob").await;
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alice", "user_id": "prof_user" }),
+    )
+    .await;
+    let alice = j["entities"][0]["id"].as_str().unwrap().to_string();
+    let profile = |refresh: Option<bool>| {
+        let app = app.clone();
+        let alice = alice.clone();
+        async move {
+            let mut body = json!({ "entity_id": alice, "user_id": "prof_user" });
+            if let Some(refresh) = refresh {
+                body["refresh"] = json!(refresh);
+            }
+            post_json(&app, "/product/entity/profile", body).await
+        }
+    };
+
+    // Without a refresh the profile lists the memories, most recently linked first.
+    let j = profile(None).await;
+    assert_eq!(j["code"], 200);
+    assert!(j["data"]["description"].is_null());
+    assert_eq!(j["data"]["refreshed"], false);
+    assert_eq!(j["data"]["pending_memory_ids"], json!([first, second]));
+    let memories: Vec<&str> = j["data"]["memories"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(memories, vec![second.as_str(), first.as_str()]);
+
+    let j = profile(Some(true)).await;
+    assert_eq!(j["data"]["description"], "profile v1");
+    assert_eq!(j["data"]["refreshed"], true);
+    assert_eq!(j["data"]["description_memory_ids"], json!([first, second]));
+    assert_eq!(j["data"]["pending_memory_ids"], json!([]));
+    assert!(j["data"]["description_updated_at"].is_string());
+    {
+        let prompts = llm.prompts.lock().unwrap();
+        assert!(prompts[0].contains("Alice works at Acme") && prompts[0].contains("Alice met Bob"));
+    }
+
+    // Up to date: no LLM call.
+    let j = profile(Some(true)).await;
+    assert_eq!(j["data"]["refreshed"], false);
+    assert_eq!(llm.prompts.lock().unwrap().len(), 1);
+
+    // A new memory is folded into the existing description.
+    let third = add_memory(&app, "prof_user", "Alice likes Tea").await;
+    let j = profile(Some(true)).await;
+    assert_eq!(j["data"]["description"], "profile v2");
+    assert_eq!(
+        j["data"]["description_memory_ids"],
+        json!([first, second, third])
+    );
+    {
+        let prompts = llm.prompts.lock().unwrap();
+        assert!(prompts[1].contains("profile v1") && prompts[1].contains("Alice likes Tea"));
+        assert!(!prompts[1].contains("Alice met Bob"));
+    }
+
+    // Forgetting a contributing memory rebuilds the description from what remains.
+    let j = post_json(
+        &app,
+        "/product/delete_memory",
+        json!({ "memory_id": second, "user_id": "prof_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+    let j = profile(Some(true)).await;
+    assert_eq!(j["data"]["description"], "profile v3");
+    assert_eq!(j["data"]["description_memory_ids"], json!([first, third]));
+    {
+        let prompts = llm.prompts.lock().unwrap();
+        assert!(!prompts[2].contains("profile v2") && !prompts[2].contains("Alice met Bob"));
+    }
+
+    let j = post_json(
+        &app,
+        "/product/entity/profile",
+        json!({ "entity_id": alice, "user_id": "prof_user", "memory_limit": 1 }),
+    )
+    .await;
+    assert_eq!(j["data"]["memories"].as_array().unwrap().len(), 1);
+    let j = post_json(
+        &app,
+        "/product/entity/profile",
+        json!({ "entity_id": alice, "user_id": "other_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 404);
+
+    // Refreshing without an LLM client is an error; reading is not.
+    let app = entity_app();
+    add_memory(&app, "prof_user", "Alice works at Acme").await;
+    let j = post_json(
+        &app,
+        "/product/entity/search",
+        json!({ "query": "alice", "user_id": "prof_user" }),
+    )
+    .await;
+    let alice = j["entities"][0]["id"].as_str().unwrap().to_string();
+    let j = post_json(
+        &app,
+        "/product/entity/profile",
+        json!({ "entity_id": alice, "user_id": "prof_user", "refresh": true }),
+    )
+    .await;
+    assert_eq!(j["code"], 500);
+    assert!(j["message"]
+        .as_str()
+        .unwrap()
+        .contains("LLM client not configured"));
+    let j = post_json(
+        &app,
+        "/product/entity/profile",
+        json!({ "entity_id": alice, "user_id": "prof_user" }),
+    )
+    .await;
+    assert_eq!(j["code"], 200);
+}
@@ -0,0 +1,195 @@
+//! Automatic linking of new memories: `similar_to` edges to the most similar existing memories
+//! of the cube and `follows` edges between consecutive memories of a session.
+
+use crate::naive::AUTHOR_KEY;
+use crate::NaiveMemCube;
+use chrono::Utc;
+use mem_embed::Embedder;
+use mem_graph::GraphStore;
+use mem_types::{GraphStoreError, MemoryEdge, MemoryNode};
+use mem_vec::VecStore;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Relation of auto-created similarity edges (new memory -> similar existing memory).
+pub const SIMILAR_TO: &str = "similar_to";
+/// Relation of auto-created session edges (new memory -> previous memory of the session).
+pub const FOLLOWS: &str = "follows";
+/// Sessions whose latest memory is cached; beyond this an arbitrary entry is evicted.
+const MAX_SESSION_HEADS: usize = 10_000;
+
+/// Auto-linker settings for `NaiveMemCube::with_auto_link`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoLinkConfig {
+    /// Most similar existing memories to link (0 disables `similar_to` edges).
+    pub similar_top_n: usize,
+    /// Minimum cosine similarity for a `similar_to` edge.
+    pub similar_threshold: f64,
+    /// Link consecutive memories with the same `session_id` by `follows` edges.
+    pub link_sessions: bool,
+}
+
+impl Default for AutoLinkConfig {
+    fn default() -> Self {
+        Self {
+            similar_top_n: 3,
+            similar_threshold: 0.8,
+            link_sessions: true,
+        }
+    }
+}
+
+fn is_tombstone(node: &MemoryNode) -> bool {
+    node.metadata.get("state").and_then(|v| v.as_str()) == Some("tombstone")
+}
+
+fn session_of(node: &MemoryNode) -> Option<&str> {
+    node.metadata.get("session_id").and_then(|v| v.as_str())
+}
+
+impl<G, V, E> NaiveMemCube<G, V, E>
+where
+    G: GraphStore + Send + Sync + 'static,
+    V: VecStore + Send + Sync,
+    E: Embedder + Send + Sync,
+{
+    /// Link a freshly added memory when auto-linking is enabled. Best effort: failures are
+    /// logged and never fail the add.
+    pub(crate) async fn auto_link(&self, node: &MemoryNode, user_name: &str) {
+        let Some(ref config) = self.auto_link else {
+            return;
+        };
+        if let Err(e) = self.try_auto_link(config, node, user_name).await {
+            tracing::warn!(memory_id = %node.id, error = %e, "auto-linking failed");
+        }
+    }
+
+    async fn try_auto_link(
+        &self,
+        config: &AutoLinkConfig,
+        node: &MemoryNode,
+        user_name: &str,
+    ) -> Result<(), GraphStoreError> {
+        let now = serde_json::Value::String(Utc::now().to_rfc3339());
+        let mut edges = Vec::new();
+        if let (Some(embedding), true) = (&node.embedding, config.similar_top_n > 0) {
+            for (other, score) in self
+                .similar_memories(config, &node.id, embedding, user_name)
+                .await?
+            {
+                edges.push(MemoryEdge {
+                    id: Uuid::new_v4().to_string(),
+                    from: node.id.clone(),
+                    to: other,
+                    relation: SIMILAR_TO.to_string(),
+                    metadata: HashMap::from([
+                        ("score".to_string(), serde_json::json!(score)),
+                        ("auto_linked".to_string(), serde_json::Value::Bool(true)),
+                        ("created_at".to_string(), now.clone()),
+                    ]),
+                });
+            }
+        }
+        if let (Some(session_id), true) = (session_of(node), config.link_sessions) {
+            if let Some(previous) = self
+                .previous_in_session(&node.id, session_id, user_name)
+                .await?
+            {
+                edges.push(MemoryEdge {
+                    id: Uuid::new_v4().to_string(),
+                    from: node.id.clone(),
+                    to: previous,
+                    relation: FOLLOWS.to_string(),
+                    metadata: HashMap::from([
+                        (
+                            "session_id".to_string(),
+                            serde_json::Value::String(session_id.to_string()),
+                        ),
+                        ("auto_linked".to_string(), serde_json::Value::Bool(true)),
+                        ("created_at".to_string(), now),
+                    ]),
+                });
+            }
+        }
+        if edges.is_empty() {
+            return Ok(());
+        }
+        // The edges are written on behalf of whoever wrote the new memory.
+        if let Some(author) = node.metadata.get(AUTHOR_KEY) {
+            for edge in &mut edges {
+                edge.metadata.insert(AUTHOR_KEY.to_string(), author.clone());
+            }
+        }
+        self.graph.add_edges_batch(&edges, Some(user_name)).await
+    }
+
+    /// Live memories of the cube at least `similar_threshold` similar, best first.
+    async fn similar_memories(
+        &self,
+        config: &AutoLinkConfig,
+        id: &str,
+        embedding: &[f32],
+        user_name: &str,
+    ) -> Result<Vec<(String, f64)>, GraphStoreError> {
+        // Over-fetch so the new memory itself and tombstones do not use up the slots.
+        let hits = self
+            .graph
+            .search_by_embedding(embedding, config.similar_top_n * 2 + 1, Some(user_name))
+            .await?;
+        let hits: Vec<_> = hits
+            .into_iter()
+            .filter(|h| h.id != id && h.score >= config.similar_threshold)
+            .collect();
+        let ids: Vec<String> = hits.iter().map(|h| h.id.clone()).collect();
+        let live: Vec<String> = self
+            .graph
+            .get_nodes(&ids, false)
+            .await?
+            .into_iter()
+            .filter(|n| !is_tombstone(n))
+            .map(|n| n.id)
+            .collect();
+        Ok(hits
+            .into_iter()
+            .filter(|h| live.contains(&h.id))
+            .take(config.similar_top_n)
+            .map(|h| (h.id, h.score))
+            .collect())
+    }
+
+    /// The session's latest memory before `id`, which becomes the session's new head. Heads
+    /// are cached per cube and session (swapped atomically, so concurrent adds still form a
+    /// chain); when the cached head is missing (e.g. after a restart or an eviction) or no
+    /// longer live, the graph is asked for the session's latest memory.
+    async fn previous_in_session(
+        &self,
+        id: &str,
+        session_id: &str,
+        user_name: &str,
+    ) -> Result<Option<String>, GraphStoreError> {
+        let key = (user_name.to_string(), session_id.to_string());
+        let cached = {
+            let mut heads = self.session_heads.lock().unwrap_or_else(|e| e.into_inner());
+            if heads.len() >= MAX_SESSION_HEADS && !heads.contains_key(&key) {
+                if let Some(evicted) = heads.keys().next().cloned() {
+                    heads.remove(&evicted);
+                }
+            }
+            heads.insert(key, id.to_string())
+        };
+        if let Some(head) = cached {
+            let live =
+                self.graph.get_node(&head, false).await?.is_some_and(|n| {
+                    !is_tombstone(&n) && Self::node_owner(&n.metadata) == user_name
+                });
+            if live {
+                return Ok(Some(head));
+            }
+        }
+        Ok(self
+            .graph
+            .latest_in_session(user_name, session_id, id)
+            .await?
+            .map(|n| n.id))
+    }
+}
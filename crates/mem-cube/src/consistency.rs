@@ -0,0 +1,454 @@
+//! Recoverable multi-store writes and the cross-store consistency checker. The graph is the
+//! system of record: a memory's vector and keyword entries are derived from its node, so
+//! replaying an unfinished update or repairing divergence re-derives them from the graph.
+
+use crate::NaiveMemCube;
+use chrono::Utc;
+use mem_embed::Embedder;
+use mem_graph::GraphStore;
+use mem_types::{
+    ConsistencyCheckRequest, ConsistencyCheckResponse, ConsistencyReport, IntentOp, MemCubeError,
+    MemoryNode, MemoryScope, VecStoreItem, WriteIntent,
+};
+use mem_vec::VecStore;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
+
+fn is_live(node: &MemoryNode) -> bool {
+    node.metadata.get("state").and_then(|v| v.as_str()) != Some("tombstone")
+}
+
+fn node_scope(node: &MemoryNode) -> &str {
+    node.metadata
+        .get("scope")
+        .and_then(|v| v.as_str())
+        .unwrap_or(MemoryScope::LongTermMemory.as_str())
+}
+
+impl<G, V, E> NaiveMemCube<G, V, E>
+where
+    G: GraphStore + Send + Sync + 'static,
+    V: VecStore + Send + Sync,
+    E: Embedder + Send + Sync,
+{
+    /// Vector payload of a memory in `cube` with `scope`.
+    pub(crate) fn vec_payload(cube: &str, scope: &str) -> HashMap<String, serde_json::Value> {
+        let mut p = HashMap::new();
+        p.insert(
+            "mem_cube_id".to_string(),
+            serde_json::Value::String(cube.to_string()),
+        );
+        p.insert(
+            "memory_type".to_string(),
+            serde_json::Value::String("text_mem".to_string()),
+        );
+        p.insert(
+            "scope".to_string(),
+            serde_json::Value::String(scope.to_string()),
+        );
+        p
+    }
+
+    /// Record a write intent before the first store write. Without an intent log this is a
+    /// no-op; if the log cannot be written the write must not proceed.
+    pub(crate) async fn begin_intent(
+        &self,
+        op: IntentOp,
+        memory_id: &str,
+        cube_id: &str,
+    ) -> Result<Option<WriteIntent>, MemCubeError> {
+        let Some(ref log) = self.intent_log else {
+            return Ok(None);
+        };
+        let intent = WriteIntent {
+            intent_id: Uuid::new_v4().to_string(),
+            op,
+            memory_id: memory_id.to_string(),
+            cube_id: cube_id.to_string(),
+            created_at: Utc::now().to_rfc3339(),
+        };
+        self.active_intents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(intent.intent_id.clone(), intent.clone());
+        if let Err(e) = log.begin(&intent).await {
+            self.active_intents
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&intent.intent_id);
+            return Err(MemCubeError::Other(e.to_string()));
+        }
+        Ok(Some(intent))
+    }
+
+    /// End a write. `consistent`: every store agrees, so the intent is completed; otherwise
+    /// it stays pending for `recover_pending_writes` (or a repairing consistency check).
+    pub(crate) async fn finish_intent(&self, intent: Option<WriteIntent>, consistent: bool) {
+        let (Some(intent), Some(ref log)) = (intent, &self.intent_log) else {
+            return;
+        };
+        if consistent {
+            if let Err(e) = log.complete(&intent.intent_id).await {
+                tracing::warn!(intent_id = %intent.intent_id, error = %e, "failed to complete write intent");
+            }
+        } else {
+            tracing::warn!(
+                intent_id = %intent.intent_id,
+                memory_id = %intent.memory_id,
+                "write left stores divergent; intent kept for recovery"
+            );
+        }
+        self.active_intents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&intent.intent_id);
+    }
+
+    /// Remove an unfinished add from every store. Ok only if every delete succeeded.
+    pub(crate) async fn roll_back_add(&self, id: &str, cube_id: &str) -> Result<(), MemCubeError> {
+        let mut result = Ok(());
+        if let Err(e) = self.vec_store.delete(&[id.to_string()], None).await {
+            result = Err(MemCubeError::Vec(e));
+        }
+        if let Some(ref kw) = self.keyword_store {
+            if let Err(e) = kw.remove(id, Some(cube_id)).await {
+                result = Err(MemCubeError::Keyword(e));
+            }
+        }
+        match self.graph.get_node(id, false).await {
+            Ok(None) => {}
+            Ok(Some(_)) => {
+                if let Err(e) = self.graph.delete_node(id, Some(cube_id)).await {
+                    result = Err(MemCubeError::Graph(e));
+                }
+            }
+            Err(e) => result = Err(MemCubeError::Graph(e)),
+        }
+        result
+    }
+
+    /// Whether an add reached every store (the keyword entry is written last); replaying
+    /// such an add keeps it, as only its completion was lost. A keyword store that cannot
+    /// list its ids cannot confirm the add, which is then treated as unfinished.
+    async fn add_reached_every_store(&self, id: &str, cube_id: &str) -> Result<bool, MemCubeError> {
+        match self.graph.get_node(id, false).await {
+            Ok(Some(node)) if is_live(&node) => {}
+            Ok(_) => return Ok(false),
+            Err(e) => return Err(MemCubeError::Graph(e)),
+        }
+        let vectors = self
+            .vec_store
+            .get_by_ids(&[id.to_string()], None)
+            .await
+            .map_err(MemCubeError::Vec)?;
+        if vectors.is_empty() {
+            return Ok(false);
+        }
+        match self.keyword_store {
+            Some(ref kw) => Ok(kw
+                .list_ids(Some(cube_id))
+                .await
+                .map(|ids| ids.iter().any(|i| i == id))
+                .unwrap_or(false)),
+            None => Ok(true),
+        }
+    }
+
+    /// Give up on an add: roll it back and complete its intent, or keep the intent pending if
+    /// the rollback left something behind.
+    pub(crate) async fn abort_add(&self, intent: Option<WriteIntent>, id: &str, cube_id: &str) {
+        let rolled_back = self.roll_back_add(id, cube_id).await;
+        if let Err(ref e) = rolled_back {
+            tracing::warn!(memory_id = %id, error = %e, "failed to roll back partial add");
+        }
+        self.finish_intent(intent, rolled_back.is_ok()).await;
+    }
+
+    /// Keep a committed add whose keyword entry could not be written: its add intent is
+    /// replaced by a pending repair (an add would be rolled back on recovery).
+    pub(crate) async fn keep_add_for_repair(
+        &self,
+        intent: Option<WriteIntent>,
+        id: &str,
+        cube_id: &str,
+    ) {
+        if intent.is_none() {
+            return;
+        }
+        match self.begin_intent(IntentOp::Repair, id, cube_id).await {
+            Ok(repair) => self.finish_intent(repair, false).await,
+            Err(e) => {
+                tracing::warn!(memory_id = %id, error = %e, "failed to record keyword repair")
+            }
+        }
+        self.finish_intent(intent, true).await;
+    }
+
+    /// Make the vector and keyword entries of `id` match its graph node: a live node gets
+    /// its vector and keyword entry under its owner; a missing or tombstoned node has neither.
+    /// The text is re-embedded because node embeddings are not refreshed by updates.
+    pub(crate) async fn sync_memory(&self, id: &str, cube_id: &str) -> Result<(), MemCubeError> {
+        let node = self
+            .graph
+            .get_node(id, false)
+            .await
+            .map_err(MemCubeError::Graph)?;
+        match node {
+            Some(node) if is_live(&node) => {
+                let owner = Self::node_owner(&node.metadata).to_string();
+                let item = VecStoreItem {
+                    id: id.to_string(),
+                    vector: self.embedder.embed(&node.memory).await?,
+                    payload: Self::vec_payload(&owner, node_scope(&node)),
+                };
+                self.vec_store
+                    .upsert(&[item], None)
+                    .await
+                    .map_err(MemCubeError::Vec)?;
+                if let Some(ref kw) = self.keyword_store {
+                    if owner != cube_id {
+                        kw.remove(id, Some(cube_id))
+                            .await
+                            .map_err(MemCubeError::Keyword)?;
+                    }
+                    kw.index(id, &node.memory, Some(&owner))
+                        .await
+                        .map_err(MemCubeError::Keyword)?;
+                }
+            }
+            node => {
+                let owner = node
+                    .as_ref()
+                    .map(|n| Self::node_owner(&n.metadata).to_string())
+                    .unwrap_or_else(|| cube_id.to_string());
+                self.vec_store
+                    .delete(&[id.to_string()], None)
+                    .await
+                    .map_err(MemCubeError::Vec)?;
+                if let Some(ref kw) = self.keyword_store {
+                    kw.remove(id, Some(&owner))
+                        .await
+                        .map_err(MemCubeError::Keyword)?;
+                    if owner != cube_id {
+                        kw.remove(id, Some(cube_id))
+                            .await
+                            .map_err(MemCubeError::Keyword)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replay intents left pending by a crash or a failed write: adds that did not reach every
+    /// store are rolled back, other writes are rolled forward from the graph. Intents of writes still running
+    /// in this process are skipped. Returns the number of intents replayed.
+    pub async fn recover_pending_writes(&self) -> Result<usize, MemCubeError> {
+        self.replay_pending_writes(None).await
+    }
+
+    /// `recover_pending_writes`, limited to the intents of `cube_id` when given.
+    async fn replay_pending_writes(&self, cube_id: Option<&str>) -> Result<usize, MemCubeError> {
+        let Some(ref log) = self.intent_log else {
+            return Ok(0);
+        };
+        let pending = log
+            .pending()
+            .await
+            .map_err(|e| MemCubeError::Other(e.to_string()))?;
+        let busy: HashSet<String> = self
+            .active_intents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|i| i.memory_id.clone())
+            .collect();
+        let mut replayed = 0;
+        for intent in pending {
+            if cube_id.is_some_and(|c| c != intent.cube_id) {
+                continue;
+            }
+            if busy.contains(&intent.memory_id) {
+                continue;
+            }
+            let result = match intent.op {
+                IntentOp::Add => {
+                    match self
+                        .add_reached_every_store(&intent.memory_id, &intent.cube_id)
+                        .await
+                    {
+                        Ok(true) => Ok(()),
+                        Ok(false) => self.roll_back_add(&intent.memory_id, &intent.cube_id).await,
+                        Err(e) => Err(e),
+                    }
+                }
+                IntentOp::Update | IntentOp::Forget | IntentOp::Repair => {
+                    self.sync_memory(&intent.memory_id, &intent.cube_id).await
+                }
+            };
+            match result {
+                Ok(()) => {
+                    log.complete(&intent.intent_id)
+                        .await
+                        .map_err(|e| MemCubeError::Other(e.to_string()))?;
+                    replayed += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        intent_id = %intent.intent_id,
+                        memory_id = %intent.memory_id,
+                        error = %e,
+                        "failed to replay write intent"
+                    );
+                }
+            }
+        }
+        Ok(replayed)
+    }
+
+    /// Compare the cube's graph with its vector and keyword stores; with `repair`, replay
+    /// abandoned intents first and re-derive every divergent entry from the graph.
+    pub(crate) async fn consistency_report(
+        &self,
+        req: &ConsistencyCheckRequest,
+    ) -> Result<ConsistencyCheckResponse, MemCubeError> {
+        let cube_id = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        if req.repair {
+            self.replay_pending_writes(Some(cube_id)).await?;
+        }
+        // Only this cube's intents: other cubes' memory ids must not appear in the report.
+        let mut in_flight: BTreeSet<String> = match self.intent_log {
+            Some(ref log) => log
+                .pending()
+                .await
+                .map_err(|e| MemCubeError::Other(e.to_string()))?
+                .into_iter()
+                .filter(|i| i.cube_id == cube_id)
+                .map(|i| i.memory_id)
+                .collect(),
+            None => BTreeSet::new(),
+        };
+        in_flight.extend(
+            self.active_intents
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .values()
+                .filter(|i| i.cube_id == cube_id)
+                .map(|i| i.memory_id.clone()),
+        );
+
+        let nodes: HashMap<String, MemoryNode> = self
+            .all_memory_nodes(cube_id)
+            .await?
+            .into_iter()
+            .filter(|n| is_live(n) && !in_flight.contains(&n.id))
+            .map(|n| (n.id.clone(), n))
+            .collect();
+        let live: BTreeSet<String> = nodes.keys().cloned().collect();
+        let mut report = ConsistencyReport {
+            cube_id: cube_id.to_string(),
+            memories: live.len() as u64,
+            in_flight: in_flight.iter().cloned().collect(),
+            ..Default::default()
+        };
+
+        let ids: Vec<String> = live.iter().cloned().collect();
+        let vectors: HashMap<String, VecStoreItem> = self
+            .vec_store
+            .get_by_ids(&ids, None)
+            .await
+            .map_err(MemCubeError::Vec)?
+            .into_iter()
+            .map(|item| (item.id.clone(), item))
+            .collect();
+        for id in &live {
+            match vectors.get(id) {
+                None => report.missing_vectors.push(id.clone()),
+                Some(item) => {
+                    let payload = |k: &str| item.payload.get(k).and_then(|v| v.as_str());
+                    if payload("mem_cube_id") != Some(cube_id)
+                        || payload("scope") != Some(node_scope(&nodes[id]))
+                    {
+                        report.stale_vectors.push(id.clone());
+                    }
+                }
+            }
+        }
+        let vector_ids = match self.vec_store.list_ids(cube_id, None).await {
+            Ok(listed) => listed,
+            Err(_) => {
+                report.unchecked.push("vector".to_string());
+                Vec::new()
+            }
+        };
+        let keyword_ids = match self.keyword_store {
+            Some(ref kw) => match kw.list_ids(Some(cube_id)).await {
+                Ok(listed) => {
+                    let listed: BTreeSet<String> = listed.into_iter().collect();
+                    report.missing_keywords = live.difference(&listed).cloned().collect();
+                    listed.into_iter().collect()
+                }
+                Err(_) => {
+                    report.unchecked.push("keyword".to_string());
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+
+        // Entries without a listed memory are orphans unless their node is a live memory of
+        // the cube that the scope listing does not cover (a custom scope).
+        let unlisted: Vec<String> = vector_ids
+            .iter()
+            .chain(&keyword_ids)
+            .filter(|id| !live.contains(*id) && !in_flight.contains(*id))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let owned: HashSet<String> = if unlisted.is_empty() {
+            HashSet::new()
+        } else {
+            self.graph
+                .get_nodes(&unlisted, false)
+                .await
+                .map_err(MemCubeError::Graph)?
+                .into_iter()
+                .filter(|n| is_live(n) && Self::node_owner(&n.metadata) == cube_id)
+                .map(|n| n.id)
+                .collect()
+        };
+        let orphan = |id: &String| unlisted.contains(id) && !owned.contains(id);
+        report.orphan_vectors = vector_ids.into_iter().filter(orphan).collect();
+        report.orphan_keywords = keyword_ids.into_iter().filter(orphan).collect();
+
+        if req.repair {
+            let divergent: BTreeSet<&String> = report
+                .missing_vectors
+                .iter()
+                .chain(&report.orphan_vectors)
+                .chain(&report.stale_vectors)
+                .chain(&report.missing_keywords)
+                .chain(&report.orphan_keywords)
+                .collect();
+            for id in divergent {
+                let intent = self.begin_intent(IntentOp::Repair, id, cube_id).await?;
+                let result = self.sync_memory(id, cube_id).await;
+                self.finish_intent(intent, result.is_ok()).await;
+                result?;
+                report.repaired += 1;
+            }
+        }
+
+        report.checked_at = Utc::now().to_rfc3339();
+        Ok(ConsistencyCheckResponse {
+            code: 200,
+            message: if req.repair {
+                "Consistency check and repair completed".to_string()
+            } else {
+                "Consistency check completed".to_string()
+            },
+            data: Some(report),
+        })
+    }
+}
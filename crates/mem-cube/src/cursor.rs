@@ -0,0 +1,119 @@
+//! Opaque keyset cursors. A page is a slice of a listing sorted by `(sort key, id)`; the
+//! cursor encodes the key of the last item returned, so the next page starts strictly after
+//! it no matter what was added or removed in between.
+
+use base64::Engine;
+use mem_types::MemCubeError;
+use serde_json::json;
+
+/// A sort position: items are ordered by `sort`, then `id`, both ascending.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct PageKey {
+    pub sort: String,
+    pub id: String,
+}
+
+impl PageKey {
+    pub(crate) fn new(sort: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            sort: sort.into(),
+            id: id.into(),
+        }
+    }
+
+    /// Key ordering by `created_at` metadata (RFC 3339, so text order is time order).
+    /// Items without it sort first.
+    pub(crate) fn created_at(
+        metadata: &std::collections::HashMap<String, serde_json::Value>,
+        id: &str,
+    ) -> Self {
+        let created_at = metadata
+            .get("created_at")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        Self::new(created_at, id)
+    }
+}
+
+/// Encode the position after `key` for the listing `kind`.
+pub(crate) fn encode_cursor(kind: &str, key: &PageKey) -> String {
+    let token = json!({ "k": kind, "s": key.sort, "id": key.id });
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(token.to_string())
+}
+
+/// Decode a cursor of the listing `kind`; cursors of other listings are rejected.
+pub(crate) fn decode_cursor(kind: &str, cursor: &str) -> Result<PageKey, MemCubeError> {
+    let invalid = || MemCubeError::BadRequest(format!("invalid {} cursor", kind));
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor)
+        .map_err(|_| invalid())?;
+    let token: serde_json::Value = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    match (
+        token["k"].as_str(),
+        token["s"].as_str(),
+        token["id"].as_str(),
+    ) {
+        (Some(k), Some(sort), Some(id)) if k == kind => Ok(PageKey::new(sort, id)),
+        _ => Err(invalid()),
+    }
+}
+
+/// The page of `items` after `cursor` (up to `limit` items in key order) and the cursor of
+/// the next page, if any item follows.
+pub(crate) fn keyset_page<T>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> PageKey,
+    kind: &str,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<(Vec<T>, Option<String>), MemCubeError> {
+    let after = cursor.map(|c| decode_cursor(kind, c)).transpose()?;
+    let mut keyed: Vec<(PageKey, T)> = items
+        .drain(..)
+        .map(|item| (key(&item), item))
+        .filter(|(k, _)| after.as_ref().is_none_or(|a| k > a))
+        .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    let more = keyed.len() > limit;
+    keyed.truncate(limit);
+    let next_cursor = if more {
+        keyed.last().map(|(k, _)| encode_cursor(kind, k))
+    } else {
+        None
+    };
+    Ok((
+        keyed.into_iter().map(|(_, item)| item).collect(),
+        next_cursor,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_stay_stable_when_items_are_inserted() {
+        let key = |s: &(&str, &str)| PageKey::new(s.0, s.1);
+        let items = vec![("t1", "a"), ("t2", "c"), ("t2", "b"), ("t3", "d")];
+        let (page, cursor) = keyset_page(items.clone(), key, "test", None, 2).unwrap();
+        assert_eq!(page, vec![("t1", "a"), ("t2", "b")]);
+
+        // An item sorting before the cursor is not returned; nothing repeats or is skipped.
+        let mut grown = items;
+        grown.push(("t0", "z"));
+        let (page, cursor) = keyset_page(grown, key, "test", cursor.as_deref(), 2).unwrap();
+        assert_eq!(page, vec![("t2", "c"), ("t3", "d")]);
+        assert!(cursor.is_none());
+    }
+
+    #[test]
+    fn cursors_are_bound_to_their_listing() {
+        let key = PageKey::new("2026-01-01T00:00:00Z", "x");
+        let cursor = encode_cursor("graph", &key);
+        assert_eq!(decode_cursor("graph", &cursor).unwrap(), key);
+        for bad in [cursor.as_str(), "2", "not-a-cursor", ""] {
+            let err = decode_cursor("entity", bad).unwrap_err();
+            assert!(matches!(err, MemCubeError::BadRequest(ref m) if m == "invalid entity cursor"));
+        }
+    }
+}
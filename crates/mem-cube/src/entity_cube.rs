@@ -3,13 +3,21 @@
 //! Wraps NaiveMemCube to add entity extraction and entity knowledge graph management.
 
 use super::naive::NaiveMemCube;
+use crate::cursor::{keyset_page, PageKey};
+use crate::graph_export::graph_export_response;
 use crate::MemCubeError;
 use async_trait::async_trait;
 use mem_embed::EntityExtractor;
-use mem_graph::{EntityKnowledgeGraph, GraphStore};
+use mem_graph::{
+    EntityKgError, EntityKnowledgeGraph, EntityResolver, ExportEdge, ExportNode, GraphStore,
+    ResolverConfig,
+};
 use mem_types::*;
 use mem_vec::VecStore;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
 /// Entity-aware MemCube configuration.
@@ -47,6 +55,10 @@ pub struct EntityAwareMemCube<G, V, E> {
     extractor: Option<Arc<dyn EntityExtractor>>,
     /// Entity knowledge graph.
     entity_kg: Arc<Mutex<EntityKnowledgeGraph>>,
+    /// Optional durable store; every KG change is written through to it.
+    entity_store: Option<Arc<dyn EntityStore + Send + Sync>>,
+    /// Merge proposals for entity resolution.
+    resolver: EntityResolver,
     /// Configuration.
     config: EntityCubeConfig,
 }
@@ -62,6 +74,8 @@ impl<G, V, E> EntityAwareMemCube<G, V, E> {
             inner,
             extractor: None,
             entity_kg: Arc::new(Mutex::new(entity_kg)),
+            entity_store: None,
+            resolver: EntityResolver::default(),
             config: config.unwrap_or_default(),
         }
     }
@@ -77,10 +91,24 @@ impl<G, V, E> EntityAwareMemCube<G, V, E> {
             inner,
             extractor: Some(extractor),
             entity_kg: Arc::new(Mutex::new(entity_kg)),
+            entity_store: None,
+            resolver: EntityResolver::default(),
             config: config.unwrap_or_default(),
         }
     }
 
+    /// Set a durable entity store. Call [`Self::load_entities`] afterwards to restore its contents.
+    pub fn with_entity_store(mut self, store: Option<Arc<dyn EntityStore + Send + Sync>>) -> Self {
+        self.entity_store = store;
+        self
+    }
+
+    /// Set the entity resolver configuration (defaults when None).
+    pub fn with_resolver(mut self, config: Option<ResolverConfig>) -> Self {
+        self.resolver = EntityResolver::new(config);
+        self
+    }
+
     /// Get reference to inner cube.
     pub fn inner(&self) -> &NaiveMemCube<G, V, E> {
         &self.inner
@@ -96,9 +124,54 @@ impl<G, V, E> EntityAwareMemCube<G, V, E> {
         &self.entity_kg
     }
 
-    /// Perform entity extraction and update the entity knowledge graph.
+    /// Rebuild the entity knowledge graph from the durable store. Returns the number of entities
+    /// loaded (0 when no store is configured).
+    pub async fn load_entities(&self) -> Result<usize, MemCubeError> {
+        let Some(ref store) = self.entity_store else {
+            return Ok(0);
+        };
+        let snapshot = store
+            .load()
+            .await
+            .map_err(|e| MemCubeError::Other(e.to_string()))?;
+        let count = snapshot.entities.len();
+        self.entity_kg
+            .lock()
+            .await
+            .load_from_snapshot(snapshot)
+            .map_err(|e| MemCubeError::Other(e.to_string()))?;
+        Ok(count)
+    }
+
+    /// Write a JSON snapshot of the whole entity knowledge graph to `path` (atomically, via a
+    /// temporary file and rename).
+    pub async fn export_snapshot(&self, path: &Path) -> Result<(), MemCubeError> {
+        export_snapshot(&self.entity_kg, path).await
+    }
+
+    /// Periodically export a snapshot to `path` in the background.
+    pub fn spawn_snapshot_export(
+        &self,
+        path: PathBuf,
+        interval: Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let kg = self.entity_kg.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = export_snapshot(&kg, &path).await {
+                    tracing::warn!(path = %path.display(), error = %e, "Entity snapshot export failed");
+                }
+            }
+        })
+    }
+
+    /// Perform entity extraction and update the cube's part of the entity knowledge graph.
     async fn extract_and_index_entities(
         &self,
+        cube_id: &str,
         content: &str,
         memory_id: &str,
     ) -> Result<(), MemCubeError> {
@@ -116,47 +189,21 @@ impl<G, V, E> EntityAwareMemCube<G, V, E> {
             .map_err(|e| MemCubeError::Other(format!("Entity extraction failed: {}", e)))?;
 
         let entity_kg = self.entity_kg.lock().await;
-
-        // Limit number of entities
-        let entities: Vec<_> = result
-            .entities
-            .into_iter()
-            .take(self.config.max_entities_per_memory)
-            .collect();
-
-        // Index entities
-        for entity in &entities {
-            if let Err(e) = entity_kg.upsert_entity(entity, memory_id) {
-                tracing::warn!(memory_id = memory_id, error = %e, "Failed to upsert entity");
-            }
-        }
-
-        // Index relations if enabled
-        if self.config.extract_relations {
-            for relation in &result.relations {
-                if let Err(e) = entity_kg.add_relation_by_name(
-                    &relation.source_text,
-                    &relation.target_text,
-                    relation.relation_type.clone(),
-                ) {
-                    tracing::warn!(
-                        source = %relation.source_text,
-                        target = %relation.target_text,
-                        error = %e,
-                        "Failed to add entity relation"
-                    );
-                }
-            }
-        }
+        let touched = index_extraction(&entity_kg, cube_id, &result, memory_id, &self.config);
+        persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
 
         Ok(())
     }
 
-    /// Extract entities from a batch of memories.
+    /// Extract entities from a batch of memories of one cube.
     #[allow(dead_code)]
-    async fn extract_batch(&self, contents: &[(String, String)]) -> Result<(), MemCubeError>
+    async fn extract_batch(
+        &self,
+        cube_id: &str,
+        contents: &[(String, String)],
+    ) -> Result<(), MemCubeError>
     where
-        G: GraphStore + Send + Sync,
+        G: GraphStore + Send + Sync + 'static,
         V: VecStore + Send + Sync,
         E: mem_embed::Embedder + Send + Sync,
     {
@@ -176,37 +223,15 @@ impl<G, V, E> EntityAwareMemCube<G, V, E> {
 
         let entity_kg = self.entity_kg.lock().await;
 
+        let mut touched: Vec<String> = Vec::new();
         for ((_content, memory_id), result) in contents.iter().zip(results.iter()) {
-            let entities: Vec<_> = result
-                .entities
-                .iter()
-                .take(self.config.max_entities_per_memory)
-                .collect();
-
-            for entity in entities {
-                if let Err(e) = entity_kg.upsert_entity(entity, memory_id) {
-                    tracing::warn!(memory_id = memory_id, error = %e, "Failed to upsert entity");
-                }
-            }
-
-            // Index relations
-            if self.config.extract_relations {
-                for relation in &result.relations {
-                    if let Err(e) = entity_kg.add_relation_by_name(
-                        &relation.source_text,
-                        &relation.target_text,
-                        relation.relation_type.clone(),
-                    ) {
-                        tracing::warn!(
-                            source = %relation.source_text,
-                            target = %relation.target_text,
-                            error = %e,
-                            "Failed to add entity relation"
-                        );
-                    }
+            for id in index_extraction(&entity_kg, cube_id, result, memory_id, &self.config) {
+                if !touched.contains(&id) {
+                    touched.push(id);
                 }
             }
         }
+        persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
 
         Ok(())
     }
@@ -215,58 +240,54 @@ impl<G, V, E> EntityAwareMemCube<G, V, E> {
 #[async_trait]
 impl<G, V, E> MemCube for EntityAwareMemCube<G, V, E>
 where
-    G: GraphStore + Send + Sync,
+    G: GraphStore + Send + Sync + 'static,
     V: VecStore + Send + Sync,
     E: mem_embed::Embedder + Send + Sync,
 {
     async fn add_memories(&self, req: &ApiAddRequest) -> Result<MemoryResponse, MemCubeError> {
-        let content = req.content_to_store().ok_or_else(|| {
-            MemCubeError::Other("no messages or memory_content in request".to_string())
-        })?;
-
         // Add memory to inner cube first so we get the real memory ID
         let response = self.inner.add_memories(req).await?;
+        let cube_id = req
+            .writable_cube_ids()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| req.user_id.clone());
 
-        let memory_id = response
-            .data
-            .as_ref()
-            .and_then(|d| d.first())
-            .and_then(|o| o.get("id"))
-            .and_then(|v| v.as_str())
-            .map(str::to_string);
+        // Extract from the stored text, which has been through the cube's PII policy.
+        let stored = response.data.as_ref().and_then(|d| d.first());
+        let field = |key: &str| {
+            stored
+                .and_then(|o| o.get(key))
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
 
-        if let Some(memory_id) = memory_id {
+        if let (Some(memory_id), Some(content)) = (field("id"), field("memory")) {
             if self.config.async_extraction {
                 let content = content.clone();
                 let extractor = self.extractor.clone();
                 let kg = self.entity_kg.clone();
+                let store = self.entity_store.clone();
                 let config = self.config.clone();
 
                 tokio::spawn(async move {
+                    if !config.enable_extraction {
+                        return;
+                    }
                     if let Some(ref extractor) = extractor {
-                        if let Ok(result) =
-                            extractor.extract(&content, config.extraction_config).await
+                        if let Ok(result) = extractor
+                            .extract(&content, config.extraction_config.clone())
+                            .await
                         {
                             let kg = kg.lock().await;
-                            for entity in result
-                                .entities
-                                .into_iter()
-                                .take(config.max_entities_per_memory)
-                            {
-                                let _ = kg.upsert_entity(&entity, &memory_id);
-                            }
-                            for relation in result.relations {
-                                let _ = kg.add_relation_by_name(
-                                    &relation.source_text,
-                                    &relation.target_text,
-                                    relation.relation_type.clone(),
-                                );
-                            }
+                            let touched =
+                                index_extraction(&kg, &cube_id, &result, &memory_id, &config);
+                            persist_entities(&kg, store.as_ref(), &touched).await;
                         }
                     }
                 });
             } else {
-                self.extract_and_index_entities(&content, &memory_id)
+                self.extract_and_index_entities(&cube_id, &content, &memory_id)
                     .await?;
             }
         }
@@ -285,53 +306,67 @@ where
         &self,
         req: &UpdateMemoryRequest,
     ) -> Result<UpdateMemoryResponse, MemCubeError> {
-        // Extract entities from updated content if memory changed
-        if let Some(ref memory) = req.memory {
-            // Get current entities for this memory
-            let entity_kg = self.entity_kg.lock().await;
-            let _current_entities = entity_kg.get_entities_for_memory(&req.memory_id);
+        let response = self.inner.update_memory(req).await?;
 
-            // Re-extract entities from new content
-            if let Some(ref extractor) = self.extractor {
-                if let Ok(result) = extractor
-                    .extract(memory, self.config.extraction_config.clone())
-                    .await
-                {
-                    let entity_kg = self.entity_kg.lock().await;
+        // Re-extract when the text changed: link the entities of the new text, then drop
+        // associations (and orphaned entities) the new text no longer mentions.
+        if req.memory.is_none() {
+            return Ok(response);
+        }
+        let Some(ref extractor) = self.extractor else {
+            return Ok(response);
+        };
+        if !self.config.enable_extraction {
+            return Ok(response);
+        }
+        // The stored text, which has been through the cube's PII policy.
+        let memory = match self.inner.graph.get_node(&req.memory_id, false).await {
+            Ok(Some(node)) => node.memory,
+            Ok(None) => return Ok(response),
+            Err(e) => {
+                tracing::warn!(memory_id = %req.memory_id, error = %e, "Entity re-extraction failed");
+                return Ok(response);
+            }
+        };
+        let result = match extractor
+            .extract(&memory, self.config.extraction_config.clone())
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!(memory_id = %req.memory_id, error = %e, "Entity re-extraction failed");
+                return Ok(response);
+            }
+        };
 
-                    // Update each entity with the new memory association
-                    for entity in result.entities {
-                        let normalized = entity_kg.normalize_name(&entity.text);
-                        if let Some(existing_id) =
-                            entity_kg.get_entity_id_by_normalized_name(&normalized)
-                        {
-                            let _ = entity_kg.add_memory_to_entity(&existing_id, &req.memory_id);
-                        }
-                    }
-                }
+        let cube_id = req.mem_cube_id.as_deref().unwrap_or(&req.user_id);
+        let entity_kg = self.entity_kg.lock().await;
+        let previous = entity_kg.get_entity_ids_for_memory(&req.memory_id);
+        let current = index_extraction(&entity_kg, cube_id, &result, &req.memory_id, &self.config);
+        let mut touched = current.clone();
+        for entity_id in previous {
+            if !current.contains(&entity_id) {
+                entity_kg.dissociate_from_memory(&entity_id, &req.memory_id);
+                entity_kg.prune_if_orphaned(&entity_id);
+                touched.push(entity_id);
             }
         }
+        persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
 
-        self.inner.update_memory(req).await
+        Ok(response)
     }
 
     async fn forget_memory(
         &self,
         req: &ForgetMemoryRequest,
     ) -> Result<ForgetMemoryResponse, MemCubeError> {
-        // Remove entity associations before forgetting
-        let entity_kg = self.entity_kg.lock().await;
-        let memory_id = req.memory_id.clone();
-        let entity_ids: Vec<String> = entity_kg.get_entity_ids_for_memory(&memory_id);
-        drop(entity_kg);
+        let response = self.inner.forget_memory(req).await?;
 
-        // Dissociate from each entity
-        for entity_id in &entity_ids {
-            let entity_kg = self.entity_kg.lock().await;
-            entity_kg.dissociate_from_memory(entity_id, &memory_id);
-        }
+        let entity_kg = self.entity_kg.lock().await;
+        let touched = entity_kg.remove_memory(&req.memory_id);
+        persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
 
-        self.inner.forget_memory(req).await
+        Ok(response)
     }
 
     async fn get_memory(&self, req: &GetMemoryRequest) -> Result<GetMemoryResponse, MemCubeError> {
@@ -357,6 +392,53 @@ where
     }
 
     // Batch Operations - delegate to inner
+    async fn add_edge(&self, req: &GraphEdgeAddRequest) -> Result<GraphEdgeResponse, MemCubeError> {
+        self.inner.add_edge(req).await
+    }
+
+    async fn get_edge(&self, req: &GraphEdgeRequest) -> Result<GraphEdgeResponse, MemCubeError> {
+        self.inner.get_edge(req).await
+    }
+
+    async fn update_edge(
+        &self,
+        req: &GraphEdgeUpdateRequest,
+    ) -> Result<GraphEdgeResponse, MemCubeError> {
+        self.inner.update_edge(req).await
+    }
+
+    async fn delete_edge(&self, req: &GraphEdgeRequest) -> Result<GraphEdgeResponse, MemCubeError> {
+        self.inner.delete_edge(req).await
+    }
+
+    async fn list_edges(
+        &self,
+        req: &GraphEdgeListRequest,
+    ) -> Result<GraphEdgeListResponse, MemCubeError> {
+        self.inner.list_edges(req).await
+    }
+
+    async fn graph_analytics(
+        &self,
+        req: &GraphAnalyticsRequest,
+    ) -> Result<GraphAnalyticsReport, MemCubeError> {
+        self.inner.graph_analytics(req).await
+    }
+
+    async fn check_consistency(
+        &self,
+        req: &ConsistencyCheckRequest,
+    ) -> Result<ConsistencyCheckResponse, MemCubeError> {
+        self.inner.check_consistency(req).await
+    }
+
+    async fn graph_query(
+        &self,
+        req: &GraphQueryRequest,
+    ) -> Result<GraphQueryResponse, MemCubeError> {
+        self.inner.graph_query(req).await
+    }
+
     async fn add_memories_batch(
         &self,
         req: &BatchAddRequest,
@@ -368,13 +450,107 @@ where
         &self,
         req: &BatchDeleteRequest,
     ) -> Result<BatchDeleteResponse, MemCubeError> {
-        self.inner.delete_memories_batch(req).await
+        let response = self.inner.delete_memories_batch(req).await?;
+
+        if let Some(ref data) = response.data {
+            let entity_kg = self.entity_kg.lock().await;
+            let mut touched: Vec<String> = Vec::new();
+            for deleted in &data.successful {
+                for entity_id in entity_kg.remove_memory(&deleted.memory_id) {
+                    if !touched.contains(&entity_id) {
+                        touched.push(entity_id);
+                    }
+                }
+            }
+            persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
+        }
+
+        Ok(response)
     }
 
     async fn export_memories(&self, req: &ExportRequest) -> Result<ExportResponse, MemCubeError> {
         self.inner.export_memories(req).await
     }
 
+    async fn export_graph(
+        &self,
+        req: &GraphExportRequest,
+    ) -> Result<GraphExportResponse, MemCubeError> {
+        let mut document = self.inner.graph_document(req).await?;
+        if req.include_entities {
+            let cube_id = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+            let entity_kg = self.entity_kg.lock().await;
+            let mut entities: BTreeMap<String, Entity> = BTreeMap::new();
+            let mut mentions = Vec::new();
+            for node in &document.nodes {
+                for entity in entity_kg.get_entities_for_memory(&node.id) {
+                    if entity.cube_id != cube_id {
+                        continue;
+                    }
+                    mentions.push(ExportEdge {
+                        id: format!("{}:mentions:{}", node.id, entity.id),
+                        source: node.id.clone(),
+                        target: entity.id.clone(),
+                        relation: "mentions".to_string(),
+                        attributes: BTreeMap::new(),
+                    });
+                    entities.insert(entity.id.clone(), entity);
+                }
+            }
+            for relation in entities
+                .keys()
+                .flat_map(|id| entity_kg.outgoing_relations(id))
+            {
+                if !entities.contains_key(&relation.target_id) {
+                    continue;
+                }
+                let relation_type = relation.relation_type.to_string();
+                let attributes = match serde_json::to_value(&relation.validity) {
+                    Ok(serde_json::Value::Object(map)) => map.into_iter().collect(),
+                    _ => BTreeMap::new(),
+                };
+                mentions.push(ExportEdge {
+                    id: format!(
+                        "{}:{}:{}",
+                        relation.source_id, relation_type, relation.target_id
+                    ),
+                    source: relation.source_id,
+                    target: relation.target_id,
+                    relation: relation_type,
+                    attributes,
+                });
+            }
+            document.edges.extend(mentions);
+            document.nodes.extend(entities.into_values().map(|entity| {
+                let mut attributes: BTreeMap<String, serde_json::Value> =
+                    entity.attributes.into_iter().collect();
+                attributes.insert(
+                    "entity_type".to_string(),
+                    serde_json::Value::String(entity.entity_type.to_string()),
+                );
+                if let Some(description) = entity.description {
+                    attributes.insert(
+                        "description".to_string(),
+                        serde_json::Value::String(description),
+                    );
+                }
+                if !entity.name_variants.is_empty() {
+                    attributes.insert(
+                        "name_variants".to_string(),
+                        serde_json::json!(entity.name_variants),
+                    );
+                }
+                ExportNode {
+                    id: entity.id,
+                    kind: "entity".to_string(),
+                    label: entity.name,
+                    attributes,
+                }
+            }));
+        }
+        Ok(graph_export_response(req, &document))
+    }
+
     // Session Management - delegate to inner
     async fn create_session(
         &self,
@@ -419,71 +595,704 @@ where
     ) -> Result<SummarizeResponse, MemCubeError> {
         self.inner.summarize_memories(req).await
     }
-}
 
-// ============================================================================
-// Entity-specific API methods (not part of MemCube trait)
-// ============================================================================
+    // Feedback - delegate to inner
+    async fn submit_feedback(
+        &self,
+        req: &FeedbackRequest,
+    ) -> Result<FeedbackResponse, MemCubeError> {
+        self.inner.submit_feedback(req).await
+    }
 
-impl<G, V, E> EntityAwareMemCube<G, V, E>
-where
-    G: GraphStore + Send + Sync,
-    V: VecStore + Send + Sync,
-    E: mem_embed::Embedder + Send + Sync,
-{
-    /// Search for entities by name.
-    pub async fn search_entities(
+    async fn export_feedback(
+        &self,
+        req: &FeedbackExportRequest,
+    ) -> Result<FeedbackExportResponse, MemCubeError> {
+        self.inner.export_feedback(req).await
+    }
+
+    // Entities - served from the entity knowledge graph
+    async fn search_entities(
         &self,
+        cube_id: &str,
         query: &str,
         entity_type: Option<EntityType>,
         limit: u32,
-    ) -> Vec<Entity> {
+    ) -> Result<Vec<Entity>, MemCubeError> {
         let entity_kg = self.entity_kg.lock().await;
-        entity_kg.search_by_type_and_name(entity_type, query, limit)
+        Ok(entity_kg.search_by_type_and_name(cube_id, entity_type, query, limit))
     }
 
-    /// Get entity by ID.
-    pub async fn get_entity(&self, entity_id: &str) -> Option<Entity> {
+    async fn fuzzy_search_entities(
+        &self,
+        cube_id: &str,
+        query: &str,
+        entity_type: Option<EntityType>,
+        min_score: Option<f64>,
+        limit: u32,
+    ) -> Result<Vec<(Entity, f64)>, MemCubeError> {
         let entity_kg = self.entity_kg.lock().await;
-        entity_kg.get_by_id(entity_id)
+        Ok(entity_kg.fuzzy_search(cube_id, query, entity_type, min_score, limit))
     }
 
-    /// Get entity by name.
-    pub async fn get_entity_by_name(&self, name: &str) -> Option<Entity> {
+    async fn get_entity(
+        &self,
+        cube_id: &str,
+        entity_id: &str,
+    ) -> Result<Option<Entity>, MemCubeError> {
         let entity_kg = self.entity_kg.lock().await;
-        entity_kg.get_by_name(name)
+        Ok(entity_kg
+            .get_by_id(entity_id)
+            .filter(|e| e.cube_id == cube_id))
     }
 
-    /// Get entities associated with a memory.
-    pub async fn get_memory_entities(&self, memory_id: &str) -> Vec<Entity> {
-        let entity_kg = self.entity_kg.lock().await;
-        entity_kg.get_entities_for_memory(memory_id)
+    async fn list_entities_by_type(
+        &self,
+        cube_id: &str,
+        entity_type: EntityType,
+        limit: u32,
+        cursor: Option<&str>,
+    ) -> Result<(Vec<Entity>, Option<String>), MemCubeError> {
+        let entities = self
+            .entity_kg
+            .lock()
+            .await
+            .find_by_type(cube_id, entity_type);
+        // Oldest first; id breaks ties. The cursor keeps the last (first_seen, id): unlike
+        // occurrence counts, these do not change while paging (a merge may only move the kept
+        // entity earlier).
+        keyset_page(
+            entities,
+            |e| PageKey::new(e.metadata.first_seen.as_str(), e.id.as_str()),
+            "entity",
+            cursor,
+            limit as usize,
+        )
     }
 
-    /// Get related entities for an entity.
-    pub async fn get_entity_relations(
+    async fn get_entity_relations(
         &self,
+        cube_id: &str,
         entity_id: &str,
         relation_type: Option<EntityRelationType>,
-    ) -> Vec<(EntityRelationType, Entity)> {
+        as_of: Option<&chrono::DateTime<chrono::Utc>>,
+        limit: u32,
+    ) -> Result<Vec<(StoredRelation, Entity)>, MemCubeError> {
         let entity_kg = self.entity_kg.lock().await;
-        if let Some(rt) = relation_type {
-            let entities = entity_kg.get_relations_by_type(entity_id, rt.clone());
-            entities.into_iter().map(|e| (rt.clone(), e)).collect()
-        } else {
+        if entity_kg
+            .get_by_id(entity_id)
+            .is_none_or(|e| e.cube_id != cube_id)
+        {
+            return Err(MemCubeError::NotFound(format!(
+                "entity not found: {}",
+                entity_id
+            )));
+        }
+        let mut related: Vec<(StoredRelation, Entity)> = entity_kg
+            .outgoing_relations(entity_id)
+            .into_iter()
+            .filter(|r| {
+                relation_type
+                    .as_ref()
+                    .is_none_or(|rt| &r.relation_type == rt)
+            })
+            .filter(|r| as_of.is_none_or(|at| r.validity.holds_at(at)))
+            .filter_map(|r| entity_kg.get_by_id(&r.target_id).map(|e| (r, e)))
+            .collect();
+        related.sort_by(|a, b| {
+            a.0.relation_type
+                .to_string()
+                .cmp(&b.0.relation_type.to_string())
+                .then_with(|| a.1.name.cmp(&b.1.name))
+        });
+        related.truncate(limit as usize);
+        Ok(related)
+    }
+
+    async fn get_memory_entities(
+        &self,
+        cube_id: &str,
+        memory_id: &str,
+    ) -> Result<Vec<Entity>, MemCubeError> {
+        let entity_kg = self.entity_kg.lock().await;
+        let mut entities = entity_kg.get_entities_for_memory(memory_id);
+        entities.retain(|e| e.cube_id == cube_id);
+        Ok(entities)
+    }
+
+    async fn entity_stats(&self, cube_id: &str) -> Result<EntityKgStats, MemCubeError> {
+        let entity_kg = self.entity_kg.lock().await;
+        Ok(entity_kg.cube_stats(cube_id))
+    }
+
+    async fn search_by_entity(
+        &self,
+        req: &EntityAwareSearchRequest,
+    ) -> Result<SearchResponse, MemCubeError> {
+        let cube_ids = req
+            .readable_cube_ids
+            .clone()
+            .filter(|ids| !ids.is_empty())
+            .unwrap_or_else(|| vec![req.user_id.clone()]);
+
+        // memory id -> names of the matched entities that mention it
+        let mut matched: Vec<(String, Vec<String>)> = Vec::new();
+        {
+            let entity_kg = self.entity_kg.lock().await;
+            let entities = cube_ids.iter().flat_map(|cube_id| {
+                entity_kg.search_by_type_and_name(
+                    cube_id,
+                    req.entity_type.clone(),
+                    &req.entity_name,
+                    req.limit,
+                )
+            });
+            for entity in entities {
+                let mut memory_ids = entity_kg.get_memory_ids_for_entity(&entity.id);
+                memory_ids.sort();
+                for memory_id in memory_ids
+                    .into_iter()
+                    .take(req.memories_per_entity as usize)
+                {
+                    match matched.iter_mut().find(|(id, _)| *id == memory_id) {
+                        Some((_, names)) => names.push(entity.name.clone()),
+                        None => matched.push((memory_id, vec![entity.name.clone()])),
+                    }
+                }
+            }
+        }
+        matched.truncate(req.limit as usize);
+
+        let ids: Vec<String> = matched.iter().map(|(id, _)| id.clone()).collect();
+        let mut memories = self.visible_memories(&cube_ids, &ids).await?;
+        for memory in &mut memories {
+            if let Some((_, names)) = matched.iter().find(|(id, _)| *id == memory.id) {
+                memory
+                    .metadata
+                    .insert("matched_entities".to_string(), serde_json::json!(names));
+            }
+        }
+
+        Ok(SearchResponse {
+            code: 200,
+            message: "Search completed successfully".to_string(),
+            data: Some(SearchResponseData {
+                text_mem: vec![MemoryBucket {
+                    name: Some("all".to_string()),
+                    total_nodes: Some(memories.len()),
+                    memories,
+                }],
+                pref_mem: vec![],
+            }),
+        })
+    }
+
+    async fn propose_entity_merges(
+        &self,
+        cube_id: &str,
+        min_confidence: Option<f64>,
+        limit: usize,
+    ) -> Result<Vec<EntityMergeCandidate>, MemCubeError> {
+        // The KG handle shares its data; release the cube lock while names are embedded.
+        let entity_kg = self.entity_kg.lock().await.clone();
+        self.resolver
+            .propose_merges(
+                &entity_kg,
+                cube_id,
+                Some(&self.inner.embedder),
+                min_confidence,
+                limit,
+            )
+            .await
+            .map_err(MemCubeError::Embedder)
+    }
+
+    async fn merge_entities(
+        &self,
+        cube_id: &str,
+        keep_id: &str,
+        merge_id: &str,
+    ) -> Result<Entity, MemCubeError> {
+        let entity_kg = self.entity_kg.lock().await;
+        for id in [keep_id, merge_id] {
+            if entity_kg.get_by_id(id).is_none_or(|e| e.cube_id != cube_id) {
+                return Err(MemCubeError::NotFound(format!("entity not found: {}", id)));
+            }
+        }
+        let mut touched = entity_kg.incoming_relation_sources(merge_id);
+        let entity = entity_kg
+            .merge_entities(keep_id, merge_id)
+            .map_err(kg_error)?;
+        touched.extend([keep_id.to_string(), merge_id.to_string()]);
+        persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
+        Ok(entity)
+    }
+
+    async fn split_entity(
+        &self,
+        cube_id: &str,
+        entity_id: &str,
+        split: &EntitySplit,
+    ) -> Result<Entity, MemCubeError> {
+        let entity_kg = self.entity_kg.lock().await;
+        if entity_kg
+            .get_by_id(entity_id)
+            .is_none_or(|e| e.cube_id != cube_id)
+        {
+            return Err(MemCubeError::NotFound(format!(
+                "entity not found: {}",
+                entity_id
+            )));
+        }
+        let entity = entity_kg.split_entity(entity_id, split).map_err(kg_error)?;
+        let touched = [entity_id.to_string(), entity.id.clone()];
+        persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
+        Ok(entity)
+    }
+
+    async fn upsert_entity_relation(
+        &self,
+        cube_id: &str,
+        relation: &StoredRelation,
+    ) -> Result<StoredRelation, MemCubeError> {
+        check_validity(&relation.validity)?;
+        let entity_kg = self.entity_kg.lock().await;
+        for id in [&relation.source_id, &relation.target_id] {
+            if entity_kg
+                .get_by_id(id)
+                .is_none_or(|e| e.cube_id != *cube_id)
+            {
+                return Err(MemCubeError::NotFound(format!("entity not found: {}", id)));
+            }
+        }
+        let stored = entity_kg
+            .add_relation_with_validity(
+                &relation.source_id,
+                &relation.target_id,
+                relation.relation_type.clone(),
+                relation.validity.clone(),
+            )
+            .map_err(kg_error)?;
+        persist_entities(
+            &entity_kg,
+            self.entity_store.as_ref(),
+            std::slice::from_ref(&relation.source_id),
+        )
+        .await;
+        Ok(stored)
+    }
+
+    async fn set_entity_attributes(
+        &self,
+        cube_id: &str,
+        entity_id: &str,
+        attributes: &HashMap<String, serde_json::Value>,
+        validity: &Validity,
+    ) -> Result<Entity, MemCubeError> {
+        if attributes.is_empty() {
+            return Err(MemCubeError::BadRequest(
+                "attributes must not be empty".to_string(),
+            ));
+        }
+        check_validity(validity)?;
+        let entity_kg = self.entity_kg.lock().await;
+        if entity_kg
+            .get_by_id(entity_id)
+            .is_none_or(|e| e.cube_id != cube_id)
+        {
+            return Err(MemCubeError::NotFound(format!(
+                "entity not found: {}",
+                entity_id
+            )));
+        }
+        let entity = entity_kg
+            .update_attributes(entity_id, attributes.clone(), validity.clone())
+            .map_err(kg_error)?;
+        persist_entities(
+            &entity_kg,
+            self.entity_store.as_ref(),
+            std::slice::from_ref(&entity.id),
+        )
+        .await;
+        Ok(entity)
+    }
+
+    async fn entity_path(
+        &self,
+        cube_id: &str,
+        source_id: &str,
+        target_id: &str,
+        traversal: &EntityTraversal,
+    ) -> Result<Option<EntityPath>, MemCubeError> {
+        let entity_kg = self.entity_kg.lock().await;
+        for id in [source_id, target_id] {
+            if entity_kg.get_by_id(id).is_none_or(|e| e.cube_id != cube_id) {
+                return Err(MemCubeError::NotFound(format!("entity not found: {}", id)));
+            }
+        }
+        Ok(entity_kg.shortest_path(source_id, target_id, traversal))
+    }
+
+    async fn entity_subgraph(
+        &self,
+        cube_id: &str,
+        entity_id: &str,
+        traversal: &EntityTraversal,
+        memories_per_entity: u32,
+    ) -> Result<EntitySubgraph, MemCubeError> {
+        let mut subgraph = {
+            let entity_kg = self.entity_kg.lock().await;
             entity_kg
-                .get_relations(entity_id)
-                .into_iter()
-                .flat_map(|(rt, entities)| entities.into_iter().map(move |e| (rt.clone(), e)))
-                .collect()
+                .subgraph(entity_id, traversal)
+                .filter(|g| g.nodes[0].entity.cube_id == cube_id)
+                .ok_or_else(|| MemCubeError::NotFound(format!("entity not found: {}", entity_id)))?
+        };
+        if memories_per_entity == 0 {
+            return Ok(subgraph);
+        }
+        let cube_ids = [cube_id.to_string()];
+        for node in &mut subgraph.nodes {
+            let mut memory_ids = node.entity.memory_ids.clone();
+            memory_ids.sort();
+            memory_ids.truncate(memories_per_entity as usize);
+            node.memories = self.visible_memories(&cube_ids, &memory_ids).await?;
+        }
+        Ok(subgraph)
+    }
+
+    async fn entity_profile(
+        &self,
+        cube_id: &str,
+        entity_id: &str,
+        refresh: Option<bool>,
+        memory_limit: u32,
+    ) -> Result<EntityProfile, MemCubeError> {
+        let mut entity = self
+            .get_entity(cube_id, entity_id)
+            .await?
+            .ok_or_else(|| MemCubeError::NotFound(format!("entity not found: {}", entity_id)))?;
+        let cube_ids = [cube_id.to_string()];
+        let mut memories = self.visible_memories(&cube_ids, &entity.memory_ids).await?;
+        let mut refreshed = false;
+        if refresh.unwrap_or(self.config.extraction_config.generate_summary) {
+            match self.refresh_profile(&entity, &memories).await {
+                Ok(Some(updated)) => {
+                    entity = updated;
+                    refreshed = true;
+                }
+                Ok(None) => {}
+                // An explicit refresh reports failures; the configured default only logs them.
+                Err(e) if refresh.is_some() => return Err(e),
+                Err(e) => {
+                    tracing::warn!(entity_id = %entity_id, error = %e, "Failed to refresh entity profile")
+                }
+            }
+        }
+        let pending_memory_ids = memories
+            .iter()
+            .filter(|m| !entity.metadata.description_memory_ids.contains(&m.id))
+            .map(|m| m.id.clone())
+            .collect();
+        memories.reverse();
+        memories.truncate(memory_limit as usize);
+        Ok(EntityProfile {
+            entity,
+            memories,
+            pending_memory_ids,
+            refreshed,
+        })
+    }
+
+    // Erasure - also drop KG associations and entities left without memories
+    async fn erase_user_data(
+        &self,
+        req: &EraseUserDataRequest,
+    ) -> Result<ErasureOutcome, MemCubeError> {
+        let (mut outcome, ids) = self.inner.erase_cube_data(req).await?;
+
+        let entity_kg = self.entity_kg.lock().await;
+        let mut touched: Vec<String> = Vec::new();
+        for memory_id in &ids {
+            for entity_id in entity_kg.get_entity_ids_for_memory(memory_id) {
+                entity_kg.dissociate_from_memory(&entity_id, memory_id);
+                if !touched.contains(&entity_id) {
+                    touched.push(entity_id);
+                }
+            }
+        }
+        for entity_id in &touched {
+            if entity_kg.get_memory_ids_for_entity(entity_id).is_empty()
+                && entity_kg.delete_entity(entity_id).is_ok()
+            {
+                outcome.counts.entities += 1;
+            }
         }
+        persist_entities(&entity_kg, self.entity_store.as_ref(), &touched).await;
+        Ok(outcome)
+    }
+}
+
+// ============================================================================
+// Entity-specific API methods (not part of MemCube trait)
+// ============================================================================
+
+impl<G, V, E> EntityAwareMemCube<G, V, E>
+where
+    G: GraphStore + Send + Sync + 'static,
+    V: VecStore + Send + Sync,
+    E: mem_embed::Embedder + Send + Sync,
+{
+    /// Get the cube's entity by name.
+    pub async fn get_entity_by_name(&self, cube_id: &str, name: &str) -> Option<Entity> {
+        let entity_kg = self.entity_kg.lock().await;
+        entity_kg.get_by_name(cube_id, name)
     }
 
-    /// Get entity knowledge graph statistics.
-    pub async fn entity_stats(&self) -> mem_graph::EntityKgStats {
+    /// Fold the linked memories not yet in the entity's description into it, `PROFILE_BATCH`
+    /// per LLM call. When a memory the description was built from is gone (erased, split off)
+    /// the description is rebuilt from scratch. `None` when there is nothing new to fold in.
+    async fn refresh_profile(
+        &self,
+        entity: &Entity,
+        memories: &[MemoryItem],
+    ) -> Result<Option<Entity>, MemCubeError> {
+        let folded = &entity.metadata.description_memory_ids;
+        let rebuild = folded
+            .iter()
+            .any(|id| !memories.iter().any(|m| &m.id == id));
+        let (mut description, mut memory_ids) = if rebuild {
+            (None, Vec::new())
+        } else {
+            (entity.description.clone(), folded.clone())
+        };
+        let pending: Vec<&MemoryItem> = memories
+            .iter()
+            .filter(|m| !memory_ids.contains(&m.id))
+            .collect();
+        if pending.is_empty() {
+            return Ok(None);
+        }
+        let llm_client = self
+            .inner
+            .llm_client
+            .as_ref()
+            .ok_or_else(|| MemCubeError::Other("LLM client not configured".to_string()))?;
+        for batch in pending.chunks(PROFILE_BATCH) {
+            let prompt = profile_prompt(entity, description.as_deref(), batch);
+            let summary = llm_client
+                .complete(&prompt)
+                .await
+                .map_err(|e| MemCubeError::Other(format!("LLM error: {}", e)))?;
+            description = Some(summary.trim().to_string());
+            memory_ids.extend(batch.iter().map(|m| m.id.clone()));
+        }
         let entity_kg = self.entity_kg.lock().await;
-        entity_kg.stats()
+        let updated = entity_kg
+            .set_description(&entity.id, description.unwrap_or_default(), memory_ids)
+            .map_err(kg_error)?;
+        persist_entities(
+            &entity_kg,
+            self.entity_store.as_ref(),
+            std::slice::from_ref(&entity.id),
+        )
+        .await;
+        Ok(Some(updated))
     }
+
+    /// Memories by id, in the given order, skipping missing, tombstoned and foreign ones;
+    /// text and metadata are redacted like search output.
+    async fn visible_memories(
+        &self,
+        cube_ids: &[String],
+        ids: &[String],
+    ) -> Result<Vec<MemoryItem>, MemCubeError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let nodes = self
+            .inner
+            .graph
+            .get_nodes(ids, false)
+            .await
+            .map_err(MemCubeError::Graph)?;
+        let mut memories = Vec::new();
+        for memory_id in ids {
+            let Some(node) = nodes.iter().find(|n| &n.id == memory_id) else {
+                continue;
+            };
+            let owner = NaiveMemCube::<G, V, E>::node_owner(&node.metadata);
+            let tombstoned =
+                node.metadata.get("state").and_then(|v| v.as_str()) == Some("tombstone");
+            if tombstoned || !cube_ids.iter().any(|c| c == owner) {
+                continue;
+            }
+            let mut memory = node.memory.clone();
+            let mut metadata = node.metadata.clone();
+            self.inner.redact_output(owner, &mut memory, &mut metadata);
+            memories.push(MemoryItem {
+                id: node.id.clone(),
+                memory,
+                metadata,
+            });
+        }
+        Ok(memories)
+    }
+}
+
+/// Memories folded into an entity description per LLM call.
+const PROFILE_BATCH: usize = 20;
+
+fn profile_prompt(entity: &Entity, description: Option<&str>, memories: &[&MemoryItem]) -> String {
+    let content = memories
+        .iter()
+        .map(|m| m.memory.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n");
+    match description {
+        Some(description) => format!(
+            "实体「{}」（类型：{}）的现有描述如下：\n\n{}\n\n请结合以下新的记忆更新该描述，保留仍然成立的信息，不超过200字，只输出描述本身：\n\n{}",
+            entity.name, entity.entity_type, description, content
+        ),
+        None => format!(
+            "请根据以下记忆为实体「{}」（类型：{}）写一段不超过200字的描述，概括关于它的关键信息，只输出描述本身：\n\n{}",
+            entity.name, entity.entity_type, content
+        ),
+    }
+}
+
+/// Reject validity bounds that are not timestamps or that end before they start.
+fn check_validity(validity: &Validity) -> Result<(), MemCubeError> {
+    let parse = |field: &str, raw: &Option<String>| match raw {
+        Some(raw) => parse_timestamp(raw)
+            .map(Some)
+            .ok_or_else(|| MemCubeError::BadRequest(format!("invalid {}: {}", field, raw))),
+        None => Ok(None),
+    };
+    let from = parse("valid_from", &validity.valid_from)?;
+    let to = parse("valid_to", &validity.valid_to)?;
+    if let (Some(from), Some(to)) = (from, to) {
+        if to <= from {
+            return Err(MemCubeError::BadRequest(
+                "valid_to must be after valid_from".to_string(),
+            ));
+        }
+    }
+    if !(0.0..=1.0).contains(&validity.confidence) {
+        return Err(MemCubeError::BadRequest(
+            "confidence must be between 0 and 1".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Map a KG error to a cube error (missing entities are 404, invalid requests 400).
+fn kg_error(e: EntityKgError) -> MemCubeError {
+    match e {
+        EntityKgError::EntityNotFound(id) => {
+            MemCubeError::NotFound(format!("entity not found: {}", id))
+        }
+        EntityKgError::InvalidOperation(_)
+        | EntityKgError::DuplicateEntity
+        | EntityKgError::CrossCubeRelation => MemCubeError::BadRequest(e.to_string()),
+        other => MemCubeError::Other(other.to_string()),
+    }
+}
+
+/// Index one extraction result into the cube's part of the KG. Returns the ids of every entity
+/// that was created or changed (including relation sources).
+fn index_extraction(
+    entity_kg: &EntityKnowledgeGraph,
+    cube_id: &str,
+    result: &ExtractionResult,
+    memory_id: &str,
+    config: &EntityCubeConfig,
+) -> Vec<String> {
+    let mut touched: Vec<String> = Vec::new();
+    for entity in result.entities.iter().take(config.max_entities_per_memory) {
+        match entity_kg.upsert_entity(cube_id, entity, memory_id) {
+            Ok((id, _)) => {
+                if !touched.contains(&id) {
+                    touched.push(id);
+                }
+            }
+            Err(e) => {
+                tracing::warn!(memory_id = memory_id, error = %e, "Failed to upsert entity");
+            }
+        }
+    }
+
+    if config.extract_relations {
+        for relation in &result.relations {
+            if let Err(e) = entity_kg.add_relation_by_name(
+                cube_id,
+                &relation.source_text,
+                &relation.target_text,
+                relation.relation_type.clone(),
+                Validity {
+                    source_memory_ids: vec![memory_id.to_string()],
+                    confidence: relation.confidence,
+                    ..Validity::default()
+                },
+            ) {
+                tracing::warn!(
+                    source = %relation.source_text,
+                    target = %relation.target_text,
+                    error = %e,
+                    "Failed to add entity relation"
+                );
+                continue;
+            }
+            let normalized = entity_kg.normalize_name(&relation.source_text);
+            if let Some(id) = entity_kg.get_entity_id_by_normalized_name(cube_id, &normalized) {
+                if !touched.contains(&id) {
+                    touched.push(id);
+                }
+            }
+        }
+    }
+    touched
+}
+
+/// Write the current KG state of the given entities through to the durable store: entities that
+/// still exist are upserted with their outgoing relations, missing ones are deleted. Failures are
+/// logged; the in-memory graph stays authoritative until the next successful write.
+async fn persist_entities(
+    entity_kg: &EntityKnowledgeGraph,
+    store: Option<&Arc<dyn EntityStore + Send + Sync>>,
+    entity_ids: &[String],
+) {
+    let Some(store) = store else {
+        return;
+    };
+    for entity_id in entity_ids {
+        let result = match entity_kg.get_by_id(entity_id) {
+            Some(entity) => {
+                store
+                    .upsert_entity(&entity, &entity_kg.outgoing_relations(entity_id))
+                    .await
+            }
+            None => store.delete_entity(entity_id).await,
+        };
+        if let Err(e) = result {
+            tracing::warn!(entity_id = %entity_id, error = %e, "Failed to persist entity");
+        }
+    }
+}
+
+async fn export_snapshot(
+    entity_kg: &Arc<Mutex<EntityKnowledgeGraph>>,
+    path: &Path,
+) -> Result<(), MemCubeError> {
+    let snapshot = entity_kg.lock().await.snapshot();
+    let json =
+        serde_json::to_vec_pretty(&snapshot).map_err(|e| MemCubeError::Other(e.to_string()))?;
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, json)
+        .await
+        .map_err(|e| MemCubeError::Other(format!("entity snapshot write failed: {}", e)))?;
+    tokio::fs::rename(&tmp, path)
+        .await
+        .map_err(|e| MemCubeError::Other(format!("entity snapshot write failed: {}", e)))
 }
 
 // ============================================================================
@@ -0,0 +1,67 @@
+//! In-memory feedback event store.
+
+use mem_types::{FeedbackEvent, FeedbackListOptions, FeedbackStore, FeedbackStoreError};
+use tokio::sync::RwLock;
+
+/// In-memory implementation of FeedbackStore (process lifetime only).
+pub struct InMemoryFeedbackStore {
+    events: RwLock<Vec<FeedbackEvent>>,
+}
+
+impl InMemoryFeedbackStore {
+    pub fn new() -> Self {
+        Self {
+            events: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for InMemoryFeedbackStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl FeedbackStore for InMemoryFeedbackStore {
+    async fn append(&self, event: FeedbackEvent) -> Result<(), FeedbackStoreError> {
+        self.events.write().await.push(event);
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        opts: &FeedbackListOptions,
+    ) -> Result<Vec<FeedbackEvent>, FeedbackStoreError> {
+        let guard = self.events.read().await;
+        let limit = opts.limit.map(|l| l as usize).unwrap_or(usize::MAX);
+        Ok(guard
+            .iter()
+            .filter(|e| opts.user_id.as_ref().is_none_or(|u| &e.user_id == u))
+            .filter(|e| opts.cube_id.as_ref().is_none_or(|c| &e.cube_id == c))
+            .filter(|e| {
+                opts.since
+                    .as_ref()
+                    .is_none_or(|s| e.timestamp.as_str() >= s.as_str())
+            })
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn purge(
+        &self,
+        user_id: Option<&str>,
+        cube_id: Option<&str>,
+    ) -> Result<u64, FeedbackStoreError> {
+        if user_id.is_none() && cube_id.is_none() {
+            return Ok(0);
+        }
+        let mut guard = self.events.write().await;
+        let before = guard.len();
+        guard.retain(|e| {
+            user_id.is_some_and(|u| e.user_id != u) || cube_id.is_some_and(|c| e.cube_id != c)
+        });
+        Ok((before - guard.len()) as u64)
+    }
+}
@@ -0,0 +1,222 @@
+//! Graph export: collect a cube's memories and the edges between them into a
+//! `GraphDocument`, filtered by scope, session or the subgraph around a root memory.
+
+use crate::NaiveMemCube;
+use mem_embed::Embedder;
+use mem_graph::{ExportEdge, ExportNode, GraphDocument, GraphStore};
+use mem_types::{
+    GraphDirection, GraphExportData, GraphExportRequest, GraphExportResponse, MemCubeError,
+    MemoryNode, MemoryScope,
+};
+use mem_vec::VecStore;
+use std::collections::{BTreeMap, HashMap};
+
+/// Edges fetched per `list_edges` call.
+const EDGE_PAGE_SIZE: usize = 1000;
+/// Characters of memory text used as a node label.
+const LABEL_CHARS: usize = 80;
+
+fn is_tombstone(node: &MemoryNode) -> bool {
+    node.metadata.get("state").and_then(|v| v.as_str()) == Some("tombstone")
+}
+
+/// Whether a node belongs to `scope`. Case, `_` and a `Memory` suffix are ignored, so
+/// `LongTermMemory`, `long_term` and `longterm` all match; nodes without a scope are long-term.
+pub(crate) fn scope_matches(node: &MemoryNode, scope: &str) -> bool {
+    let normalize = |s: &str| s.to_lowercase().replace('_', "").replace("memory", "");
+    let node_scope = node
+        .metadata
+        .get("scope")
+        .and_then(|v| v.as_str())
+        .unwrap_or(MemoryScope::LongTermMemory.as_str());
+    normalize(node_scope) == normalize(scope)
+}
+
+/// Memory node label: the start of its text.
+fn memory_label(memory: &str) -> String {
+    let mut chars = memory.chars();
+    let label: String = chars.by_ref().take(LABEL_CHARS).collect();
+    if chars.next().is_some() {
+        format!("{}…", label)
+    } else {
+        label
+    }
+}
+
+pub(crate) fn graph_export_response(
+    req: &GraphExportRequest,
+    document: &GraphDocument,
+) -> GraphExportResponse {
+    GraphExportResponse {
+        code: 200,
+        message: "Export completed".to_string(),
+        data: Some(GraphExportData {
+            format: req.format,
+            total_nodes: document.nodes.len() as u32,
+            total_edges: document.edges.len() as u32,
+            data: document.render(req.format),
+        }),
+    }
+}
+
+impl<G, V, E> NaiveMemCube<G, V, E>
+where
+    G: GraphStore + Send + Sync + 'static,
+    V: VecStore + Send + Sync,
+    E: Embedder + Send + Sync,
+{
+    /// Every memory of the cube across all scopes, tombstones included.
+    pub(crate) async fn all_memory_nodes(
+        &self,
+        user_name: &str,
+    ) -> Result<Vec<MemoryNode>, MemCubeError> {
+        let mut nodes = Vec::new();
+        for scope in [
+            MemoryScope::WorkingMemory,
+            MemoryScope::UserMemory,
+            MemoryScope::LongTermMemory,
+        ] {
+            nodes.extend(
+                self.graph
+                    .get_all_memory_items(scope.as_str(), user_name, false)
+                    .await
+                    .map_err(MemCubeError::Graph)?,
+            );
+        }
+        Ok(nodes)
+    }
+
+    /// The memories selected by `req` as `memory` nodes, with the cube's edges between them.
+    pub(crate) async fn graph_document(
+        &self,
+        req: &GraphExportRequest,
+    ) -> Result<GraphDocument, MemCubeError> {
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        let mut nodes = match req.root_memory_id {
+            Some(ref root) => self.subgraph_nodes(req, root, user_name).await?,
+            None => self.all_memory_nodes(user_name).await?,
+        };
+        nodes.retain(|n| {
+            (req.include_deleted || !is_tombstone(n))
+                && req.scope.as_deref().is_none_or(|s| scope_matches(n, s))
+                && req.session_id.as_deref().is_none_or(|s| {
+                    n.metadata.get("session_id").and_then(|v| v.as_str()) == Some(s)
+                })
+        });
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes.dedup_by(|a, b| a.id == b.id);
+
+        let mut edges = Vec::new();
+        let mut after: Option<String> = None;
+        loop {
+            let page = self
+                .graph
+                .list_edges(
+                    user_name,
+                    None,
+                    req.relation.as_deref(),
+                    GraphDirection::Both,
+                    after.as_deref(),
+                    EDGE_PAGE_SIZE,
+                )
+                .await
+                .map_err(MemCubeError::Graph)?;
+            let more = page.len() == EDGE_PAGE_SIZE;
+            after = page.last().map(|e| e.id.clone());
+            edges.extend(page.into_iter().filter(|e| {
+                nodes.binary_search_by(|n| n.id.cmp(&e.from)).is_ok()
+                    && nodes.binary_search_by(|n| n.id.cmp(&e.to)).is_ok()
+            }));
+            if !more {
+                break;
+            }
+        }
+
+        Ok(GraphDocument {
+            nodes: nodes
+                .into_iter()
+                .map(|n| {
+                    let n = self.visible_item(user_name, n);
+                    let mut attributes: BTreeMap<String, serde_json::Value> =
+                        n.metadata.into_iter().collect();
+                    attributes.insert(
+                        "memory".to_string(),
+                        serde_json::Value::String(n.memory.clone()),
+                    );
+                    ExportNode {
+                        label: memory_label(&n.memory),
+                        id: n.id,
+                        kind: "memory".to_string(),
+                        attributes,
+                    }
+                })
+                .collect(),
+            edges: edges
+                .into_iter()
+                .map(|e| self.visible_edge(user_name, e))
+                .map(|e| ExportEdge {
+                    id: e.id,
+                    source: e.from,
+                    target: e.to,
+                    relation: e.relation,
+                    attributes: e.metadata.into_iter().collect(),
+                })
+                .collect(),
+        })
+    }
+
+    /// The root memory and the memories within `req.depth` hops of it (breadth-first).
+    async fn subgraph_nodes(
+        &self,
+        req: &GraphExportRequest,
+        root: &str,
+        user_name: &str,
+    ) -> Result<Vec<MemoryNode>, MemCubeError> {
+        let not_found = || MemCubeError::NotFound(format!("memory not found: {}", root));
+        let root_node = self
+            .graph
+            .get_node(root, false)
+            .await
+            .map_err(MemCubeError::Graph)?
+            .ok_or_else(not_found)?;
+        if Self::node_owner(&root_node.metadata) != user_name
+            || (is_tombstone(&root_node) && !req.include_deleted)
+        {
+            return Err(not_found());
+        }
+        let mut seen: HashMap<String, MemoryNode> = HashMap::new();
+        let mut frontier = vec![root.to_string()];
+        seen.insert(root.to_string(), root_node);
+        for _ in 0..req.depth {
+            let mut next = Vec::new();
+            for id in &frontier {
+                let neighbors = self
+                    .graph
+                    .get_neighbors(
+                        id,
+                        req.relation.as_deref(),
+                        req.direction,
+                        usize::MAX,
+                        false,
+                        Some(user_name),
+                    )
+                    .await
+                    .map_err(MemCubeError::Graph)?;
+                for neighbor in neighbors {
+                    if (is_tombstone(&neighbor.node) && !req.include_deleted)
+                        || seen.contains_key(&neighbor.node.id)
+                    {
+                        continue;
+                    }
+                    next.push(neighbor.node.id.clone());
+                    seen.insert(neighbor.node.id.clone(), neighbor.node);
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        Ok(seen.into_values().collect())
+    }
+}
@@ -1,8 +1,19 @@
 //! MemCube orchestration: add and search using graph, vector store, and embedder.
 
+mod auto_link;
+mod consistency;
+mod cursor;
 mod entity_cube;
+mod feedback;
+mod graph_export;
 mod naive;
+mod outbox;
+mod pii;
 
+pub use auto_link::{AutoLinkConfig, FOLLOWS, SIMILAR_TO};
 pub use entity_cube::{EntityAwareMemCube, EntityCubeConfig};
+pub use feedback::InMemoryFeedbackStore;
 pub use mem_types::MemCubeError;
 pub use naive::NaiveMemCube;
+pub use outbox::{FileIntentLog, InMemoryIntentLog};
+pub use pii::{decrypt_pii_original, detect_pii, PiiMatch};
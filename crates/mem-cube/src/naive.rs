@@ -1,5 +1,11 @@
 //! NaiveMemCube: single MemCube with text_mem path.
 
+use crate::auto_link::AutoLinkConfig;
+use crate::cursor::{decode_cursor, keyset_page, PageKey};
+use crate::graph_export::{graph_export_response, scope_matches};
+use crate::pii::{
+    apply_pii_policy, apply_pii_policy_to_value, redact_text, redact_value, PiiOutcome,
+};
 use chrono::Utc;
 use mem_embed::{Embedder, LLMClient};
 use mem_graph::GraphStore;
@@ -9,6 +15,32 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Metadata key recording the user who wrote a node or edge (erasure deletes by it).
+pub(crate) const AUTHOR_KEY: &str = "author_id";
+
+/// Metadata the cube writes itself (ids, timestamps, labels); output redaction leaves it alone.
+const SYSTEM_METADATA_KEYS: &[&str] = &[
+    "user_name",
+    AUTHOR_KEY,
+    "scope",
+    "state",
+    "created_at",
+    "updated_at",
+    "last_accessed_at",
+    "last_feedback_at",
+    "analytics_updated_at",
+    "session_id",
+    "task_id",
+    "corrects",
+    "corrected_by",
+    "feedback_type",
+    "memory_type",
+    "pii_kinds",
+];
+
+/// Caller-supplied metadata of a batch item after the PII policy, with the detected kinds.
+type Supplied = (HashMap<String, serde_json::Value>, Vec<String>);
+
 /// Candidate from merging vector/graph/keyword channel hits (id + per-channel scores).
 struct HybridCandidate {
     id: String,
@@ -19,7 +51,8 @@ struct HybridCandidate {
 
 /// MemCube that composes a graph store, vector store, and embedder for add/search.
 pub struct NaiveMemCube<G, V, E> {
-    pub graph: G,
+    /// Graph store; shared so access tracking can write from background tasks.
+    pub graph: Arc<G>,
     pub vec_store: V,
     pub embedder: E,
     /// Default scope for new memories (e.g. LongTermMemory).
@@ -32,17 +65,38 @@ pub struct NaiveMemCube<G, V, E> {
     pub llm_client: Option<Arc<dyn LLMClient + Send + Sync>>,
     /// Optional session store for session management (P1-3).
     pub session_store: Option<Arc<dyn SessionStore + Send + Sync>>,
+    /// Optional feedback event store; enables feedback export.
+    pub feedback_store: Option<Arc<dyn FeedbackStore + Send + Sync>>,
+    /// Ask the LLM client to estimate `importance` when an add does not provide one.
+    pub estimate_importance: bool,
+    /// PII policy per cube id; cubes without an entry use `default_pii_policy`.
+    pub pii_policies: HashMap<String, PiiPolicy>,
+    /// PII policy for cubes without their own entry (None = no PII handling).
+    pub default_pii_policy: Option<PiiPolicy>,
+    /// AES-256 key for `pii_original` and keyed PII hashes.
+    pii_key: Option<[u8; 32]>,
+    /// Auto-linking of new memories (`similar_to` / `follows` edges); None = off.
+    pub auto_link: Option<AutoLinkConfig>,
+    /// Latest memory per (cube, session) for `follows` edges (a bounded cache).
+    pub(crate) session_heads: std::sync::Mutex<HashMap<(String, String), String>>,
+    /// Write-ahead log of multi-store writes; None = best-effort rollback only.
+    pub intent_log: Option<Arc<dyn IntentLog + Send + Sync>>,
+    /// Intents of writes running in this process, by intent id; recovery skips their memories.
+    pub(crate) active_intents: std::sync::Mutex<HashMap<String, WriteIntent>>,
+    /// Serializes read-modify-write of counters in node metadata (access and feedback
+    /// counts) so concurrent updates are not lost.
+    counter_lock: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl<G, V, E> NaiveMemCube<G, V, E>
 where
-    G: GraphStore + Send + Sync,
+    G: GraphStore + Send + Sync + 'static,
     V: VecStore + Send + Sync,
     E: Embedder + Send + Sync,
 {
     pub fn new(graph: G, vec_store: V, embedder: E) -> Self {
         Self {
-            graph,
+            graph: Arc::new(graph),
             vec_store,
             embedder,
             default_scope: "LongTermMemory".to_string(),
@@ -50,6 +104,16 @@ where
             reranker: None,
             llm_client: None,
             session_store: None,
+            feedback_store: None,
+            estimate_importance: false,
+            pii_policies: HashMap::new(),
+            default_pii_policy: None,
+            pii_key: None,
+            auto_link: None,
+            session_heads: std::sync::Mutex::new(HashMap::new()),
+            intent_log: None,
+            active_intents: std::sync::Mutex::new(HashMap::new()),
+            counter_lock: Arc::new(tokio::sync::Mutex::new(())),
         }
     }
 
@@ -83,20 +147,155 @@ where
         self
     }
 
-    fn node_owner(metadata: &HashMap<String, serde_json::Value>) -> &str {
+    /// Attach an optional feedback store for exporting feedback events.
+    pub fn with_feedback_store(
+        mut self,
+        feedback_store: Option<Arc<dyn FeedbackStore + Send + Sync>>,
+    ) -> Self {
+        self.feedback_store = feedback_store;
+        self
+    }
+
+    /// Enable LLM-estimated `importance` for adds that do not set one (requires `llm_client`).
+    pub fn with_importance_estimation(mut self, enabled: bool) -> Self {
+        self.estimate_importance = enabled;
+        self
+    }
+
+    /// Set the PII policy for one cube.
+    pub fn with_pii_policy(mut self, cube_id: impl Into<String>, policy: PiiPolicy) -> Self {
+        self.pii_policies.insert(cube_id.into(), policy);
+        self
+    }
+
+    /// Set the PII policy for cubes without their own policy.
+    pub fn with_default_pii_policy(mut self, policy: Option<PiiPolicy>) -> Self {
+        self.default_pii_policy = policy;
+        self
+    }
+
+    /// Set the key used to encrypt `pii_original` and to key PII hashes.
+    pub fn with_pii_key(mut self, key: Option<[u8; 32]>) -> Self {
+        self.pii_key = key;
+        self
+    }
+
+    /// Enable automatic `similar_to` / `follows` edges for new memories.
+    pub fn with_auto_link(mut self, config: Option<AutoLinkConfig>) -> Self {
+        self.auto_link = config;
+        self
+    }
+
+    /// Attach a write intent log so interrupted multi-store writes can be recovered.
+    pub fn with_intent_log(mut self, intent_log: Option<Arc<dyn IntentLog + Send + Sync>>) -> Self {
+        self.intent_log = intent_log;
+        self
+    }
+
+    pub(crate) fn node_owner(metadata: &HashMap<String, serde_json::Value>) -> &str {
         metadata
             .get("user_name")
             .and_then(|v| v.as_str())
             .unwrap_or("")
     }
 
-    fn parse_cursor(cursor: Option<&str>) -> Result<usize, MemCubeError> {
-        match cursor {
-            Some(c) => c
-                .parse::<usize>()
-                .map_err(|_| MemCubeError::BadRequest("invalid graph cursor".to_string())),
-            None => Ok(0),
+    /// Cost options only make sense with `cost_field`; reject them on hop-count queries.
+    fn check_unweighted(
+        cost_transform: CostTransform,
+        default_cost: Option<f64>,
+        max_cost: Option<f64>,
+    ) -> Result<(), MemCubeError> {
+        if cost_transform != CostTransform::Value || default_cost.is_some() || max_cost.is_some() {
+            return Err(MemCubeError::BadRequest(
+                "cost_transform, default_cost and max_cost require cost_field".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Cheapest paths by total edge cost (`cost_field` set), cheapest first.
+    async fn weighted_graph_paths(
+        &self,
+        req: &GraphPathsRequest,
+    ) -> Result<Vec<GraphPathData>, MemCubeError> {
+        let cost_field = req.cost_field.as_deref().unwrap_or_default();
+        if cost_field.trim().is_empty() {
+            return Err(MemCubeError::BadRequest(
+                "cost_field must not be empty".to_string(),
+            ));
+        }
+        let default_cost = req.default_cost.unwrap_or(1.0);
+        for (name, value) in [
+            ("default_cost", Some(default_cost)),
+            ("max_cost", req.max_cost),
+        ] {
+            if value.is_some_and(|v| !v.is_finite() || v < 0.0) {
+                return Err(MemCubeError::BadRequest(format!(
+                    "{} must be a non-negative number",
+                    name
+                )));
+            }
+        }
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        let query = mem_graph::WeightedPathQuery {
+            cost_field,
+            cost_transform: req.cost_transform,
+            default_cost,
+            max_cost: req.max_cost,
+            relation: req.relation.as_deref(),
+            direction: req.direction,
+            max_depth: req.max_depth as usize,
+            include_deleted: req.include_deleted,
+            user_name: Some(user_name),
+        };
+        let paths = mem_graph::weighted_paths(
+            self.graph.as_ref(),
+            &req.source_memory_id,
+            &req.target_memory_id,
+            &query,
+            req.top_k_paths as usize,
+        )
+        .await
+        .map_err(|e| {
+            let msg = e.to_string();
+            if msg.contains("not found") || msg.contains("access denied") {
+                MemCubeError::NotFound(format!(
+                    "memory not found: {} or {}",
+                    req.source_memory_id, req.target_memory_id
+                ))
+            } else {
+                MemCubeError::Graph(e)
+            }
+        })?;
+        if paths.is_empty() {
+            return Err(MemCubeError::NotFound(format!(
+                "path not found: {} -> {}",
+                req.source_memory_id, req.target_memory_id
+            )));
+        }
+
+        let mut out = Vec::with_capacity(paths.len());
+        for (path, cost) in paths {
+            let nodes = self
+                .graph
+                .get_nodes(&path.node_ids, false)
+                .await
+                .map_err(MemCubeError::Graph)?;
+            out.push(GraphPathData {
+                hops: path.edges.len() as u32,
+                nodes: nodes
+                    .into_iter()
+                    .map(|n| self.visible_item(user_name, n))
+                    .collect(),
+                edges: path
+                    .edges
+                    .into_iter()
+                    .map(|e| self.visible_edge(user_name, e))
+                    .collect(),
+                cost: Some(cost),
+            });
         }
+        Ok(out)
     }
 
     fn normalize_scope(scope: &str) -> Option<&'static str> {
@@ -255,12 +454,430 @@ where
             })
             .collect()
     }
+
+    fn importance_or_error(value: &serde_json::Value) -> Result<f64, MemCubeError> {
+        MemorySignals::parse_importance(value).ok_or_else(|| {
+            MemCubeError::BadRequest("importance must be a number between 0 and 1".to_string())
+        })
+    }
+
+    /// Importance for a new memory: explicit `info.importance`, else LLM estimate when
+    /// enabled, else the default.
+    async fn resolve_importance(
+        &self,
+        req: &ApiAddRequest,
+        content: &str,
+    ) -> Result<f64, MemCubeError> {
+        if let Some(v) = req.info.as_ref().and_then(|i| i.get("importance")) {
+            return Self::importance_or_error(v);
+        }
+        if self.estimate_importance {
+            if let Some(importance) = self.estimate_importance_with_llm(content).await {
+                return Ok(importance);
+            }
+        }
+        Ok(MemorySignals::DEFAULT_IMPORTANCE)
+    }
+
+    async fn estimate_importance_with_llm(&self, content: &str) -> Option<f64> {
+        let llm_client = self.llm_client.as_ref()?;
+        let prompt = format!(
+            "Rate how important the following memory is to remember long-term about the user, \
+             as a single number between 0 and 1. Reply with the number only.\n\n{}",
+            content
+        );
+        match llm_client.complete(&prompt).await {
+            Ok(reply) => {
+                let parsed = reply
+                    .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .find_map(|tok| tok.parse::<f64>().ok())
+                    .map(|v| v.clamp(0.0, 1.0));
+                if parsed.is_none() {
+                    tracing::warn!(reply = %reply, "unparseable importance estimate");
+                }
+                parsed
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "importance estimation failed");
+                None
+            }
+        }
+    }
+
+    /// Bump `access_count` and `last_accessed_at` for retrieved memories on a background
+    /// task so reads do not wait for the extra graph writes.
+    fn track_access(&self, ids: Vec<String>, user_name: &str) {
+        if ids.is_empty() {
+            return;
+        }
+        let graph = Arc::clone(&self.graph);
+        let lock = Arc::clone(&self.counter_lock);
+        let user_name = user_name.to_string();
+        tokio::spawn(async move {
+            let _guard = lock.lock().await;
+            let now = Utc::now().to_rfc3339();
+            for id in ids {
+                let count = match graph.get_node(&id, false).await {
+                    Ok(Some(node)) => MemorySignals::from_metadata(&node.metadata).access_count,
+                    _ => continue,
+                };
+                let mut fields = HashMap::new();
+                fields.insert("access_count".to_string(), serde_json::json!(count + 1));
+                fields.insert(
+                    "last_accessed_at".to_string(),
+                    serde_json::Value::String(now.clone()),
+                );
+                if let Err(e) = graph.update_node(&id, &fields, Some(&user_name)).await {
+                    tracing::warn!(memory_id = %id, error = %e, "failed to record memory access");
+                }
+            }
+        });
+    }
+
+    /// Returns `Some(true)` for a correction, `Some(false)` for an annotation, and `None`
+    /// for a regular (non-feedback) add.
+    fn feedback_kind_or_error(req: &ApiAddRequest) -> Result<Option<bool>, MemCubeError> {
+        if !req.is_feedback {
+            return Ok(None);
+        }
+        match req.info.as_ref().and_then(|i| i.get("feedback_type")) {
+            None => Ok(Some(false)),
+            Some(v) => match v.as_str() {
+                Some("annotation") => Ok(Some(false)),
+                Some("correction") => Ok(Some(true)),
+                _ => Err(MemCubeError::BadRequest(
+                    "feedback_type must be \"annotation\" or \"correction\"".to_string(),
+                )),
+            },
+        }
+    }
+
+    /// Whether `node` belongs to cube `user_name` and is not tombstoned.
+    fn is_live_in(node: &MemoryNode, user_name: &str) -> bool {
+        Self::node_owner(&node.metadata) == user_name
+            && node.metadata.get("state").and_then(|v| v.as_str()) != Some("tombstone")
+    }
+
+    /// Increment a counter in node metadata and merge `extra` into the same update.
+    /// Returns the node metadata after the update.
+    async fn bump_counter(
+        &self,
+        id: &str,
+        user_name: &str,
+        key: &str,
+        mut extra: HashMap<String, serde_json::Value>,
+    ) -> Result<HashMap<String, serde_json::Value>, MemCubeError> {
+        let _guard = self.counter_lock.lock().await;
+        let node = self
+            .graph
+            .get_node(id, false)
+            .await
+            .map_err(MemCubeError::Graph)?
+            .filter(|n| Self::node_owner(&n.metadata) == user_name)
+            .ok_or_else(|| MemCubeError::NotFound(format!("memory not found: {}", id)))?;
+        let current = node.metadata.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+        extra.insert(key.to_string(), serde_json::json!(current + 1));
+        self.graph
+            .update_node(id, &extra, Some(user_name))
+            .await
+            .map_err(MemCubeError::Graph)?;
+        let mut metadata = node.metadata;
+        metadata.extend(extra);
+        Ok(metadata)
+    }
+
+    /// Apply a feedback add to the memories it references: append a note and, for
+    /// corrections, mark them `corrected_by` and count a wrong judgement.
+    async fn apply_feedback_add(
+        &self,
+        feedback_id: &str,
+        content: &str,
+        targets: &[String],
+        is_correction: bool,
+        user_id: &str,
+        user_name: &str,
+    ) {
+        let now = Utc::now().to_rfc3339();
+        let mut events = Vec::with_capacity(targets.len());
+        for target in targets {
+            let note = serde_json::json!({
+                "memory_id": feedback_id,
+                "memory": content,
+                "type": if is_correction { "correction" } else { "annotation" },
+                "created_at": now,
+            });
+            let result = async {
+                let _guard = self.counter_lock.lock().await;
+                let node = self
+                    .graph
+                    .get_node(target, false)
+                    .await
+                    .map_err(MemCubeError::Graph)?
+                    .filter(|n| Self::is_live_in(n, user_name))
+                    .ok_or_else(|| {
+                        MemCubeError::NotFound(format!("memory not found: {}", target))
+                    })?;
+                let mut notes = node
+                    .metadata
+                    .get("feedback_notes")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                notes.push(note);
+                let mut fields = HashMap::new();
+                fields.insert(
+                    "feedback_notes".to_string(),
+                    serde_json::Value::Array(notes),
+                );
+                if is_correction {
+                    let wrong = MemorySignals::from_metadata(&node.metadata).wrong_count;
+                    fields.insert(
+                        "corrected_by".to_string(),
+                        serde_json::Value::String(feedback_id.to_string()),
+                    );
+                    fields.insert("feedback_wrong".to_string(), serde_json::json!(wrong + 1));
+                }
+                self.graph
+                    .update_node(target, &fields, Some(user_name))
+                    .await
+                    .map_err(MemCubeError::Graph)
+            }
+            .await;
+            if let Err(e) = result {
+                tracing::warn!(memory_id = %target, error = %e, "failed to apply feedback");
+                continue;
+            }
+            events.push(FeedbackEvent {
+                event_id: Uuid::new_v4().to_string(),
+                user_id: user_id.to_string(),
+                cube_id: user_name.to_string(),
+                memory_id: target.clone(),
+                label: is_correction.then_some(FeedbackLabel::Wrong),
+                query: None,
+                comment: Some(content.to_string()),
+                feedback_memory_id: Some(feedback_id.to_string()),
+                timestamp: now.clone(),
+            });
+        }
+        self.record_feedback_events(events).await;
+    }
+
+    async fn record_feedback_events(&self, events: Vec<FeedbackEvent>) {
+        let Some(ref store) = self.feedback_store else {
+            return;
+        };
+        for event in events {
+            if let Err(e) = store.append(event).await {
+                tracing::warn!(error = %e, "failed to record feedback event");
+            }
+        }
+    }
+
+    fn pii_policy_for(&self, cube_id: &str) -> Option<&PiiPolicy> {
+        self.pii_policies
+            .get(cube_id)
+            .or(self.default_pii_policy.as_ref())
+    }
+
+    /// Apply the cube's PII policy to incoming text (None when the cube has no policy).
+    fn ingest_pii(&self, cube_id: &str, text: &str) -> Result<Option<PiiOutcome>, MemCubeError> {
+        self.pii_policy_for(cube_id)
+            .map(|policy| apply_pii_policy(text, policy, self.pii_key.as_ref()))
+            .transpose()
+    }
+
+    /// Apply the cube's PII policy to caller-supplied metadata values (reject fails the write).
+    /// Returns the detected kinds.
+    fn ingest_pii_metadata(
+        &self,
+        cube_id: &str,
+        metadata: &mut HashMap<String, serde_json::Value>,
+    ) -> Result<Vec<String>, MemCubeError> {
+        let mut kinds = Vec::new();
+        if let Some(policy) = self.pii_policy_for(cube_id) {
+            for value in metadata.values_mut() {
+                apply_pii_policy_to_value(value, policy, self.pii_key.as_ref(), &mut kinds)?;
+            }
+        }
+        // Map order is arbitrary; keep the recorded kinds stable.
+        kinds.sort();
+        Ok(kinds)
+    }
+
+    fn insert_pii_metadata(
+        metadata: &mut HashMap<String, serde_json::Value>,
+        outcome: Option<&PiiOutcome>,
+        metadata_kinds: &[String],
+    ) {
+        let mut kinds = outcome.map(|o| o.kinds.clone()).unwrap_or_default();
+        for kind in metadata_kinds {
+            if !kinds.contains(kind) {
+                kinds.push(kind.clone());
+            }
+        }
+        if !kinds.is_empty() {
+            metadata.insert("pii_kinds".to_string(), serde_json::json!(kinds));
+        }
+        if let Some(original) = outcome.and_then(|o| o.encrypted_original.as_ref()) {
+            metadata.insert(
+                "pii_original".to_string(),
+                serde_json::Value::String(original.clone()),
+            );
+        }
+    }
+
+    /// How stored text is redacted on the way out (None: returned as stored).
+    fn output_pii_action(&self, cube_id: &str) -> Option<PiiAction> {
+        match self.pii_policy_for(cube_id).map(|p| p.action) {
+            Some(PiiAction::Hash) => Some(PiiAction::Hash),
+            Some(PiiAction::Mask) | Some(PiiAction::Reject) => Some(PiiAction::Mask),
+            _ => None,
+        }
+    }
+
+    fn redact_metadata(
+        &self,
+        action: PiiAction,
+        metadata: &mut HashMap<String, serde_json::Value>,
+    ) {
+        for (key, value) in metadata.iter_mut() {
+            if !SYSTEM_METADATA_KEYS.contains(&key.as_str()) {
+                redact_value(value, action, self.pii_key.as_ref());
+            }
+        }
+    }
+
+    /// Make a memory leaving the cube respect the cube's policy: never return `pii_original`,
+    /// and redact text and metadata stored before a mask/hash/reject policy was set.
+    pub(crate) fn redact_output(
+        &self,
+        cube_id: &str,
+        memory: &mut String,
+        metadata: &mut HashMap<String, serde_json::Value>,
+    ) {
+        metadata.remove("pii_original");
+        if let Some(action) = self.output_pii_action(cube_id) {
+            *memory = redact_text(memory, action, self.pii_key.as_ref());
+            self.redact_metadata(action, metadata);
+        }
+    }
+
+    /// `node` as the API returns it (see `redact_output`).
+    pub(crate) fn visible_item(&self, cube_id: &str, node: MemoryNode) -> MemoryItem {
+        let MemoryNode {
+            id,
+            mut memory,
+            mut metadata,
+            ..
+        } = node;
+        self.redact_output(cube_id, &mut memory, &mut metadata);
+        MemoryItem {
+            id,
+            memory,
+            metadata,
+        }
+    }
+
+    /// `edge` as the API returns it: metadata redacted like a memory's.
+    pub(crate) fn visible_edge(&self, cube_id: &str, mut edge: MemoryEdge) -> MemoryEdge {
+        if let Some(action) = self.output_pii_action(cube_id) {
+            self.redact_metadata(action, &mut edge.metadata);
+        }
+        edge
+    }
+
+    /// Erase what the user wrote (or, with `whole_cube`, everything in the cube) from graph,
+    /// vector and keyword stores, plus the matching feedback events and, for a user erased from
+    /// every cube, their sessions. Returns the outcome and the deleted memory ids so wrappers can
+    /// clean their own indexes.
+    pub(crate) async fn erase_cube_data(
+        &self,
+        req: &EraseUserDataRequest,
+    ) -> Result<(ErasureOutcome, Vec<String>), MemCubeError> {
+        req.validate().map_err(MemCubeError::BadRequest)?;
+        let cubes = match req.mem_cube_id {
+            Some(ref cube) => vec![cube.clone()],
+            None => self
+                .graph
+                .author_cubes(&req.user_id)
+                .await
+                .map_err(MemCubeError::Graph)?,
+        };
+        let mut counts = ErasureCounts::default();
+        let mut ids = Vec::new();
+        // Keep background counter updates from racing the deletes.
+        let _guard = self.counter_lock.lock().await;
+
+        for cube in &cubes {
+            let deleted = if req.whole_cube {
+                self.graph.delete_all_by_user(cube).await
+            } else {
+                self.graph.delete_by_author(cube, &req.user_id).await
+            }
+            .map_err(MemCubeError::Graph)?;
+            counts.memories += deleted.len() as u64;
+
+            if !deleted.is_empty() {
+                let present = self
+                    .vec_store
+                    .get_by_ids(&deleted, None)
+                    .await
+                    .map_err(MemCubeError::Vec)?;
+                counts.vectors += present.len() as u64;
+                self.vec_store
+                    .delete(&deleted, None)
+                    .await
+                    .map_err(MemCubeError::Vec)?;
+            }
+
+            if let Some(ref kw) = self.keyword_store {
+                for id in &deleted {
+                    if kw.remove(id, Some(cube)).await.is_ok() {
+                        counts.keyword_docs += 1;
+                    }
+                }
+            }
+            ids.extend(deleted);
+        }
+
+        // Sessions are keyed by user, not cube: only erasing the user everywhere removes them.
+        if req.mem_cube_id.is_none() {
+            if let Some(ref store) = self.session_store {
+                loop {
+                    let (sessions, _) = store
+                        .list_sessions(&req.user_id, 100, None)
+                        .await
+                        .map_err(|e| MemCubeError::Other(e.to_string()))?;
+                    if sessions.is_empty() {
+                        break;
+                    }
+                    for session in sessions {
+                        store
+                            .delete_session(&session.session_id, &req.user_id)
+                            .await
+                            .map_err(|e| MemCubeError::Other(e.to_string()))?;
+                        counts.sessions += 1;
+                    }
+                }
+            }
+        }
+
+        if let Some(ref store) = self.feedback_store {
+            let user_id = (!req.whole_cube).then_some(req.user_id.as_str());
+            counts.feedback_events = store
+                .purge(user_id, req.mem_cube_id.as_deref())
+                .await
+                .map_err(|e| MemCubeError::Other(e.to_string()))?;
+        }
+
+        Ok((ErasureOutcome { cubes, counts }, ids))
+    }
 }
 
 #[async_trait::async_trait]
 impl<G, V, E> MemCube for NaiveMemCube<G, V, E>
 where
-    G: GraphStore + Send + Sync,
+    G: GraphStore + Send + Sync + 'static,
     V: VecStore + Send + Sync,
     E: Embedder + Send + Sync,
 {
@@ -271,6 +888,47 @@ where
         let cube_ids = req.writable_cube_ids();
         let user_name = cube_ids.first().map(String::as_str).unwrap_or(&req.user_id);
         let scope = Self::resolve_scope_or_error(req, &self.default_scope)?;
+        // Before the LLM and the embedder see the text.
+        let pii = self.ingest_pii(user_name, &content)?;
+        let content = pii.as_ref().map(|o| o.text.clone()).unwrap_or(content);
+        let mut supplied = HashMap::new();
+        if let Some(ref custom_tags) = req.custom_tags {
+            supplied.insert("custom_tags".to_string(), serde_json::json!(custom_tags));
+        }
+        if let Some(ref chat_history) = req.chat_history {
+            supplied.insert("chat_history".to_string(), serde_json::json!(chat_history));
+        }
+        if let Some(ref info) = req.info {
+            supplied.extend(info.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        let metadata_pii = self.ingest_pii_metadata(user_name, &mut supplied)?;
+        let importance = self.resolve_importance(req, &content).await?;
+        let feedback_kind = Self::feedback_kind_or_error(req)?;
+        let mut feedback_targets: Vec<String> = Vec::new();
+        if feedback_kind.is_some() {
+            for rel in req.relations.iter().flatten() {
+                if !feedback_targets.contains(&rel.memory_id) {
+                    feedback_targets.push(rel.memory_id.clone());
+                }
+            }
+            // Feedback may only reference live memories of this cube; check before writing.
+            let nodes = self
+                .graph
+                .get_nodes(&feedback_targets, false)
+                .await
+                .map_err(MemCubeError::Graph)?;
+            for target in &feedback_targets {
+                if !nodes
+                    .iter()
+                    .any(|n| &n.id == target && Self::is_live_in(n, user_name))
+                {
+                    return Err(MemCubeError::NotFound(format!(
+                        "memory not found: {}",
+                        target
+                    )));
+                }
+            }
+        }
 
         let id = Uuid::new_v4().to_string();
         let embedding = self.embedder.embed(&content).await?;
@@ -295,20 +953,34 @@ where
                 serde_json::Value::String(task_id.clone()),
             );
         }
-        if let Some(ref custom_tags) = req.custom_tags {
-            metadata.insert("custom_tags".to_string(), serde_json::json!(custom_tags));
-        }
-        if let Some(ref chat_history) = req.chat_history {
-            metadata.insert("chat_history".to_string(), serde_json::json!(chat_history));
-        }
-        if let Some(ref info) = req.info {
-            for (k, v) in info {
-                metadata.insert(k.clone(), v.clone());
-            }
+        metadata.extend(supplied);
+        metadata.insert(
+            "scope".to_string(),
+            serde_json::Value::String(scope.clone()),
+        );
+        metadata.insert(
+            AUTHOR_KEY.to_string(),
+            serde_json::Value::String(req.user_id.clone()),
+        );
+        metadata.insert("importance".to_string(), serde_json::json!(importance));
+        metadata.insert("access_count".to_string(), serde_json::json!(0));
+        Self::insert_pii_metadata(&mut metadata, pii.as_ref(), &metadata_pii);
+        if let Some(is_correction) = feedback_kind {
+            metadata.insert("is_feedback".to_string(), serde_json::Value::Bool(true));
             metadata.insert(
-                "scope".to_string(),
-                serde_json::Value::String(scope.clone()),
+                "feedback_type".to_string(),
+                serde_json::Value::String(
+                    if is_correction {
+                        "correction"
+                    } else {
+                        "annotation"
+                    }
+                    .to_string(),
+                ),
             );
+            if is_correction {
+                metadata.insert("corrects".to_string(), serde_json::json!(feedback_targets));
+            }
         }
 
         let node = MemoryNode {
@@ -317,10 +989,15 @@ where
             metadata: metadata.clone(),
             embedding: Some(embedding.clone()),
         };
-        self.graph
-            .add_nodes_batch(&[node], Some(user_name))
+        let intent = self.begin_intent(IntentOp::Add, &id, user_name).await?;
+        if let Err(e) = self
+            .graph
+            .add_nodes_batch(std::slice::from_ref(&node), Some(user_name))
             .await
-            .map_err(MemCubeError::Graph)?;
+        {
+            self.abort_add(intent, &id, user_name).await;
+            return Err(MemCubeError::Graph(e));
+        }
 
         if let Some(relations) = req.relations.as_ref() {
             if !relations.is_empty() {
@@ -331,6 +1008,10 @@ where
                         "created_at".to_string(),
                         serde_json::Value::String(Utc::now().to_rfc3339()),
                     );
+                    base_metadata.insert(
+                        AUTHOR_KEY.to_string(),
+                        serde_json::Value::String(req.user_id.clone()),
+                    );
                     match rel.direction {
                         GraphDirection::Outbound => {
                             edges.push(MemoryEdge {
@@ -370,44 +1051,43 @@ where
                 }
                 if let Err(e) = self.graph.add_edges_batch(&edges, Some(user_name)).await {
                     // Keep add operation atomic-ish for graph writes.
-                    let _ = self.graph.delete_node(&id, Some(user_name)).await;
+                    self.abort_add(intent, &id, user_name).await;
                     return Err(MemCubeError::Graph(e));
                 }
             }
         }
 
-        let payload = {
-            let mut p = HashMap::new();
-            p.insert(
-                "mem_cube_id".to_string(),
-                serde_json::Value::String(user_name.to_string()),
-            );
-            p.insert(
-                "memory_type".to_string(),
-                serde_json::Value::String("text_mem".to_string()),
-            );
-            p.insert("scope".to_string(), serde_json::Value::String(scope));
-            p
-        };
         let item = VecStoreItem {
             id: id.clone(),
             vector: embedding,
-            payload,
+            payload: Self::vec_payload(user_name, &scope),
         };
         if let Err(e) = self.vec_store.add(&[item], None).await {
             // Avoid partial success: if vec write fails, rollback graph node and edges.
-            let _ = self.graph.delete_node(&id, Some(user_name)).await;
+            self.abort_add(intent, &id, user_name).await;
             return Err(MemCubeError::Vec(e));
         }
 
         if let Some(ref kw) = self.keyword_store {
             if let Err(e) = kw.index(&id, &content, Some(user_name)).await {
-                #[allow(clippy::cloned_ref_to_slice_refs)]
-                let _ = self.vec_store.delete(&[id.clone()], None).await;
-                let _ = self.graph.delete_node(&id, Some(user_name)).await;
+                self.abort_add(intent, &id, user_name).await;
                 return Err(MemCubeError::Keyword(e));
             }
         }
+        self.finish_intent(intent, true).await;
+        self.auto_link(&node, user_name).await;
+
+        if let Some(is_correction) = feedback_kind {
+            self.apply_feedback_add(
+                &id,
+                &content,
+                &feedback_targets,
+                is_correction,
+                &req.user_id,
+                user_name,
+            )
+            .await;
+        }
 
         let data = vec![serde_json::json!({ "id": id, "memory": content })];
         Ok(MemoryResponse {
@@ -468,7 +1148,7 @@ where
         // P0: Apply time range filtering
         let nodes = Self::filter_nodes_by_time(nodes, req);
 
-        let memories: Vec<MemoryItem> = nodes
+        let mut ranked: Vec<(f64, MemoryItem)> = nodes
             .into_iter()
             .filter(|n| {
                 n.metadata
@@ -479,22 +1159,34 @@ where
             })
             .map(|n| {
                 let mut meta = n.metadata.clone();
-                if let Some(score) = hits.iter().find(|h| h.id == n.id).map(|h| h.score) {
-                    meta.insert(
-                        "relativity".to_string(),
-                        serde_json::Value::Number(
-                            serde_json::Number::from_f64(score)
-                                .unwrap_or(serde_json::Number::from(0)),
-                        ),
-                    );
-                }
-                MemoryItem {
-                    id: n.id,
-                    memory: n.memory,
-                    metadata: meta,
-                }
+                let score = hits
+                    .iter()
+                    .find(|h| h.id == n.id)
+                    .map(|h| h.score)
+                    .unwrap_or(0.0);
+                meta.insert(
+                    "relativity".to_string(),
+                    serde_json::Value::Number(
+                        serde_json::Number::from_f64(score).unwrap_or(serde_json::Number::from(0)),
+                    ),
+                );
+                let rank_score = score * MemorySignals::from_metadata(&n.metadata).ranking_boost();
+                let mut memory = n.memory;
+                self.redact_output(user_name, &mut memory, &mut meta);
+                (
+                    rank_score,
+                    MemoryItem {
+                        id: n.id,
+                        memory,
+                        metadata: meta,
+                    },
+                )
             })
             .collect();
+        // Importance and usage adjust the order; `relativity` stays the raw similarity.
+        ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        let memories: Vec<MemoryItem> = ranked.into_iter().map(|(_, m)| m).collect();
+        self.track_access(memories.iter().map(|m| m.id.clone()).collect(), user_name);
 
         let all_bucket = MemoryBucket {
             name: Some("all".to_string()),
@@ -690,11 +1382,15 @@ where
                 } else {
                     k_raw
                 };
-                let fused = weights.0 * v_norm + weights.1 * k_norm + weights.2 * g_norm;
+                let fused = (weights.0 * v_norm + weights.1 * k_norm + weights.2 * g_norm)
+                    * MemorySignals::from_metadata(&n.metadata).ranking_boost();
+                let mut memory_content = n.memory.clone();
+                let mut metadata = n.metadata.clone();
+                self.redact_output(user_name, &mut memory_content, &mut metadata);
                 Some(HybridSearchHit {
                     memory_id: n.id.clone(),
-                    memory_content: n.memory.clone(),
-                    metadata: n.metadata.clone(),
+                    memory_content,
+                    metadata,
                     vector_score: scores.0,
                     keyword_score: scores.2,
                     graph_score: scores.1,
@@ -760,6 +1456,10 @@ where
             hits.truncate(top_k);
             (hits, false)
         };
+        self.track_access(
+            hits.iter().map(|h| h.memory_id.clone()).collect(),
+            user_name,
+        );
 
         let latency_ms = start.elapsed().as_millis() as u64;
         Ok(HybridSearchResponse {
@@ -801,15 +1501,35 @@ where
             .unwrap_or(MemoryScope::LongTermMemory.as_str())
             .to_string();
 
+        let pii = match req.memory {
+            Some(ref memory) => self.ingest_pii(user_name, memory)?,
+            None => None,
+        };
+        let new_memory: Option<String> = match pii {
+            Some(ref outcome) => Some(outcome.text.clone()),
+            None => req.memory.clone(),
+        };
+        let mut supplied = req.metadata.clone();
+        let metadata_pii = match supplied {
+            Some(ref mut meta) => self.ingest_pii_metadata(user_name, meta)?,
+            None => Vec::new(),
+        };
+
         let mut fields = HashMap::new();
-        if let Some(ref memory) = req.memory {
+        if let Some(ref memory) = new_memory {
             fields.insert(
                 "memory".to_string(),
                 serde_json::Value::String(memory.clone()),
             );
         }
         let mut scope_changed = false;
-        if let Some(ref meta) = req.metadata {
+        if let Some(ref meta) = supplied {
+            if meta.contains_key(AUTHOR_KEY) {
+                return Err(MemCubeError::BadRequest(format!(
+                    "{} metadata cannot be changed",
+                    AUTHOR_KEY
+                )));
+            }
             for (k, v) in meta {
                 if k == "scope" {
                     if let Some(raw_scope) = v.as_str() {
@@ -831,70 +1551,96 @@ where
                             "scope must be a string".to_string(),
                         ));
                     }
+                } else if k == "importance" {
+                    fields.insert(k.clone(), serde_json::json!(Self::importance_or_error(v)?));
                 } else {
                     fields.insert(k.clone(), v.clone());
                 }
             }
         }
+        if let Some(ref outcome) = pii {
+            // Replace (not merge) what the previous text recorded.
+            fields.insert("pii_kinds".to_string(), serde_json::json!(outcome.kinds));
+            fields.insert(
+                "pii_original".to_string(),
+                outcome
+                    .encrypted_original
+                    .clone()
+                    .map(serde_json::Value::String)
+                    .unwrap_or(serde_json::Value::Null),
+            );
+        }
+        if !metadata_pii.is_empty() {
+            let mut kinds: Vec<String> = match pii {
+                Some(ref outcome) => outcome.kinds.clone(),
+                None => node
+                    .metadata
+                    .get("pii_kinds")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or_default(),
+            };
+            for kind in metadata_pii {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+            }
+            fields.insert("pii_kinds".to_string(), serde_json::json!(kinds));
+        }
         fields.insert(
             "updated_at".to_string(),
             serde_json::Value::String(Utc::now().to_rfc3339()),
         );
 
-        if fields.len() > 1 || req.memory.is_some() {
-            self.graph
-                .update_node(id, &fields, Some(user_name))
-                .await
-                .map_err(MemCubeError::Graph)?;
-        }
+        let intent = self.begin_intent(IntentOp::Update, id, user_name).await?;
+        let stores = async {
+            if fields.len() > 1 || new_memory.is_some() {
+                self.graph
+                    .update_node(id, &fields, Some(user_name))
+                    .await
+                    .map_err(MemCubeError::Graph)?;
+            }
 
-        if req.memory.is_some() || scope_changed {
-            let embedding = if let Some(ref new_memory) = req.memory {
-                self.embedder.embed(new_memory).await?
-            } else {
-                let ids = vec![id.to_string()];
-                let mut existing_items = self
-                    .vec_store
-                    .get_by_ids(&ids, None)
+            if new_memory.is_some() || scope_changed {
+                let embedding = if let Some(ref new_memory) = new_memory {
+                    self.embedder.embed(new_memory).await?
+                } else {
+                    let ids = vec![id.to_string()];
+                    let mut existing_items = self
+                        .vec_store
+                        .get_by_ids(&ids, None)
+                        .await
+                        .map_err(MemCubeError::Vec)?;
+                    if let Some(existing_item) = existing_items.pop() {
+                        existing_item.vector
+                    } else {
+                        self.embedder.embed(&node.memory).await?
+                    }
+                };
+                let item = VecStoreItem {
+                    id: id.to_string(),
+                    vector: embedding,
+                    payload: Self::vec_payload(user_name, &payload_scope),
+                };
+                self.vec_store
+                    .upsert(&[item], None)
                     .await
                     .map_err(MemCubeError::Vec)?;
-                if let Some(existing_item) = existing_items.pop() {
-                    existing_item.vector
-                } else {
-                    self.embedder.embed(&node.memory).await?
-                }
-            };
-            let payload = {
-                let mut p = HashMap::new();
-                p.insert(
-                    "mem_cube_id".to_string(),
-                    serde_json::Value::String(user_name.to_string()),
-                );
-                p.insert(
-                    "memory_type".to_string(),
-                    serde_json::Value::String("text_mem".to_string()),
-                );
-                p.insert(
-                    "scope".to_string(),
-                    serde_json::Value::String(payload_scope),
-                );
-                p
-            };
-            let item = VecStoreItem {
-                id: id.to_string(),
-                vector: embedding,
-                payload,
-            };
-            self.vec_store
-                .upsert(&[item], None)
-                .await
-                .map_err(MemCubeError::Vec)?;
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(e) = stores {
+            // The graph may already hold the update; recovery re-derives the other stores.
+            self.finish_intent(intent, false).await;
+            return Err(e);
         }
 
+        let mut indexed = true;
         if let Some(ref kw) = self.keyword_store {
-            let content = req.memory.as_deref().unwrap_or(&node.memory);
-            let _ = kw.index(id, content, Some(user_name)).await;
+            let content = new_memory.as_deref().unwrap_or(&node.memory);
+            indexed = kw.index(id, content, Some(user_name)).await.is_ok();
         }
+        self.finish_intent(intent, indexed).await;
 
         let data = vec![serde_json::json!({ "id": id, "updated": true })];
         Ok(UpdateMemoryResponse {
@@ -923,37 +1669,43 @@ where
             return Err(MemCubeError::NotFound(format!("memory not found: {}", id)));
         }
 
-        if req.soft {
-            let mut fields = HashMap::new();
-            fields.insert(
-                "state".to_string(),
-                serde_json::Value::String("tombstone".to_string()),
-            );
-            fields.insert(
-                "updated_at".to_string(),
-                serde_json::Value::String(Utc::now().to_rfc3339()),
-            );
-            self.graph
-                .update_node(id, &fields, Some(user_name))
-                .await
-                .map_err(MemCubeError::Graph)?;
-            self.vec_store
-                .delete(&[id.to_string()], None)
-                .await
-                .map_err(MemCubeError::Vec)?;
-        } else {
-            self.graph
-                .delete_node(id, Some(user_name))
-                .await
-                .map_err(MemCubeError::Graph)?;
+        let intent = self.begin_intent(IntentOp::Forget, id, user_name).await?;
+        let stores = async {
+            if req.soft {
+                let mut fields = HashMap::new();
+                fields.insert(
+                    "state".to_string(),
+                    serde_json::Value::String("tombstone".to_string()),
+                );
+                fields.insert(
+                    "updated_at".to_string(),
+                    serde_json::Value::String(Utc::now().to_rfc3339()),
+                );
+                self.graph
+                    .update_node(id, &fields, Some(user_name))
+                    .await
+                    .map_err(MemCubeError::Graph)?;
+            } else {
+                self.graph
+                    .delete_node(id, Some(user_name))
+                    .await
+                    .map_err(MemCubeError::Graph)?;
+            }
             self.vec_store
                 .delete(&[id.to_string()], None)
                 .await
-                .map_err(MemCubeError::Vec)?;
+                .map_err(MemCubeError::Vec)
         }
+        .await;
+        if let Err(e) = stores {
+            self.finish_intent(intent, false).await;
+            return Err(e);
+        }
+        let mut removed = true;
         if let Some(ref kw) = self.keyword_store {
-            let _ = kw.remove(id, Some(user_name)).await;
+            removed = kw.remove(id, Some(user_name)).await.is_ok();
         }
+        self.finish_intent(intent, removed).await;
         let data = vec![serde_json::json!({ "id": id, "forgotten": true })];
         Ok(ForgetMemoryResponse {
             code: 200,
@@ -999,11 +1751,8 @@ where
                 data: None,
             });
         }
-        let item = MemoryItem {
-            id: node.id,
-            memory: node.memory,
-            metadata: node.metadata,
-        };
+        self.track_access(vec![node.id.clone()], user_name);
+        let item = self.visible_item(user_name, node);
         Ok(GetMemoryResponse {
             code: 200,
             message: "Success".to_string(),
@@ -1016,7 +1765,9 @@ where
         req: &GraphNeighborsRequest,
     ) -> Result<GraphNeighborsResponse, MemCubeError> {
         let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
-        let offset = Self::parse_cursor(req.cursor.as_deref())?;
+        if let Some(ref cursor) = req.cursor {
+            decode_cursor("graph", cursor)?;
+        }
         let source = self
             .graph
             .get_node(&req.memory_id, false)
@@ -1077,23 +1828,19 @@ where
                     != "tombstone"
             })
             .map(|n| GraphNeighborItem {
-                edge: n.edge,
-                memory: MemoryItem {
-                    id: n.node.id,
-                    memory: n.node.memory,
-                    metadata: n.node.metadata,
-                },
+                edge: self.visible_edge(user_name, n.edge),
+                memory: self.visible_item(user_name, n.node),
             })
             .collect();
 
-        let limit = req.limit as usize;
-        let items: Vec<GraphNeighborItem> =
-            all_items.iter().skip(offset).take(limit).cloned().collect();
-        let next_cursor = if offset + items.len() < all_items.len() {
-            Some((offset + items.len()).to_string())
-        } else {
-            None
-        };
+        // Oldest edges first, so edges added between pages land after the cursor.
+        let (items, next_cursor) = keyset_page(
+            all_items,
+            |n| PageKey::created_at(&n.edge.metadata, &n.edge.id),
+            "graph",
+            req.cursor.as_deref(),
+            req.limit as usize,
+        )?;
 
         Ok(GraphNeighborsResponse {
             code: 200,
@@ -1103,6 +1850,31 @@ where
     }
 
     async fn graph_path(&self, req: &GraphPathRequest) -> Result<GraphPathResponse, MemCubeError> {
+        if req.cost_field.is_some() {
+            let paths = self
+                .weighted_graph_paths(&GraphPathsRequest {
+                    source_memory_id: req.source_memory_id.clone(),
+                    target_memory_id: req.target_memory_id.clone(),
+                    user_id: req.user_id.clone(),
+                    mem_cube_id: req.mem_cube_id.clone(),
+                    relation: req.relation.clone(),
+                    direction: req.direction,
+                    max_depth: req.max_depth,
+                    top_k_paths: 1,
+                    include_deleted: req.include_deleted,
+                    cost_field: req.cost_field.clone(),
+                    cost_transform: req.cost_transform,
+                    default_cost: req.default_cost,
+                    max_cost: req.max_cost,
+                })
+                .await?;
+            return Ok(GraphPathResponse {
+                code: 200,
+                message: "Success".to_string(),
+                data: paths.into_iter().next(),
+            });
+        }
+        Self::check_unweighted(req.cost_transform, req.default_cost, req.max_cost)?;
         let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
         let path = self
             .graph
@@ -1141,11 +1913,7 @@ where
             .map_err(MemCubeError::Graph)?;
         let items: Vec<MemoryItem> = nodes
             .into_iter()
-            .map(|n| MemoryItem {
-                id: n.id,
-                memory: n.memory,
-                metadata: n.metadata,
-            })
+            .map(|n| self.visible_item(user_name, n))
             .collect();
 
         Ok(GraphPathResponse {
@@ -1154,7 +1922,12 @@ where
             data: Some(GraphPathData {
                 hops: path.edges.len() as u32,
                 nodes: items,
-                edges: path.edges,
+                edges: path
+                    .edges
+                    .into_iter()
+                    .map(|e| self.visible_edge(user_name, e))
+                    .collect(),
+                cost: None,
             }),
         })
     }
@@ -1168,6 +1941,14 @@ where
                 "top_k_paths must be greater than 0".to_string(),
             ));
         }
+        if req.cost_field.is_some() {
+            return Ok(GraphPathsResponse {
+                code: 200,
+                message: "Success".to_string(),
+                data: Some(self.weighted_graph_paths(req).await?),
+            });
+        }
+        Self::check_unweighted(req.cost_transform, req.default_cost, req.max_cost)?;
         let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
         let paths = self
             .graph
@@ -1189,6 +1970,8 @@ where
                         "memory not found: {} or {}",
                         req.source_memory_id, req.target_memory_id
                     ))
+                } else if msg.contains("path search exceeded") {
+                    MemCubeError::BadRequest(msg)
                 } else {
                     MemCubeError::Graph(e)
                 }
@@ -1209,16 +1992,17 @@ where
                 .map_err(MemCubeError::Graph)?;
             let items: Vec<MemoryItem> = nodes
                 .into_iter()
-                .map(|n| MemoryItem {
-                    id: n.id,
-                    memory: n.memory,
-                    metadata: n.metadata,
-                })
+                .map(|n| self.visible_item(user_name, n))
                 .collect();
             out.push(GraphPathData {
                 hops: path.edges.len() as u32,
                 nodes: items,
-                edges: path.edges,
+                edges: path
+                    .edges
+                    .into_iter()
+                    .map(|e| self.visible_edge(user_name, e))
+                    .collect(),
+                cost: None,
             });
         }
 
@@ -1229,6 +2013,315 @@ where
         })
     }
 
+    async fn add_edge(&self, req: &GraphEdgeAddRequest) -> Result<GraphEdgeResponse, MemCubeError> {
+        if req.relation.trim().is_empty() {
+            return Err(MemCubeError::BadRequest(
+                "relation must not be empty".to_string(),
+            ));
+        }
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        for memory_id in [&req.from, &req.to] {
+            let visible = self
+                .graph
+                .get_node(memory_id, false)
+                .await
+                .map_err(MemCubeError::Graph)?
+                .is_some_and(|n| Self::is_live_in(&n, user_name));
+            if !visible {
+                return Err(MemCubeError::NotFound(format!(
+                    "memory not found: {}",
+                    memory_id
+                )));
+            }
+        }
+        let mut metadata = req.metadata.clone();
+        metadata.remove("user_name");
+        self.ingest_pii_metadata(user_name, &mut metadata)?;
+        metadata.insert(
+            "created_at".to_string(),
+            serde_json::Value::String(Utc::now().to_rfc3339()),
+        );
+        metadata.insert(
+            AUTHOR_KEY.to_string(),
+            serde_json::Value::String(req.user_id.clone()),
+        );
+        let edge = MemoryEdge {
+            id: Uuid::new_v4().to_string(),
+            from: req.from.clone(),
+            to: req.to.clone(),
+            relation: req.relation.clone(),
+            metadata,
+        };
+        self.graph
+            .add_edges_batch(std::slice::from_ref(&edge), Some(user_name))
+            .await
+            .map_err(MemCubeError::Graph)?;
+        let edge = self
+            .graph
+            .get_edge(&edge.id, Some(user_name))
+            .await
+            .map_err(MemCubeError::Graph)?
+            .unwrap_or(edge);
+        Ok(GraphEdgeResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(self.visible_edge(user_name, edge)),
+        })
+    }
+
+    async fn get_edge(&self, req: &GraphEdgeRequest) -> Result<GraphEdgeResponse, MemCubeError> {
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        let edge = self
+            .graph
+            .get_edge(&req.edge_id, Some(user_name))
+            .await
+            .map_err(MemCubeError::Graph)?
+            .ok_or_else(|| MemCubeError::NotFound(format!("edge not found: {}", req.edge_id)))?;
+        Ok(GraphEdgeResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(self.visible_edge(user_name, edge)),
+        })
+    }
+
+    async fn update_edge(
+        &self,
+        req: &GraphEdgeUpdateRequest,
+    ) -> Result<GraphEdgeResponse, MemCubeError> {
+        if req.relation.as_deref().is_some_and(|r| r.trim().is_empty()) {
+            return Err(MemCubeError::BadRequest(
+                "relation must not be empty".to_string(),
+            ));
+        }
+        for key in ["user_name", AUTHOR_KEY] {
+            if req.metadata.contains_key(key) {
+                return Err(MemCubeError::BadRequest(format!(
+                    "{} metadata cannot be changed",
+                    key
+                )));
+            }
+        }
+        if req.relation.is_none() && req.metadata.is_empty() {
+            return Err(MemCubeError::BadRequest(
+                "nothing to update: need relation or metadata".to_string(),
+            ));
+        }
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        let mut metadata = req.metadata.clone();
+        self.ingest_pii_metadata(user_name, &mut metadata)?;
+        metadata.insert(
+            "updated_at".to_string(),
+            serde_json::Value::String(Utc::now().to_rfc3339()),
+        );
+        let edge = self
+            .graph
+            .update_edge(
+                &req.edge_id,
+                req.relation.as_deref(),
+                &metadata,
+                Some(user_name),
+            )
+            .await
+            .map_err(MemCubeError::Graph)?
+            .ok_or_else(|| MemCubeError::NotFound(format!("edge not found: {}", req.edge_id)))?;
+        Ok(GraphEdgeResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(self.visible_edge(user_name, edge)),
+        })
+    }
+
+    async fn delete_edge(&self, req: &GraphEdgeRequest) -> Result<GraphEdgeResponse, MemCubeError> {
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        let edge = self
+            .graph
+            .delete_edge(&req.edge_id, Some(user_name))
+            .await
+            .map_err(MemCubeError::Graph)?
+            .ok_or_else(|| MemCubeError::NotFound(format!("edge not found: {}", req.edge_id)))?;
+        Ok(GraphEdgeResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(self.visible_edge(user_name, edge)),
+        })
+    }
+
+    async fn list_edges(
+        &self,
+        req: &GraphEdgeListRequest,
+    ) -> Result<GraphEdgeListResponse, MemCubeError> {
+        if req.limit == 0 {
+            return Err(MemCubeError::BadRequest(
+                "limit must be greater than 0".to_string(),
+            ));
+        }
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        // One extra row tells whether another page follows.
+        let mut items = self
+            .graph
+            .list_edges(
+                user_name,
+                req.memory_id.as_deref(),
+                req.relation.as_deref(),
+                req.direction,
+                req.cursor.as_deref(),
+                req.limit as usize + 1,
+            )
+            .await
+            .map_err(MemCubeError::Graph)?;
+        let next_cursor = if items.len() > req.limit as usize {
+            items.truncate(req.limit as usize);
+            items.last().map(|e| e.id.clone())
+        } else {
+            None
+        };
+        Ok(GraphEdgeListResponse {
+            code: 200,
+            message: "Success".to_string(),
+            data: Some(GraphEdgeListData {
+                items: items
+                    .into_iter()
+                    .map(|e| self.visible_edge(user_name, e))
+                    .collect(),
+                next_cursor,
+            }),
+        })
+    }
+
+    async fn graph_analytics(
+        &self,
+        req: &GraphAnalyticsRequest,
+    ) -> Result<GraphAnalyticsReport, MemCubeError> {
+        req.validate().map_err(MemCubeError::BadRequest)?;
+        let cube_id = req.cube_id();
+        let options = mem_graph::GraphAnalyticsOptions {
+            relation: req.relation.as_deref(),
+            damping: req.damping,
+            max_iterations: req.max_iterations as usize,
+            ..Default::default()
+        };
+        let analytics = mem_graph::analyze_graph(self.graph.as_ref(), cube_id, &options)
+            .await
+            .map_err(MemCubeError::Graph)?;
+        let completed_at = Utc::now().to_rfc3339();
+        for node in &analytics.nodes {
+            let fields = HashMap::from([
+                ("pagerank".to_string(), serde_json::json!(node.pagerank)),
+                ("centrality".to_string(), serde_json::json!(node.centrality)),
+                ("degree".to_string(), serde_json::json!(node.degree)),
+                (
+                    "degree_centrality".to_string(),
+                    serde_json::json!(node.degree_centrality),
+                ),
+                (
+                    "component_id".to_string(),
+                    serde_json::json!(node.component_id),
+                ),
+                (
+                    "community_id".to_string(),
+                    serde_json::json!(node.community_id),
+                ),
+                (
+                    "analytics_updated_at".to_string(),
+                    serde_json::Value::String(completed_at.clone()),
+                ),
+            ]);
+            // Memories deleted while the job ran are skipped.
+            match self
+                .graph
+                .update_node(&node.memory_id, &fields, Some(cube_id))
+                .await
+            {
+                Err(e) if !e.to_string().contains("not found") => {
+                    return Err(MemCubeError::Graph(e))
+                }
+                _ => {}
+            }
+        }
+        let mut top_nodes = analytics.nodes.clone();
+        top_nodes.sort_by(|a, b| {
+            b.pagerank
+                .total_cmp(&a.pagerank)
+                .then_with(|| a.memory_id.cmp(&b.memory_id))
+        });
+        top_nodes.truncate(req.top_k as usize);
+        Ok(GraphAnalyticsReport {
+            cube_id: cube_id.to_string(),
+            nodes: analytics.nodes.len() as u64,
+            edges: analytics.edges as u64,
+            components: analytics.component_sizes.len() as u64,
+            communities: analytics.community_sizes.len() as u64,
+            component_sizes: analytics
+                .component_sizes
+                .iter()
+                .map(|&s| s as u64)
+                .collect(),
+            community_sizes: analytics
+                .community_sizes
+                .iter()
+                .map(|&s| s as u64)
+                .collect(),
+            pagerank_iterations: analytics.pagerank_iterations as u32,
+            top_nodes,
+            completed_at,
+        })
+    }
+
+    async fn check_consistency(
+        &self,
+        req: &ConsistencyCheckRequest,
+    ) -> Result<ConsistencyCheckResponse, MemCubeError> {
+        self.consistency_report(req).await
+    }
+
+    async fn graph_query(
+        &self,
+        req: &GraphQueryRequest,
+    ) -> Result<GraphQueryResponse, MemCubeError> {
+        let query = mem_graph::GraphQuery::parse(&req.query)
+            .map_err(|e| MemCubeError::BadRequest(e.to_string()))?;
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+        let options = mem_graph::QueryOptions {
+            user_name,
+            limit: req.limit as usize,
+            include_deleted: req.include_deleted,
+        };
+        let result = query
+            .execute(self.graph.as_ref(), &options)
+            .await
+            .map_err(|e| match e {
+                mem_graph::QueryError::Graph(e) => MemCubeError::Graph(e),
+                other => MemCubeError::BadRequest(other.to_string()),
+            })?;
+        let to_json = |value: mem_graph::QueryValue| match value {
+            mem_graph::QueryValue::Node(n) => serde_json::to_value(self.visible_item(user_name, n)),
+            mem_graph::QueryValue::Relationship(e) => {
+                serde_json::to_value(self.visible_edge(user_name, e))
+            }
+            mem_graph::QueryValue::Path(edges) => serde_json::to_value(
+                edges
+                    .into_iter()
+                    .map(|e| self.visible_edge(user_name, e))
+                    .collect::<Vec<_>>(),
+            ),
+            mem_graph::QueryValue::Value(v) => Ok(v),
+        };
+        let rows = result
+            .rows
+            .into_iter()
+            .map(|row| row.into_iter().map(to_json).collect())
+            .collect::<Result<Vec<Vec<_>>, _>>()
+            .map_err(|e| MemCubeError::Other(e.to_string()))?;
+        Ok(GraphQueryResponse {
+            code: 200,
+            message: "Query completed".to_string(),
+            data: Some(GraphQueryData {
+                columns: result.columns,
+                rows,
+            }),
+        })
+    }
+
     // ============================================================================
     // Batch Operations (P1-2)
     // ============================================================================
@@ -1247,25 +2340,51 @@ where
         let mut successful = Vec::new();
         let mut failed = Vec::new();
 
+        // PII policy runs before embedding; rejected items never reach the embedder.
+        let mut accepted: Vec<(
+            usize,
+            &BatchMemoryContent,
+            String,
+            Option<PiiOutcome>,
+            Supplied,
+        )> = Vec::new();
+        for (idx, item) in req.memories.iter().enumerate() {
+            let mut supplied = item.metadata.clone().unwrap_or_default();
+            let ingested = self.ingest_pii(user_name, &item.memory).and_then(|pii| {
+                let kinds = self.ingest_pii_metadata(user_name, &mut supplied)?;
+                Ok((pii, kinds))
+            });
+            match ingested {
+                Ok((pii, kinds)) => {
+                    let text = pii
+                        .as_ref()
+                        .map(|o| o.text.clone())
+                        .unwrap_or_else(|| item.memory.clone());
+                    accepted.push((idx, item, text, pii, (supplied, kinds)));
+                }
+                Err(e) => failed.push(BatchFailure {
+                    index: idx as u32,
+                    error: e.to_string(),
+                }),
+            }
+        }
+
         // Parallel embedding generation
-        let contents: Vec<String> = req.memories.iter().map(|m| m.memory.clone()).collect();
+        let contents: Vec<String> = accepted.iter().map(|a| a.2.clone()).collect();
         let embeddings = match self.embedder.embed_batch(&contents).await {
             Ok(emb) => emb,
             Err(e) => {
+                failed.extend(accepted.iter().map(|(i, ..)| BatchFailure {
+                    index: *i as u32,
+                    error: format!("embedding failed: {}", e),
+                }));
+                failed.sort_by_key(|f| f.index);
                 return Ok(BatchAddResponse {
                     code: 500,
                     message: format!("embedding failed: {}", e),
                     data: Some(BatchAddData {
                         successful: vec![],
-                        failed: req
-                            .memories
-                            .iter()
-                            .enumerate()
-                            .map(|(i, _)| BatchFailure {
-                                index: i as u32,
-                                error: format!("embedding failed: {}", e),
-                            })
-                            .collect(),
+                        failed,
                         total: req.memories.len() as u32,
                     }),
                 });
@@ -1273,7 +2392,9 @@ where
         };
 
         // Add each memory
-        for (idx, (content, emb)) in req.memories.iter().zip(embeddings).enumerate() {
+        for ((idx, content, text, pii, (supplied, metadata_pii)), emb) in
+            accepted.into_iter().zip(embeddings)
+        {
             let id = Uuid::new_v4().to_string();
             let mut metadata = HashMap::new();
             metadata.insert(
@@ -1285,27 +2406,59 @@ where
                 serde_json::Value::String(Utc::now().to_rfc3339()),
             );
 
-            if let Some(ref meta) = content.metadata {
-                for (k, v) in meta {
-                    metadata.insert(k.clone(), v.clone());
-                }
-            }
+            metadata.extend(supplied);
 
             let scope = content.scope.as_deref().unwrap_or(&self.default_scope);
             metadata.insert(
                 "scope".to_string(),
                 serde_json::Value::String(scope.to_string()),
             );
+            let importance = match metadata.get("importance") {
+                Some(v) => match Self::importance_or_error(v) {
+                    Ok(importance) => importance,
+                    Err(e) => {
+                        failed.push(BatchFailure {
+                            index: idx as u32,
+                            error: e.to_string(),
+                        });
+                        continue;
+                    }
+                },
+                None => MemorySignals::DEFAULT_IMPORTANCE,
+            };
+            metadata.insert("importance".to_string(), serde_json::json!(importance));
+            metadata.insert("access_count".to_string(), serde_json::json!(0));
+            metadata.insert(
+                AUTHOR_KEY.to_string(),
+                serde_json::Value::String(req.user_id.clone()),
+            );
+            Self::insert_pii_metadata(&mut metadata, pii.as_ref(), &metadata_pii);
 
             let node = MemoryNode {
                 id: id.clone(),
-                memory: content.memory.clone(),
+                memory: text.clone(),
                 metadata: metadata.clone(),
                 embedding: Some(emb.clone()),
             };
 
+            let intent = match self.begin_intent(IntentOp::Add, &id, user_name).await {
+                Ok(intent) => intent,
+                Err(e) => {
+                    failed.push(BatchFailure {
+                        index: idx as u32,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
             // Write to graph
-            if let Err(e) = self.graph.add_nodes_batch(&[node], Some(user_name)).await {
+            if let Err(e) = self
+                .graph
+                .add_nodes_batch(std::slice::from_ref(&node), Some(user_name))
+                .await
+            {
+                self.abort_add(intent, &id, user_name).await;
                 failed.push(BatchFailure {
                     index: idx as u32,
                     error: format!("graph error: {}", e),
@@ -1314,30 +2467,14 @@ where
             }
 
             // Write to vector store
-            let payload = {
-                let mut p = HashMap::new();
-                p.insert(
-                    "mem_cube_id".to_string(),
-                    serde_json::Value::String(user_name.to_string()),
-                );
-                p.insert(
-                    "memory_type".to_string(),
-                    serde_json::Value::String("text_mem".to_string()),
-                );
-                p.insert(
-                    "scope".to_string(),
-                    serde_json::Value::String(scope.to_string()),
-                );
-                p
-            };
             let item = VecStoreItem {
                 id: id.clone(),
                 vector: emb,
-                payload,
+                payload: Self::vec_payload(user_name, scope),
             };
 
             if let Err(e) = self.vec_store.add(&[item], None).await {
-                let _ = self.graph.delete_node(&id, Some(user_name)).await;
+                self.abort_add(intent, &id, user_name).await;
                 failed.push(BatchFailure {
                     index: idx as u32,
                     error: format!("vector store error: {}", e),
@@ -1345,10 +2482,18 @@ where
                 continue;
             }
 
-            // Index to keyword store if available
-            if let Some(ref kw) = self.keyword_store {
-                let _ = kw.index(&id, &content.memory, Some(user_name)).await;
+            // Index to keyword store if available; a failure keeps the memory and leaves
+            // a repair pending.
+            let indexed = match self.keyword_store {
+                Some(ref kw) => kw.index(&id, &text, Some(user_name)).await.is_ok(),
+                None => true,
+            };
+            if indexed {
+                self.finish_intent(intent, true).await;
+            } else {
+                self.keep_add_for_repair(intent, &id, user_name).await;
             }
+            self.auto_link(&node, user_name).await;
 
             successful.push(BatchResult {
                 memory_id: id,
@@ -1400,6 +2545,20 @@ where
                 continue;
             }
 
+            let intent = match self
+                .begin_intent(IntentOp::Forget, memory_id, user_name)
+                .await
+            {
+                Ok(intent) => intent,
+                Err(e) => {
+                    failed.push(BatchFailure {
+                        index: idx as u32,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
             if req.soft {
                 // Soft delete
                 let mut fields = HashMap::new();
@@ -1416,34 +2575,34 @@ where
                     .update_node(memory_id, &fields, Some(user_name))
                     .await
                 {
+                    self.finish_intent(intent, false).await;
                     failed.push(BatchFailure {
                         index: idx as u32,
                         error: format!("update error: {}", e),
                     });
                     continue;
                 }
-                let _ = self
-                    .vec_store
-                    .delete(std::slice::from_ref(memory_id), None)
-                    .await;
             } else {
                 // Hard delete
                 if let Err(e) = self.graph.delete_node(memory_id, Some(user_name)).await {
+                    self.finish_intent(intent, false).await;
                     failed.push(BatchFailure {
                         index: idx as u32,
                         error: format!("delete error: {}", e),
                     });
                     continue;
                 }
-                let _ = self
-                    .vec_store
-                    .delete(std::slice::from_ref(memory_id), None)
-                    .await;
             }
-
+            // Vector and keyword failures keep the delete; the pending intent finishes it.
+            let mut consistent = self
+                .vec_store
+                .delete(std::slice::from_ref(memory_id), None)
+                .await
+                .is_ok();
             if let Some(ref kw) = self.keyword_store {
-                let _ = kw.remove(memory_id, Some(user_name)).await;
+                consistent &= kw.remove(memory_id, Some(user_name)).await.is_ok();
             }
+            self.finish_intent(intent, consistent).await;
 
             successful.push(BatchResult {
                 memory_id: memory_id.clone(),
@@ -1466,11 +2625,7 @@ where
         let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
 
         // Get all memories for this user
-        let all_nodes = self
-            .graph
-            .get_all_memory_items("all", user_name, false)
-            .await
-            .map_err(MemCubeError::Graph)?;
+        let all_nodes = self.all_memory_nodes(user_name).await?;
 
         // Filter by scope if specified
         let filtered: Vec<MemoryNode> = if req.scope == "all" {
@@ -1478,27 +2633,21 @@ where
         } else {
             all_nodes
                 .into_iter()
-                .filter(|n| {
-                    n.metadata
-                        .get("scope")
-                        .and_then(|v| v.as_str())
-                        .map(|s| {
-                            let normalized = s.to_lowercase().replace("memory", "");
-                            normalized == req.scope.to_lowercase()
-                        })
-                        .unwrap_or(false)
-                })
+                .filter(|n| scope_matches(n, &req.scope))
                 .collect()
         };
 
-        // Convert to MemoryItem
-        let memories: Vec<MemoryItem> = filtered
+        // Oldest first; pages continue after the cursor's (created_at, id).
+        let (page, next_cursor) = keyset_page(
+            filtered,
+            |n| PageKey::created_at(&n.metadata, &n.id),
+            "export",
+            req.cursor.as_deref(),
+            req.limit.map_or(usize::MAX, |l| l as usize),
+        )?;
+        let memories: Vec<MemoryItem> = page
             .into_iter()
-            .map(|n| MemoryItem {
-                id: n.id,
-                memory: n.memory,
-                metadata: n.metadata,
-            })
+            .map(|n| self.visible_item(user_name, n))
             .collect();
 
         let total = memories.len() as u32;
@@ -1522,10 +2671,24 @@ where
             data: Some(ExportData {
                 total_memories: total,
                 data,
+                next_cursor,
             }),
         })
     }
 
+    async fn export_graph(
+        &self,
+        req: &GraphExportRequest,
+    ) -> Result<GraphExportResponse, MemCubeError> {
+        if req.include_entities {
+            return Err(MemCubeError::BadRequest(
+                "include_entities requires entity extraction".to_string(),
+            ));
+        }
+        let document = self.graph_document(req).await?;
+        Ok(graph_export_response(req, &document))
+    }
+
     // ============================================================================
     // Session Management (P1-3)
     // ============================================================================
@@ -1650,11 +2813,7 @@ where
         let user_name = &req.user_id;
 
         // Get all memories for this user and filter by session_id
-        let all_nodes = self
-            .graph
-            .get_all_memory_items("all", user_name, false)
-            .await
-            .map_err(MemCubeError::Graph)?;
+        let all_nodes = self.all_memory_nodes(user_name).await?;
 
         let filtered: Vec<MemoryNode> = all_nodes
             .into_iter()
@@ -1669,15 +2828,16 @@ where
 
         let total = filtered.len() as u32;
 
-        // Apply limit
-        let limited: Vec<MemoryItem> = filtered
+        let (page, next_cursor) = keyset_page(
+            filtered,
+            |n| PageKey::created_at(&n.metadata, &n.id),
+            "timeline",
+            req.cursor.as_deref(),
+            req.limit as usize,
+        )?;
+        let limited: Vec<MemoryItem> = page
             .into_iter()
-            .take(req.limit as usize)
-            .map(|n| MemoryItem {
-                id: n.id,
-                memory: n.memory,
-                metadata: n.metadata,
-            })
+            .map(|n| self.visible_item(user_name, n))
             .collect();
 
         Ok(SessionTimelineResponse {
@@ -1687,6 +2847,7 @@ where
                 session_id: req.session_id.clone(),
                 memories: limited,
                 total,
+                next_cursor,
             }),
         })
     }
@@ -1713,11 +2874,7 @@ where
                 .await
                 .map_err(MemCubeError::Graph)?
         } else if let Some(ref session_id) = req.session_id {
-            let all_nodes = self
-                .graph
-                .get_all_memory_items("all", user_name, false)
-                .await
-                .map_err(MemCubeError::Graph)?;
+            let all_nodes = self.all_memory_nodes(user_name).await?;
             all_nodes
                 .into_iter()
                 .filter(|n| {
@@ -1737,11 +2894,19 @@ where
         if nodes.is_empty() {
             return Err(MemCubeError::NotFound("no memories found".to_string()));
         }
+        // Lead with the memories the cube relies on most (importance, use, recency).
+        let now = Utc::now();
+        let retention = |n: &MemoryNode| {
+            MemorySignals::from_metadata(&n.metadata)
+                .retention_score(n.metadata.get("created_at").and_then(|v| v.as_str()), now)
+        };
+        let mut nodes = nodes;
+        nodes.sort_by(|a, b| retention(b).total_cmp(&retention(a)));
 
-        // Build prompt for summarization
+        // Build prompt for summarization (from the text the API would return)
         let content = nodes
             .iter()
-            .map(|n| n.memory.as_str())
+            .map(|n| self.visible_item(user_name, n.clone()).memory)
             .collect::<Vec<_>>()
             .join("\n\n---\n\n");
 
@@ -1784,6 +2949,19 @@ where
             "summarized_count".to_string(),
             serde_json::Value::Number(nodes.len().into()),
         );
+        // A consolidated memory is as important as the most important memory it replaces and
+        // keeps their combined use, so ranking does not demote it for being new.
+        let signals: Vec<MemorySignals> = nodes
+            .iter()
+            .map(|n| MemorySignals::from_metadata(&n.metadata))
+            .collect();
+        let importance = signals
+            .iter()
+            .map(|s| s.importance)
+            .fold(MemorySignals::DEFAULT_IMPORTANCE, f64::max);
+        let access_count: u64 = signals.iter().map(|s| s.access_count).sum();
+        metadata.insert("importance".to_string(), serde_json::json!(importance));
+        metadata.insert("access_count".to_string(), serde_json::json!(access_count));
 
         let node = MemoryNode {
             id: id.clone(),
@@ -1833,4 +3011,123 @@ where
             }),
         })
     }
+
+    // ============================================================================
+    // Feedback
+    // ============================================================================
+
+    async fn submit_feedback(
+        &self,
+        req: &FeedbackRequest,
+    ) -> Result<FeedbackResponse, MemCubeError> {
+        if req.memory_ids.is_empty() {
+            return Err(MemCubeError::BadRequest(
+                "memory_ids must not be empty".to_string(),
+            ));
+        }
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+
+        // Validate every id before changing any counters.
+        let nodes = self
+            .graph
+            .get_nodes(&req.memory_ids, false)
+            .await
+            .map_err(MemCubeError::Graph)?;
+        for id in &req.memory_ids {
+            let owned = nodes
+                .iter()
+                .any(|n| &n.id == id && Self::node_owner(&n.metadata) == user_name);
+            if !owned {
+                return Err(MemCubeError::NotFound(format!("memory not found: {}", id)));
+            }
+        }
+
+        let key = match req.label {
+            FeedbackLabel::Helpful => "feedback_helpful",
+            FeedbackLabel::Wrong => "feedback_wrong",
+        };
+        let now = Utc::now().to_rfc3339();
+        let mut data = Vec::with_capacity(req.memory_ids.len());
+        let mut events = Vec::with_capacity(req.memory_ids.len());
+        for id in &req.memory_ids {
+            let mut extra = HashMap::new();
+            extra.insert(
+                "last_feedback_at".to_string(),
+                serde_json::Value::String(now.clone()),
+            );
+            let metadata = self.bump_counter(id, user_name, key, extra).await?;
+            let signals = MemorySignals::from_metadata(&metadata);
+            data.push(serde_json::json!({
+                "memory_id": id,
+                "feedback_helpful": signals.helpful_count,
+                "feedback_wrong": signals.wrong_count,
+            }));
+            events.push(FeedbackEvent {
+                event_id: Uuid::new_v4().to_string(),
+                user_id: req.user_id.clone(),
+                cube_id: user_name.to_string(),
+                memory_id: id.clone(),
+                label: Some(req.label),
+                query: req.query.clone(),
+                comment: req.comment.clone(),
+                feedback_memory_id: None,
+                timestamp: now.clone(),
+            });
+        }
+        self.record_feedback_events(events).await;
+
+        Ok(FeedbackResponse {
+            code: 200,
+            message: "Feedback recorded".to_string(),
+            data: Some(data),
+        })
+    }
+
+    async fn export_feedback(
+        &self,
+        req: &FeedbackExportRequest,
+    ) -> Result<FeedbackExportResponse, MemCubeError> {
+        let feedback_store = self
+            .feedback_store
+            .as_ref()
+            .ok_or_else(|| MemCubeError::Other("feedback store not configured".to_string()))?;
+        let user_name = req.mem_cube_id.as_deref().unwrap_or(req.user_id.as_str());
+
+        let opts = FeedbackListOptions {
+            user_id: None,
+            cube_id: Some(user_name.to_string()),
+            since: req.since.clone(),
+            limit: req.limit,
+        };
+        let events = feedback_store
+            .list(&opts)
+            .await
+            .map_err(|e| MemCubeError::Other(e.to_string()))?;
+
+        let data = match req.format.as_str() {
+            "jsonl" => events
+                .iter()
+                .map(|e| serde_json::to_string(e).unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => serde_json::to_string(&events).unwrap_or_default(),
+        };
+
+        Ok(FeedbackExportResponse {
+            code: 200,
+            message: "Export completed".to_string(),
+            data: Some(FeedbackExportData {
+                total_events: events.len() as u32,
+                data,
+            }),
+        })
+    }
+
+    async fn erase_user_data(
+        &self,
+        req: &EraseUserDataRequest,
+    ) -> Result<ErasureOutcome, MemCubeError> {
+        let (outcome, _) = self.erase_cube_data(req).await?;
+        Ok(outcome)
+    }
 }
@@ -0,0 +1,299 @@
+//! Write intent logs (outbox) for multi-store writes. A write records its intent before the
+//! first store is touched and completes it once graph, vector and keyword stores agree, so
+//! intents left pending by a crash or a failed rollback can be replayed on recovery.
+
+use mem_types::{IntentLog, IntentLogError, IntentOp, WriteIntent};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Log lines after which a file log is compacted to its pending intents...
+const COMPACT_AFTER_LINES: usize = 1000;
+/// ...provided pending intents make up at most one line in this many.
+const COMPACT_RATIO: usize = 4;
+
+fn io_err(e: std::io::Error) -> IntentLogError {
+    IntentLogError::Other(e.to_string())
+}
+
+/// In-memory intent log (process lifetime only: nothing survives a crash).
+pub struct InMemoryIntentLog {
+    pending: Mutex<Vec<WriteIntent>>,
+}
+
+impl InMemoryIntentLog {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl Default for InMemoryIntentLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl IntentLog for InMemoryIntentLog {
+    async fn begin(&self, intent: &WriteIntent) -> Result<(), IntentLogError> {
+        self.pending.lock().await.push(intent.clone());
+        Ok(())
+    }
+
+    async fn complete(&self, intent_id: &str) -> Result<(), IntentLogError> {
+        self.pending
+            .lock()
+            .await
+            .retain(|i| i.intent_id != intent_id);
+        Ok(())
+    }
+
+    async fn pending(&self) -> Result<Vec<WriteIntent>, IntentLogError> {
+        Ok(self.pending.lock().await.clone())
+    }
+}
+
+struct FileLogState {
+    file: tokio::fs::File,
+    pending: Vec<WriteIntent>,
+    lines: usize,
+}
+
+/// Append-only JSONL intent log: `{"begin": <intent>}` and `{"complete": "<intent_id>"}`
+/// lines. Begins, and completions of adds, are synced to disk before the write proceeds or
+/// returns. The file is compacted to the pending intents on open and whenever it has grown
+/// large while few intents are pending.
+pub struct FileIntentLog {
+    path: PathBuf,
+    state: Mutex<FileLogState>,
+}
+
+impl FileIntentLog {
+    /// Open (or create) the log at `path`. A torn last line from a crash mid-append is
+    /// ignored; any other unreadable line is an error.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, IntentLogError> {
+        let path = path.as_ref().to_path_buf();
+        let pending = match std::fs::read_to_string(&path) {
+            Ok(text) => Self::replay(&text)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(io_err(e)),
+        };
+        let file = Self::compact(&path, &pending)?;
+        let lines = pending.len();
+        Ok(Self {
+            path,
+            state: Mutex::new(FileLogState {
+                file: tokio::fs::File::from_std(file),
+                pending,
+                lines,
+            }),
+        })
+    }
+
+    /// Path of the log file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn replay(text: &str) -> Result<Vec<WriteIntent>, IntentLogError> {
+        let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+        let mut pending: Vec<WriteIntent> = Vec::new();
+        for (n, line) in lines.iter().enumerate() {
+            let record: serde_json::Value = match serde_json::from_str(line) {
+                Ok(v) => v,
+                Err(_) if n + 1 == lines.len() => break,
+                Err(e) => {
+                    return Err(IntentLogError::Other(format!(
+                        "corrupt intent log line {}: {}",
+                        n + 1,
+                        e
+                    )))
+                }
+            };
+            if let Some(begin) = record.get("begin") {
+                let intent: WriteIntent = serde_json::from_value(begin.clone()).map_err(|e| {
+                    IntentLogError::Other(format!("corrupt intent log line {}: {}", n + 1, e))
+                })?;
+                pending.push(intent);
+            } else if let Some(id) = record.get("complete").and_then(|v| v.as_str()) {
+                pending.retain(|i| i.intent_id != id);
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Atomically replace the log with the begin lines of `pending` and reopen it for appends.
+    fn compact(path: &Path, pending: &[WriteIntent]) -> Result<std::fs::File, IntentLogError> {
+        let mut compacted = String::new();
+        for intent in pending {
+            compacted.push_str(&Self::begin_line(intent)?);
+        }
+        let tmp = path.with_extension("compact");
+        std::fs::write(&tmp, compacted).map_err(io_err)?;
+        std::fs::File::open(&tmp)
+            .and_then(|f| f.sync_all())
+            .map_err(io_err)?;
+        std::fs::rename(&tmp, path).map_err(io_err)?;
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(io_err)
+    }
+
+    fn begin_line(intent: &WriteIntent) -> Result<String, IntentLogError> {
+        let line = serde_json::to_string(&serde_json::json!({ "begin": intent }))
+            .map_err(|e| IntentLogError::Other(e.to_string()))?;
+        Ok(format!("{}\n", line))
+    }
+}
+
+#[async_trait::async_trait]
+impl IntentLog for FileIntentLog {
+    async fn begin(&self, intent: &WriteIntent) -> Result<(), IntentLogError> {
+        let line = Self::begin_line(intent)?;
+        let mut state = self.state.lock().await;
+        state
+            .file
+            .write_all(line.as_bytes())
+            .await
+            .map_err(io_err)?;
+        state.file.sync_data().await.map_err(io_err)?;
+        state.lines += 1;
+        state.pending.push(intent.clone());
+        Ok(())
+    }
+
+    async fn complete(&self, intent_id: &str) -> Result<(), IntentLogError> {
+        let mut state = self.state.lock().await;
+        let Some(pos) = state.pending.iter().position(|i| i.intent_id == intent_id) else {
+            return Ok(());
+        };
+        let done = state.pending.remove(pos);
+        if state.lines >= COMPACT_AFTER_LINES && state.pending.len() * COMPACT_RATIO <= state.lines
+        {
+            // Rewriting the log without the intent is the (synced) completion.
+            return match Self::compact(&self.path, &state.pending) {
+                Ok(file) => {
+                    state.file = tokio::fs::File::from_std(file);
+                    state.lines = state.pending.len();
+                    Ok(())
+                }
+                Err(e) => {
+                    state.pending.insert(pos, done);
+                    Err(e)
+                }
+            };
+        }
+        let line = format!("{}\n", serde_json::json!({ "complete": intent_id }));
+        let written = async {
+            state.file.write_all(line.as_bytes()).await?;
+            // A lost add completion would roll back an acknowledged add on replay; other
+            // intents replay idempotently from the graph, so flushing them is enough.
+            if done.op == IntentOp::Add {
+                state.file.sync_data().await
+            } else {
+                state.file.flush().await
+            }
+        }
+        .await;
+        if let Err(e) = written {
+            state.pending.insert(pos, done);
+            return Err(io_err(e));
+        }
+        state.lines += 1;
+        Ok(())
+    }
+
+    async fn pending(&self) -> Result<Vec<WriteIntent>, IntentLogError> {
+        Ok(self.state.lock().await.pending.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn intent(id: &str, op: IntentOp) -> WriteIntent {
+        WriteIntent {
+            intent_id: id.to_string(),
+            op,
+            memory_id: format!("mem-{}", id),
+            cube_id: "cube".to_string(),
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn temp_log() -> PathBuf {
+        std::env::temp_dir().join(format!("intent-log-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn pending_intents_survive_reopen() {
+        let path = temp_log();
+        {
+            let log = FileIntentLog::open(&path).unwrap();
+            log.begin(&intent("a", IntentOp::Add)).await.unwrap();
+            log.begin(&intent("b", IntentOp::Update)).await.unwrap();
+            log.begin(&intent("c", IntentOp::Forget)).await.unwrap();
+            log.complete("b").await.unwrap();
+            log.complete("unknown").await.unwrap();
+        }
+        let log = FileIntentLog::open(&path).unwrap();
+        let pending = log.pending().await.unwrap();
+        assert_eq!(
+            pending,
+            vec![intent("a", IntentOp::Add), intent("c", IntentOp::Forget)]
+        );
+        // Reopening compacted the file to the pending begins.
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn torn_last_line_is_ignored_but_corruption_is_not() {
+        let path = temp_log();
+        let complete = format!("{}\n", serde_json::json!({ "complete": "a" }));
+        let torn = format!(
+            "{}{}{{\"begin\":{{\"intent_id\":\"x\"",
+            FileIntentLog::begin_line(&intent("a", IntentOp::Add)).unwrap(),
+            FileIntentLog::begin_line(&intent("b", IntentOp::Repair)).unwrap(),
+        );
+        std::fs::write(&path, &torn).unwrap();
+        let log = FileIntentLog::open(&path).unwrap();
+        assert_eq!(log.pending().await.unwrap().len(), 2);
+        drop(log);
+
+        std::fs::write(&path, format!("not json\n{}", complete)).unwrap();
+        assert!(FileIntentLog::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn log_is_compacted_while_few_intents_are_pending() {
+        let path = temp_log();
+        let log = FileIntentLog::open(&path).unwrap();
+        // Two intents stay pending throughout, so the log is never empty.
+        log.begin(&intent("stuck-1", IntentOp::Repair))
+            .await
+            .unwrap();
+        log.begin(&intent("stuck-2", IntentOp::Add)).await.unwrap();
+        // Each round writes a begin and a complete; the last one crosses the threshold.
+        for n in 0..COMPACT_AFTER_LINES / 2 {
+            let id = n.to_string();
+            log.begin(&intent(&id, IntentOp::Update)).await.unwrap();
+            log.complete(&id).await.unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+        log.complete("stuck-1").await.unwrap();
+        drop(log);
+
+        let log = FileIntentLog::open(&path).unwrap();
+        assert_eq!(
+            log.pending().await.unwrap(),
+            vec![intent("stuck-2", IntentOp::Add)]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,430 @@
+//! PII detection and redaction applied to memory text on ingest (emails and phone numbers).
+//!
+//! Detection is heuristic and dependency-free: emails are `local@domain.tld`, phone numbers
+//! are runs of 7-15 digits with optional `+`, spaces, dashes, dots or parentheses. Bare digit
+//! runs under 10 digits and dates such as `2024-01-15` are not treated as phone numbers.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use mem_types::{EntityType, MemCubeError, PiiAction, PiiPolicy};
+use sha2::Sha256;
+
+/// One PII value found in a text (byte offsets).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiMatch {
+    pub kind: EntityType,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Result of applying a policy to one memory text.
+pub(crate) struct PiiOutcome {
+    /// Text to embed and store.
+    pub text: String,
+    /// Detected kinds (`email`, `phone`), deduplicated.
+    pub kinds: Vec<String>,
+    /// Base64 nonce+ciphertext of the original text, when the policy asks for it.
+    pub encrypted_original: Option<String>,
+}
+
+/// Find emails and phone numbers in `text`, ordered by position.
+pub fn detect_pii(text: &str) -> Vec<PiiMatch> {
+    let mut out = detect_emails(text);
+    for m in detect_phones(text) {
+        if !out.iter().any(|e| m.start < e.end && e.start < m.end) {
+            out.push(m);
+        }
+    }
+    out.sort_by_key(|m| m.start);
+    out
+}
+
+fn is_local_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'.' | b'_' | b'%' | b'+' | b'-')
+}
+
+fn is_domain_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, b'.' | b'-')
+}
+
+fn detect_emails(text: &str) -> Vec<PiiMatch> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    for (at, _) in text.match_indices('@') {
+        let mut start = at;
+        while start > 0 && is_local_char(bytes[start - 1]) {
+            start -= 1;
+        }
+        let mut end = at + 1;
+        while end < bytes.len() && is_domain_char(bytes[end]) {
+            end += 1;
+        }
+        // Sentence punctuation is not part of the domain.
+        while end > at + 1 && matches!(bytes[end - 1], b'.' | b'-') {
+            end -= 1;
+        }
+        let domain = &text[at + 1..end];
+        let tld = domain.rsplit('.').next().unwrap_or("");
+        if start < at
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && tld.len() >= 2
+            && tld.bytes().all(|c| c.is_ascii_alphabetic())
+        {
+            out.push(PiiMatch {
+                kind: EntityType::Email,
+                start,
+                end,
+            });
+        }
+    }
+    out
+}
+
+fn detect_phones(text: &str) -> Vec<PiiMatch> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let boundary = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+        if !(boundary && (c.is_ascii_digit() || c == b'+' || c == b'(')) {
+            i += 1;
+            continue;
+        }
+        if let Some(len) = date_len_at(bytes, i) {
+            i += len;
+            continue;
+        }
+        let start = i;
+        let mut end = i;
+        let mut j = i;
+        while j < bytes.len() {
+            let b = bytes[j];
+            let allowed = b.is_ascii_digit()
+                || matches!(b, b'-' | b'.' | b' ' | b'(' | b')')
+                || (b == b'+' && j == start);
+            if !allowed {
+                break;
+            }
+            if b.is_ascii_digit() {
+                end = j + 1;
+            }
+            j += 1;
+        }
+        let candidate = &text[start..end];
+        let digits = candidate.bytes().filter(u8::is_ascii_digit).count();
+        let formatted = candidate.starts_with('+')
+            || candidate.bytes().any(|b| !b.is_ascii_digit())
+            || digits >= 10;
+        let followed_by_alnum = end < bytes.len() && bytes[end].is_ascii_alphanumeric();
+        if (7..=15).contains(&digits) && formatted && !followed_by_alnum {
+            out.push(PiiMatch {
+                kind: EntityType::Phone,
+                start,
+                end,
+            });
+            i = end;
+        } else {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Length of a date (`2024-01-15`, `15.01.2024`, `15/01/2024`) starting at `i`, if any.
+fn date_len_at(bytes: &[u8], i: usize) -> Option<usize> {
+    let digit_run = |from: usize| {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let is_sep = |at: usize| matches!(bytes.get(at), Some(b'-' | b'.' | b'/'));
+    let a = digit_run(i);
+    if !is_sep(i + a) {
+        return None;
+    }
+    let b = digit_run(i + a + 1);
+    if !is_sep(i + a + 1 + b) {
+        return None;
+    }
+    let c = digit_run(i + a + b + 2);
+    let groups = [a, b, c];
+    if groups == [4, 2, 2] || groups == [2, 2, 4] {
+        Some(a + b + c + 2)
+    } else {
+        None
+    }
+}
+
+fn kind_label(kind: &EntityType) -> &'static str {
+    match kind {
+        EntityType::Email => "email",
+        _ => "phone",
+    }
+}
+
+/// Keyed short hash so equal values map to equal tokens. Never unkeyed: a truncated plain
+/// hash of an email or phone number is easily brute-forced.
+fn hash_value(value: &str, key: &[u8; 32]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(value.as_bytes());
+    hex::encode(&mac.finalize().into_bytes()[..6])
+}
+
+/// Replace PII in `text` according to `action` (detect leaves the text unchanged). Without a
+/// key, hash masks instead.
+pub(crate) fn redact_text(text: &str, action: PiiAction, key: Option<&[u8; 32]>) -> String {
+    if action == PiiAction::Detect {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for m in detect_pii(text) {
+        out.push_str(&text[last..m.start]);
+        let label = kind_label(&m.kind).to_ascii_uppercase();
+        match (action, key) {
+            (PiiAction::Hash, Some(key)) => {
+                out.push_str(&format!(
+                    "[{}:{}]",
+                    label,
+                    hash_value(&text[m.start..m.end], key)
+                ));
+            }
+            _ => out.push_str(&format!("[{}]", label)),
+        }
+        last = m.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// Redact every string inside a metadata value (arrays and objects included) with `action`.
+pub(crate) fn redact_value(
+    value: &mut serde_json::Value,
+    action: PiiAction,
+    key: Option<&[u8; 32]>,
+) {
+    match value {
+        serde_json::Value::String(text) => *text = redact_text(text, action, key),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_value(item, action, key);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                redact_value(item, action, key);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Apply `policy` to an incoming memory text. The hash action requires a key.
+pub(crate) fn apply_pii_policy(
+    text: &str,
+    policy: &PiiPolicy,
+    key: Option<&[u8; 32]>,
+) -> Result<PiiOutcome, MemCubeError> {
+    if policy.action == PiiAction::Hash && key.is_none() {
+        return Err(MemCubeError::BadRequest(
+            "PII hash policy requires a PII key".to_string(),
+        ));
+    }
+    let matches = detect_pii(text);
+    let mut kinds: Vec<String> = Vec::new();
+    for m in &matches {
+        let label = kind_label(&m.kind).to_string();
+        if !kinds.contains(&label) {
+            kinds.push(label);
+        }
+    }
+    if matches.is_empty() {
+        return Ok(PiiOutcome {
+            text: text.to_string(),
+            kinds,
+            encrypted_original: None,
+        });
+    }
+    if policy.action == PiiAction::Reject {
+        return Err(MemCubeError::BadRequest(format!(
+            "memory contains PII ({})",
+            kinds.join(", ")
+        )));
+    }
+    let encrypted_original = if policy.encrypt_original && policy.action != PiiAction::Detect {
+        let key = key
+            .ok_or_else(|| MemCubeError::Other("PII encryption key not configured".to_string()))?;
+        Some(encrypt_original(text, key)?)
+    } else {
+        None
+    };
+    Ok(PiiOutcome {
+        text: redact_text(text, policy.action, key),
+        kinds,
+        encrypted_original,
+    })
+}
+
+/// Apply `policy` to every string inside a metadata value (arrays and objects included),
+/// adding the detected kinds to `kinds`. Only the memory text keeps an encrypted original.
+pub(crate) fn apply_pii_policy_to_value(
+    value: &mut serde_json::Value,
+    policy: &PiiPolicy,
+    key: Option<&[u8; 32]>,
+    kinds: &mut Vec<String>,
+) -> Result<(), MemCubeError> {
+    match value {
+        serde_json::Value::String(text) => {
+            let policy = PiiPolicy {
+                encrypt_original: false,
+                ..policy.clone()
+            };
+            let outcome = apply_pii_policy(text, &policy, key)?;
+            for kind in outcome.kinds {
+                if !kinds.contains(&kind) {
+                    kinds.push(kind);
+                }
+            }
+            *text = outcome.text;
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                apply_pii_policy_to_value(item, policy, key, kinds)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                apply_pii_policy_to_value(item, policy, key, kinds)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn encrypt_original(text: &str, key: &[u8; 32]) -> Result<String, MemCubeError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, text.as_bytes())
+        .map_err(|e| MemCubeError::Other(format!("PII encryption failed: {}", e)))?;
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+    Ok(base64::engine::general_purpose::STANDARD.encode(blob))
+}
+
+/// Decrypt a `metadata.pii_original` value written on ingest.
+pub fn decrypt_pii_original(encoded: &str, key: &[u8; 32]) -> Result<String, MemCubeError> {
+    let blob = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| MemCubeError::Other(format!("invalid pii_original: {}", e)))?;
+    if blob.len() < 12 {
+        return Err(MemCubeError::Other("invalid pii_original".to_string()));
+    }
+    let (nonce, ciphertext) = blob.split_at(12);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| MemCubeError::Other(format!("PII decryption failed: {}", e)))?;
+    String::from_utf8(plain).map_err(|e| MemCubeError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(text: &str) -> Vec<&str> {
+        detect_pii(text)
+            .into_iter()
+            .map(|m| &text[m.start..m.end])
+            .collect()
+    }
+
+    #[test]
+    fn detects_emails_and_phones() {
+        assert_eq!(
+            found("Mail jane.doe+x@example.co.uk or call +1 (415) 555-0100."),
+            vec!["jane.doe+x@example.co.uk", "+1 (415) 555-0100"]
+        );
+        assert_eq!(found("My number is 555-0100"), vec!["555-0100"]);
+    }
+
+    #[test]
+    fn ignores_dates_and_plain_numbers() {
+        assert!(found("Met on 2024-01-15 and paid 1000000 at 10:30, user@localhost").is_empty());
+        assert_eq!(found("On 15.01.2024 555 0100 rang"), vec!["555 0100"]);
+    }
+
+    #[test]
+    fn mask_hash_and_encrypted_original_round_trip() {
+        let key = [7u8; 32];
+        let text = "email a@b.io";
+        assert_eq!(redact_text(text, PiiAction::Mask, None), "email [EMAIL]");
+        let hashed = redact_text(text, PiiAction::Hash, Some(&key));
+        assert!(hashed.starts_with("email [EMAIL:") && hashed.len() == "email [EMAIL:]".len() + 12);
+        assert_eq!(hashed, redact_text(text, PiiAction::Hash, Some(&key)));
+        assert_ne!(hashed, redact_text(text, PiiAction::Hash, Some(&[8u8; 32])));
+        assert_eq!(redact_text(text, PiiAction::Hash, None), "email [EMAIL]");
+        let hash = PiiPolicy {
+            action: PiiAction::Hash,
+            encrypt_original: false,
+        };
+        assert!(matches!(
+            apply_pii_policy(text, &hash, None),
+            Err(MemCubeError::BadRequest(_))
+        ));
+        assert!(apply_pii_policy("no pii here", &hash, None).is_err());
+
+        let policy = PiiPolicy {
+            action: PiiAction::Mask,
+            encrypt_original: true,
+        };
+        let outcome = apply_pii_policy(text, &policy, Some(&key)).unwrap();
+        let encoded = outcome.encrypted_original.unwrap();
+        assert_eq!(decrypt_pii_original(&encoded, &key).unwrap(), text);
+        assert!(decrypt_pii_original(&encoded, &[8u8; 32]).is_err());
+    }
+
+    #[test]
+    fn metadata_values_are_redacted_recursively() {
+        let mask = PiiPolicy {
+            action: PiiAction::Mask,
+            encrypt_original: true,
+        };
+        let mut value = serde_json::json!({
+            "tags": ["a@b.io", "plain"],
+            "contact": { "phone": "call 555-0100" },
+            "count": 3
+        });
+        let mut kinds = Vec::new();
+        apply_pii_policy_to_value(&mut value, &mask, None, &mut kinds).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "tags": ["[EMAIL]", "plain"],
+                "contact": { "phone": "call [PHONE]" },
+                "count": 3
+            })
+        );
+        kinds.sort();
+        assert_eq!(kinds, vec!["email", "phone"]);
+
+        let reject = PiiPolicy {
+            action: PiiAction::Reject,
+            encrypt_original: false,
+        };
+        let mut value = serde_json::json!(["fine", ["a@b.io"]]);
+        assert!(matches!(
+            apply_pii_policy_to_value(&mut value, &reject, None, &mut Vec::new()),
+            Err(MemCubeError::BadRequest(_))
+        ));
+    }
+}
@@ -7,6 +7,7 @@ pub mod mock;
 mod openai;
 mod openai_entity_extractor;
 mod reranker;
+mod rule_extractor;
 
 pub use entity_extractor::{
     CachedExtractor, CompositeExtractor, EntityExtractor, ExtractionOutput, ExtractorError,
@@ -17,6 +18,7 @@ pub use mem_types::{Embedder, EmbedderError};
 pub use openai::OpenAiEmbedder;
 pub use openai_entity_extractor::{OpenAiEntityExtractor, OpenAiExtractorConfig};
 pub use reranker::HttpReranker;
+pub use rule_extractor::{GazetteerEntry, RelationRule, RuleBasedExtractor};
 
 #[cfg(feature = "test-util")]
 pub use mock::MockEmbedder;
@@ -0,0 +1,522 @@
+//! Offline entity extractor: regexes for structured values, a gazetteer of known names and
+//! pattern-based relation rules. No network access and no API key.
+
+use crate::entity_extractor::{deduplicate_entities, EntityExtractor, ExtractorError};
+use async_trait::async_trait;
+use mem_types::{
+    EntityRelationType, EntityType, ExtractedEntity, ExtractedRelation, ExtractionConfig,
+    ExtractionResult, TextPosition,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Characters that end a sentence; relation rules never link entities across them.
+const SENTENCE_BREAKS: &[char] = &['.', '!', '?', ';', '\n'];
+
+/// Maximum number of characters between a relation connector and its source/target entity.
+const MAX_RELATION_GAP: usize = 40;
+
+/// A known entity: canonical name, type and alternative spellings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GazetteerEntry {
+    pub name: String,
+    pub entity_type: EntityType,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl GazetteerEntry {
+    pub fn new(name: impl Into<String>, entity_type: EntityType) -> Self {
+        Self {
+            name: name.into(),
+            entity_type,
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Add an alternative spelling that resolves to this entry.
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.aliases.push(alias.into());
+        self
+    }
+}
+
+/// A relation rule: when `pattern` matches between two entities of the same sentence, the
+/// nearest entity before the match becomes the source and the nearest one after the target.
+#[derive(Debug, Clone)]
+pub struct RelationRule {
+    pattern: Regex,
+    relation_type: EntityRelationType,
+}
+
+impl RelationRule {
+    /// Build a rule from a connector regex, e.g. `(?i)\bworks (?:at|for)\b`.
+    pub fn new(pattern: &str, relation_type: EntityRelationType) -> Result<Self, ExtractorError> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| ExtractorError::ConfigError(format!("invalid relation rule: {}", e)))?;
+        Ok(Self {
+            pattern,
+            relation_type,
+        })
+    }
+
+    /// Built-in English connectors for the common relation types.
+    pub fn defaults() -> Vec<Self> {
+        [
+            (
+                r"(?i)\b(?:works|worked|working) (?:at|for)\b",
+                EntityRelationType::WorksAt,
+            ),
+            (
+                r"(?i)\b(?:is an? )?employee of\b",
+                EntityRelationType::WorksAt,
+            ),
+            (
+                r"(?i)\b(?:lives|lived|is located|is based|located|based) in\b",
+                EntityRelationType::LocatedIn,
+            ),
+            (
+                r"(?i)\b(?:graduated|graduates) from\b",
+                EntityRelationType::GraduatedFrom,
+            ),
+            (
+                r"(?i)\b(?:is an? )?member of\b",
+                EntityRelationType::MemberOf,
+            ),
+            (r"(?i)\b(?:is )?part of\b", EntityRelationType::PartOf),
+            (r"(?i)\b(?:owns|owned)\b", EntityRelationType::Owns),
+            (
+                r"(?i)\b(?:was )?(?:created|built|written) by\b",
+                EntityRelationType::CreatedBy,
+            ),
+            (
+                r"(?i)\b(?:was )?founded by\b",
+                EntityRelationType::FoundedBy,
+            ),
+            (
+                r"(?i)\b(?:participated|took part) in\b",
+                EntityRelationType::ParticipatedIn,
+            ),
+        ]
+        .into_iter()
+        .map(|(pattern, relation_type)| Self {
+            pattern: Regex::new(pattern).expect("built-in relation rule"),
+            relation_type,
+        })
+        .collect()
+    }
+}
+
+/// Rule-based extractor combining value regexes, a gazetteer and relation rules.
+///
+/// Gazetteer hits are reported under the entry's canonical `name` (the span still points at
+/// the matched alias), so aliases merge into one entity in the knowledge graph.
+pub struct RuleBasedExtractor {
+    patterns: Vec<(EntityType, Regex, f64)>,
+    gazetteer: Vec<GazetteerEntry>,
+    gazetteer_regex: Option<Regex>,
+    /// Lowercased surface form -> gazetteer entry index.
+    surface_index: HashMap<String, usize>,
+    relation_rules: Vec<RelationRule>,
+}
+
+impl Default for RuleBasedExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuleBasedExtractor {
+    /// Extractor with the built-in value patterns (email, URL, date/time, phone, number) and
+    /// relation rules, and an empty gazetteer.
+    pub fn new() -> Self {
+        let patterns = [
+            (
+                EntityType::Email,
+                r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}",
+                0.95,
+            ),
+            (EntityType::Url, r#"(?i)\b(?:https?://|www\.)[^\s<>"']+"#, 0.95),
+            (
+                EntityType::DateTime,
+                r"(?i)\b\d{4}-\d{2}-\d{2}(?:[T ]\d{2}:\d{2}(?::\d{2})?)?\b|\b\d{1,2}/\d{1,2}/\d{2,4}\b|\b(?:jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?)\.? \d{1,2}(?:st|nd|rd|th)?(?:,? \d{4})?\b|\b\d{1,2}:\d{2}(?: ?[ap]m)?\b",
+                0.9,
+            ),
+            (EntityType::Phone, r"\+?\(?\d[\d\s().-]{5,}\d", 0.8),
+            (EntityType::Number, r"-?\b\d+(?:,\d{3})*(?:\.\d+)?%?", 0.85),
+        ]
+        .into_iter()
+        .map(|(t, p, c)| (t, Regex::new(p).expect("built-in entity pattern"), c))
+        .collect();
+        Self {
+            patterns,
+            gazetteer: Vec::new(),
+            gazetteer_regex: None,
+            surface_index: HashMap::new(),
+            relation_rules: RelationRule::defaults(),
+        }
+    }
+
+    /// Replace the gazetteer. Fails when the surfaces do not fit in one matcher (regex size
+    /// limit), e.g. for dictionaries of hundreds of thousands of names.
+    pub fn with_gazetteer(mut self, entries: Vec<GazetteerEntry>) -> Result<Self, ExtractorError> {
+        let mut surface_index = HashMap::new();
+        let mut surfaces: Vec<String> = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            for surface in std::iter::once(&entry.name).chain(&entry.aliases) {
+                let surface = surface.trim();
+                if surface.is_empty() {
+                    continue;
+                }
+                if surface_index.insert(surface.to_lowercase(), i).is_none() {
+                    surfaces.push(surface.to_string());
+                }
+            }
+        }
+        // Longest first so "Acme Labs" wins over "Acme" at the same position.
+        surfaces.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        self.gazetteer_regex = if surfaces.is_empty() {
+            None
+        } else {
+            let alternation = surfaces
+                .iter()
+                .map(|s| regex::escape(s))
+                .collect::<Vec<_>>()
+                .join("|");
+            let re = Regex::new(&format!("(?i){}", alternation)).map_err(|e| {
+                ExtractorError::ConfigError(format!(
+                    "gazetteer of {} surfaces: {}",
+                    surfaces.len(),
+                    e
+                ))
+            })?;
+            Some(re)
+        };
+        self.gazetteer = entries;
+        self.surface_index = surface_index;
+        Ok(self)
+    }
+
+    /// Load the gazetteer from a JSON file (array of `{name, entity_type, aliases}`).
+    pub fn with_gazetteer_file(self, path: impl AsRef<Path>) -> Result<Self, ExtractorError> {
+        let path = path.as_ref();
+        let raw = std::fs::read_to_string(path).map_err(|e| {
+            ExtractorError::ConfigError(format!("gazetteer {}: {}", path.display(), e))
+        })?;
+        let entries: Vec<GazetteerEntry> = serde_json::from_str(&raw).map_err(|e| {
+            ExtractorError::ConfigError(format!("gazetteer {}: {}", path.display(), e))
+        })?;
+        self.with_gazetteer(entries)
+    }
+
+    /// Replace the relation rules (use [`RelationRule::defaults`] to extend the built-ins).
+    pub fn with_relation_rules(mut self, rules: Vec<RelationRule>) -> Self {
+        self.relation_rules = rules;
+        self
+    }
+
+    fn gazetteer_matches(
+        &self,
+        text: &str,
+        taken: &mut Vec<(usize, usize)>,
+    ) -> Vec<ExtractedEntity> {
+        let Some(ref re) = self.gazetteer_regex else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        let mut at = 0;
+        while let Some(m) = re.find_at(text, at) {
+            let bounded = !text[..m.start()]
+                .chars()
+                .next_back()
+                .is_some_and(char::is_alphanumeric)
+                && !text[m.end()..]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_alphanumeric);
+            if bounded && !overlaps(taken, m.start(), m.end()) {
+                if let Some(&i) = self.surface_index.get(&m.as_str().to_lowercase()) {
+                    let entry = &self.gazetteer[i];
+                    taken.push((m.start(), m.end()));
+                    out.push(ExtractedEntity::new(
+                        entry.name.clone(),
+                        entry.entity_type.clone(),
+                        TextPosition::new(m.start(), m.end()),
+                        0.95,
+                    ));
+                }
+                at = m.end();
+            } else {
+                at = m.start() + text[m.start()..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        out
+    }
+
+    /// Value pattern matches outside `taken` spans. `structured` selects emails and URLs (matched
+    /// before the gazetteer, so names inside addresses are not picked up) or the other patterns.
+    fn pattern_matches(
+        &self,
+        text: &str,
+        taken: &mut Vec<(usize, usize)>,
+        structured: bool,
+    ) -> Vec<ExtractedEntity> {
+        let mut out = Vec::new();
+        for (entity_type, re, confidence) in &self.patterns {
+            if matches!(entity_type, EntityType::Email | EntityType::Url) != structured {
+                continue;
+            }
+            for m in re.find_iter(text) {
+                let value = m
+                    .as_str()
+                    .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ' ']);
+                let (start, end) = (m.start(), m.start() + value.len());
+                if value.is_empty() || overlaps(taken, start, end) {
+                    continue;
+                }
+                if *entity_type == EntityType::Phone && !looks_like_phone(value) {
+                    continue;
+                }
+                taken.push((start, end));
+                out.push(ExtractedEntity::new(
+                    value.to_string(),
+                    entity_type.clone(),
+                    TextPosition::new(start, end),
+                    *confidence,
+                ));
+            }
+        }
+        out
+    }
+
+    fn relations(&self, text: &str, entities: &[ExtractedEntity]) -> Vec<ExtractedRelation> {
+        let mut out: Vec<ExtractedRelation> = Vec::new();
+        for rule in &self.relation_rules {
+            for m in rule.pattern.find_iter(text) {
+                let source = entities
+                    .iter()
+                    .filter(|e| e.position.end <= m.start())
+                    .max_by_key(|e| e.position.end)
+                    .filter(|e| same_clause(&text[e.position.end..m.start()]));
+                let target = entities
+                    .iter()
+                    .filter(|e| e.position.start >= m.end())
+                    .min_by_key(|e| e.position.start)
+                    .filter(|e| same_clause(&text[m.end()..e.position.start]));
+                let (Some(source), Some(target)) = (source, target) else {
+                    continue;
+                };
+                if source.text == target.text {
+                    continue;
+                }
+                let duplicate = out.iter().any(|r| {
+                    r.source_text == source.text
+                        && r.target_text == target.text
+                        && r.relation_type == rule.relation_type
+                });
+                if !duplicate {
+                    out.push(ExtractedRelation {
+                        source_text: source.text.clone(),
+                        target_text: target.text.clone(),
+                        relation_type: rule.relation_type.clone(),
+                        confidence: source.confidence.min(target.confidence),
+                    });
+                }
+            }
+        }
+        out
+    }
+}
+
+#[async_trait]
+impl EntityExtractor for RuleBasedExtractor {
+    async fn extract(
+        &self,
+        text: &str,
+        config: ExtractionConfig,
+    ) -> Result<ExtractionResult, ExtractorError> {
+        let started = std::time::Instant::now();
+
+        // Emails and URLs first, then gazetteer names, then the remaining value patterns.
+        let mut taken: Vec<(usize, usize)> = Vec::new();
+        let mut entities = self.pattern_matches(text, &mut taken, true);
+        entities.extend(self.gazetteer_matches(text, &mut taken));
+        entities.extend(self.pattern_matches(text, &mut taken, false));
+        entities.sort_by_key(|e| e.position.start);
+
+        entities.retain(|e| e.confidence >= config.min_confidence);
+        if let Some(ref types) = config.target_types {
+            entities.retain(|e| types.contains(&e.entity_type));
+        }
+
+        let relations = if config.extract_relations {
+            self.relations(text, &entities)
+        } else {
+            Vec::new()
+        };
+        if config.enable_deduplication {
+            deduplicate_entities(&mut entities);
+        }
+
+        Ok(ExtractionResult {
+            entities,
+            relations,
+            summary: None,
+            processing_time_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
+    async fn extract_batch(
+        &self,
+        texts: &[String],
+        config: ExtractionConfig,
+    ) -> Result<Vec<ExtractionResult>, ExtractorError> {
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            results.push(self.extract(text, config.clone()).await?);
+        }
+        Ok(results)
+    }
+
+    fn supported_types(&self) -> Vec<EntityType> {
+        let mut types: Vec<EntityType> = self.patterns.iter().map(|(t, _, _)| t.clone()).collect();
+        for entry in &self.gazetteer {
+            if !types.contains(&entry.entity_type) {
+                types.push(entry.entity_type.clone());
+            }
+        }
+        types
+    }
+
+    fn name(&self) -> &str {
+        "rule_based"
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+}
+
+fn overlaps(taken: &[(usize, usize)], start: usize, end: usize) -> bool {
+    taken.iter().any(|&(s, e)| start < e && s < end)
+}
+
+/// 7-15 digits, and either formatted (`+`, parentheses, separators) or at least 10 digits.
+fn looks_like_phone(value: &str) -> bool {
+    let digits = value.chars().filter(char::is_ascii_digit).count();
+    if !(7..=15).contains(&digits) {
+        return false;
+    }
+    let formatted =
+        value.starts_with('+') || value.contains('(') || value.contains([' ', '-', '.']);
+    formatted || digits >= 10
+}
+
+fn same_clause(gap: &str) -> bool {
+    gap.chars().count() <= MAX_RELATION_GAP && !gap.contains(SENTENCE_BREAKS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extractor() -> RuleBasedExtractor {
+        RuleBasedExtractor::new()
+            .with_gazetteer(vec![
+                GazetteerEntry::new("Alice Smith", EntityType::Person).with_alias("Alice"),
+                GazetteerEntry::new("Acme Corp", EntityType::Organization).with_alias("Acme"),
+                GazetteerEntry::new("Berlin", EntityType::Location),
+            ])
+            .unwrap()
+    }
+
+    fn find(result: &ExtractionResult, t: EntityType) -> Vec<&str> {
+        result
+            .entities
+            .iter()
+            .filter(|e| e.entity_type == t)
+            .map(|e| e.text.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn extracts_values_gazetteer_entries_and_relations() {
+        let text =
+            "Alice works at ACME, based in Berlin. Mail alice@acme.io or call +49 30 1234567 \
+                    before 2024-03-01; see https://acme.io/jobs. Budget: 1,500 for 3 people.";
+        let result = extractor()
+            .extract(text, ExtractionConfig::default())
+            .await
+            .unwrap();
+
+        assert_eq!(find(&result, EntityType::Person), vec!["Alice Smith"]);
+        assert_eq!(find(&result, EntityType::Organization), vec!["Acme Corp"]);
+        assert_eq!(find(&result, EntityType::Location), vec!["Berlin"]);
+        assert_eq!(find(&result, EntityType::Email), vec!["alice@acme.io"]);
+        assert_eq!(find(&result, EntityType::Phone), vec!["+49 30 1234567"]);
+        assert_eq!(find(&result, EntityType::DateTime), vec!["2024-03-01"]);
+        assert_eq!(find(&result, EntityType::Url), vec!["https://acme.io/jobs"]);
+        assert_eq!(find(&result, EntityType::Number), vec!["1,500", "3"]);
+
+        let alice = &result.entities[0];
+        assert_eq!(alice.text, "Alice Smith");
+        assert_eq!(&text[alice.position.start..alice.position.end], "Alice");
+
+        let relations: Vec<_> = result
+            .relations
+            .iter()
+            .map(|r| {
+                (
+                    r.source_text.as_str(),
+                    r.relation_type.clone(),
+                    r.target_text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            relations,
+            vec![
+                ("Alice Smith", EntityRelationType::WorksAt, "Acme Corp"),
+                ("Acme Corp", EntityRelationType::LocatedIn, "Berlin"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn respects_word_boundaries_and_config() {
+        let result = extractor()
+            .extract("Acmeville is not Acme.", ExtractionConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(result.entities.len(), 1);
+        assert_eq!(result.entities[0].position.start, 17);
+
+        let config = ExtractionConfig {
+            target_types: Some(vec![EntityType::Email]),
+            ..Default::default()
+        };
+        let result = extractor()
+            .extract("Alice: alice@acme.io, 42", config)
+            .await
+            .unwrap();
+        assert_eq!(find(&result, EntityType::Email), vec!["alice@acme.io"]);
+        assert_eq!(result.entities.len(), 1);
+    }
+
+    #[test]
+    fn oversized_gazetteer_is_an_error() {
+        // `k` and `s` have three case forms each (Kelvin sign, long s), so these surfaces
+        // exceed the regex size limit quickly.
+        let entries = (0..3_000)
+            .map(|i| {
+                GazetteerEntry::new(format!("{:05}{}", i, "ks".repeat(50)), EntityType::Concept)
+            })
+            .collect();
+        assert!(matches!(
+            RuleBasedExtractor::new().with_gazetteer(entries),
+            Err(ExtractorError::ConfigError(_))
+        ));
+    }
+}
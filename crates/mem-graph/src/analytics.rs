@@ -0,0 +1,439 @@
+//! Whole-graph analytics of one user/cube: PageRank, degree centrality, weakly connected
+//! components and Louvain communities. Store-agnostic: live nodes are loaded with
+//! `GraphStore::get_all_memory_items` and edges page by page with `GraphStore::list_edges`.
+
+use mem_types::{GraphDirection, GraphStore, GraphStoreError, MemoryScope, NodeAnalytics};
+use std::collections::{BTreeMap, HashMap};
+
+/// Edges fetched per `list_edges` call.
+const EDGE_PAGE_SIZE: usize = 1000;
+
+/// Parameters of a graph analytics run.
+#[derive(Debug, Clone)]
+pub struct GraphAnalyticsOptions<'a> {
+    /// Only edges with this relation are analyzed.
+    pub relation: Option<&'a str>,
+    /// PageRank damping factor in (0, 1).
+    pub damping: f64,
+    /// Iteration cap for PageRank and for each Louvain pass.
+    pub max_iterations: usize,
+    /// PageRank stops once an iteration changes the ranks by less than this (L1 norm).
+    pub tolerance: f64,
+}
+
+impl Default for GraphAnalyticsOptions<'_> {
+    fn default() -> Self {
+        Self {
+            relation: None,
+            damping: 0.85,
+            max_iterations: 100,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/// Analytics of a cube's graph.
+#[derive(Debug, Clone, Default)]
+pub struct GraphAnalytics {
+    /// One entry per live memory, ordered by id.
+    pub nodes: Vec<NodeAnalytics>,
+    /// Distinct directed edges between live memories (self-loops ignored).
+    pub edges: usize,
+    /// Component sizes, largest first; `component_id` indexes this.
+    pub component_sizes: Vec<usize>,
+    /// Community sizes, largest first; `community_id` indexes this.
+    pub community_sizes: Vec<usize>,
+    pub pagerank_iterations: usize,
+}
+
+/// Analyze the live (non-tombstone) memories of `user_name` and the edges between them.
+pub async fn analyze_graph<G: GraphStore + ?Sized>(
+    graph: &G,
+    user_name: &str,
+    options: &GraphAnalyticsOptions<'_>,
+) -> Result<GraphAnalytics, GraphStoreError> {
+    let mut ids = Vec::new();
+    for scope in [
+        MemoryScope::WorkingMemory,
+        MemoryScope::UserMemory,
+        MemoryScope::LongTermMemory,
+    ] {
+        for node in graph
+            .get_all_memory_items(scope.as_str(), user_name, false)
+            .await?
+        {
+            if node.metadata.get("state").and_then(|v| v.as_str()) != Some("tombstone") {
+                ids.push(node.id);
+            }
+        }
+    }
+    ids.sort();
+    ids.dedup();
+    let index: HashMap<&str, usize> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+
+    let mut edges = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let page = graph
+            .list_edges(
+                user_name,
+                None,
+                options.relation,
+                GraphDirection::Both,
+                after.as_deref(),
+                EDGE_PAGE_SIZE,
+            )
+            .await?;
+        let more = page.len() == EDGE_PAGE_SIZE;
+        after = page.last().map(|e| e.id.clone());
+        for edge in page {
+            if let (Some(&from), Some(&to)) =
+                (index.get(edge.from.as_str()), index.get(edge.to.as_str()))
+            {
+                if from != to {
+                    edges.push((from, to));
+                }
+            }
+        }
+        if !more {
+            break;
+        }
+    }
+    Ok(compute(ids, edges, options))
+}
+
+fn compute(
+    ids: Vec<String>,
+    mut edges: Vec<(usize, usize)>,
+    options: &GraphAnalyticsOptions<'_>,
+) -> GraphAnalytics {
+    let n = ids.len();
+    if n == 0 {
+        return GraphAnalytics::default();
+    }
+    edges.sort_unstable();
+    edges.dedup();
+    let mut outbound = vec![Vec::new(); n];
+    let mut undirected = vec![Vec::new(); n];
+    for &(from, to) in &edges {
+        outbound[from].push(to);
+        undirected[from].push(to);
+        undirected[to].push(from);
+    }
+    for neighbors in &mut undirected {
+        neighbors.sort_unstable();
+        neighbors.dedup();
+    }
+
+    let (pagerank, pagerank_iterations) = pagerank(&outbound, options);
+    let max_rank = pagerank.iter().cloned().fold(0.0, f64::max);
+    let (component_ids, component_sizes) = number_groups(&components(&undirected));
+    let (community_ids, community_sizes) =
+        number_groups(&louvain(&undirected, options.max_iterations));
+
+    let nodes = ids
+        .into_iter()
+        .enumerate()
+        .map(|(i, memory_id)| {
+            let degree = undirected[i].len();
+            NodeAnalytics {
+                memory_id,
+                pagerank: pagerank[i],
+                centrality: if max_rank > 0.0 {
+                    pagerank[i] / max_rank
+                } else {
+                    0.0
+                },
+                degree: degree as u64,
+                degree_centrality: if n > 1 {
+                    degree as f64 / (n - 1) as f64
+                } else {
+                    0.0
+                },
+                component_id: component_ids[i],
+                community_id: community_ids[i],
+            }
+        })
+        .collect();
+    GraphAnalytics {
+        nodes,
+        edges: edges.len(),
+        component_sizes,
+        community_sizes,
+        pagerank_iterations,
+    }
+}
+
+/// Power iteration; the rank of nodes without outbound edges is spread over all nodes.
+fn pagerank(outbound: &[Vec<usize>], options: &GraphAnalyticsOptions<'_>) -> (Vec<f64>, usize) {
+    let n = outbound.len() as f64;
+    let damping = options.damping;
+    let mut rank = vec![1.0 / n; outbound.len()];
+    let mut iterations = 0;
+    while iterations < options.max_iterations {
+        iterations += 1;
+        let dangling: f64 = outbound
+            .iter()
+            .zip(&rank)
+            .filter(|(targets, _)| targets.is_empty())
+            .map(|(_, r)| r)
+            .sum();
+        let mut next = vec![(1.0 - damping + damping * dangling) / n; outbound.len()];
+        for (targets, r) in outbound.iter().zip(&rank) {
+            let share = damping * r / targets.len() as f64;
+            for &t in targets {
+                next[t] += share;
+            }
+        }
+        let delta: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if delta < options.tolerance {
+            break;
+        }
+    }
+    (rank, iterations)
+}
+
+/// Weakly connected components by union-find; returns a representative per node.
+fn components(undirected: &[Vec<usize>]) -> Vec<usize> {
+    fn find(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut parent: Vec<usize> = (0..undirected.len()).collect();
+    for (i, neighbors) in undirected.iter().enumerate() {
+        for &j in neighbors {
+            let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+            if a != b {
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+    (0..undirected.len())
+        .map(|i| find(&mut parent, i))
+        .collect()
+}
+
+/// Louvain modularity optimization: nodes move to the neighboring community with the best
+/// modularity gain (visited in id order, ties keep the current community, then the smallest),
+/// then communities are merged into nodes and the process repeats until nothing moves.
+/// Returns a community label per node.
+fn louvain(undirected: &[Vec<usize>], max_iterations: usize) -> Vec<usize> {
+    let mut membership: Vec<usize> = (0..undirected.len()).collect();
+    let mut adjacency: Vec<Vec<(usize, f64)>> = undirected
+        .iter()
+        .map(|neighbors| neighbors.iter().map(|&j| (j, 1.0)).collect())
+        .collect();
+    let mut strength: Vec<f64> = undirected.iter().map(|n| n.len() as f64).collect();
+    let two_m: f64 = strength.iter().sum();
+    if two_m == 0.0 {
+        return membership;
+    }
+    loop {
+        let size = adjacency.len();
+        let mut community: Vec<usize> = (0..size).collect();
+        let mut total = strength.clone();
+        let mut moved_any = false;
+        for _ in 0..max_iterations {
+            let mut moved = false;
+            for i in 0..size {
+                let current = community[i];
+                let k = strength[i];
+                let mut links: BTreeMap<usize, f64> = BTreeMap::new();
+                for &(j, w) in &adjacency[i] {
+                    *links.entry(community[j]).or_default() += w;
+                }
+                total[current] -= k;
+                let gain = |c: usize, w: f64| w - total[c] * k / two_m;
+                let mut best = current;
+                let mut best_gain = gain(current, links.get(&current).copied().unwrap_or(0.0));
+                for (&c, &w) in &links {
+                    let g = gain(c, w);
+                    if g > best_gain + 1e-12 {
+                        best = c;
+                        best_gain = g;
+                    }
+                }
+                total[best] += k;
+                if best != current {
+                    community[i] = best;
+                    moved = true;
+                }
+            }
+            if !moved {
+                break;
+            }
+            moved_any = true;
+        }
+        if !moved_any {
+            return membership;
+        }
+
+        let mut renumber: HashMap<usize, usize> = HashMap::new();
+        for &c in &community {
+            let next = renumber.len();
+            renumber.entry(c).or_insert(next);
+        }
+        let mut merged: Vec<BTreeMap<usize, f64>> = vec![BTreeMap::new(); renumber.len()];
+        let mut merged_strength = vec![0.0; renumber.len()];
+        for i in 0..size {
+            let ci = renumber[&community[i]];
+            merged_strength[ci] += strength[i];
+            for &(j, w) in &adjacency[i] {
+                let cj = renumber[&community[j]];
+                if ci != cj {
+                    *merged[ci].entry(cj).or_default() += w;
+                }
+            }
+        }
+        for m in &mut membership {
+            *m = renumber[&community[*m]];
+        }
+        adjacency = merged
+            .into_iter()
+            .map(|m| m.into_iter().collect())
+            .collect();
+        strength = merged_strength;
+    }
+}
+
+/// Number groups by size (largest first, ties by their smallest node) and return each node's
+/// group number and the group sizes.
+fn number_groups(labels: &[usize]) -> (Vec<u64>, Vec<usize>) {
+    let mut groups: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    for (i, &label) in labels.iter().enumerate() {
+        let group = groups.entry(label).or_insert((0, i));
+        group.0 += 1;
+    }
+    let mut order: Vec<(usize, usize, usize)> = groups
+        .into_iter()
+        .map(|(label, (size, first))| (label, size, first))
+        .collect();
+    order.sort_by_key(|&(_, size, first)| (std::cmp::Reverse(size), first));
+    let number: HashMap<usize, u64> = order
+        .iter()
+        .enumerate()
+        .map(|(n, &(label, _, _))| (label, n as u64))
+        .collect();
+    (
+        labels.iter().map(|l| number[l]).collect(),
+        order.into_iter().map(|(_, size, _)| size).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryGraphStore;
+    use mem_types::MemoryEdge;
+
+    async fn two_triangles() -> InMemoryGraphStore {
+        let store = InMemoryGraphStore::new();
+        for id in ["a", "b", "c", "d", "e", "f", "lonely", "gone"] {
+            let mut metadata = HashMap::new();
+            if id == "gone" {
+                metadata.insert("state".to_string(), serde_json::json!("tombstone"));
+            }
+            store.add_node(id, id, &metadata, Some("u1")).await.unwrap();
+        }
+        store
+            .add_node("x", "x", &HashMap::new(), Some("u2"))
+            .await
+            .unwrap();
+        let edge = |id: &str, from: &str, to: &str| MemoryEdge {
+            id: id.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            relation: "r".to_string(),
+            metadata: HashMap::new(),
+        };
+        store
+            .add_edges_batch(
+                &[
+                    edge("e1", "a", "b"),
+                    edge("e2", "b", "c"),
+                    edge("e3", "c", "a"),
+                    edge("e4", "c", "d"),
+                    edge("e5", "d", "e"),
+                    edge("e6", "e", "f"),
+                    edge("e7", "f", "d"),
+                    edge("e8", "a", "gone"),
+                ],
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn analytics_find_hubs_components_and_communities() {
+        let store = two_triangles().await;
+        let result = analyze_graph(&store, "u1", &GraphAnalyticsOptions::default())
+            .await
+            .unwrap();
+        let by_id: HashMap<&str, &NodeAnalytics> = result
+            .nodes
+            .iter()
+            .map(|n| (n.memory_id.as_str(), n))
+            .collect();
+        assert_eq!(
+            result.nodes.len(),
+            7,
+            "tombstones and other cubes are skipped"
+        );
+        assert_eq!(result.edges, 7);
+        assert_eq!(result.component_sizes, vec![6, 1]);
+        assert_eq!(result.community_sizes, vec![3, 3, 1]);
+
+        let total: f64 = result.nodes.iter().map(|n| n.pagerank).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+        assert!(result.pagerank_iterations > 1);
+        let hub = result
+            .nodes
+            .iter()
+            .max_by(|a, b| a.pagerank.total_cmp(&b.pagerank))
+            .unwrap();
+        assert_eq!(hub.centrality, 1.0);
+        assert!(by_id["d"].pagerank > by_id["e"].pagerank);
+
+        assert_eq!(by_id["c"].degree, 3);
+        assert_eq!(by_id["c"].degree_centrality, 0.5);
+        assert_eq!(by_id["lonely"].degree, 0);
+        assert_eq!(by_id["lonely"].component_id, 1);
+        assert_eq!(by_id["lonely"].community_id, 2);
+        assert_eq!(by_id["a"].community_id, by_id["c"].community_id);
+        assert_eq!(by_id["d"].community_id, by_id["f"].community_id);
+        assert_ne!(by_id["c"].community_id, by_id["d"].community_id);
+        assert_eq!(by_id["a"].community_id, 0, "ties go to the smallest member");
+    }
+
+    #[tokio::test]
+    async fn relation_filter_and_empty_cubes() {
+        let store = two_triangles().await;
+        let options = GraphAnalyticsOptions {
+            relation: Some("other"),
+            ..Default::default()
+        };
+        let result = analyze_graph(&store, "u1", &options).await.unwrap();
+        assert_eq!(result.edges, 0);
+        assert_eq!(result.component_sizes, vec![1; 7]);
+        assert!(result
+            .nodes
+            .iter()
+            .all(|n| (n.pagerank - 1.0 / 7.0).abs() < 1e-9 && n.centrality == 1.0));
+
+        let empty = analyze_graph(&store, "nobody", &GraphAnalyticsOptions::default())
+            .await
+            .unwrap();
+        assert!(empty.nodes.is_empty());
+        assert!(empty.community_sizes.is_empty());
+    }
+}
@@ -1,31 +1,53 @@
 //! Entity Knowledge Graph implementation.
 //!
 //! Provides a simple in-memory graph structure for managing entities and their relations.
-
-use mem_types::{Entity, EntityMetadata, EntityRelationType, EntityType, ExtractedEntity};
-use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+//! The graph is partitioned per cube: names, variants and types are indexed per cube, so the
+//! same name in two cubes yields two entities, and relations never cross a cube boundary.
+
+use mem_types::{
+    Entity, EntityMetadata, EntityPath, EntityRelationType, EntitySplit, EntitySubgraph,
+    EntitySubgraphNode, EntityTraversal, EntityType, ExtractedEntity, GraphDirection, Validity,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
-/// Map from relation type to target entity ids.
-type RelationMap = HashMap<EntityRelationType, HashSet<String>>;
+/// Minimum score of a fuzzy search hit when the caller gives none.
+pub const DEFAULT_FUZZY_MIN_SCORE: f64 = 0.85;
+
+/// Map from relation type to target entity id to when that relation holds.
+type RelationMap = HashMap<EntityRelationType, HashMap<String, Validity>>;
+/// cube_id -> name (normalized name or variant) -> entity_id.
+type NameIndex = HashMap<String, HashMap<String, String>>;
+/// cube_id -> entity_type -> entity_ids.
+type TypeIndex = HashMap<String, HashMap<EntityType, HashSet<String>>>;
+
+/// Result of a breadth-first walk over entity relations.
+#[derive(Default)]
+struct Walk {
+    /// Visited entity ids with their hop count, in visiting order (start first).
+    order: Vec<(String, u32)>,
+    /// entity id -> (the entity it was reached from, the relation followed).
+    via: HashMap<String, (String, StoredRelation)>,
+    /// The walk hit `max_entities`.
+    truncated: bool,
+}
 
 /// Entity Knowledge Graph - stores and manages entities and their relations.
 #[derive(Debug, Clone, Default)]
 pub struct EntityKnowledgeGraph {
     /// Core entity storage: entity_id -> Entity
     entities: Arc<RwLock<HashMap<String, Entity>>>,
-    /// Name index: normalized_name -> entity_id
-    name_index: Arc<RwLock<HashMap<String, String>>>,
-    /// Type index: entity_type -> set of entity_ids
-    type_index: Arc<RwLock<HashMap<EntityType, HashSet<String>>>>,
+    /// Name index: cube_id -> normalized_name -> entity_id
+    name_index: Arc<RwLock<NameIndex>>,
+    /// Type index: cube_id -> entity_type -> set of entity_ids
+    type_index: Arc<RwLock<TypeIndex>>,
     /// Entity relations: source_id -> (relation_type -> set of target_ids)
     relations: Arc<RwLock<HashMap<String, RelationMap>>>,
     /// Entity to memories index: entity_id -> set of memory_ids
     memory_index: Arc<RwLock<HashMap<String, HashSet<String>>>>,
-    /// Name variants index: variant -> entity_id
-    variant_index: Arc<RwLock<HashMap<String, String>>>,
+    /// Name variants index: cube_id -> variant -> entity_id
+    variant_index: Arc<RwLock<NameIndex>>,
 }
 
 impl EntityKnowledgeGraph {
@@ -60,9 +82,10 @@ impl EntityKnowledgeGraph {
     // Entity Operations
     // =========================================================================
 
-    /// Create or update an entity from an extraction result.
+    /// Create or update an entity of `cube_id` from an extraction result.
     pub fn upsert_entity(
         &self,
+        cube_id: &str,
         extracted: &ExtractedEntity,
         memory_id: &str,
     ) -> Result<(String, bool), EntityKgError> {
@@ -72,11 +95,21 @@ impl EntityKnowledgeGraph {
         let mut name_index = self.name_index.write().unwrap();
 
         // Check for existing entity by name
-        if let Some(existing_id) = name_index.get(&normalized_name) {
+        if let Some(existing_id) = name_index
+            .get(cube_id)
+            .and_then(|names| names.get(&normalized_name))
+        {
             if let Some(entity) = entities.get_mut(existing_id) {
                 // Add memory ID if not already present
                 if !entity.memory_ids.contains(&memory_id.to_string()) {
                     entity.memory_ids.push(memory_id.to_string());
+                    entity.metadata.occurrence_count += 1;
+                    self.memory_index
+                        .write()
+                        .unwrap()
+                        .entry(entity.id.clone())
+                        .or_default()
+                        .insert(memory_id.to_string());
                 }
 
                 // Add name variant if new
@@ -91,7 +124,10 @@ impl EntityKnowledgeGraph {
 
                 // Update variant index
                 let mut variant_index = self.variant_index.write().unwrap();
-                variant_index.insert(extracted.text.clone(), entity.id.clone());
+                variant_index
+                    .entry(cube_id.to_string())
+                    .or_default()
+                    .insert(extracted.text.clone(), entity.id.clone());
 
                 return Ok((entity.id.clone(), false));
             }
@@ -105,26 +141,35 @@ impl EntityKnowledgeGraph {
             id: id.clone(),
             name: normalized_name.clone(),
             entity_type: extracted.entity_type.clone(),
+            cube_id: cube_id.to_string(),
             name_variants: vec![extracted.text.clone()],
             description: None,
             memory_ids: vec![memory_id.to_string()],
             attributes: HashMap::new(),
+            attribute_history: Vec::new(),
             metadata: EntityMetadata {
                 first_seen: now.clone(),
                 last_updated: now,
                 occurrence_count: 1,
                 source_memory_id: memory_id.to_string(),
                 confidence: extracted.confidence,
+                description_memory_ids: Vec::new(),
+                description_updated_at: None,
             },
             version: 0,
         };
 
         // Insert into all indices
         entities.insert(id.clone(), entity.clone());
-        name_index.insert(normalized_name, id.clone());
+        name_index
+            .entry(cube_id.to_string())
+            .or_default()
+            .insert(normalized_name, id.clone());
 
         let mut type_index = self.type_index.write().unwrap();
         type_index
+            .entry(cube_id.to_string())
+            .or_default()
             .entry(extracted.entity_type.clone())
             .or_default()
             .insert(id.clone());
@@ -136,7 +181,10 @@ impl EntityKnowledgeGraph {
             .insert(memory_id.to_string());
 
         let mut variant_index = self.variant_index.write().unwrap();
-        variant_index.insert(extracted.text.clone(), id.clone());
+        variant_index
+            .entry(cube_id.to_string())
+            .or_default()
+            .insert(extracted.text.clone(), id.clone());
 
         Ok((id, true))
     }
@@ -146,29 +194,34 @@ impl EntityKnowledgeGraph {
         self.entities.read().unwrap().get(id).cloned()
     }
 
-    /// Get an entity by name (normalized).
-    pub fn get_by_name(&self, name: &str) -> Option<Entity> {
+    /// Get an entity of the cube by name (normalized).
+    pub fn get_by_name(&self, cube_id: &str, name: &str) -> Option<Entity> {
         let normalized = self.normalize_name(name);
         let name_index = self.name_index.read().unwrap();
         name_index
-            .get(&normalized)
+            .get(cube_id)
+            .and_then(|names| names.get(&normalized))
             .and_then(|id| self.entities.read().unwrap().get(id).cloned())
     }
 
-    /// Find entity by name variant (fuzzy).
-    pub fn find_by_variant(&self, variant: &str) -> Option<Entity> {
+    /// Find an entity of the cube by name variant (fuzzy).
+    pub fn find_by_variant(&self, cube_id: &str, variant: &str) -> Option<Entity> {
         let variant_index = self.variant_index.read().unwrap();
         variant_index
-            .get(variant)
+            .get(cube_id)
+            .and_then(|variants| variants.get(variant))
             .and_then(|id| self.entities.read().unwrap().get(id).cloned())
     }
 
-    /// Find entities by type.
-    pub fn find_by_type(&self, entity_type: EntityType) -> Vec<Entity> {
+    /// Find entities of the cube by type.
+    pub fn find_by_type(&self, cube_id: &str, entity_type: EntityType) -> Vec<Entity> {
         let type_index = self.type_index.read().unwrap();
         let entities = self.entities.read().unwrap();
 
-        if let Some(ids) = type_index.get(&entity_type) {
+        if let Some(ids) = type_index
+            .get(cube_id)
+            .and_then(|types| types.get(&entity_type))
+        {
             ids.iter()
                 .filter_map(|id| entities.get(id).cloned())
                 .collect()
@@ -177,39 +230,79 @@ impl EntityKnowledgeGraph {
         }
     }
 
-    /// Fuzzy search entities by name.
-    pub fn fuzzy_search(&self, query: &str, limit: u32) -> Vec<Entity> {
-        let query_lower = query.to_lowercase();
+    /// All entities of the cube.
+    pub fn entities_in_cube(&self, cube_id: &str) -> Vec<Entity> {
+        let name_index = self.name_index.read().unwrap();
         let entities = self.entities.read().unwrap();
+        name_index
+            .get(cube_id)
+            .map(|names| {
+                names
+                    .values()
+                    .filter_map(|id| entities.get(id).cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 
-        let mut results: Vec<_> = entities
-            .values()
-            .filter(|e| e.name.contains(&query_lower))
-            .take(limit as usize)
-            .cloned()
+    /// Typo-tolerant search over names and variants of the cube's entities. Each hit carries
+    /// the best match score (0.0 - 1.0, see [`crate::fuzzy::match_score`]); hits below
+    /// `min_score` (default 0.85) are dropped. Sorted by score, then mentions, before truncation.
+    pub fn fuzzy_search(
+        &self,
+        cube_id: &str,
+        query: &str,
+        entity_type: Option<EntityType>,
+        min_score: Option<f64>,
+        limit: u32,
+    ) -> Vec<(Entity, f64)> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let min_score = min_score.unwrap_or(DEFAULT_FUZZY_MIN_SCORE);
+        let candidates = match entity_type {
+            Some(t) => self.find_by_type(cube_id, t),
+            None => self.entities_in_cube(cube_id),
+        };
+
+        let mut results: Vec<(Entity, f64)> = candidates
+            .into_iter()
+            .filter_map(|e| {
+                let score = std::iter::once(&e.name)
+                    .chain(&e.name_variants)
+                    .map(|candidate| crate::fuzzy::match_score(&query, &candidate.to_lowercase()))
+                    .fold(0.0, f64::max);
+                (score >= min_score).then_some((e, score))
+            })
             .collect();
 
-        // Sort by occurrence count
-        results.sort_by(|a, b| {
-            b.metadata
-                .occurrence_count
-                .cmp(&a.metadata.occurrence_count)
+        results.sort_by(|(a, sa), (b, sb)| {
+            sb.partial_cmp(sa)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| {
+                    b.metadata
+                        .occurrence_count
+                        .cmp(&a.metadata.occurrence_count)
+                })
+                .then_with(|| a.id.cmp(&b.id))
         });
-
+        results.truncate(limit as usize);
         results
     }
 
-    /// Search entities by type with fuzzy name matching.
+    /// Search entities of the cube by type with fuzzy name matching.
     pub fn search_by_type_and_name(
         &self,
+        cube_id: &str,
         entity_type: Option<EntityType>,
         query: &str,
         limit: u32,
     ) -> Vec<Entity> {
         let mut candidates: Vec<Entity> = if let Some(t) = entity_type {
-            self.find_by_type(t)
+            self.find_by_type(cube_id, t)
         } else {
-            self.entities.read().unwrap().values().cloned().collect()
+            self.entities_in_cube(cube_id)
         };
 
         if !query.is_empty() {
@@ -236,27 +329,39 @@ impl EntityKnowledgeGraph {
         if let Some(entity) = entities.remove(id) {
             // Remove from name index
             let mut name_index = self.name_index.write().unwrap();
-            name_index.remove(&entity.name);
+            if let Some(names) = name_index.get_mut(&entity.cube_id) {
+                names.remove(&entity.name);
+            }
 
             // Remove from type index
             let mut type_index = self.type_index.write().unwrap();
-            if let Some(type_set) = type_index.get_mut(&entity.entity_type) {
+            if let Some(type_set) = type_index
+                .get_mut(&entity.cube_id)
+                .and_then(|types| types.get_mut(&entity.entity_type))
+            {
                 type_set.remove(id);
             }
 
             // Remove from variant index
             let mut variant_index = self.variant_index.write().unwrap();
-            for variant in &entity.name_variants {
-                variant_index.remove(variant);
+            if let Some(variants) = variant_index.get_mut(&entity.cube_id) {
+                variants.retain(|_, entity_id| entity_id != id);
             }
 
             // Remove from memory index
             let mut memory_index = self.memory_index.write().unwrap();
             memory_index.remove(id);
 
-            // Remove relations
+            // Remove outgoing and incoming relations
             let mut relations = self.relations.write().unwrap();
             relations.remove(id);
+            for rel_map in relations.values_mut() {
+                for targets in rel_map.values_mut() {
+                    targets.remove(id);
+                }
+                rel_map.retain(|_, targets| !targets.is_empty());
+            }
+            relations.retain(|_, rel_map| !rel_map.is_empty());
 
             Ok(())
         } else {
@@ -264,19 +369,42 @@ impl EntityKnowledgeGraph {
         }
     }
 
-    /// Update entity attributes.
+    /// Update entity attributes. Changed values close the previous value's validity rather
+    /// than overwrite it (see [`Entity::set_attribute`]).
     pub fn update_attributes(
         &self,
         entity_id: &str,
         attributes: HashMap<String, serde_json::Value>,
-    ) -> Result<(), EntityKgError> {
+        validity: Validity,
+    ) -> Result<Entity, EntityKgError> {
         let mut entities = self.entities.write().unwrap();
         if let Some(entity) = entities.get_mut(entity_id) {
-            for (key, value) in attributes {
-                entity.attributes.insert(key, value);
+            let mut keys: Vec<&String> = attributes.keys().collect();
+            keys.sort();
+            for key in keys {
+                entity.set_attribute(key, attributes[key].clone(), validity.clone());
             }
+            Ok(entity.clone())
+        } else {
+            Err(EntityKgError::EntityNotFound(entity_id.to_string()))
+        }
+    }
+
+    /// Replace the entity's description, recording the memories it was generated from.
+    pub fn set_description(
+        &self,
+        entity_id: &str,
+        description: String,
+        memory_ids: Vec<String>,
+    ) -> Result<Entity, EntityKgError> {
+        let mut entities = self.entities.write().unwrap();
+        if let Some(entity) = entities.get_mut(entity_id) {
+            let now = chrono::Utc::now().to_rfc3339();
+            entity.description = Some(description);
+            entity.metadata.description_memory_ids = memory_ids;
+            entity.metadata.description_updated_at = Some(now);
             entity.increment_version();
-            Ok(())
+            Ok(entity.clone())
         } else {
             Err(EntityKgError::EntityNotFound(entity_id.to_string()))
         }
@@ -286,53 +414,87 @@ impl EntityKnowledgeGraph {
     // Relation Operations
     // =========================================================================
 
-    /// Add a relation between two entities (by their normalized names).
+    /// Add a relation between two entities of the cube (by their normalized names).
     pub fn add_relation_by_name(
         &self,
+        cube_id: &str,
         source_name: &str,
         target_name: &str,
         relation_type: EntityRelationType,
+        validity: Validity,
     ) -> Result<(), EntityKgError> {
         let name_index = self.name_index.read().unwrap();
-        let source_id = name_index
+        let names = name_index
+            .get(cube_id)
+            .ok_or_else(|| EntityKgError::EntityNotFound(source_name.to_string()))?;
+        let source_id = names
             .get(&self.normalize_name(source_name))
             .ok_or_else(|| EntityKgError::EntityNotFound(source_name.to_string()))?
             .clone();
 
-        let target_id = name_index
+        let target_id = names
             .get(&self.normalize_name(target_name))
             .ok_or_else(|| EntityKgError::EntityNotFound(target_name.to_string()))?
             .clone();
         drop(name_index);
 
-        self.add_relation(&source_id, &target_id, relation_type)
+        self.add_relation_with_validity(&source_id, &target_id, relation_type, validity)
+            .map(|_| ())
     }
 
-    /// Add a relation between two entities (by ID).
+    /// Add a relation between two entities (by ID). Both must belong to the same cube.
     pub fn add_relation(
         &self,
         source_id: &str,
         target_id: &str,
         relation_type: EntityRelationType,
     ) -> Result<(), EntityKgError> {
+        self.add_relation_with_validity(source_id, target_id, relation_type, Validity::default())
+            .map(|_| ())
+    }
+
+    /// Add a relation with temporal bounds and evidence, or fold them into the existing relation
+    /// (see [`Validity::absorb`]; an explicit `valid_to` ends it). Returns the stored relation.
+    pub fn add_relation_with_validity(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        relation_type: EntityRelationType,
+        validity: Validity,
+    ) -> Result<StoredRelation, EntityKgError> {
         let entities = self.entities.read().unwrap();
-        if !entities.contains_key(source_id) {
-            return Err(EntityKgError::EntityNotFound(source_id.to_string()));
-        }
-        if !entities.contains_key(target_id) {
-            return Err(EntityKgError::EntityNotFound(target_id.to_string()));
+        let source = entities
+            .get(source_id)
+            .ok_or_else(|| EntityKgError::EntityNotFound(source_id.to_string()))?;
+        let target = entities
+            .get(target_id)
+            .ok_or_else(|| EntityKgError::EntityNotFound(target_id.to_string()))?;
+        if source.cube_id != target.cube_id {
+            return Err(EntityKgError::CrossCubeRelation);
         }
         drop(entities);
 
         let mut relations = self.relations.write().unwrap();
-        relations
+        let stored = match relations
             .entry(source_id.to_string())
             .or_default()
-            .entry(relation_type)
+            .entry(relation_type.clone())
             .or_default()
-            .insert(target_id.to_string());
+            .entry(target_id.to_string())
+        {
+            std::collections::hash_map::Entry::Occupied(mut e) => {
+                e.get_mut().absorb(&validity);
+                e.get().clone()
+            }
+            std::collections::hash_map::Entry::Vacant(e) => e.insert(validity).clone(),
+        };
 
-        Ok(())
+        Ok(StoredRelation {
+            source_id: source_id.to_string(),
+            target_id: target_id.to_string(),
+            relation_type,
+            validity: stored,
+        })
     }
 
     /// Get all relations for an entity.
@@ -345,7 +507,7 @@ impl EntityKnowledgeGraph {
                 .iter()
                 .map(|(rel_type, target_ids)| {
                     let entities_list: Vec<Entity> = target_ids
-                        .iter()
+                        .keys()
                         .filter_map(|id| entities.get(id).cloned())
                         .collect();
                     (rel_type.clone(), entities_list)
@@ -356,6 +518,31 @@ impl EntityKnowledgeGraph {
         }
     }
 
+    /// Outgoing relations of an entity as stored records.
+    pub fn outgoing_relations(&self, entity_id: &str) -> Vec<StoredRelation> {
+        let relations = self.relations.read().unwrap();
+        let mut out: Vec<StoredRelation> = relations
+            .get(entity_id)
+            .into_iter()
+            .flat_map(|rel_map| rel_map.iter())
+            .flat_map(|(rel_type, targets)| {
+                targets
+                    .iter()
+                    .map(move |(target_id, validity)| StoredRelation {
+                        source_id: entity_id.to_string(),
+                        target_id: target_id.clone(),
+                        relation_type: rel_type.clone(),
+                        validity: validity.clone(),
+                    })
+            })
+            .collect();
+        out.sort_by(|a, b| {
+            (&a.target_id, a.relation_type.to_string())
+                .cmp(&(&b.target_id, b.relation_type.to_string()))
+        });
+        out
+    }
+
     /// Get relations filtered by type.
     pub fn get_relations_by_type(
         &self,
@@ -370,7 +557,7 @@ impl EntityKnowledgeGraph {
             .and_then(|rel_map| rel_map.get(&relation_type))
             .map(|target_ids| {
                 target_ids
-                    .iter()
+                    .keys()
                     .filter_map(|id| entities.get(id).cloned())
                     .collect()
             })
@@ -397,6 +584,511 @@ impl EntityKnowledgeGraph {
         }
     }
 
+    /// Mark a relation as no longer holding from `valid_to` on; it stays in the graph for
+    /// as-of queries.
+    pub fn end_relation(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        relation_type: &EntityRelationType,
+        valid_to: &str,
+    ) -> Result<StoredRelation, EntityKgError> {
+        let mut relations = self.relations.write().unwrap();
+        let validity = relations
+            .get_mut(source_id)
+            .and_then(|rels| rels.get_mut(relation_type))
+            .and_then(|targets| targets.get_mut(target_id))
+            .ok_or(EntityKgError::RelationNotFound)?;
+        validity.valid_to = Some(valid_to.to_string());
+        Ok(StoredRelation {
+            source_id: source_id.to_string(),
+            target_id: target_id.to_string(),
+            relation_type: relation_type.clone(),
+            validity: validity.clone(),
+        })
+    }
+
+    // =========================================================================
+    // Merge / Split
+    // =========================================================================
+
+    /// Fold `merge_id` into `keep_id` (same cube): names, variants, memories and attributes are
+    /// unioned (attributes of `keep_id` win), relations from and to `merge_id` are rewired to
+    /// `keep_id`, and `merge_id` is removed. All indexes are updated under one set of locks.
+    pub fn merge_entities(&self, keep_id: &str, merge_id: &str) -> Result<Entity, EntityKgError> {
+        if keep_id == merge_id {
+            return Err(EntityKgError::InvalidOperation(
+                "cannot merge an entity into itself".to_string(),
+            ));
+        }
+        let mut entities = self.entities.write().unwrap();
+        let mut name_index = self.name_index.write().unwrap();
+        let mut type_index = self.type_index.write().unwrap();
+        let mut variant_index = self.variant_index.write().unwrap();
+        let mut memory_index = self.memory_index.write().unwrap();
+        let mut relations = self.relations.write().unwrap();
+
+        if !entities.contains_key(keep_id) {
+            return Err(EntityKgError::EntityNotFound(keep_id.to_string()));
+        }
+        let merged = entities
+            .get(merge_id)
+            .cloned()
+            .ok_or_else(|| EntityKgError::EntityNotFound(merge_id.to_string()))?;
+        let keep = entities.get_mut(keep_id).expect("checked above");
+        if keep.cube_id != merged.cube_id {
+            return Err(EntityKgError::InvalidOperation(
+                "cannot merge entities of different cubes".to_string(),
+            ));
+        }
+        entities.remove(merge_id);
+        let keep = entities.get_mut(keep_id).expect("checked above");
+
+        for variant in std::iter::once(&merged.name).chain(&merged.name_variants) {
+            keep.add_variant(variant.clone());
+        }
+        for memory_id in &merged.memory_ids {
+            if !keep.memory_ids.contains(memory_id) {
+                keep.memory_ids.push(memory_id.clone());
+            }
+        }
+        keep.metadata.occurrence_count = keep.memory_ids.len() as u32;
+        for (key, value) in merged.attributes {
+            keep.attributes.entry(key).or_insert(value);
+        }
+        keep.attribute_history.extend(merged.attribute_history);
+        if keep.description.is_none() {
+            keep.description = merged.description;
+            keep.metadata.description_memory_ids = merged.metadata.description_memory_ids;
+            keep.metadata.description_updated_at = merged.metadata.description_updated_at;
+        }
+        keep.metadata.confidence = keep.metadata.confidence.max(merged.metadata.confidence);
+        if merged.metadata.first_seen < keep.metadata.first_seen {
+            keep.metadata.first_seen = merged.metadata.first_seen;
+            keep.metadata.source_memory_id = merged.metadata.source_memory_id;
+        }
+        keep.increment_version();
+        let keep = keep.clone();
+
+        // Name and variant lookups of the merged entity now resolve to the kept one.
+        for index in [&mut *name_index, &mut *variant_index] {
+            if let Some(names) = index.get_mut(&keep.cube_id) {
+                for id in names.values_mut() {
+                    if id == merge_id {
+                        *id = keep_id.to_string();
+                    }
+                }
+            }
+        }
+        if let Some(ids) = type_index
+            .get_mut(&merged.cube_id)
+            .and_then(|types| types.get_mut(&merged.entity_type))
+        {
+            ids.remove(merge_id);
+        }
+        let merged_memories = memory_index.remove(merge_id).unwrap_or_default();
+        memory_index
+            .entry(keep_id.to_string())
+            .or_default()
+            .extend(merged_memories);
+
+        // Rewire relations, dropping the self-loops a merge can create.
+        let absorb =
+            |targets: &mut HashMap<String, Validity>, target: String, v: Validity| match targets
+                .entry(target)
+            {
+                std::collections::hash_map::Entry::Occupied(mut e) => e.get_mut().absorb(&v),
+                std::collections::hash_map::Entry::Vacant(e) => {
+                    e.insert(v);
+                }
+            };
+        if let Some(outgoing) = relations.remove(merge_id) {
+            let keep_rels = relations.entry(keep_id.to_string()).or_default();
+            for (rel_type, targets) in outgoing {
+                let keep_targets = keep_rels.entry(rel_type).or_default();
+                for (target, validity) in targets {
+                    absorb(keep_targets, target, validity);
+                }
+            }
+        }
+        for (source_id, rel_map) in relations.iter_mut() {
+            for targets in rel_map.values_mut() {
+                if let Some(validity) = targets.remove(merge_id) {
+                    if source_id != keep_id {
+                        absorb(targets, keep_id.to_string(), validity);
+                    }
+                }
+                if source_id == keep_id {
+                    targets.remove(keep_id);
+                }
+            }
+            rel_map.retain(|_, targets| !targets.is_empty());
+        }
+        relations.retain(|_, rel_map| !rel_map.is_empty());
+
+        Ok(keep)
+    }
+
+    /// Move part of an entity (memories, variants, outgoing relations) into a new entity of the
+    /// same cube. At least one memory must stay with the source entity.
+    pub fn split_entity(
+        &self,
+        entity_id: &str,
+        split: &EntitySplit,
+    ) -> Result<Entity, EntityKgError> {
+        let normalized_name = self.normalize_name(&split.name);
+        if normalized_name.is_empty() {
+            return Err(EntityKgError::InvalidOperation(
+                "split name is required".to_string(),
+            ));
+        }
+        let mut entities = self.entities.write().unwrap();
+        let mut name_index = self.name_index.write().unwrap();
+        let mut type_index = self.type_index.write().unwrap();
+        let mut variant_index = self.variant_index.write().unwrap();
+        let mut memory_index = self.memory_index.write().unwrap();
+        let mut relations = self.relations.write().unwrap();
+
+        let source = entities
+            .get(entity_id)
+            .ok_or_else(|| EntityKgError::EntityNotFound(entity_id.to_string()))?;
+        let cube_id = source.cube_id.clone();
+        if name_index
+            .get(&cube_id)
+            .is_some_and(|names| names.contains_key(&normalized_name))
+        {
+            return Err(EntityKgError::DuplicateEntity);
+        }
+        if split.memory_ids.is_empty() {
+            return Err(EntityKgError::InvalidOperation(
+                "split must move at least one memory".to_string(),
+            ));
+        }
+        if let Some(missing) = split
+            .memory_ids
+            .iter()
+            .find(|m| !source.memory_ids.contains(m))
+        {
+            return Err(EntityKgError::InvalidOperation(format!(
+                "memory {} is not associated with entity {}",
+                missing, entity_id
+            )));
+        }
+        if source
+            .memory_ids
+            .iter()
+            .all(|m| split.memory_ids.contains(m))
+        {
+            return Err(EntityKgError::InvalidOperation(
+                "split must leave at least one memory with the source entity".to_string(),
+            ));
+        }
+
+        let source = entities.get_mut(entity_id).expect("checked above");
+        source.memory_ids.retain(|m| !split.memory_ids.contains(m));
+        source.metadata.occurrence_count = source.memory_ids.len() as u32;
+        let moved_variants: Vec<String> = source
+            .name_variants
+            .iter()
+            .filter(|v| split.variants.contains(v))
+            .cloned()
+            .collect();
+        source.name_variants.retain(|v| !split.variants.contains(v));
+        source.increment_version();
+        let entity_type = split
+            .entity_type
+            .clone()
+            .unwrap_or_else(|| source.entity_type.clone());
+        let confidence = source.metadata.confidence;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut name_variants = vec![split.name.trim().to_string()];
+        for v in moved_variants {
+            if !name_variants.contains(&v) {
+                name_variants.push(v);
+            }
+        }
+        let id = Uuid::new_v4().to_string();
+        let entity = Entity {
+            id: id.clone(),
+            name: normalized_name.clone(),
+            entity_type: entity_type.clone(),
+            cube_id: cube_id.clone(),
+            name_variants,
+            description: None,
+            memory_ids: split.memory_ids.clone(),
+            attributes: HashMap::new(),
+            attribute_history: Vec::new(),
+            metadata: EntityMetadata {
+                first_seen: now.clone(),
+                last_updated: now,
+                occurrence_count: split.memory_ids.len() as u32,
+                source_memory_id: split.memory_ids[0].clone(),
+                confidence,
+                description_memory_ids: Vec::new(),
+                description_updated_at: None,
+            },
+            version: 0,
+        };
+        entities.insert(id.clone(), entity.clone());
+
+        name_index
+            .entry(cube_id.clone())
+            .or_default()
+            .insert(normalized_name, id.clone());
+        let variants = variant_index.entry(cube_id.clone()).or_default();
+        for v in &entity.name_variants {
+            variants.insert(v.clone(), id.clone());
+        }
+        type_index
+            .entry(cube_id)
+            .or_default()
+            .entry(entity_type)
+            .or_default()
+            .insert(id.clone());
+        if let Some(ids) = memory_index.get_mut(entity_id) {
+            ids.retain(|m| !split.memory_ids.contains(m));
+        }
+        memory_index.insert(id.clone(), split.memory_ids.iter().cloned().collect());
+
+        if !split.relation_targets.is_empty() {
+            let mut moved: RelationMap = HashMap::new();
+            if let Some(rel_map) = relations.get_mut(entity_id) {
+                for (rel_type, targets) in rel_map.iter_mut() {
+                    for target in &split.relation_targets {
+                        if let Some(validity) = targets.remove(target) {
+                            moved
+                                .entry(rel_type.clone())
+                                .or_default()
+                                .insert(target.clone(), validity);
+                        }
+                    }
+                }
+                rel_map.retain(|_, targets| !targets.is_empty());
+            }
+            if !moved.is_empty() {
+                relations.insert(id, moved);
+            }
+        }
+
+        Ok(entity)
+    }
+
+    /// Ids of entities with a relation pointing at `entity_id`.
+    pub fn incoming_relation_sources(&self, entity_id: &str) -> Vec<String> {
+        let relations = self.relations.read().unwrap();
+        let mut sources: Vec<String> = relations
+            .iter()
+            .filter(|(_, rel_map)| rel_map.values().any(|t| t.contains_key(entity_id)))
+            .map(|(source_id, _)| source_id.clone())
+            .collect();
+        sources.sort();
+        sources
+    }
+
+    // =========================================================================
+    // Traversal
+    // =========================================================================
+
+    /// Entities within `traversal.max_depth` hops of `entity_id` (itself excluded), with their
+    /// hop count, nearest first.
+    pub fn neighborhood(&self, entity_id: &str, traversal: &EntityTraversal) -> Vec<(Entity, u32)> {
+        let walk = self.walk(entity_id, traversal, None);
+        let entities = self.entities.read().unwrap();
+        walk.order
+            .into_iter()
+            .skip(1)
+            .filter_map(|(id, depth)| entities.get(&id).map(|e| (e.clone(), depth)))
+            .collect()
+    }
+
+    /// Shortest path (fewest hops, at most `traversal.max_depth`) from `source_id` to
+    /// `target_id`, or `None` when they are not connected within the limits.
+    pub fn shortest_path(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        traversal: &EntityTraversal,
+    ) -> Option<EntityPath> {
+        let walk = self.walk(source_id, traversal, Some(target_id));
+        if !walk.order.iter().any(|(id, _)| id == target_id) {
+            return None;
+        }
+        let mut ids = vec![target_id.to_string()];
+        let mut relations = Vec::new();
+        while let Some((parent, relation)) = walk.via.get(ids.last().unwrap()) {
+            relations.push(relation.clone());
+            ids.push(parent.clone());
+        }
+        ids.reverse();
+        relations.reverse();
+        let entities = self.entities.read().unwrap();
+        let entities = ids
+            .iter()
+            .map(|id| entities.get(id).cloned())
+            .collect::<Option<Vec<Entity>>>()?;
+        Some(EntityPath {
+            entities,
+            relations,
+        })
+    }
+
+    /// Subgraph around `entity_id`: the entities of [`Self::neighborhood`] plus the center, and
+    /// every relation (of an allowed type) among them. Memories are left for the caller to fill.
+    pub fn subgraph(&self, entity_id: &str, traversal: &EntityTraversal) -> Option<EntitySubgraph> {
+        let walk = self.walk(entity_id, traversal, None);
+        let entities = self.entities.read().unwrap();
+        let relations = self.relations.read().unwrap();
+        let members: HashSet<&str> = walk.order.iter().map(|(id, _)| id.as_str()).collect();
+
+        let nodes: Vec<EntitySubgraphNode> = walk
+            .order
+            .iter()
+            .filter_map(|(id, depth)| {
+                entities.get(id).map(|e| EntitySubgraphNode {
+                    entity: e.clone(),
+                    depth: *depth,
+                    memories: Vec::new(),
+                })
+            })
+            .collect();
+        if nodes.is_empty() {
+            return None;
+        }
+        let mut edges: Vec<StoredRelation> = walk
+            .order
+            .iter()
+            .filter_map(|(id, _)| relations.get(id).map(|rel_map| (id, rel_map)))
+            .flat_map(|(id, rel_map)| {
+                rel_map.iter().flat_map(move |(rel_type, targets)| {
+                    targets
+                        .iter()
+                        .map(move |(target, validity)| (id, rel_type, target, validity))
+                })
+            })
+            .filter(|(_, rel_type, target, validity)| {
+                members.contains(target.as_str()) && traversal.follows(rel_type, validity)
+            })
+            .map(|(id, rel_type, target, validity)| StoredRelation {
+                source_id: id.clone(),
+                target_id: target.clone(),
+                relation_type: rel_type.clone(),
+                validity: validity.clone(),
+            })
+            .collect();
+        edges.sort_by(|a, b| {
+            (&a.source_id, &a.target_id, a.relation_type.to_string()).cmp(&(
+                &b.source_id,
+                &b.target_id,
+                b.relation_type.to_string(),
+            ))
+        });
+        Some(EntitySubgraph {
+            nodes,
+            relations: edges,
+            truncated: walk.truncated,
+        })
+    }
+
+    /// Breadth-first walk from `start_id` within the traversal limits; stops once `stop_at`
+    /// is reached. Neighbors are visited in id order so results are deterministic.
+    fn walk(&self, start_id: &str, traversal: &EntityTraversal, stop_at: Option<&str>) -> Walk {
+        let mut walk = Walk::default();
+        if !self.entities.read().unwrap().contains_key(start_id) {
+            return walk;
+        }
+        let relations = self.relations.read().unwrap();
+        let outbound = traversal.direction != GraphDirection::Inbound;
+        let inbound = traversal.direction != GraphDirection::Outbound;
+        // target -> relations pointing at it; only built when walking inbound.
+        let mut incoming: HashMap<&str, Vec<StoredRelation>> = HashMap::new();
+        if inbound {
+            for (source_id, rel_map) in relations.iter() {
+                for (rel_type, targets) in rel_map {
+                    for (target_id, validity) in targets {
+                        if !traversal.follows(rel_type, validity) {
+                            continue;
+                        }
+                        incoming
+                            .entry(target_id.as_str())
+                            .or_default()
+                            .push(StoredRelation {
+                                source_id: source_id.clone(),
+                                target_id: target_id.clone(),
+                                relation_type: rel_type.clone(),
+                                validity: validity.clone(),
+                            });
+                    }
+                }
+            }
+        }
+
+        let max_entities = traversal.max_entities.max(1) as usize;
+        let mut seen: HashSet<String> = HashSet::from([start_id.to_string()]);
+        let mut queue = VecDeque::from([(start_id.to_string(), 0u32)]);
+        walk.order.push((start_id.to_string(), 0));
+        while let Some((id, depth)) = queue.pop_front() {
+            if stop_at == Some(id.as_str()) || depth >= traversal.max_depth {
+                continue;
+            }
+            // (neighbor, relation) pairs, as stored.
+            let mut next: Vec<(String, StoredRelation)> = Vec::new();
+            if outbound {
+                for (rel_type, targets) in relations.get(&id).into_iter().flatten() {
+                    next.extend(
+                        targets
+                            .iter()
+                            .filter(|(_, validity)| traversal.follows(rel_type, validity))
+                            .map(|(target_id, validity)| {
+                                (
+                                    target_id.clone(),
+                                    StoredRelation {
+                                        source_id: id.clone(),
+                                        target_id: target_id.clone(),
+                                        relation_type: rel_type.clone(),
+                                        validity: validity.clone(),
+                                    },
+                                )
+                            }),
+                    );
+                }
+            }
+            if inbound {
+                next.extend(
+                    incoming
+                        .get(id.as_str())
+                        .into_iter()
+                        .flatten()
+                        .map(|r| (r.source_id.clone(), r.clone())),
+                );
+            }
+            next.sort_by(|(a, ra), (b, rb)| {
+                a.cmp(b).then_with(|| {
+                    ra.relation_type
+                        .to_string()
+                        .cmp(&rb.relation_type.to_string())
+                })
+            });
+            for (neighbor, relation) in next {
+                if seen.contains(&neighbor) {
+                    continue;
+                }
+                if walk.order.len() >= max_entities {
+                    walk.truncated = true;
+                    return walk;
+                }
+                seen.insert(neighbor.clone());
+                walk.via.insert(neighbor.clone(), (id.clone(), relation));
+                walk.order.push((neighbor.clone(), depth + 1));
+                if stop_at == Some(neighbor.as_str()) {
+                    return walk;
+                }
+                queue.push_back((neighbor, depth + 1));
+            }
+        }
+        walk
+    }
+
     // =========================================================================
     // Memory-Entity Association
     // =========================================================================
@@ -446,19 +1138,48 @@ impl EntityKnowledgeGraph {
         }
 
         if let Some(entity) = self.entities.write().unwrap().get_mut(entity_id) {
+            let before = entity.memory_ids.len();
             entity.memory_ids.retain(|id| id != memory_id);
+            if entity.memory_ids.len() < before {
+                entity.metadata.occurrence_count =
+                    entity.metadata.occurrence_count.saturating_sub(1);
+                entity.increment_version();
+            }
+        }
+    }
+
+    /// Delete the entity if it is no longer mentioned by any memory. Returns true if deleted.
+    pub fn prune_if_orphaned(&self, entity_id: &str) -> bool {
+        let orphaned = self
+            .entities
+            .read()
+            .unwrap()
+            .get(entity_id)
+            .is_some_and(|e| e.memory_ids.is_empty());
+        orphaned && self.delete_entity(entity_id).is_ok()
+    }
+
+    /// Drop every association with a forgotten memory and delete entities left without
+    /// memories. Returns the ids of all affected entities (changed or deleted).
+    pub fn remove_memory(&self, memory_id: &str) -> Vec<String> {
+        let mut entity_ids = self.get_entity_ids_for_memory(memory_id);
+        entity_ids.sort();
+        for entity_id in &entity_ids {
+            self.dissociate_from_memory(entity_id, memory_id);
+            self.prune_if_orphaned(entity_id);
         }
+        entity_ids
     }
 
     // =========================================================================
     // Statistics
     // =========================================================================
 
-    /// Get entity statistics.
+    /// Get entity statistics across all cubes.
     pub fn stats(&self) -> EntityKgStats {
         let type_index = self.type_index.read().unwrap();
         let mut type_counts: HashMap<String, u32> = HashMap::new();
-        for (entity_type, ids) in type_index.iter() {
+        for (entity_type, ids) in type_index.values().flat_map(|types| types.iter()) {
             *type_counts.entry(entity_type.to_string()).or_insert(0) += ids.len() as u32;
         }
 
@@ -469,13 +1190,47 @@ impl EntityKnowledgeGraph {
         }
     }
 
+    /// Get entity statistics of one cube.
+    pub fn cube_stats(&self, cube_id: &str) -> EntityKgStats {
+        let type_index = self.type_index.read().unwrap();
+        let mut type_counts: HashMap<String, u32> = HashMap::new();
+        let mut ids: HashSet<&String> = HashSet::new();
+        for (entity_type, type_ids) in type_index.get(cube_id).into_iter().flatten() {
+            if !type_ids.is_empty() {
+                *type_counts.entry(entity_type.to_string()).or_insert(0) += type_ids.len() as u32;
+            }
+            ids.extend(type_ids);
+        }
+        let relations = self.relations.read().unwrap();
+        let total_relations = relations
+            .iter()
+            .filter(|(source_id, _)| ids.contains(source_id))
+            .map(|(_, rels)| rels.values().map(|s| s.len()).sum::<usize>())
+            .sum();
+
+        EntityKgStats {
+            total_entities: ids.len(),
+            total_relations,
+            type_counts,
+        }
+    }
+
     // =========================================================================
     // Utilities and crate-internal accessors for EntityAwareMemCube
     // =========================================================================
 
-    /// Return entity id for a normalized name, if any.
-    pub fn get_entity_id_by_normalized_name(&self, normalized: &str) -> Option<String> {
-        self.name_index.read().unwrap().get(normalized).cloned()
+    /// Return the id of the cube's entity with a normalized name, if any.
+    pub fn get_entity_id_by_normalized_name(
+        &self,
+        cube_id: &str,
+        normalized: &str,
+    ) -> Option<String> {
+        self.name_index
+            .read()
+            .unwrap()
+            .get(cube_id)
+            .and_then(|names| names.get(normalized))
+            .cloned()
     }
 
     /// Add a memory id to an entity's memory_ids list and memory_index.
@@ -488,6 +1243,7 @@ impl EntityKnowledgeGraph {
         if let Some(entity) = entities.get_mut(entity_id) {
             if !entity.memory_ids.contains(&memory_id.to_string()) {
                 entity.memory_ids.push(memory_id.to_string());
+                entity.metadata.occurrence_count += 1;
             }
             drop(entities);
             self.memory_index
@@ -557,6 +1313,12 @@ pub enum EntityKgError {
     #[error("Duplicate entity")]
     DuplicateEntity,
 
+    #[error("Relations cannot link entities of different cubes")]
+    CrossCubeRelation,
+
+    #[error("Invalid operation: {0}")]
+    InvalidOperation(String),
+
     #[error("Serialization error: {0}")]
     SerializationError(String),
 }
@@ -565,28 +1327,9 @@ pub enum EntityKgError {
 // Stats and Types
 // ============================================================================
 
-/// Statistics about the entity knowledge graph.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EntityKgStats {
-    pub total_entities: usize,
-    pub total_relations: usize,
-    pub type_counts: HashMap<String, u32>,
-}
-
-/// Snapshot of the entity knowledge graph for serialization.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EntityKgSnapshot {
-    pub entities: Vec<Entity>,
-    pub relations: Vec<StoredRelation>,
-    pub timestamp: String,
-}
+pub use mem_types::EntityKgStats;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StoredRelation {
-    pub source_id: String,
-    pub target_id: String,
-    pub relation_type: EntityRelationType,
-}
+pub use mem_types::{EntityKgSnapshot, StoredRelation};
 
 impl EntityKnowledgeGraph {
     /// Create a snapshot for serialization.
@@ -597,11 +1340,12 @@ impl EntityKnowledgeGraph {
         let rel_map = self.relations.read().unwrap();
         for (source_id, rel_type_map) in rel_map.iter() {
             for (rel_type, target_ids) in rel_type_map.iter() {
-                for target_id in target_ids.iter() {
+                for (target_id, validity) in target_ids.iter() {
                     relations.push(StoredRelation {
                         source_id: source_id.clone(),
                         target_id: target_id.clone(),
                         relation_type: rel_type.clone(),
+                        validity: validity.clone(),
                     });
                 }
             }
@@ -633,10 +1377,14 @@ impl EntityKnowledgeGraph {
             self.name_index
                 .write()
                 .unwrap()
+                .entry(entity.cube_id.clone())
+                .or_default()
                 .insert(entity.name.clone(), entity.id.clone());
             self.type_index
                 .write()
                 .unwrap()
+                .entry(entity.cube_id.clone())
+                .or_default()
                 .entry(entity.entity_type.clone())
                 .or_default()
                 .insert(entity.id.clone());
@@ -645,6 +1393,8 @@ impl EntityKnowledgeGraph {
                 self.variant_index
                     .write()
                     .unwrap()
+                    .entry(entity.cube_id.clone())
+                    .or_default()
                     .insert(variant.clone(), entity.id.clone());
             }
 
@@ -667,7 +1417,7 @@ impl EntityKnowledgeGraph {
                 .or_default()
                 .entry(rel.relation_type)
                 .or_default()
-                .insert(rel.target_id);
+                .insert(rel.target_id, rel.validity);
         }
 
         Ok(())
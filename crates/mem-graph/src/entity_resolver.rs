@@ -0,0 +1,232 @@
+//! Entity resolution: propose merges of entities that likely denote the same thing.
+//!
+//! A pair of entities of one cube is scored from
+//! - name similarity: cosine of name embeddings, or a token match that accepts initials
+//!   ("B. Smith" ~ "Bob Smith"), whichever is higher;
+//! - type compatibility: same type, or one side `Concept`/custom (penalized);
+//! - shared context: Jaccard overlap of the entities each one is co-mentioned or related with.
+
+use crate::EntityKnowledgeGraph;
+use mem_types::{Embedder, EmbedderError, Entity, EntityMergeCandidate, EntityType};
+use std::collections::{HashMap, HashSet};
+
+/// Entity resolver configuration.
+#[derive(Debug, Clone)]
+pub struct ResolverConfig {
+    /// Minimum combined confidence for a proposal.
+    pub min_confidence: f64,
+    /// Weight of name similarity in the combined score; the rest goes to context.
+    pub name_weight: f64,
+    /// Pairs whose name similarity is below this are never proposed, whatever the context.
+    pub min_name_similarity: f64,
+    /// Upper bound on the entities compared per cube (most mentioned first).
+    pub max_entities: usize,
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.6,
+            name_weight: 0.7,
+            min_name_similarity: 0.5,
+            max_entities: 2000,
+        }
+    }
+}
+
+/// Proposes entity merges; merging itself is [`EntityKnowledgeGraph::merge_entities`].
+#[derive(Debug, Clone, Default)]
+pub struct EntityResolver {
+    config: ResolverConfig,
+}
+
+impl EntityResolver {
+    pub fn new(config: Option<ResolverConfig>) -> Self {
+        Self {
+            config: config.unwrap_or_default(),
+        }
+    }
+
+    /// Score all same-cube entity pairs and return merge proposals, best first.
+    /// Without an embedder, name similarity is the token match only.
+    pub async fn propose_merges(
+        &self,
+        kg: &EntityKnowledgeGraph,
+        cube_id: &str,
+        embedder: Option<&dyn Embedder>,
+        min_confidence: Option<f64>,
+        limit: usize,
+    ) -> Result<Vec<EntityMergeCandidate>, EmbedderError> {
+        let min_confidence = min_confidence.unwrap_or(self.config.min_confidence);
+        let mut entities = kg.entities_in_cube(cube_id);
+        entities.sort_by(|a, b| {
+            b.metadata
+                .occurrence_count
+                .cmp(&a.metadata.occurrence_count)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        entities.truncate(self.config.max_entities);
+        if entities.len() < 2 {
+            return Ok(Vec::new());
+        }
+
+        let contexts = contexts(kg, &entities);
+        let embeddings = match embedder {
+            Some(embedder) => {
+                let names: Vec<String> = entities.iter().map(|e| e.name.clone()).collect();
+                Some(embedder.embed_batch(&names).await?)
+            }
+            None => None,
+        };
+
+        let mut out = Vec::new();
+        for i in 0..entities.len() {
+            for j in (i + 1)..entities.len() {
+                let (a, b) = (&entities[i], &entities[j]);
+                let Some(type_factor) = type_compatibility(&a.entity_type, &b.entity_type) else {
+                    continue;
+                };
+                let mut name_similarity = token_similarity(&a.name, &b.name);
+                if let Some(ref vectors) = embeddings {
+                    name_similarity = name_similarity.max(cosine(&vectors[i], &vectors[j]));
+                }
+                if name_similarity < self.config.min_name_similarity {
+                    continue;
+                }
+                let context_similarity = jaccard(&contexts[i], &contexts[j], &a.id, &b.id);
+                let confidence = type_factor
+                    * (self.config.name_weight * name_similarity
+                        + (1.0 - self.config.name_weight) * context_similarity);
+                if confidence < min_confidence {
+                    continue;
+                }
+                // Entities are sorted by mentions, so `a` is the one to keep.
+                out.push(EntityMergeCandidate {
+                    keep_id: a.id.clone(),
+                    keep_name: a.name.clone(),
+                    merge_id: b.id.clone(),
+                    merge_name: b.name.clone(),
+                    entity_type: a.entity_type.clone(),
+                    confidence,
+                    name_similarity,
+                    context_similarity,
+                });
+            }
+        }
+        out.sort_by(|x, y| {
+            y.confidence
+                .partial_cmp(&x.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| x.keep_id.cmp(&y.keep_id))
+                .then_with(|| x.merge_id.cmp(&y.merge_id))
+        });
+        out.truncate(limit);
+        Ok(out)
+    }
+}
+
+/// For each entity: ids of entities sharing a memory with it or linked to it by a relation.
+fn contexts(kg: &EntityKnowledgeGraph, entities: &[Entity]) -> Vec<HashSet<String>> {
+    let mut by_memory: HashMap<&str, Vec<&str>> = HashMap::new();
+    for e in entities {
+        for m in &e.memory_ids {
+            by_memory.entry(m.as_str()).or_default().push(e.id.as_str());
+        }
+    }
+    entities
+        .iter()
+        .map(|e| {
+            let mut ctx: HashSet<String> = e
+                .memory_ids
+                .iter()
+                .flat_map(|m| by_memory.get(m.as_str()).into_iter().flatten())
+                .map(|id| id.to_string())
+                .collect();
+            ctx.extend(
+                kg.outgoing_relations(&e.id)
+                    .into_iter()
+                    .map(|r| r.target_id),
+            );
+            ctx.extend(kg.incoming_relation_sources(&e.id));
+            ctx.remove(&e.id);
+            ctx
+        })
+        .collect()
+}
+
+/// Jaccard overlap of two contexts, ignoring the pair itself.
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>, a_id: &str, b_id: &str) -> f64 {
+    let a: HashSet<&String> = a.iter().filter(|id| *id != b_id).collect();
+    let b: HashSet<&String> = b.iter().filter(|id| *id != a_id).collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// `Some(factor)` when the two types may denote the same entity.
+fn type_compatibility(a: &EntityType, b: &EntityType) -> Option<f64> {
+    let loose = |t: &EntityType| matches!(t, EntityType::Concept | EntityType::Custom(_));
+    if a == b {
+        Some(1.0)
+    } else if loose(a) || loose(b) {
+        Some(0.8)
+    } else {
+        None
+    }
+}
+
+/// Token match aligned from the last token: equal tokens score 1, an initial matching the
+/// other token's first letter 0.8; normalized by the longer name. Last tokens must agree.
+fn token_similarity(a: &str, b: &str) -> f64 {
+    let tokens = |s: &str| -> Vec<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (ta, tb) = (tokens(a), tokens(b));
+    if ta.is_empty() || tb.is_empty() {
+        return 0.0;
+    }
+    if ta == tb {
+        return 1.0;
+    }
+    let token_score = |x: &str, y: &str| -> f64 {
+        if x == y {
+            1.0
+        } else if (x.chars().count() == 1 && y.starts_with(x))
+            || (y.chars().count() == 1 && x.starts_with(y))
+        {
+            0.8
+        } else {
+            0.0
+        }
+    };
+    if token_score(ta.last().unwrap(), tb.last().unwrap()) < 1.0 {
+        return 0.0;
+    }
+    let score: f64 = ta
+        .iter()
+        .rev()
+        .zip(tb.iter().rev())
+        .map(|(x, y)| token_score(x, y))
+        .sum();
+    score / ta.len().max(tb.len()) as f64
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| (*x as f64) * (*y as f64))
+        .sum();
+    let na: f64 = a.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let nb: f64 = b.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    if na == 0.0 || nb == 0.0 {
+        0.0
+    } else {
+        (dot / (na * nb)).max(0.0)
+    }
+}
@@ -0,0 +1,71 @@
+//! In-memory entity store (process lifetime only; mainly for tests and as a reference).
+
+use async_trait::async_trait;
+use mem_types::{Entity, EntityKgSnapshot, EntityStore, EntityStoreError, StoredRelation};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// In-memory implementation of EntityStore.
+#[derive(Default)]
+pub struct InMemoryEntityStore {
+    /// entity_id -> entity.
+    entities: RwLock<HashMap<String, Entity>>,
+    /// source_id -> outgoing relations.
+    relations: RwLock<HashMap<String, Vec<StoredRelation>>>,
+}
+
+impl InMemoryEntityStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl EntityStore for InMemoryEntityStore {
+    async fn upsert_entity(
+        &self,
+        entity: &Entity,
+        relations: &[StoredRelation],
+    ) -> Result<(), EntityStoreError> {
+        self.entities
+            .write()
+            .await
+            .insert(entity.id.clone(), entity.clone());
+        let mut guard = self.relations.write().await;
+        if relations.is_empty() {
+            guard.remove(&entity.id);
+        } else {
+            guard.insert(entity.id.clone(), relations.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn delete_entity(&self, entity_id: &str) -> Result<(), EntityStoreError> {
+        self.entities.write().await.remove(entity_id);
+        let mut guard = self.relations.write().await;
+        guard.remove(entity_id);
+        for rels in guard.values_mut() {
+            rels.retain(|r| r.target_id != entity_id);
+        }
+        Ok(())
+    }
+
+    async fn load(&self) -> Result<EntityKgSnapshot, EntityStoreError> {
+        let mut entities: Vec<Entity> = self.entities.read().await.values().cloned().collect();
+        entities.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut relations: Vec<StoredRelation> = self
+            .relations
+            .read()
+            .await
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+        relations.sort_by(|a, b| (&a.source_id, &a.target_id).cmp(&(&b.source_id, &b.target_id)));
+        Ok(EntityKgSnapshot {
+            entities,
+            relations,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
@@ -0,0 +1,351 @@
+//! Graph documents for export: memory (and entity) nodes with the edges between them,
+//! encoded as JSON Graph Format, GraphML or Graphviz DOT.
+
+use mem_types::GraphExportFormat;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// One exported node. `label` and `kind` (`memory` or `entity`) are written as attributes of
+/// their own, so attributes with those names are left out of GraphML and DOT.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportNode {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    pub attributes: BTreeMap<String, Value>,
+}
+
+/// One exported directed edge; `relation` is written like `label` for nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub relation: String,
+    pub attributes: BTreeMap<String, Value>,
+}
+
+/// Nodes and edges to export; edges should only reference exported nodes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDocument {
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<ExportEdge>,
+}
+
+impl GraphDocument {
+    /// Encode the document in `format`.
+    pub fn render(&self, format: GraphExportFormat) -> String {
+        match format {
+            GraphExportFormat::Json => self.to_json_graph(),
+            GraphExportFormat::GraphMl => self.to_graphml(),
+            GraphExportFormat::Dot => self.to_dot(),
+        }
+    }
+
+    /// JSON Graph Format v2: nodes keyed by id, attributes under `metadata`.
+    fn to_json_graph(&self) -> String {
+        let nodes: serde_json::Map<String, Value> = self
+            .nodes
+            .iter()
+            .map(|n| {
+                let mut metadata = serde_json::Map::from_iter(n.attributes.clone());
+                metadata.insert("kind".to_string(), Value::String(n.kind.clone()));
+                (
+                    n.id.clone(),
+                    serde_json::json!({ "label": n.label, "metadata": metadata }),
+                )
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .edges
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "id": e.id,
+                    "source": e.source,
+                    "target": e.target,
+                    "relation": e.relation,
+                    "directed": true,
+                    "metadata": e.attributes,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "graph": { "directed": true, "type": "memory", "nodes": nodes, "edges": edges }
+        })
+        .to_string()
+    }
+
+    fn to_graphml(&self) -> String {
+        let node_keys = graphml_keys(self.nodes.iter().map(|n| &n.attributes), &["label", "kind"]);
+        let edge_keys = graphml_keys(self.edges.iter().map(|e| &e.attributes), &["relation"]);
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        );
+        for (prefix, target, keys) in [("n", "node", &node_keys), ("e", "edge", &edge_keys)] {
+            for (i, (name, kind)) in keys.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "  <key id=\"{prefix}{i}\" for=\"{target}\" attr.name=\"{}\" attr.type=\"{kind}\"/>",
+                    xml_escape(name)
+                );
+            }
+        }
+        out.push_str("  <graph id=\"memories\" edgedefault=\"directed\">\n");
+        for node in &self.nodes {
+            let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(&node.id));
+            let label = Value::String(node.label.clone());
+            let kind = Value::String(node.kind.clone());
+            for (i, (name, _)) in node_keys.iter().enumerate() {
+                let value = match name.as_str() {
+                    "label" => Some(&label),
+                    "kind" => Some(&kind),
+                    _ => node.attributes.get(name),
+                };
+                write_graphml_data(&mut out, "n", i, value);
+            }
+            out.push_str("    </node>\n");
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+                xml_escape(&edge.id),
+                xml_escape(&edge.source),
+                xml_escape(&edge.target)
+            );
+            let relation = Value::String(edge.relation.clone());
+            for (i, (name, _)) in edge_keys.iter().enumerate() {
+                let value = match name.as_str() {
+                    "relation" => Some(&relation),
+                    _ => edge.attributes.get(name),
+                };
+                write_graphml_data(&mut out, "e", i, value);
+            }
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    fn to_dot(&self) -> String {
+        let mut out = String::from("digraph memories {\n");
+        for node in &self.nodes {
+            let _ = write!(
+                out,
+                "  {} [label={}, kind={}",
+                dot_quote(&node.id),
+                dot_quote(&node.label),
+                dot_quote(&node.kind)
+            );
+            if node.kind == "entity" {
+                out.push_str(", shape=box");
+            }
+            write_dot_attributes(&mut out, &node.attributes, &["label", "kind", "shape"]);
+            out.push_str("];\n");
+        }
+        for edge in &self.edges {
+            let _ = write!(
+                out,
+                "  {} -> {} [id={}, label={}",
+                dot_quote(&edge.source),
+                dot_quote(&edge.target),
+                dot_quote(&edge.id),
+                dot_quote(&edge.relation)
+            );
+            write_dot_attributes(&mut out, &edge.attributes, &["id", "label"]);
+            out.push_str("];\n");
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Attribute keys with their GraphML type: the fixed keys first, then every other attribute
+/// name in order. A key whose values mix types is a string.
+fn graphml_keys<'a>(
+    attributes: impl Iterator<Item = &'a BTreeMap<String, Value>>,
+    fixed: &[&str],
+) -> Vec<(String, &'static str)> {
+    let mut types: BTreeMap<String, &'static str> = BTreeMap::new();
+    for attrs in attributes {
+        for (name, value) in attrs {
+            if fixed.contains(&name.as_str()) {
+                continue;
+            }
+            let kind = match value {
+                Value::Null => continue,
+                Value::Bool(_) => "boolean",
+                Value::Number(n) if n.is_i64() || n.is_u64() => "long",
+                Value::Number(_) => "double",
+                _ => "string",
+            };
+            types
+                .entry(name.clone())
+                .and_modify(|t| {
+                    *t = match (*t, kind) {
+                        (a, b) if a == b => a,
+                        ("long", "double") | ("double", "long") => "double",
+                        _ => "string",
+                    }
+                })
+                .or_insert(kind);
+        }
+    }
+    fixed
+        .iter()
+        .map(|name| (name.to_string(), "string"))
+        .chain(types)
+        .collect()
+}
+
+fn write_graphml_data(out: &mut String, prefix: &str, key: usize, value: Option<&Value>) {
+    let text = match value {
+        None | Some(Value::Null) => return,
+        Some(v) => scalar_text(v),
+    };
+    let _ = writeln!(
+        out,
+        "      <data key=\"{prefix}{key}\">{}</data>",
+        xml_escape(&text)
+    );
+}
+
+fn write_dot_attributes(out: &mut String, attributes: &BTreeMap<String, Value>, reserved: &[&str]) {
+    for (name, value) in attributes {
+        if value.is_null() || reserved.contains(&name.as_str()) {
+            continue;
+        }
+        let _ = write!(
+            out,
+            ", {}={}",
+            dot_quote(name),
+            dot_quote(&scalar_text(value))
+        );
+    }
+}
+
+/// Strings as-is, everything else as JSON text.
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than tab/newline are not allowed in XML 1.0.
+            c if c.is_control() && c != '\t' && c != '\n' && c != '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn dot_quote(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> GraphDocument {
+        GraphDocument {
+            nodes: vec![
+                ExportNode {
+                    id: "m1".to_string(),
+                    kind: "memory".to_string(),
+                    label: "Alice said \"hi\" & <left>".to_string(),
+                    attributes: BTreeMap::from([
+                        ("importance".to_string(), serde_json::json!(0.5)),
+                        ("tags".to_string(), serde_json::json!(["a", "b"])),
+                        ("access_count".to_string(), serde_json::json!(3)),
+                    ]),
+                },
+                ExportNode {
+                    id: "e1".to_string(),
+                    kind: "entity".to_string(),
+                    label: "Alice".to_string(),
+                    attributes: BTreeMap::from([
+                        ("access_count".to_string(), serde_json::json!(1.5)),
+                        ("kind".to_string(), serde_json::json!("ignored")),
+                    ]),
+                },
+            ],
+            edges: vec![ExportEdge {
+                id: "m1->e1".to_string(),
+                source: "m1".to_string(),
+                target: "e1".to_string(),
+                relation: "mentions".to_string(),
+                attributes: BTreeMap::from([("weight".to_string(), serde_json::json!(2))]),
+            }],
+        }
+    }
+
+    #[test]
+    fn json_graph_format_keys_nodes_by_id() {
+        let doc: Value = serde_json::from_str(&document().render(GraphExportFormat::Json)).unwrap();
+        let graph = &doc["graph"];
+        assert_eq!(graph["directed"], true);
+        assert_eq!(graph["nodes"]["m1"]["label"], "Alice said \"hi\" & <left>");
+        assert_eq!(graph["nodes"]["m1"]["metadata"]["kind"], "memory");
+        assert_eq!(graph["nodes"]["e1"]["metadata"]["kind"], "entity");
+        assert_eq!(graph["edges"][0]["source"], "m1");
+        assert_eq!(graph["edges"][0]["relation"], "mentions");
+        assert_eq!(graph["edges"][0]["metadata"]["weight"], 2);
+    }
+
+    #[test]
+    fn graphml_declares_typed_keys_and_escapes_text() {
+        let xml = document().render(GraphExportFormat::GraphMl);
+        assert!(
+            xml.contains("<key id=\"n0\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>")
+        );
+        // 3 and 1.5 widen to double; arrays are JSON strings.
+        assert!(xml.contains("attr.name=\"access_count\" attr.type=\"double\""));
+        assert!(xml.contains("attr.name=\"tags\" attr.type=\"string\""));
+        assert!(xml.contains("attr.name=\"weight\" attr.type=\"long\""));
+        assert!(xml.contains("Alice said &quot;hi&quot; &amp; &lt;left&gt;"));
+        assert!(xml.contains("[&quot;a&quot;,&quot;b&quot;]"));
+        assert!(xml.contains("<edge id=\"m1-&gt;e1\" source=\"m1\" target=\"e1\">"));
+        assert!(!xml.contains("ignored"));
+        assert_eq!(xml.matches("<node ").count(), 2);
+    }
+
+    #[test]
+    fn dot_quotes_ids_and_attributes() {
+        let dot = document().render(GraphExportFormat::Dot);
+        assert!(dot.starts_with("digraph memories {\n"));
+        assert!(dot.contains(
+            "\"m1\" [label=\"Alice said \\\"hi\\\" & <left>\", kind=\"memory\", \"access_count\"=\"3\""
+        ));
+        assert!(dot.contains("\"e1\" [label=\"Alice\", kind=\"entity\", shape=box"));
+        assert!(
+            dot.contains("\"m1\" -> \"e1\" [id=\"m1->e1\", label=\"mentions\", \"weight\"=\"2\"];")
+        );
+        assert!(dot.trim_end().ends_with('}'));
+    }
+}
@@ -0,0 +1,94 @@
+//! String similarity for fuzzy entity search.
+
+/// Score how well `query` matches `candidate` (both already lowercased), 0.0 - 1.0:
+/// - exact match: 1.0;
+/// - prefix: 0.9 - 1.0, substring: 0.85 - 0.95 (higher the more of the candidate is covered);
+/// - otherwise Jaro-Winkler against the whole candidate, or against its best word (x 0.95),
+///   so typos ("alcie" ~ "alice") and single words of longer names still score.
+pub fn match_score(query: &str, candidate: &str) -> f64 {
+    if query.is_empty() || candidate.is_empty() {
+        return 0.0;
+    }
+    if query == candidate {
+        return 1.0;
+    }
+    let coverage = query.chars().count() as f64 / candidate.chars().count() as f64;
+    if candidate.starts_with(query) {
+        return 0.9 + 0.1 * coverage.min(1.0);
+    }
+    if candidate.contains(query) {
+        return 0.85 + 0.1 * coverage.min(1.0);
+    }
+    let whole = jaro_winkler(query, candidate);
+    let best_word = candidate
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty() && *w != candidate)
+        .map(|w| jaro_winkler(query, w) * 0.95)
+        .fold(0.0, f64::max);
+    whole.max(best_word)
+}
+
+/// Jaro-Winkler similarity (prefix scale 0.1, prefix up to 4 chars).
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let jaro = jaro(&a, &b);
+    let prefix = a.iter().zip(&b).take(4).take_while(|(x, y)| x == y).count() as f64;
+    jaro + prefix * 0.1 * (1.0 - jaro)
+}
+
+fn jaro(a: &[char], b: &[char]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches: Vec<char> = Vec::new();
+    for (i, ca) in a.iter().enumerate() {
+        let lo = i.saturating_sub(window);
+        let hi = (i + window + 1).min(b.len());
+        for j in lo..hi {
+            if !b_matched[j] && b[j] == *ca {
+                b_matched[j] = true;
+                a_matches.push(*ca);
+                break;
+            }
+        }
+    }
+    let m = a_matches.len();
+    if m == 0 {
+        return 0.0;
+    }
+    let b_matches = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, matched)| **matched)
+        .map(|(c, _)| *c);
+    let transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(x, y)| **x != *y)
+        .count() as f64
+        / 2.0;
+    let m = m as f64;
+    (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions) / m) / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_typos_prefixes_and_words() {
+        assert!((jaro_winkler("martha", "marhta") - 0.9611).abs() < 1e-3);
+        assert!((jaro_winkler("dixon", "dicksonx") - 0.8133).abs() < 1e-3);
+        assert_eq!(match_score("alice", "alice"), 1.0);
+        assert!(match_score("ali", "alice") > 0.9);
+        assert!(match_score("alcie", "alice") > 0.9);
+        assert!(match_score("smth", "bob smith") > 0.85);
+        assert!(match_score("acme", "globex") < 0.6);
+    }
+}
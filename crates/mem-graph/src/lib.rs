@@ -1,20 +1,40 @@
 //! Graph store trait and in-memory implementation.
 
+mod analytics;
 mod entity_knowledge_graph;
+mod entity_resolver;
+mod entity_store;
+mod export;
+mod fuzzy;
 mod memory;
+mod query;
 mod store;
+mod weighted;
 
 #[cfg(feature = "sqlite")]
 mod sqlite;
+#[cfg(feature = "sqlite")]
+mod sqlite_entity;
 
+pub use analytics::{analyze_graph, GraphAnalytics, GraphAnalyticsOptions};
 pub use entity_knowledge_graph::{
-    EntityKgError, EntityKgSnapshot, EntityKgStats, EntityKnowledgeGraph,
+    EntityKgError, EntityKgSnapshot, EntityKgStats, EntityKnowledgeGraph, StoredRelation,
+    DEFAULT_FUZZY_MIN_SCORE,
 };
+pub use entity_resolver::{EntityResolver, ResolverConfig};
+pub use entity_store::InMemoryEntityStore;
+pub use export::{ExportEdge, ExportNode, GraphDocument};
 pub use mem_types::{
     GraphDirection, GraphNeighbor, GraphPath, GraphStoreError, MemoryEdge, MemoryNode, VecSearchHit,
 };
 pub use memory::InMemoryGraphStore;
+pub use query::{
+    GraphQuery, QueryError, QueryOptions, QueryResult, QueryValue, MAX_QUERY_HOPS, MAX_QUERY_LEN,
+};
 pub use store::GraphStore;
+pub use weighted::{weighted_paths, WeightedPathQuery};
 
 #[cfg(feature = "sqlite")]
 pub use sqlite::SqliteGraphStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite_entity::SqliteEntityStore;
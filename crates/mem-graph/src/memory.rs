@@ -1,5 +1,6 @@
 //! In-memory graph store with KNN search over embeddings.
 
+use crate::store::{apply_edge_update, author_of, edge_matches};
 use mem_types::{
     GraphDirection, GraphNeighbor, GraphPath, GraphStore, GraphStoreError, MemoryEdge, MemoryNode,
     VecSearchHit,
@@ -210,6 +211,91 @@ impl GraphStore for InMemoryGraphStore {
         Ok(())
     }
 
+    async fn get_edge(
+        &self,
+        id: &str,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError> {
+        let edge_guard = self.edges.read().await;
+        Ok(edge_guard
+            .get(id)
+            .filter(|e| user_name.is_none_or(|un| Self::owner_from_metadata(&e.metadata) == un))
+            .cloned())
+    }
+
+    async fn update_edge(
+        &self,
+        id: &str,
+        relation: Option<&str>,
+        metadata: &HashMap<String, serde_json::Value>,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError> {
+        let mut edge_guard = self.edges.write().await;
+        let Some(edge) = edge_guard
+            .get_mut(id)
+            .filter(|e| user_name.is_none_or(|un| Self::owner_from_metadata(&e.metadata) == un))
+        else {
+            return Ok(None);
+        };
+        apply_edge_update(edge, relation, metadata);
+        Ok(Some(edge.clone()))
+    }
+
+    async fn delete_edge(
+        &self,
+        id: &str,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError> {
+        let mut edge_guard = self.edges.write().await;
+        let owned = edge_guard.get(id).is_some_and(|e| {
+            user_name.is_none_or(|un| Self::owner_from_metadata(&e.metadata) == un)
+        });
+        if !owned {
+            return Ok(None);
+        }
+        let mut out_guard = self.out_index.write().await;
+        let mut in_guard = self.in_index.write().await;
+        let edge = edge_guard.remove(id);
+        if let Some(ref edge) = edge {
+            Self::remove_edge_indexes(edge, &mut out_guard, &mut in_guard);
+        }
+        Ok(edge)
+    }
+
+    async fn list_edges(
+        &self,
+        user_name: &str,
+        node_id: Option<&str>,
+        relation: Option<&str>,
+        direction: GraphDirection,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<MemoryEdge>, GraphStoreError> {
+        let edge_guard = self.edges.read().await;
+        let mut edges: Vec<&MemoryEdge> = match node_id {
+            Some(id) => {
+                let out_guard = self.out_index.read().await;
+                let in_guard = self.in_index.read().await;
+                let mut ids: HashSet<&String> = HashSet::new();
+                if direction != GraphDirection::Inbound {
+                    ids.extend(out_guard.get(id).into_iter().flatten());
+                }
+                if direction != GraphDirection::Outbound {
+                    ids.extend(in_guard.get(id).into_iter().flatten());
+                }
+                ids.into_iter().filter_map(|e| edge_guard.get(e)).collect()
+            }
+            None => edge_guard.values().collect(),
+        };
+        edges.retain(|e| {
+            Self::owner_from_metadata(&e.metadata) == user_name
+                && after.is_none_or(|a| e.id.as_str() > a)
+                && edge_matches(e, node_id, relation, direction)
+        });
+        edges.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(edges.into_iter().take(limit).cloned().collect())
+    }
+
     async fn get_node(
         &self,
         id: &str,
@@ -770,6 +856,35 @@ impl GraphStore for InMemoryGraphStore {
         Ok(nodes)
     }
 
+    async fn latest_in_session(
+        &self,
+        user_name: &str,
+        session_id: &str,
+        exclude_id: &str,
+    ) -> Result<Option<MemoryNode>, GraphStoreError> {
+        if session_id.is_empty() {
+            return Ok(None);
+        }
+        let text = |node: &MemoryNode, key: &str| -> String {
+            node.metadata
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        let guard = self.nodes.read().await;
+        let latest = guard
+            .values()
+            .filter(|n| {
+                n.id != exclude_id
+                    && Self::owner_from_metadata(&n.metadata) == user_name
+                    && text(n, "session_id") == session_id
+                    && text(n, "state") != "tombstone"
+            })
+            .max_by_key(|n| (text(n, "created_at"), n.id.clone()));
+        Ok(latest.cloned())
+    }
+
     async fn update_node(
         &self,
         id: &str,
@@ -878,6 +993,97 @@ impl GraphStore for InMemoryGraphStore {
         }
         Ok(deleted)
     }
+
+    async fn delete_all_by_user(&self, user_name: &str) -> Result<Vec<String>, GraphStoreError> {
+        let mut ids: Vec<String> = {
+            let nodes = self.nodes.read().await;
+            nodes
+                .values()
+                .filter(|n| Self::owner_from_metadata(&n.metadata) == user_name)
+                .map(|n| n.id.clone())
+                .collect()
+        };
+        ids.sort();
+        for id in &ids {
+            // Edges touching the user's nodes go too, whoever created them.
+            self.delete_node(id, None).await?;
+        }
+        self.scope_index.write().await.remove(user_name);
+
+        let mut edge_guard = self.edges.write().await;
+        let mut out_guard = self.out_index.write().await;
+        let mut in_guard = self.in_index.write().await;
+        let owned: Vec<String> = edge_guard
+            .values()
+            .filter(|e| Self::owner_from_metadata(&e.metadata) == user_name)
+            .map(|e| e.id.clone())
+            .collect();
+        for edge_id in owned {
+            if let Some(edge) = edge_guard.remove(&edge_id) {
+                Self::remove_edge_indexes(&edge, &mut out_guard, &mut in_guard);
+            }
+        }
+        Ok(ids)
+    }
+
+    async fn author_cubes(&self, author: &str) -> Result<Vec<String>, GraphStoreError> {
+        let mut cubes: Vec<String> = {
+            let nodes = self.nodes.read().await;
+            let edges = self.edges.read().await;
+            nodes
+                .values()
+                .map(|n| &n.metadata)
+                .chain(edges.values().map(|e| &e.metadata))
+                .filter(|m| author_of(m) == author)
+                .map(|m| Self::owner_from_metadata(m).to_string())
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect()
+        };
+        cubes.sort();
+        Ok(cubes)
+    }
+
+    async fn delete_by_author(
+        &self,
+        user_name: &str,
+        author: &str,
+    ) -> Result<Vec<String>, GraphStoreError> {
+        let mut ids: Vec<String> = {
+            let nodes = self.nodes.read().await;
+            nodes
+                .values()
+                .filter(|n| {
+                    Self::owner_from_metadata(&n.metadata) == user_name
+                        && author_of(&n.metadata) == author
+                })
+                .map(|n| n.id.clone())
+                .collect()
+        };
+        ids.sort();
+        for id in &ids {
+            // Edges touching the author's nodes go too, whoever created them.
+            self.delete_node(id, None).await?;
+        }
+
+        let mut edge_guard = self.edges.write().await;
+        let mut out_guard = self.out_index.write().await;
+        let mut in_guard = self.in_index.write().await;
+        let written: Vec<String> = edge_guard
+            .values()
+            .filter(|e| {
+                Self::owner_from_metadata(&e.metadata) == user_name
+                    && author_of(&e.metadata) == author
+            })
+            .map(|e| e.id.clone())
+            .collect();
+        for edge_id in written {
+            if let Some(edge) = edge_guard.remove(&edge_id) {
+                Self::remove_edge_indexes(&edge, &mut out_guard, &mut in_guard);
+            }
+        }
+        Ok(ids)
+    }
 }
 
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
@@ -1114,4 +1320,85 @@ mod tests {
         assert_eq!(paths[0].edges.len(), 2);
         assert_eq!(paths[1].edges.len(), 2);
     }
+
+    #[tokio::test]
+    async fn edges_can_be_updated_listed_and_deleted() {
+        let store = InMemoryGraphStore::new();
+        for (id, owner) in [("a", "u1"), ("b", "u1"), ("c", "u1"), ("x", "u2")] {
+            store
+                .add_node(id, id, &HashMap::new(), Some(owner))
+                .await
+                .unwrap();
+        }
+        let edge = |id: &str, from: &str, to: &str, relation: &str| MemoryEdge {
+            id: id.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            relation: relation.to_string(),
+            metadata: HashMap::from([("weight".to_string(), serde_json::json!(1))]),
+        };
+        store
+            .add_edges_batch(
+                &[edge("e1", "a", "b", "knows"), edge("e2", "c", "a", "cites")],
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+
+        assert!(store.get_edge("e1", Some("u2")).await.unwrap().is_none());
+        let updated = store
+            .update_edge(
+                "e1",
+                Some("likes"),
+                &HashMap::from([
+                    ("weight".to_string(), serde_json::Value::Null),
+                    ("note".to_string(), serde_json::json!("x")),
+                    ("user_name".to_string(), serde_json::json!("u2")),
+                ]),
+                Some("u1"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.relation, "likes");
+        assert!(!updated.metadata.contains_key("weight"));
+        assert_eq!(updated.metadata["note"], "x");
+        assert_eq!(updated.metadata["user_name"], "u1");
+        assert!(store
+            .update_edge("e1", Some("r"), &HashMap::new(), Some("u2"))
+            .await
+            .unwrap()
+            .is_none());
+
+        let ids = |edges: Vec<MemoryEdge>| edges.into_iter().map(|e| e.id).collect::<Vec<_>>();
+        let all = store
+            .list_edges("u1", None, None, GraphDirection::Both, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(ids(all), vec!["e1", "e2"]);
+        let inbound = store
+            .list_edges("u1", Some("a"), None, GraphDirection::Inbound, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(ids(inbound), vec!["e2"]);
+        let page = store
+            .list_edges("u1", Some("a"), None, GraphDirection::Both, Some("e1"), 10)
+            .await
+            .unwrap();
+        assert_eq!(ids(page), vec!["e2"]);
+        assert!(store
+            .list_edges("u2", None, None, GraphDirection::Both, None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(store.delete_edge("e1", Some("u2")).await.unwrap().is_none());
+        assert!(store.delete_edge("e1", Some("u1")).await.unwrap().is_some());
+        assert!(store.get_edge("e1", None).await.unwrap().is_none());
+        let neighbors = store
+            .get_neighbors("a", None, GraphDirection::Both, 10, false, Some("u1"))
+            .await
+            .unwrap();
+        assert_eq!(neighbors.len(), 1);
+    }
 }
@@ -0,0 +1,1409 @@
+//! Declarative graph pattern queries: a small Cypher-like subset, parsed here and executed
+//! against any `GraphStore` within one user/cube.
+//!
+//! ```text
+//! MATCH (a {scope: 'UserMemory'})-[r:caused_by|follows*1..3]->(b)<--(c)
+//! WHERE a.importance >= 0.5 AND NOT b.memory CONTAINS 'draft'
+//! RETURN DISTINCT b, b.memory AS text, r
+//! LIMIT 20
+//! ```
+//!
+//! Supported: one path pattern of nodes (optional variable and `{key: literal}` properties)
+//! and relationships (`-->`, `<--`, `--`, optional variable, `:type|type` and `*min..max`
+//! hops up to [`MAX_QUERY_HOPS`]); `WHERE` with `AND`/`OR`/`NOT`, comparisons, `IN [...]`,
+//! `CONTAINS`, `STARTS WITH`, `ENDS WITH` and `IS [NOT] NULL`; `RETURN [DISTINCT]` of
+//! variables or properties with `AS` aliases; `LIMIT`. Node properties are `id`, `memory` and
+//! metadata keys; relationship properties are `id`, `relation`, `from`, `to` and metadata keys.
+//! A relationship is used at most once per match. Tombstoned memories never match unless
+//! requested.
+
+use mem_types::{
+    GraphDirection, GraphNeighbor, GraphStore, GraphStoreError, MemoryEdge, MemoryNode, MemoryScope,
+};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Upper bound of variable-length relationships.
+pub const MAX_QUERY_HOPS: usize = 10;
+/// Longest accepted query text, in bytes.
+pub const MAX_QUERY_LEN: usize = 16 * 1024;
+/// Deepest nesting of `NOT` and parentheses in a `WHERE` condition.
+const MAX_EXPR_DEPTH: usize = 64;
+/// Partial matches explored before a query is abandoned.
+const MAX_QUERY_STATES: usize = 1_000_000;
+
+/// Errors of pattern queries.
+#[derive(Debug, thiserror::Error)]
+pub enum QueryError {
+    /// The query text is invalid; `position` is a byte offset.
+    #[error("query syntax error at {position}: {message}")]
+    Syntax { position: usize, message: String },
+    /// The query is valid but explores too much of the graph.
+    #[error("query exceeds the traversal budget of {0} partial matches")]
+    TooExpensive(usize),
+    #[error(transparent)]
+    Graph(#[from] GraphStoreError),
+}
+
+/// A parsed pattern query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQuery {
+    start: NodePattern,
+    steps: Vec<(RelPattern, NodePattern)>,
+    filter: Option<Expr>,
+    returns: Vec<ReturnItem>,
+    distinct: bool,
+    limit: Option<usize>,
+    /// Variable name -> binding slot.
+    variables: HashMap<String, Variable>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Variable {
+    slot: usize,
+    kind: VariableKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum VariableKind {
+    Node,
+    Relationship { variable_length: bool },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct NodePattern {
+    var: Option<String>,
+    properties: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RelPattern {
+    var: Option<String>,
+    types: Vec<String>,
+    direction: GraphDirection,
+    min_hops: usize,
+    max_hops: usize,
+    variable_length: bool,
+    properties: Vec<(String, Value)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    IsNull(Operand, bool),
+    In(Operand, Vec<Value>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Property(String, String),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ReturnItem {
+    var: String,
+    property: Option<String>,
+    column: String,
+}
+
+/// One returned value.
+#[derive(Debug, Clone)]
+pub enum QueryValue {
+    Node(MemoryNode),
+    Relationship(MemoryEdge),
+    /// The relationships of a variable-length pattern, in path order.
+    Path(Vec<MemoryEdge>),
+    Value(Value),
+}
+
+/// Query result: one column per `RETURN` item and one row per match.
+#[derive(Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<QueryValue>>,
+}
+
+/// Execution options.
+#[derive(Debug, Clone)]
+pub struct QueryOptions<'a> {
+    /// Only nodes and edges owned by this user/cube are matched.
+    pub user_name: &'a str,
+    /// Row cap applied on top of the query's `LIMIT`.
+    pub limit: usize,
+    pub include_deleted: bool,
+}
+
+// ============================================================================
+// Lexer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    /// Backtick-quoted identifier; never a keyword.
+    Quoted(String),
+    Str(String),
+    Number(f64, bool),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Colon,
+    Comma,
+    Dot,
+    DotDot,
+    Pipe,
+    Star,
+    Dash,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    End,
+}
+
+fn syntax(position: usize, message: impl Into<String>) -> QueryError {
+    QueryError::Syntax {
+        position,
+        message: message.into(),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let single = match c {
+            '(' => Some(Token::LParen),
+            ')' => Some(Token::RParen),
+            '[' => Some(Token::LBracket),
+            ']' => Some(Token::RBracket),
+            '{' => Some(Token::LBrace),
+            '}' => Some(Token::RBrace),
+            ':' => Some(Token::Colon),
+            ',' => Some(Token::Comma),
+            '|' => Some(Token::Pipe),
+            '*' => Some(Token::Star),
+            '-' => Some(Token::Dash),
+            '=' => Some(Token::Eq),
+            _ => None,
+        };
+        if let Some(token) = single {
+            tokens.push((token, pos));
+            i += 1;
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '.' if next == Some('.') => {
+                tokens.push((Token::DotDot, pos));
+                i += 2;
+            }
+            '.' => {
+                tokens.push((Token::Dot, pos));
+                i += 1;
+            }
+            '<' | '>' | '!' => {
+                let (token, len) = match (c, next) {
+                    ('<', Some('=')) => (Token::Le, 2),
+                    ('<', Some('>')) => (Token::Ne, 2),
+                    ('<', _) => (Token::Lt, 1),
+                    ('>', Some('=')) => (Token::Ge, 2),
+                    ('>', _) => (Token::Gt, 1),
+                    ('!', Some('=')) => (Token::Ne, 2),
+                    _ => return Err(syntax(pos, "unexpected '!'")),
+                };
+                tokens.push((token, pos));
+                i += len;
+            }
+            '\'' | '"' => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        None => return Err(syntax(pos, "unterminated string")),
+                        Some(&(_, ch)) if ch == c => break,
+                        Some(&(_, '\\')) => {
+                            let escaped = chars
+                                .get(j + 1)
+                                .map(|&(_, e)| e)
+                                .ok_or_else(|| syntax(pos, "unterminated string"))?;
+                            value.push(match escaped {
+                                'n' => '\n',
+                                't' => '\t',
+                                other => other,
+                            });
+                            j += 2;
+                        }
+                        Some(&(_, ch)) => {
+                            value.push(ch);
+                            j += 1;
+                        }
+                    }
+                }
+                tokens.push((Token::Str(value), pos));
+                i = j + 1;
+            }
+            '`' => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        None => return Err(syntax(pos, "unterminated `identifier`")),
+                        Some(&(_, '`')) => break,
+                        Some(&(_, ch)) => value.push(ch),
+                    }
+                    j += 1;
+                }
+                tokens.push((Token::Quoted(value), pos));
+                i = j + 1;
+            }
+            c if c.is_ascii_digit() => {
+                let mut j = i;
+                while chars.get(j).is_some_and(|&(_, d)| d.is_ascii_digit()) {
+                    j += 1;
+                }
+                let mut integer = true;
+                if chars.get(j).is_some_and(|&(_, d)| d == '.')
+                    && chars.get(j + 1).is_some_and(|&(_, d)| d.is_ascii_digit())
+                {
+                    integer = false;
+                    j += 1;
+                    while chars.get(j).is_some_and(|&(_, d)| d.is_ascii_digit()) {
+                        j += 1;
+                    }
+                }
+                let end = chars.get(j).map(|&(p, _)| p).unwrap_or(text.len());
+                let number = text[pos..end]
+                    .parse::<f64>()
+                    .map_err(|_| syntax(pos, "invalid number"))?;
+                tokens.push((Token::Number(number, integer), pos));
+                i = j;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut j = i;
+                while chars
+                    .get(j)
+                    .is_some_and(|&(_, d)| d.is_alphanumeric() || d == '_')
+                {
+                    j += 1;
+                }
+                let end = chars.get(j).map(|&(p, _)| p).unwrap_or(text.len());
+                tokens.push((Token::Ident(text[pos..end].to_string()), pos));
+                i = j;
+            }
+            other => return Err(syntax(pos, format!("unexpected character '{}'", other))),
+        }
+    }
+    tokens.push((Token::End, text.len()));
+    Ok(tokens)
+}
+
+// ============================================================================
+// Parser
+// ============================================================================
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Current nesting of `NOT` and parentheses.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].0.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, QueryError> {
+        Err(syntax(self.position(), message))
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == token {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, what: &str) -> Result<(), QueryError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            self.error(format!("expected {}", what))
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(s) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.error(format!("expected {}", keyword))
+        }
+    }
+
+    fn identifier(&mut self, what: &str) -> Result<String, QueryError> {
+        match self.peek().clone() {
+            Token::Ident(s) if !is_reserved(&s) => {
+                self.advance();
+                Ok(s)
+            }
+            Token::Quoted(s) => {
+                self.advance();
+                Ok(s)
+            }
+            _ => self.error(format!("expected {}", what)),
+        }
+    }
+
+    fn integer(&mut self, what: &str) -> Result<usize, QueryError> {
+        match *self.peek() {
+            Token::Number(n, true) => {
+                self.advance();
+                Ok(n as usize)
+            }
+            _ => self.error(format!("expected {}", what)),
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value, QueryError> {
+        let negative = self.eat(&Token::Dash);
+        match self.peek().clone() {
+            Token::Number(n, integer) => {
+                self.advance();
+                let n = if negative { -n } else { n };
+                Ok(if integer {
+                    Value::from(n as i64)
+                } else {
+                    serde_json::json!(n)
+                })
+            }
+            _ if negative => self.error("expected a number"),
+            Token::Str(s) => {
+                self.advance();
+                Ok(Value::String(s))
+            }
+            Token::Ident(s) if s.eq_ignore_ascii_case("true") => {
+                self.advance();
+                Ok(Value::Bool(true))
+            }
+            Token::Ident(s) if s.eq_ignore_ascii_case("false") => {
+                self.advance();
+                Ok(Value::Bool(false))
+            }
+            Token::Ident(s) if s.eq_ignore_ascii_case("null") => {
+                self.advance();
+                Ok(Value::Null)
+            }
+            _ => self.error("expected a literal"),
+        }
+    }
+
+    fn properties(&mut self) -> Result<Vec<(String, Value)>, QueryError> {
+        let mut properties = Vec::new();
+        if !self.eat(&Token::LBrace) {
+            return Ok(properties);
+        }
+        loop {
+            let key = self.identifier("a property name")?;
+            self.expect(&Token::Colon, "':'")?;
+            properties.push((key, self.literal()?));
+            if !self.eat(&Token::Comma) {
+                break;
+            }
+        }
+        self.expect(&Token::RBrace, "'}'")?;
+        Ok(properties)
+    }
+
+    fn node(&mut self) -> Result<NodePattern, QueryError> {
+        self.expect(&Token::LParen, "'(' starting a node pattern")?;
+        let var = match self.peek() {
+            Token::Ident(_) | Token::Quoted(_) => Some(self.identifier("a variable")?),
+            _ => None,
+        };
+        if *self.peek() == Token::Colon {
+            return self.error("node labels are not supported; filter on properties instead");
+        }
+        let properties = self.properties()?;
+        self.expect(&Token::RParen, "')' closing the node pattern")?;
+        Ok(NodePattern { var, properties })
+    }
+
+    fn relationship(&mut self) -> Result<RelPattern, QueryError> {
+        let inbound = self.eat(&Token::Lt);
+        self.expect(&Token::Dash, "'-'")?;
+        let mut rel = RelPattern {
+            var: None,
+            types: Vec::new(),
+            direction: GraphDirection::Both,
+            min_hops: 1,
+            max_hops: 1,
+            variable_length: false,
+            properties: Vec::new(),
+        };
+        if self.eat(&Token::LBracket) {
+            if matches!(self.peek(), Token::Ident(_) | Token::Quoted(_)) {
+                rel.var = Some(self.identifier("a variable")?);
+            }
+            if self.eat(&Token::Colon) {
+                loop {
+                    rel.types.push(self.identifier("a relationship type")?);
+                    if !self.eat(&Token::Pipe) {
+                        break;
+                    }
+                    self.eat(&Token::Colon);
+                }
+            }
+            if self.eat(&Token::Star) {
+                rel.variable_length = true;
+                rel.max_hops = MAX_QUERY_HOPS;
+                if let Token::Number(_, true) = self.peek() {
+                    rel.min_hops = self.integer("a hop count")?;
+                    if !matches!(self.peek(), Token::DotDot) {
+                        rel.max_hops = rel.min_hops;
+                    }
+                }
+                if self.eat(&Token::DotDot) {
+                    if let Token::Number(_, true) = self.peek() {
+                        rel.max_hops = self.integer("a hop count")?;
+                    }
+                }
+                if rel.max_hops > MAX_QUERY_HOPS {
+                    return self.error(format!(
+                        "variable-length relationships are limited to {} hops",
+                        MAX_QUERY_HOPS
+                    ));
+                }
+                if rel.min_hops > rel.max_hops {
+                    return self.error("minimum hops exceed maximum hops");
+                }
+            }
+            rel.properties = self.properties()?;
+            self.expect(&Token::RBracket, "']'")?;
+            self.expect(&Token::Dash, "'-'")?;
+        } else {
+            self.expect(&Token::Dash, "'-'")?;
+        }
+        let outbound = self.eat(&Token::Gt);
+        rel.direction = match (inbound, outbound) {
+            (true, true) => return self.error("a relationship cannot point both ways"),
+            (true, false) => GraphDirection::Inbound,
+            (false, true) => GraphDirection::Outbound,
+            (false, false) => GraphDirection::Both,
+        };
+        Ok(rel)
+    }
+
+    fn expr(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.and_expr()?;
+        while self.eat_keyword("OR") {
+            left = Expr::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, QueryError> {
+        let mut left = self.not_expr()?;
+        while self.eat_keyword("AND") {
+            left = Expr::And(Box::new(left), Box::new(self.not_expr()?));
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr, QueryError> {
+        if !self.is_keyword("NOT") && *self.peek() != Token::LParen {
+            return self.predicate();
+        }
+        if self.depth == MAX_EXPR_DEPTH {
+            return self.error(format!(
+                "conditions are nested more than {} deep",
+                MAX_EXPR_DEPTH
+            ));
+        }
+        self.depth += 1;
+        let expr = if self.eat_keyword("NOT") {
+            Expr::Not(Box::new(self.not_expr()?))
+        } else {
+            self.advance();
+            let inner = self.expr()?;
+            self.expect(&Token::RParen, "')'")?;
+            inner
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn operand(&mut self) -> Result<Operand, QueryError> {
+        if matches!(self.peek(), Token::Ident(s) if !is_reserved(s))
+            || matches!(self.peek(), Token::Quoted(_))
+        {
+            let var = self.identifier("a variable")?;
+            self.expect(&Token::Dot, "'.' and a property name")?;
+            let key = self.identifier("a property name")?;
+            return Ok(Operand::Property(var, key));
+        }
+        Ok(Operand::Literal(self.literal()?))
+    }
+
+    fn predicate(&mut self) -> Result<Expr, QueryError> {
+        let left = self.operand()?;
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull(left, negated));
+        }
+        if self.eat_keyword("IN") {
+            self.expect(&Token::LBracket, "'['")?;
+            let mut values = Vec::new();
+            if !self.eat(&Token::RBracket) {
+                loop {
+                    values.push(self.literal()?);
+                    if !self.eat(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.expect(&Token::RBracket, "']'")?;
+            }
+            return Ok(Expr::In(left, values));
+        }
+        let op = match self.peek() {
+            Token::Eq => CompareOp::Eq,
+            Token::Ne => CompareOp::Ne,
+            Token::Lt => CompareOp::Lt,
+            Token::Le => CompareOp::Le,
+            Token::Gt => CompareOp::Gt,
+            Token::Ge => CompareOp::Ge,
+            _ if self.is_keyword("CONTAINS") => CompareOp::Contains,
+            _ if self.is_keyword("STARTS") => CompareOp::StartsWith,
+            _ if self.is_keyword("ENDS") => CompareOp::EndsWith,
+            _ => return self.error("expected a comparison"),
+        };
+        self.advance();
+        if matches!(op, CompareOp::StartsWith | CompareOp::EndsWith) {
+            self.expect_keyword("WITH")?;
+        }
+        Ok(Expr::Compare(left, op, self.operand()?))
+    }
+
+    fn return_item(&mut self) -> Result<ReturnItem, QueryError> {
+        let var = self.identifier("a variable to return")?;
+        let property = if self.eat(&Token::Dot) {
+            Some(self.identifier("a property name")?)
+        } else {
+            None
+        };
+        let column = if self.eat_keyword("AS") {
+            self.identifier("a column name")?
+        } else {
+            match property {
+                Some(ref p) => format!("{}.{}", var, p),
+                None => var.clone(),
+            }
+        };
+        Ok(ReturnItem {
+            var,
+            property,
+            column,
+        })
+    }
+}
+
+const RESERVED: &[&str] = &[
+    "MATCH", "WHERE", "RETURN", "DISTINCT", "LIMIT", "AND", "OR", "NOT", "IS", "NULL", "IN",
+    "CONTAINS", "STARTS", "ENDS", "WITH", "AS", "TRUE", "FALSE",
+];
+
+fn is_reserved(word: &str) -> bool {
+    RESERVED.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+impl GraphQuery {
+    /// Parse and validate a query.
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        if text.len() > MAX_QUERY_LEN {
+            return Err(syntax(
+                MAX_QUERY_LEN,
+                format!("queries are limited to {} bytes", MAX_QUERY_LEN),
+            ));
+        }
+        let mut p = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+            depth: 0,
+        };
+        p.expect_keyword("MATCH")?;
+        let start = p.node()?;
+        let mut steps = Vec::new();
+        while matches!(p.peek(), Token::Lt | Token::Dash) {
+            let rel = p.relationship()?;
+            steps.push((rel, p.node()?));
+        }
+        let filter = if p.eat_keyword("WHERE") {
+            Some(p.expr()?)
+        } else {
+            None
+        };
+        p.expect_keyword("RETURN")?;
+        let distinct = p.eat_keyword("DISTINCT");
+        let mut returns = vec![p.return_item()?];
+        while p.eat(&Token::Comma) {
+            returns.push(p.return_item()?);
+        }
+        let limit = if p.eat_keyword("LIMIT") {
+            Some(p.integer("a row limit")?)
+        } else {
+            None
+        };
+        if *p.peek() != Token::End {
+            return p.error("unexpected input after the query");
+        }
+
+        let mut variables: HashMap<String, Variable> = HashMap::new();
+        let mut declare = |name: &Option<String>, kind: VariableKind| -> Result<(), QueryError> {
+            let Some(name) = name else {
+                return Ok(());
+            };
+            let slot = variables.len();
+            match variables.get(name) {
+                None => {
+                    variables.insert(name.clone(), Variable { slot, kind });
+                    Ok(())
+                }
+                // A repeated node variable must bind the same node (checked per match).
+                Some(existing)
+                    if existing.kind == VariableKind::Node && kind == VariableKind::Node =>
+                {
+                    Ok(())
+                }
+                Some(_) => Err(syntax(
+                    0,
+                    format!("variable '{}' is bound more than once", name),
+                )),
+            }
+        };
+        declare(&start.var, VariableKind::Node)?;
+        for (rel, node) in &steps {
+            declare(
+                &rel.var,
+                VariableKind::Relationship {
+                    variable_length: rel.variable_length,
+                },
+            )?;
+            declare(&node.var, VariableKind::Node)?;
+        }
+        let query = Self {
+            start,
+            steps,
+            filter,
+            returns,
+            distinct,
+            limit,
+            variables,
+        };
+        for item in &query.returns {
+            query.check_variable(&item.var)?;
+        }
+        if let Some(ref filter) = query.filter {
+            query.check_expr(filter)?;
+        }
+        Ok(query)
+    }
+
+    /// Column names, one per `RETURN` item.
+    pub fn columns(&self) -> Vec<String> {
+        self.returns.iter().map(|r| r.column.clone()).collect()
+    }
+
+    fn check_variable(&self, name: &str) -> Result<(), QueryError> {
+        if self.variables.contains_key(name) {
+            Ok(())
+        } else {
+            Err(syntax(0, format!("variable '{}' is not defined", name)))
+        }
+    }
+
+    fn check_expr(&self, expr: &Expr) -> Result<(), QueryError> {
+        let check_operand = |operand: &Operand| match operand {
+            Operand::Property(var, _) => self.check_variable(var),
+            Operand::Literal(_) => Ok(()),
+        };
+        match expr {
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                self.check_expr(a)?;
+                self.check_expr(b)
+            }
+            Expr::Not(a) => self.check_expr(a),
+            Expr::Compare(a, _, b) => {
+                check_operand(a)?;
+                check_operand(b)
+            }
+            Expr::IsNull(a, _) | Expr::In(a, _) => check_operand(a),
+        }
+    }
+
+    /// Run the query: matches are found depth-first from the start node candidates and along
+    /// relationships in id order, stopping once the row limit is reached.
+    pub async fn execute<G: GraphStore + ?Sized>(
+        &self,
+        graph: &G,
+        options: &QueryOptions<'_>,
+    ) -> Result<QueryResult, QueryError> {
+        let limit = self.limit.unwrap_or(usize::MAX).min(options.limit);
+        let mut result = QueryResult {
+            columns: self.columns(),
+            rows: Vec::new(),
+        };
+        if limit == 0 {
+            return Ok(result);
+        }
+        let mut exec = Executor {
+            graph,
+            options,
+            neighbors: HashMap::new(),
+            states: 0,
+        };
+        let mut seen_rows: HashSet<Vec<String>> = HashSet::new();
+        let mut stack: Vec<State> = Vec::new();
+        for node in exec.start_candidates(&self.start).await?.into_iter().rev() {
+            let mut bindings = vec![None; self.variables.len()];
+            let node_id = node.id.clone();
+            if self.bind_node(&mut bindings, &self.start, node).is_some() {
+                stack.push(State {
+                    step: 0,
+                    node_id,
+                    bindings,
+                    used_edges: Vec::new(),
+                });
+            }
+        }
+
+        while let Some(state) = stack.pop() {
+            exec.states += 1;
+            if exec.states > MAX_QUERY_STATES {
+                return Err(QueryError::TooExpensive(MAX_QUERY_STATES));
+            }
+            if state.step == self.steps.len() {
+                if self
+                    .filter
+                    .as_ref()
+                    .is_some_and(|f| eval(f, &self.variables, &state.bindings) != Some(true))
+                {
+                    continue;
+                }
+                let row: Vec<QueryValue> = self
+                    .returns
+                    .iter()
+                    .map(|item| self.project(item, &state.bindings))
+                    .collect();
+                if self.distinct && !seen_rows.insert(row.iter().map(row_key).collect()) {
+                    continue;
+                }
+                result.rows.push(row);
+                if result.rows.len() >= limit {
+                    break;
+                }
+                continue;
+            }
+            let (rel, node_pattern) = &self.steps[state.step];
+            let mut children = Vec::new();
+            for (end, edges) in exec.expand(rel, &state.node_id, &state.used_edges).await? {
+                let mut bindings = state.bindings.clone();
+                if !end_matches(node_pattern, &end, options)
+                    || self
+                        .bind_node(&mut bindings, node_pattern, end.clone())
+                        .is_none()
+                {
+                    continue;
+                }
+                if let Some(ref var) = rel.var {
+                    bindings[self.variables[var].slot] = Some(Bound::Edges(edges.clone()));
+                }
+                let mut used_edges = state.used_edges.clone();
+                used_edges.extend(edges.iter().map(|e| e.id.clone()));
+                children.push(State {
+                    step: state.step + 1,
+                    node_id: end.id,
+                    bindings,
+                    used_edges,
+                });
+            }
+            stack.extend(children.into_iter().rev());
+        }
+        Ok(result)
+    }
+
+    /// Bind `node` to the pattern's variable; `None` when the variable is already bound to
+    /// another node.
+    fn bind_node(
+        &self,
+        bindings: &mut [Option<Bound>],
+        pattern: &NodePattern,
+        node: MemoryNode,
+    ) -> Option<()> {
+        let Some(ref var) = pattern.var else {
+            return Some(());
+        };
+        let slot = self.variables[var].slot;
+        match bindings[slot] {
+            Some(Bound::Node(ref bound)) if bound.id != node.id => None,
+            Some(Bound::Node(_)) => Some(()),
+            _ => {
+                bindings[slot] = Some(Bound::Node(node));
+                Some(())
+            }
+        }
+    }
+
+    fn project(&self, item: &ReturnItem, bindings: &[Option<Bound>]) -> QueryValue {
+        let variable = self.variables[&item.var];
+        let bound = bindings[variable.slot].as_ref();
+        match (&item.property, bound) {
+            (Some(key), bound) => {
+                QueryValue::Value(bound.and_then(|b| b.property(key)).unwrap_or(Value::Null))
+            }
+            (None, Some(Bound::Node(n))) => QueryValue::Node(n.clone()),
+            (None, Some(Bound::Edges(edges))) => match variable.kind {
+                VariableKind::Relationship {
+                    variable_length: true,
+                } => QueryValue::Path(edges.clone()),
+                _ => edges
+                    .first()
+                    .cloned()
+                    .map(QueryValue::Relationship)
+                    .unwrap_or(QueryValue::Value(Value::Null)),
+            },
+            (None, None) => QueryValue::Value(Value::Null),
+        }
+    }
+}
+
+// ============================================================================
+// Execution
+// ============================================================================
+
+#[derive(Debug, Clone)]
+enum Bound {
+    Node(MemoryNode),
+    Edges(Vec<MemoryEdge>),
+}
+
+impl Bound {
+    fn property(&self, key: &str) -> Option<Value> {
+        match self {
+            Bound::Node(n) => node_property(n, key),
+            Bound::Edges(edges) if edges.len() == 1 => edge_property(&edges[0], key),
+            Bound::Edges(_) => None,
+        }
+    }
+}
+
+struct State {
+    step: usize,
+    /// Node the next relationship starts from.
+    node_id: String,
+    bindings: Vec<Option<Bound>>,
+    used_edges: Vec<String>,
+}
+
+fn node_property(node: &MemoryNode, key: &str) -> Option<Value> {
+    match key {
+        "id" => Some(Value::String(node.id.clone())),
+        "memory" => Some(Value::String(node.memory.clone())),
+        _ => node.metadata.get(key).filter(|v| !v.is_null()).cloned(),
+    }
+}
+
+fn edge_property(edge: &MemoryEdge, key: &str) -> Option<Value> {
+    match key {
+        "id" => Some(Value::String(edge.id.clone())),
+        "relation" => Some(Value::String(edge.relation.clone())),
+        "from" => Some(Value::String(edge.from.clone())),
+        "to" => Some(Value::String(edge.to.clone())),
+        _ => edge.metadata.get(key).filter(|v| !v.is_null()).cloned(),
+    }
+}
+
+fn is_tombstone(node: &MemoryNode) -> bool {
+    node.metadata.get("state").and_then(|v| v.as_str()) == Some("tombstone")
+}
+
+fn properties_match(actual: impl Fn(&str) -> Option<Value>, expected: &[(String, Value)]) -> bool {
+    expected
+        .iter()
+        .all(|(key, value)| actual(key).is_some_and(|a| values_equal(&a, value) == Some(true)))
+}
+
+fn end_matches(pattern: &NodePattern, node: &MemoryNode, options: &QueryOptions<'_>) -> bool {
+    (options.include_deleted || !is_tombstone(node))
+        && properties_match(|k| node_property(node, k), &pattern.properties)
+}
+
+struct Executor<'g, 'o, G: ?Sized> {
+    graph: &'g G,
+    options: &'o QueryOptions<'o>,
+    /// Neighbors per node and direction, fetched once per query.
+    neighbors: HashMap<(String, GraphDirection), Arc<Vec<GraphNeighbor>>>,
+    states: usize,
+}
+
+impl<G: GraphStore + ?Sized> Executor<'_, '_, G> {
+    /// Start node candidates in id order: the node with the pattern's `id` property, or every
+    /// memory of the cube matching the pattern.
+    async fn start_candidates(&self, pattern: &NodePattern) -> Result<Vec<MemoryNode>, QueryError> {
+        let user_name = self.options.user_name;
+        let id = pattern
+            .properties
+            .iter()
+            .find(|(k, _)| k == "id")
+            .and_then(|(_, v)| v.as_str());
+        let mut nodes = match id {
+            Some(id) => self
+                .graph
+                .get_node(id, false)
+                .await?
+                .filter(|n| n.metadata.get("user_name").and_then(|v| v.as_str()) == Some(user_name))
+                .into_iter()
+                .collect(),
+            None => {
+                let mut nodes = Vec::new();
+                for scope in [
+                    MemoryScope::WorkingMemory,
+                    MemoryScope::UserMemory,
+                    MemoryScope::LongTermMemory,
+                ] {
+                    nodes.extend(
+                        self.graph
+                            .get_all_memory_items(scope.as_str(), user_name, false)
+                            .await?,
+                    );
+                }
+                nodes
+            }
+        };
+        nodes.retain(|n| end_matches(pattern, n, self.options));
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(nodes)
+    }
+
+    async fn neighbors_of(
+        &mut self,
+        id: &str,
+        direction: GraphDirection,
+    ) -> Result<Arc<Vec<GraphNeighbor>>, QueryError> {
+        let key = (id.to_string(), direction);
+        if let Some(cached) = self.neighbors.get(&key) {
+            return Ok(Arc::clone(cached));
+        }
+        let mut neighbors = self
+            .graph
+            .get_neighbors(
+                id,
+                None,
+                direction,
+                usize::MAX,
+                false,
+                Some(self.options.user_name),
+            )
+            .await?;
+        // Stores list neighbors in different orders; edge id order keeps results stable.
+        neighbors.sort_by(|a, b| a.edge.id.cmp(&b.edge.id));
+        let neighbors = Arc::new(neighbors);
+        self.neighbors.insert(key, Arc::clone(&neighbors));
+        Ok(neighbors)
+    }
+
+    /// Every (end node, relationships) reachable from `from` through `rel`, without reusing
+    /// relationships of the match so far.
+    async fn expand(
+        &mut self,
+        rel: &RelPattern,
+        from: &str,
+        used_edges: &[String],
+    ) -> Result<Vec<(MemoryNode, Vec<MemoryEdge>)>, QueryError> {
+        let mut results = Vec::new();
+        if rel.min_hops == 0 {
+            if let Some(node) = self.graph.get_node(from, false).await? {
+                results.push((node, Vec::new()));
+            }
+        }
+        let mut stack: Vec<(String, Vec<MemoryEdge>)> = vec![(from.to_string(), Vec::new())];
+        while let Some((at, path)) = stack.pop() {
+            if path.len() >= rel.max_hops {
+                continue;
+            }
+            self.states += 1;
+            if self.states > MAX_QUERY_STATES {
+                return Err(QueryError::TooExpensive(MAX_QUERY_STATES));
+            }
+            let neighbors = self.neighbors_of(&at, rel.direction).await?;
+            for neighbor in neighbors.iter() {
+                let edge = &neighbor.edge;
+                if (!rel.types.is_empty() && !rel.types.contains(&edge.relation))
+                    || !properties_match(|k| edge_property(edge, k), &rel.properties)
+                    || used_edges.contains(&edge.id)
+                    || path.iter().any(|e| e.id == edge.id)
+                {
+                    continue;
+                }
+                let mut next = path.clone();
+                next.push(edge.clone());
+                if next.len() >= rel.min_hops {
+                    results.push((neighbor.node.clone(), next.clone()));
+                }
+                if next.len() < rel.max_hops
+                    && (self.options.include_deleted || !is_tombstone(&neighbor.node))
+                {
+                    stack.push((neighbor.node.id.clone(), next));
+                }
+            }
+        }
+        Ok(results)
+    }
+}
+
+// ============================================================================
+// Expressions
+// ============================================================================
+
+fn operand_value(
+    operand: &Operand,
+    variables: &HashMap<String, Variable>,
+    bindings: &[Option<Bound>],
+) -> Option<Value> {
+    match operand {
+        Operand::Literal(v) => (!v.is_null()).then(|| v.clone()),
+        Operand::Property(var, key) => bindings[variables[var].slot]
+            .as_ref()
+            .and_then(|b| b.property(key)),
+    }
+}
+
+fn values_equal(a: &Value, b: &Value) -> Option<bool> {
+    match (a, b) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        (Value::Number(x), Value::Number(y)) => Some(x.as_f64() == y.as_f64()),
+        _ => Some(a == b),
+    }
+}
+
+fn compare_values(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x.as_f64()?.partial_cmp(&y.as_f64()?),
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        (Value::Bool(x), Value::Bool(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+/// Three-valued evaluation: `None` is null (a missing property or incomparable values).
+fn eval(
+    expr: &Expr,
+    variables: &HashMap<String, Variable>,
+    bindings: &[Option<Bound>],
+) -> Option<bool> {
+    match expr {
+        Expr::And(a, b) => match (eval(a, variables, bindings), eval(b, variables, bindings)) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        },
+        Expr::Or(a, b) => match (eval(a, variables, bindings), eval(b, variables, bindings)) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        },
+        Expr::Not(a) => eval(a, variables, bindings).map(|v| !v),
+        Expr::IsNull(operand, negated) => {
+            Some(operand_value(operand, variables, bindings).is_none() != *negated)
+        }
+        Expr::In(operand, values) => {
+            let value = operand_value(operand, variables, bindings)?;
+            Some(values.iter().any(|v| values_equal(&value, v) == Some(true)))
+        }
+        Expr::Compare(left, op, right) => {
+            let a = operand_value(left, variables, bindings)?;
+            let b = operand_value(right, variables, bindings)?;
+            match op {
+                CompareOp::Eq => values_equal(&a, &b),
+                CompareOp::Ne => values_equal(&a, &b).map(|v| !v),
+                CompareOp::Lt => compare_values(&a, &b).map(|o| o == Ordering::Less),
+                CompareOp::Le => compare_values(&a, &b).map(|o| o != Ordering::Greater),
+                CompareOp::Gt => compare_values(&a, &b).map(|o| o == Ordering::Greater),
+                CompareOp::Ge => compare_values(&a, &b).map(|o| o != Ordering::Less),
+                CompareOp::Contains | CompareOp::StartsWith | CompareOp::EndsWith => {
+                    let (Value::String(a), Value::String(b)) = (&a, &b) else {
+                        return None;
+                    };
+                    Some(match op {
+                        CompareOp::Contains => a.contains(b.as_str()),
+                        CompareOp::StartsWith => a.starts_with(b.as_str()),
+                        _ => a.ends_with(b.as_str()),
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Identity of a returned value, for `DISTINCT`.
+fn row_key(value: &QueryValue) -> String {
+    match value {
+        QueryValue::Node(n) => format!("n:{}", n.id),
+        QueryValue::Relationship(e) => format!("r:{}", e.id),
+        QueryValue::Path(edges) => {
+            let ids: Vec<&str> = edges.iter().map(|e| e.id.as_str()).collect();
+            format!("p:{}", ids.join(","))
+        }
+        QueryValue::Value(v) => format!("v:{}", v),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryGraphStore;
+
+    /// a -caused_by-> b -caused_by-> c -caused_by-> d and a -follows-> c, plus a tombstone
+    /// hanging off b and a node of another cube.
+    async fn chain() -> InMemoryGraphStore {
+        let store = InMemoryGraphStore::new();
+        for (id, scope, importance) in [
+            ("a", "UserMemory", 0.9),
+            ("b", "LongTermMemory", 0.4),
+            ("c", "LongTermMemory", 0.7),
+            ("d", "WorkingMemory", 0.2),
+            ("gone", "LongTermMemory", 0.5),
+        ] {
+            let mut metadata = HashMap::new();
+            metadata.insert("scope".to_string(), serde_json::json!(scope));
+            metadata.insert("importance".to_string(), serde_json::json!(importance));
+            if id == "gone" {
+                metadata.insert("state".to_string(), serde_json::json!("tombstone"));
+            }
+            store
+                .add_node(id, &format!("memory {}", id), &metadata, Some("u1"))
+                .await
+                .unwrap();
+        }
+        store
+            .add_node("x", "memory x", &HashMap::new(), Some("u2"))
+            .await
+            .unwrap();
+        let edge = |id: &str, from: &str, to: &str, relation: &str| MemoryEdge {
+            id: id.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            relation: relation.to_string(),
+            metadata: HashMap::new(),
+        };
+        store
+            .add_edges_batch(
+                &[
+                    edge("e1", "a", "b", "caused_by"),
+                    edge("e2", "b", "c", "caused_by"),
+                    edge("e3", "c", "d", "caused_by"),
+                    edge("e4", "a", "c", "follows"),
+                    edge("e5", "b", "gone", "caused_by"),
+                ],
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        store
+    }
+
+    async fn run(store: &InMemoryGraphStore, text: &str) -> QueryResult {
+        GraphQuery::parse(text)
+            .unwrap()
+            .execute(
+                store,
+                &QueryOptions {
+                    user_name: "u1",
+                    limit: 100,
+                    include_deleted: false,
+                },
+            )
+            .await
+            .unwrap()
+    }
+
+    fn ids(result: &QueryResult, column: usize) -> Vec<String> {
+        result
+            .rows
+            .iter()
+            .map(|row| match &row[column] {
+                QueryValue::Node(n) => n.id.clone(),
+                QueryValue::Relationship(e) => e.id.clone(),
+                QueryValue::Path(edges) => edges
+                    .iter()
+                    .map(|e| e.id.as_str())
+                    .collect::<Vec<_>>()
+                    .join(","),
+                QueryValue::Value(v) => v.to_string(),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn variable_length_paths_follow_direction_and_type() {
+        let store = chain().await;
+        let result = run(
+            &store,
+            "MATCH (a)-[:caused_by*1..3]->(b) WHERE a.scope = 'UserMemory' RETURN b",
+        )
+        .await;
+        assert_eq!(result.columns, vec!["b"]);
+        assert_eq!(
+            ids(&result, 0),
+            vec!["b", "c", "d"],
+            "tombstones and u2 are skipped"
+        );
+
+        let result = run(
+            &store,
+            "MATCH (x {id: 'd'})<-[p:caused_by|follows*2]-(y) RETURN DISTINCT y.id AS id, p",
+        )
+        .await;
+        assert_eq!(result.columns, vec!["id", "p"]);
+        assert_eq!(ids(&result, 0), vec!["\"b\"", "\"a\""]);
+        assert_eq!(ids(&result, 1), vec!["e3,e2", "e3,e4"]);
+    }
+
+    #[tokio::test]
+    async fn where_clauses_use_three_valued_logic() {
+        let store = chain().await;
+        let result = run(
+            &store,
+            "MATCH (n)-[r]-(m) WHERE n.importance >= 0.7 AND NOT m.memory ENDS WITH 'b' \
+             AND r.relation IN ['caused_by', 'follows'] RETURN n.id, r, m.missing LIMIT 2",
+        )
+        .await;
+        assert_eq!(ids(&result, 0), vec!["\"a\"", "\"c\""]);
+        assert_eq!(ids(&result, 1), vec!["e4", "e3"]);
+        assert_eq!(ids(&result, 2), vec!["null", "null"]);
+
+        // Comparisons with a missing property are null, so neither side matches.
+        let missing = run(&store, "MATCH (n) WHERE n.nope = 1 OR n.nope <> 1 RETURN n").await;
+        assert!(missing.rows.is_empty());
+        let null = run(&store, "MATCH (n) WHERE n.nope IS NULL RETURN n").await;
+        assert_eq!(ids(&null, 0), vec!["a", "b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn repeated_variables_and_edge_uniqueness() {
+        let store = chain().await;
+        // a -> b -> c and back to a through the `follows` edge: a cycle of three relationships.
+        let cycle = run(&store, "MATCH (s)-->(t)-->(u)<--(s) RETURN s, t, u").await;
+        assert_eq!(ids(&cycle, 0), vec!["a"]);
+        assert_eq!(ids(&cycle, 2), vec!["c"]);
+        // An undirected relationship cannot be walked back along the same edge.
+        let back = run(&store, "MATCH (s {id: 'a'})--(t)--(s) RETURN t").await;
+        assert!(back.rows.is_empty());
+    }
+
+    #[test]
+    fn invalid_queries_report_positions() {
+        for (text, expected) in [
+            ("MATCH (a RETURN a", "expected ')'"),
+            ("MATCH (a:Memory) RETURN a", "labels are not supported"),
+            ("MATCH (a)-[*1..11]->(b) RETURN b", "limited to 10 hops"),
+            ("MATCH (a)-[*3..2]->(b) RETURN b", "minimum hops"),
+            ("MATCH (a)<-->(b) RETURN b", "both ways"),
+            ("MATCH (a) RETURN b", "'b' is not defined"),
+            ("MATCH (a)-[a]->(b) RETURN b", "bound more than once"),
+            (
+                "MATCH (a) WHERE a.x = 'open RETURN a",
+                "unterminated string",
+            ),
+            ("MATCH (a) RETURN a LIMIT 1 extra", "unexpected input"),
+        ] {
+            let err = GraphQuery::parse(text).unwrap_err();
+            assert!(
+                matches!(err, QueryError::Syntax { .. }) && err.to_string().contains(expected),
+                "{}: {}",
+                text,
+                err
+            );
+        }
+        match GraphQuery::parse("MATCH (a) RETURN a LIMIT x").unwrap_err() {
+            QueryError::Syntax { position, .. } => assert_eq!(position, 25),
+            other => panic!("unexpected error {}", other),
+        }
+    }
+
+    #[test]
+    fn oversized_and_deeply_nested_queries_are_rejected() {
+        let nested = |prefix: &str, suffix: &str, n: usize| {
+            format!(
+                "MATCH (a) WHERE {}a.x = 1{} RETURN a",
+                prefix.repeat(n),
+                suffix.repeat(n)
+            )
+        };
+        for text in [nested("NOT ", "", 50_000), nested("(", ")", 50_000)] {
+            let err = GraphQuery::parse(&text).unwrap_err();
+            assert!(err.to_string().contains("limited to"), "{}", err);
+        }
+        for text in [nested("NOT ", "", 1_000), nested("(", ")", 1_000)] {
+            let err = GraphQuery::parse(&text).unwrap_err();
+            assert!(err.to_string().contains("nested more than"), "{}", err);
+        }
+        let shallow = nested("NOT (", ")", MAX_EXPR_DEPTH / 2);
+        assert!(GraphQuery::parse(&shallow).is_ok());
+        assert!(GraphQuery::parse(&nested("NOT (", ")", MAX_EXPR_DEPTH / 2 + 1)).is_err());
+    }
+}
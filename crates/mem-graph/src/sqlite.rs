@@ -1,23 +1,79 @@
 //! SQLite-backed graph store implementation (P0: persistence).
+//!
+//! With `with_encryption`, node `memory`/`metadata`, edge `metadata` and (optionally) node
+//! embeddings are sealed with per-cube data keys; see `mem_types::encryption`.
 
+use crate::store::{apply_edge_update, author_of};
 use crate::{
     GraphNeighbor, GraphPath, GraphStore, GraphStoreError, MemoryEdge, MemoryNode, VecSearchHit,
 };
 use async_trait::async_trait;
+use mem_types::encryption::{
+    DataKey, EncryptionConfig, EncryptionError, KeyRing, MasterKey, WrappedKeyRecord,
+};
 use mem_types::GraphDirection;
-use std::collections::HashMap;
-use std::path::Path;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Read-only connections opened next to the writer by default.
+pub const DEFAULT_READ_CONNECTIONS: usize = 4;
+
+/// Upper bound on partial paths explored by one `find_paths` query; a search that needs more
+/// fails (`PATH_SEARCH_LIMIT_ERROR`) instead of returning whatever it found so far.
+const PATH_SEARCH_ROW_LIMIT: i64 = 100_000;
+
+/// Start of the error message of a `find_paths` query that hit `PATH_SEARCH_ROW_LIMIT`.
+const PATH_SEARCH_LIMIT_ERROR: &str = "path search exceeded";
 
 /// SQLite-backed graph store for persistence.
+///
+/// Writes go through a single connection; lookups and traversals use a small pool of
+/// read-only connections (WAL mode lets them run beside the writer). Owner, scope and state
+/// are kept in indexed columns extracted from metadata on write, so they stay queryable when
+/// the metadata itself is sealed.
 pub struct SqliteGraphStore {
     conn: std::sync::Mutex<rusqlite::Connection>,
+    readers: Vec<std::sync::Mutex<rusqlite::Connection>>,
+    next_reader: AtomicUsize,
+    path: PathBuf,
+    /// Data keys when encryption at rest is enabled.
+    keys: Option<KeyRing>,
+    /// Ids of data keys created by the write in progress. They are in `keys` before the write
+    /// commits, so pooled readers can open its rows at once, and leave it if the write rolls back.
+    new_keys: std::sync::Mutex<Vec<i64>>,
 }
 
 impl SqliteGraphStore {
     /// Create a new SQLite graph store at the given path.
     pub fn new(path: impl AsRef<Path>) -> Result<Self, GraphStoreError> {
-        let conn =
-            rusqlite::Connection::open(path).map_err(|e| GraphStoreError::Other(e.to_string()))?;
+        Self::open(path, None)
+    }
+
+    /// Create a store that encrypts memory text and metadata at rest (envelope encryption
+    /// with per-cube data keys wrapped by `config.master_key`).
+    pub fn with_encryption(
+        path: impl AsRef<Path>,
+        config: EncryptionConfig,
+    ) -> Result<Self, GraphStoreError> {
+        Self::open(path, Some(config))
+    }
+
+    /// Replace the read pool with `count` connections (0 sends reads to the writer).
+    /// In-memory databases always read through the writer.
+    pub fn with_read_connections(mut self, count: usize) -> Result<Self, GraphStoreError> {
+        self.readers = open_readers(&self.path, count)?;
+        Ok(self)
+    }
+
+    fn open(
+        path: impl AsRef<Path>,
+        encryption: Option<EncryptionConfig>,
+    ) -> Result<Self, GraphStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let conn = open_connection(&path).map_err(|e| GraphStoreError::Other(e.to_string()))?;
 
         // Initialize schema
         conn.execute_batch(
@@ -28,7 +84,10 @@ impl SqliteGraphStore {
                 metadata TEXT NOT NULL,
                 embedding BLOB,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                owner TEXT NOT NULL DEFAULT '',
+                scope TEXT NOT NULL DEFAULT '',
+                state TEXT NOT NULL DEFAULT ''
             );
 
             CREATE TABLE IF NOT EXISTS edges (
@@ -38,11 +97,46 @@ impl SqliteGraphStore {
                 relation TEXT NOT NULL,
                 metadata TEXT NOT NULL,
                 created_at TEXT NOT NULL,
+                owner TEXT NOT NULL DEFAULT '',
                 FOREIGN KEY (from_node) REFERENCES nodes(id) ON DELETE CASCADE,
                 FOREIGN KEY (to_node) REFERENCES nodes(id) ON DELETE CASCADE
             );
 
-            CREATE INDEX IF NOT EXISTS idx_nodes_user ON nodes(metadata);
+            CREATE TABLE IF NOT EXISTS data_keys (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cube_id TEXT NOT NULL,
+                wrapped_key BLOB NOT NULL,
+                master_key_id TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            "#,
+        )
+        .map_err(|e| GraphStoreError::Other(e.to_string()))?;
+
+        // Databases created before the extracted columns existed are migrated and backfilled.
+        let mut added = false;
+        for (table, column) in [
+            ("nodes", "owner"),
+            ("nodes", "scope"),
+            ("nodes", "state"),
+            ("nodes", "session"),
+            ("nodes", "author"),
+            ("edges", "owner"),
+            ("edges", "author"),
+        ] {
+            added |= add_column_if_missing(&conn, table, column)
+                .map_err(|e| GraphStoreError::Other(e.to_string()))?;
+        }
+        conn.execute_batch(
+            r#"
+            DROP INDEX IF EXISTS idx_nodes_user;
+            CREATE INDEX IF NOT EXISTS idx_nodes_owner_scope ON nodes(owner, scope, id);
+            CREATE INDEX IF NOT EXISTS idx_nodes_owner_state ON nodes(owner, state);
+            CREATE INDEX IF NOT EXISTS idx_nodes_owner_session
+                ON nodes(owner, session, created_at);
+            CREATE INDEX IF NOT EXISTS idx_nodes_author ON nodes(author, owner);
+            CREATE INDEX IF NOT EXISTS idx_edges_owner ON edges(owner, id);
+            CREATE INDEX IF NOT EXISTS idx_edges_author ON edges(author, owner);
             CREATE INDEX IF NOT EXISTS idx_edges_from ON edges(from_node);
             CREATE INDEX IF NOT EXISTS idx_edges_to ON edges(to_node);
             CREATE INDEX IF NOT EXISTS idx_edges_relation ON edges(relation);
@@ -50,11 +144,399 @@ impl SqliteGraphStore {
         )
         .map_err(|e| GraphStoreError::Other(e.to_string()))?;
 
-        Ok(Self {
+        let keys = match encryption {
+            Some(config) => {
+                let records =
+                    load_key_records(&conn).map_err(|e| GraphStoreError::Other(e.to_string()))?;
+                Some(KeyRing::load(&config, records).map_err(key_error)?)
+            }
+            None => None,
+        };
+
+        let store = Self {
             conn: std::sync::Mutex::new(conn),
+            readers: open_readers(&path, DEFAULT_READ_CONNECTIONS)?,
+            next_reader: AtomicUsize::new(0),
+            path,
+            keys,
+            new_keys: std::sync::Mutex::new(Vec::new()),
+        };
+        if added {
+            store.backfill_index_columns()?;
+        }
+        Ok(store)
+    }
+
+    /// Fill the extracted owner/scope/state/session/author columns from (decoded) metadata.
+    fn backfill_index_columns(&self) -> Result<(), GraphStoreError> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let nodes =
+                self.read_nodes(&tx, "SELECT id, memory, metadata, embedding FROM nodes", [])?;
+            for node in nodes {
+                let (owner, scope, state, session) = node_index_columns(&node.metadata);
+                tx.execute(
+                    "UPDATE nodes SET owner = ?1, scope = ?2, state = ?3, session = ?4, author = ?5
+                     WHERE id = ?6",
+                    rusqlite::params![
+                        owner,
+                        scope,
+                        state,
+                        session,
+                        author_of(&node.metadata),
+                        node.id
+                    ],
+                )?;
+            }
+            let edges = self.read_edges(
+                &tx,
+                "SELECT id, from_node, to_node, relation, metadata FROM edges",
+                [],
+            )?;
+            for edge in edges {
+                tx.execute(
+                    "UPDATE edges SET owner = ?1, author = ?2 WHERE id = ?3",
+                    rusqlite::params![owner_of(&edge.metadata), author_of(&edge.metadata), edge.id],
+                )?;
+            }
+            tx.commit()
+        })
+    }
+
+    /// Rewrap every data key with `new_master`. Row data is not touched.
+    /// Returns the number of rewrapped keys.
+    pub fn rotate_master_key(&self, new_master: MasterKey) -> Result<usize, GraphStoreError> {
+        let keys = self.key_ring()?;
+        self.with_conn(|conn| {
+            let old_master = keys.master();
+            let records = load_key_records(conn)?;
+            let tx = conn.unchecked_transaction()?;
+            for record in &records {
+                let dek = old_master.unwrap(&record.wrapped).map_err(crypto_err)?;
+                let wrapped = new_master.wrap(&dek).map_err(crypto_err)?;
+                tx.execute(
+                    "UPDATE data_keys SET wrapped_key = ?1, master_key_id = ?2 WHERE id = ?3",
+                    rusqlite::params![wrapped, new_master.id(), record.key_id],
+                )?;
+            }
+            tx.commit()?;
+            keys.set_master(new_master);
+            Ok(records.len())
+        })
+    }
+
+    /// Give `cube_id` a fresh data key, re-encrypt its nodes and edges (including rows still
+    /// in plaintext) and destroy the old keys. Returns the number of re-encrypted rows.
+    pub fn rotate_data_key(&self, cube_id: &str) -> Result<usize, GraphStoreError> {
+        let keys = self.key_ring()?;
+        self.with_conn(|conn| {
+            let old_ids = keys.key_ids_for_cube(cube_id);
+            let tx = conn.unchecked_transaction()?;
+            let new_key = self.create_data_key(&tx, keys, cube_id)?;
+
+            let nodes = self.read_nodes(
+                &tx,
+                "SELECT id, memory, metadata, embedding FROM nodes WHERE owner = ?1",
+                [cube_id],
+            )?;
+            let mut rotated = 0usize;
+            for node in nodes {
+                let (memory, metadata, embedding) = self.encode_node(
+                    &new_key,
+                    &node.id,
+                    &node.memory,
+                    &node.metadata,
+                    &node.embedding,
+                )?;
+                tx.execute(
+                    "UPDATE nodes SET memory = ?1, metadata = ?2, embedding = ?3 WHERE id = ?4",
+                    rusqlite::params![memory, metadata, embedding, node.id],
+                )?;
+                rotated += 1;
+            }
+            let edges = self.read_edges(
+                &tx,
+                "SELECT id, from_node, to_node, relation, metadata FROM edges WHERE owner = ?1",
+                [cube_id],
+            )?;
+            for edge in edges {
+                let metadata = self.encode_edge_metadata(&new_key, &edge.id, &edge.metadata)?;
+                tx.execute(
+                    "UPDATE edges SET metadata = ?1 WHERE id = ?2",
+                    rusqlite::params![metadata, edge.id],
+                )?;
+                rotated += 1;
+            }
+            for id in &old_ids {
+                tx.execute("DELETE FROM data_keys WHERE id = ?1", [id])?;
+            }
+            tx.commit()?;
+            self.retire_keys(keys, &old_ids);
+            Ok(rotated)
+        })
+    }
+
+    fn key_ring(&self) -> Result<&KeyRing, GraphStoreError> {
+        self.keys
+            .as_ref()
+            .ok_or_else(|| GraphStoreError::Other("encryption is not enabled".to_string()))
+    }
+
+    /// Active key for the cube, creating a data key on first use.
+    fn key_for_cube(
+        &self,
+        conn: &rusqlite::Connection,
+        cube_id: &str,
+    ) -> Result<Option<(i64, DataKey)>, rusqlite::Error> {
+        let Some(ref keys) = self.keys else {
+            return Ok(None);
+        };
+        match keys.active_key(cube_id) {
+            Some(key) => Ok(Some(key)),
+            None => self.create_data_key(conn, keys, cube_id).map(Some),
+        }
+    }
+
+    /// Generate, wrap and persist a new data key for the cube and make it the cube's active
+    /// key. It is recorded in `new_keys` so `with_conn` drops it if the write rolls back.
+    fn create_data_key(
+        &self,
+        conn: &rusqlite::Connection,
+        keys: &KeyRing,
+        cube_id: &str,
+    ) -> Result<(i64, DataKey), rusqlite::Error> {
+        let master = keys.master();
+        let dek = DataKey::generate();
+        let wrapped = master.wrap(&dek).map_err(crypto_err)?;
+        conn.execute(
+            "INSERT INTO data_keys (cube_id, wrapped_key, master_key_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![cube_id, wrapped, master.id(), chrono::Utc::now().to_rfc3339()],
+        )?;
+        let id = conn.last_insert_rowid();
+        keys.insert(id, cube_id, dek.clone());
+        self.new_keys
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(id);
+        Ok((id, dek))
+    }
+
+    /// Drop committed-away keys from the ring once no pooled read is running: a read that
+    /// started before the commit still sees rows sealed with them. Call with the writer held.
+    fn retire_keys(&self, keys: &KeyRing, key_ids: &[i64]) {
+        let _idle: Vec<_> = self
+            .readers
+            .iter()
+            .map(|reader| reader.lock().unwrap_or_else(|e| e.into_inner()))
+            .collect();
+        keys.remove(key_ids);
+    }
+
+    /// Serialize and seal node columns with `key`.
+    fn encode_node(
+        &self,
+        (key_id, dek): &(i64, DataKey),
+        id: &str,
+        memory: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+        embedding: &Option<Vec<f32>>,
+    ) -> Result<(String, String, Option<Vec<u8>>), rusqlite::Error> {
+        let keys = self.keys.as_ref().expect("encode_node requires encryption");
+        let metadata_json = serde_json::to_string(metadata)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let embedding_blob = embedding.as_ref().and_then(|e| serde_json::to_vec(e).ok());
+        let memory = KeyRing::seal_text_with(*key_id, dek, memory, &format!("nodes.memory:{}", id))
+            .map_err(crypto_err)?;
+        let metadata_json = KeyRing::seal_text_with(
+            *key_id,
+            dek,
+            &metadata_json,
+            &format!("nodes.metadata:{}", id),
+        )
+        .map_err(crypto_err)?;
+        let embedding_blob = match embedding_blob {
+            Some(blob) if keys.encrypt_vectors() => Some(
+                KeyRing::seal_blob_with(*key_id, dek, &blob, &format!("nodes.embedding:{}", id))
+                    .map_err(crypto_err)?,
+            ),
+            other => other,
+        };
+        Ok((memory, metadata_json, embedding_blob))
+    }
+
+    fn encode_edge_metadata(
+        &self,
+        (key_id, dek): &(i64, DataKey),
+        id: &str,
+        metadata: &HashMap<String, serde_json::Value>,
+    ) -> Result<String, rusqlite::Error> {
+        let metadata_json = serde_json::to_string(metadata)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        KeyRing::seal_text_with(
+            *key_id,
+            dek,
+            &metadata_json,
+            &format!("edges.metadata:{}", id),
+        )
+        .map_err(crypto_err)
+    }
+
+    /// Node columns ready to insert for the node's cube.
+    fn node_columns(
+        &self,
+        conn: &rusqlite::Connection,
+        node: &MemoryNode,
+    ) -> Result<(String, String, Option<Vec<u8>>), rusqlite::Error> {
+        match self.key_for_cube(conn, owner_of(&node.metadata))? {
+            Some(key) => self.encode_node(
+                &key,
+                &node.id,
+                &node.memory,
+                &node.metadata,
+                &node.embedding,
+            ),
+            None => {
+                let metadata_json = serde_json::to_string(&node.metadata)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                Ok((
+                    node.memory.clone(),
+                    metadata_json,
+                    node.embedding
+                        .as_ref()
+                        .and_then(|e| serde_json::to_vec(e).ok()),
+                ))
+            }
+        }
+    }
+
+    fn edge_metadata_column(
+        &self,
+        conn: &rusqlite::Connection,
+        edge: &MemoryEdge,
+    ) -> Result<String, rusqlite::Error> {
+        match self.key_for_cube(conn, owner_of(&edge.metadata))? {
+            Some(key) => self.encode_edge_metadata(&key, &edge.id, &edge.metadata),
+            None => serde_json::to_string(&edge.metadata)
+                .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e))),
+        }
+    }
+
+    /// Decode (and open, when sealed) node columns.
+    fn decode_node(
+        &self,
+        id: String,
+        memory: String,
+        metadata_json: String,
+        embedding_blob: Option<Vec<u8>>,
+    ) -> Result<MemoryNode, rusqlite::Error> {
+        let (memory, metadata_json) = match self.keys {
+            Some(ref keys) => (
+                keys.open_text(&memory, &format!("nodes.memory:{}", id))
+                    .map_err(crypto_err)?,
+                keys.open_text(&metadata_json, &format!("nodes.metadata:{}", id))
+                    .map_err(crypto_err)?,
+            ),
+            None => (memory, metadata_json),
+        };
+        let embedding = embedding_blob
+            .map(|blob| self.decode_embedding(&id, blob))
+            .transpose()?
+            .flatten();
+        Ok(MemoryNode {
+            id,
+            memory,
+            metadata: serde_json::from_str(&metadata_json).unwrap_or_default(),
+            embedding,
+        })
+    }
+
+    /// Decode (and open, when sealed) an `embedding` column; `None` when unreadable.
+    fn decode_embedding(
+        &self,
+        id: &str,
+        blob: Vec<u8>,
+    ) -> Result<Option<Vec<f32>>, rusqlite::Error> {
+        let blob = match self.keys {
+            Some(ref keys) => keys
+                .open_blob(&blob, &format!("nodes.embedding:{}", id))
+                .map_err(crypto_err)?,
+            None => blob,
+        };
+        Ok(serde_json::from_slice(&blob).ok())
+    }
+
+    fn decode_edge(
+        &self,
+        id: String,
+        from: String,
+        to: String,
+        relation: String,
+        metadata_json: String,
+    ) -> Result<MemoryEdge, rusqlite::Error> {
+        let metadata_json = match self.keys {
+            Some(ref keys) => keys
+                .open_text(&metadata_json, &format!("edges.metadata:{}", id))
+                .map_err(crypto_err)?,
+            None => metadata_json,
+        };
+        Ok(MemoryEdge {
+            id,
+            from,
+            to,
+            relation,
+            metadata: serde_json::from_str(&metadata_json).unwrap_or_default(),
         })
     }
 
+    /// Run a query selecting `id, memory, metadata, embedding` and decode the nodes.
+    fn read_nodes<P: rusqlite::Params>(
+        &self,
+        conn: &rusqlite::Connection,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<MemoryNode>, rusqlite::Error> {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<Vec<u8>>>(3)?,
+            ))
+        })?;
+        let mut nodes = Vec::new();
+        for row in rows {
+            let (id, memory, metadata_json, embedding) = row?;
+            nodes.push(self.decode_node(id, memory, metadata_json, embedding)?);
+        }
+        Ok(nodes)
+    }
+
+    /// Run a query selecting `id, from_node, to_node, relation, metadata` and decode the edges.
+    fn read_edges<P: rusqlite::Params>(
+        &self,
+        conn: &rusqlite::Connection,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<MemoryEdge>, rusqlite::Error> {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+        let mut edges = Vec::new();
+        for row in rows {
+            let (id, from, to, relation, metadata_json) = row?;
+            edges.push(self.decode_edge(id, from, to, relation, metadata_json)?);
+        }
+        Ok(edges)
+    }
+
     fn with_conn<T, F>(&self, f: F) -> Result<T, GraphStoreError>
     where
         F: FnOnce(&rusqlite::Connection) -> Result<T, rusqlite::Error>,
@@ -63,8 +545,84 @@ impl SqliteGraphStore {
             .conn
             .lock()
             .map_err(|e| GraphStoreError::Other(format!("failed to acquire lock: {}", e)))?;
+        let result = f(&conn);
+        // Keys created by a write that rolled back were never saved: forget them.
+        let new_keys =
+            std::mem::take(&mut *self.new_keys.lock().unwrap_or_else(|e| e.into_inner()));
+        if let (Err(_), Some(keys)) = (&result, &self.keys) {
+            keys.remove(&new_keys);
+        }
+        result.map_err(|e| GraphStoreError::Other(e.to_string()))
+    }
+
+    /// Run a read on an idle pooled connection (waiting on one when all are busy).
+    fn with_read<T, F>(&self, f: F) -> Result<T, GraphStoreError>
+    where
+        F: FnOnce(&rusqlite::Connection) -> Result<T, rusqlite::Error>,
+    {
+        if self.readers.is_empty() {
+            return self.with_conn(f);
+        }
+        let count = self.readers.len();
+        let start = self.next_reader.fetch_add(1, Ordering::Relaxed) % count;
+        let conn = match (0..count).find_map(|i| self.readers[(start + i) % count].try_lock().ok())
+        {
+            Some(conn) => conn,
+            None => self.readers[start]
+                .lock()
+                .map_err(|e| GraphStoreError::Other(format!("failed to acquire lock: {}", e)))?,
+        };
         f(&conn).map_err(|e| GraphStoreError::Other(e.to_string()))
     }
+
+    /// Check path endpoints like the in-memory store: missing or foreign endpoints are an
+    /// error, tombstoned ones (without `include_deleted`) mean no path (`false`).
+    fn check_path_endpoints(
+        &self,
+        source_id: &str,
+        target_id: &str,
+        include_deleted: bool,
+        user_name: Option<&str>,
+    ) -> Result<bool, GraphStoreError> {
+        let rows: HashMap<String, (String, String)> = self.with_read(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT id, owner, state FROM nodes WHERE id IN (?1, ?2)")?;
+            let rows = stmt.query_map([source_id, target_id], |row| {
+                Ok((row.get(0)?, (row.get(1)?, row.get(2)?)))
+            })?;
+            rows.collect()
+        })?;
+        for id in [source_id, target_id] {
+            let (owner, _) = rows
+                .get(id)
+                .ok_or_else(|| GraphStoreError::Other(format!("node not found: {}", id)))?;
+            if user_name.is_some_and(|un| owner != un) {
+                return Err(GraphStoreError::Other(format!(
+                    "node not found or access denied: {} -> {}",
+                    source_id, target_id
+                )));
+            }
+        }
+        Ok(include_deleted || rows.values().all(|(_, state)| state != "tombstone"))
+    }
+
+    /// Decoded edges keyed by id.
+    fn edges_by_ids(
+        &self,
+        conn: &rusqlite::Connection,
+        ids: &[&str],
+    ) -> Result<HashMap<String, MemoryEdge>, rusqlite::Error> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders: Vec<&str> = ids.iter().map(|_| "?").collect();
+        let sql = format!(
+            "SELECT id, from_node, to_node, relation, metadata FROM edges WHERE id IN ({})",
+            placeholders.join(",")
+        );
+        let edges = self.read_edges(conn, &sql, rusqlite::params_from_iter(ids))?;
+        Ok(edges.into_iter().map(|e| (e.id.clone(), e)).collect())
+    }
 }
 
 #[async_trait]
@@ -74,21 +632,24 @@ impl GraphStore for SqliteGraphStore {
         id: &str,
         memory: &str,
         metadata: &HashMap<String, serde_json::Value>,
-        _user_name: Option<&str>,
+        user_name: Option<&str>,
     ) -> Result<(), GraphStoreError> {
-        let id = id.to_string();
-        let memory = memory.to_string();
-        let metadata_json =
-            serde_json::to_string(metadata).map_err(|e| GraphStoreError::Other(e.to_string()))?;
+        let node = MemoryNode {
+            id: id.to_string(),
+            memory: memory.to_string(),
+            metadata: with_owner(metadata, user_name),
+            embedding: None,
+        };
         let now = chrono::Utc::now().to_rfc3339();
 
         self.with_conn(|conn| {
+            let (memory, metadata_json, _) = self.node_columns(conn, &node)?;
+            let (owner, scope, state, session) = node_index_columns(&node.metadata);
             conn.execute(
-                "INSERT OR REPLACE INTO nodes (id, memory, metadata, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-                rusqlite::params![id, memory, metadata_json, now, now],
+                "INSERT OR REPLACE INTO nodes (id, memory, metadata, created_at, updated_at, owner, scope, state, session, author) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![node.id, memory, metadata_json, now, now, owner, scope, state, session, author_of(&node.metadata)],
             )
-        })
-        .map_err(|e| GraphStoreError::Other(e.to_string()))?;
+        })?;
 
         Ok(())
     }
@@ -96,29 +657,37 @@ impl GraphStore for SqliteGraphStore {
     async fn add_nodes_batch(
         &self,
         nodes: &[MemoryNode],
-        _user_name: Option<&str>,
+        user_name: Option<&str>,
     ) -> Result<(), GraphStoreError> {
         let now = chrono::Utc::now().to_rfc3339();
         self.with_conn(|conn| {
             let tx = conn.unchecked_transaction()?;
             for node in nodes {
-                let metadata_json = serde_json::to_string(&node.metadata)
-                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                let node = MemoryNode {
+                    metadata: with_owner(&node.metadata, user_name),
+                    ..node.clone()
+                };
+                let (memory, metadata_json, embedding) = self.node_columns(&tx, &node)?;
+                let (owner, scope, state, session) = node_index_columns(&node.metadata);
                 tx.execute(
-                    "INSERT OR REPLACE INTO nodes (id, memory, metadata, embedding, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    "INSERT OR REPLACE INTO nodes (id, memory, metadata, embedding, created_at, updated_at, owner, scope, state, session, author) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                     rusqlite::params![
                         node.id,
-                        node.memory,
+                        memory,
                         metadata_json,
-                        node.embedding.as_ref().and_then(|e| serde_json::to_vec(e).ok()),
+                        embedding,
                         now,
                         now,
+                        owner,
+                        scope,
+                        state,
+                        session,
+                        author_of(&node.metadata),
                     ],
                 )?;
             }
             tx.commit()
-        })
-        .map_err(|e| GraphStoreError::Other(e.to_string()))?;
+        })?;
 
         Ok(())
     }
@@ -126,16 +695,19 @@ impl GraphStore for SqliteGraphStore {
     async fn add_edges_batch(
         &self,
         edges: &[MemoryEdge],
-        _user_name: Option<&str>,
+        user_name: Option<&str>,
     ) -> Result<(), GraphStoreError> {
         let now = chrono::Utc::now().to_rfc3339();
         self.with_conn(|conn| {
             let tx = conn.unchecked_transaction()?;
             for edge in edges {
-                let metadata_json = serde_json::to_string(&edge.metadata)
-                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                let edge = MemoryEdge {
+                    metadata: with_owner(&edge.metadata, user_name),
+                    ..edge.clone()
+                };
+                let metadata_json = self.edge_metadata_column(&tx, &edge)?;
                 tx.execute(
-                    "INSERT OR REPLACE INTO edges (id, from_node, to_node, relation, metadata, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    "INSERT OR REPLACE INTO edges (id, from_node, to_node, relation, metadata, created_at, owner, author) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
                     rusqlite::params![
                         edge.id,
                         edge.from,
@@ -143,55 +715,130 @@ impl GraphStore for SqliteGraphStore {
                         edge.relation,
                         metadata_json,
                         now,
+                        owner_of(&edge.metadata),
+                        author_of(&edge.metadata),
                     ],
                 )?;
             }
             tx.commit()
-        })
-        .map_err(|e| GraphStoreError::Other(e.to_string()))?;
+        })?;
 
         Ok(())
     }
 
+    async fn get_edge(
+        &self,
+        id: &str,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError> {
+        self.with_read(|conn| {
+            let mut edges = self.read_edges(
+                conn,
+                "SELECT id, from_node, to_node, relation, metadata FROM edges WHERE id = ?1",
+                [id],
+            )?;
+            Ok(edges
+                .pop()
+                .filter(|e| user_name.is_none_or(|un| owner_of(&e.metadata) == un)))
+        })
+    }
+
+    async fn update_edge(
+        &self,
+        id: &str,
+        relation: Option<&str>,
+        metadata: &HashMap<String, serde_json::Value>,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let Some(mut edge) = self
+                .read_edges(
+                    &tx,
+                    "SELECT id, from_node, to_node, relation, metadata FROM edges WHERE id = ?1",
+                    [id],
+                )?
+                .pop()
+                .filter(|e| user_name.is_none_or(|un| owner_of(&e.metadata) == un))
+            else {
+                return Ok(None);
+            };
+            apply_edge_update(&mut edge, relation, metadata);
+            let metadata_json = self.edge_metadata_column(&tx, &edge)?;
+            tx.execute(
+                "UPDATE edges SET relation = ?1, metadata = ?2 WHERE id = ?3",
+                rusqlite::params![edge.relation, metadata_json, edge.id],
+            )?;
+            tx.commit()?;
+            Ok(Some(edge))
+        })
+    }
+
+    async fn delete_edge(
+        &self,
+        id: &str,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError> {
+        self.with_conn(|conn| {
+            let edge = self
+                .read_edges(
+                    conn,
+                    "SELECT id, from_node, to_node, relation, metadata FROM edges WHERE id = ?1",
+                    [id],
+                )?
+                .pop()
+                .filter(|e| user_name.is_none_or(|un| owner_of(&e.metadata) == un));
+            if edge.is_some() {
+                conn.execute("DELETE FROM edges WHERE id = ?1", [id])?;
+            }
+            Ok(edge)
+        })
+    }
+
+    async fn list_edges(
+        &self,
+        user_name: &str,
+        node_id: Option<&str>,
+        relation: Option<&str>,
+        direction: GraphDirection,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<MemoryEdge>, GraphStoreError> {
+        let node_clause = match direction {
+            GraphDirection::Outbound => "from_node = ?1",
+            GraphDirection::Inbound => "to_node = ?1",
+            GraphDirection::Both => "(from_node = ?1 OR to_node = ?1)",
+        };
+        let sql = format!(
+            "SELECT id, from_node, to_node, relation, metadata FROM edges
+             WHERE owner = ?4 AND (?1 IS NULL OR {}) AND (?2 IS NULL OR relation = ?2)
+               AND (?3 IS NULL OR id > ?3)
+             ORDER BY id LIMIT ?5",
+            node_clause
+        );
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_read(|conn| {
+            self.read_edges(
+                conn,
+                &sql,
+                rusqlite::params![node_id, relation, after, user_name, limit],
+            )
+        })
+    }
+
     async fn get_node(
         &self,
         id: &str,
         _include_embedding: bool,
     ) -> Result<Option<MemoryNode>, GraphStoreError> {
-        let id = id.to_string();
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT id, memory, metadata, embedding, created_at, updated_at FROM nodes WHERE id = ?1",
+        self.with_read(|conn| {
+            let mut nodes = self.read_nodes(
+                conn,
+                "SELECT id, memory, metadata, embedding FROM nodes WHERE id = ?1",
+                [id],
             )?;
-            let result = stmt.query_row([&id], |row| {
-                let metadata_json: String = row.get(2)?;
-                let embedding_blob: Option<Vec<u8>> = row.get(3)?;
-                let embedding = embedding_blob
-                    .and_then(|b| serde_json::from_slice(&b).ok());
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    metadata_json,
-                    embedding,
-                ))
-            });
-
-            match result {
-                Ok((id, memory, metadata_json, embedding)) => {
-                    let metadata: HashMap<String, serde_json::Value> =
-                        serde_json::from_str(&metadata_json).unwrap_or_default();
-                    Ok(Some(MemoryNode {
-                        id,
-                        memory,
-                        metadata,
-                        embedding,
-                    }))
-                }
-                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-                Err(e) => Err(e),
-            }
+            Ok(nodes.pop())
         })
-        .map_err(|e| GraphStoreError::Other(e.to_string()))
     }
 
     async fn get_nodes(
@@ -205,41 +852,15 @@ impl GraphStore for SqliteGraphStore {
 
         let placeholders: Vec<String> = ids.iter().map(|_| "?".to_string()).collect();
         let sql = format!(
-            "SELECT id, memory, metadata, embedding, created_at, updated_at FROM nodes WHERE id IN ({})",
+            "SELECT id, memory, metadata, embedding FROM nodes WHERE id IN ({})",
             placeholders.join(",")
         );
 
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(&sql)?;
+        self.with_read(|conn| {
             let params: Vec<&dyn rusqlite::ToSql> =
                 ids.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
-            let rows = stmt.query_map(params.as_slice(), |row| {
-                let metadata_json: String = row.get(2)?;
-                let embedding_blob: Option<Vec<u8>> = row.get(3)?;
-                let embedding = embedding_blob.and_then(|b| serde_json::from_slice(&b).ok());
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    metadata_json,
-                    embedding,
-                ))
-            })?;
-
-            let mut nodes = Vec::new();
-            for row in rows {
-                let (id, memory, metadata_json, embedding) = row?;
-                let metadata: HashMap<String, serde_json::Value> =
-                    serde_json::from_str(&metadata_json).unwrap_or_default();
-                nodes.push(MemoryNode {
-                    id,
-                    memory,
-                    metadata,
-                    embedding,
-                });
-            }
-            Ok(nodes)
+            self.read_nodes(conn, &sql, params.as_slice())
         })
-        .map_err(|e| GraphStoreError::Other(e.to_string()))
     }
 
     async fn get_neighbors(
@@ -249,112 +870,65 @@ impl GraphStore for SqliteGraphStore {
         direction: GraphDirection,
         limit: usize,
         _include_embedding: bool,
-        _user_name: Option<&str>,
+        user_name: Option<&str>,
     ) -> Result<Vec<GraphNeighbor>, GraphStoreError> {
-        let id = id.to_string();
-        let limit = limit as i64;
-
-        let neighbors = self
-            .with_conn(|conn| {
-                let mut neighbors = Vec::new();
-
-                // Get outbound edges (from -> to)
-                if direction == GraphDirection::Outbound || direction == GraphDirection::Both {
-                    // Always use two-parameter query for simplicity
-                    let sql =
-                        "SELECT e.id, e.from_node, e.to_node, e.relation, e.metadata, e.created_at,
-                         n.id, n.memory, n.metadata, n.embedding, n.created_at, n.updated_at
-                         FROM edges e JOIN nodes n ON e.to_node = n.id
-                         WHERE e.from_node = ?1 AND (e.relation = ?2 OR ?2 IS NULL)";
-
-                    let mut stmt = conn.prepare(sql)?;
-                    let rel_dummy: Option<&str> = relation;
-                    let rows = stmt.query_map(rusqlite::params![&id, rel_dummy], |row| {
-                        // Extract all data from row immediately to avoid lifetime issues
-                        let edge = MemoryEdge {
-                            id: row.get(0)?,
-                            from: row.get(1)?,
-                            to: row.get(2)?,
-                            relation: row.get(3)?,
-                            metadata: serde_json::from_str(
-                                &row.get::<_, String>(4).unwrap_or_default(),
-                            )
-                            .unwrap_or_default(),
-                        };
-                        let node = MemoryNode {
-                            id: row.get(6)?,
-                            memory: row.get(7)?,
-                            metadata: serde_json::from_str(
-                                &row.get::<_, String>(8).unwrap_or_default(),
-                            )
-                            .unwrap_or_default(),
-                            embedding: row
-                                .get::<_, Option<Vec<u8>>>(9)
-                                .ok()
-                                .flatten()
-                                .and_then(|b| serde_json::from_slice(&b).ok()),
-                        };
-                        Ok((edge, node, true))
-                    })?;
-
-                    for row in rows {
-                        let (edge, node, _) = row?;
-                        neighbors.push(GraphNeighbor { edge, node });
-                    }
-                }
+        let mut queries: Vec<&str> = Vec::new();
+        // Outbound edges (from -> to): neighbor is `to_node`.
+        if direction == GraphDirection::Outbound || direction == GraphDirection::Both {
+            queries.push(
+                "SELECT e.id, e.from_node, e.to_node, e.relation, e.metadata,
+                 n.id, n.memory, n.metadata, n.embedding
+                 FROM edges e JOIN nodes n ON e.to_node = n.id
+                 WHERE e.from_node = ?1 AND (e.relation = ?2 OR ?2 IS NULL)
+                   AND (?3 IS NULL OR (e.owner = ?3 AND n.owner = ?3))",
+            );
+        }
+        // Inbound edges (from -> to): neighbor is `from_node`.
+        if direction == GraphDirection::Inbound || direction == GraphDirection::Both {
+            queries.push(
+                "SELECT e.id, e.from_node, e.to_node, e.relation, e.metadata,
+                 n.id, n.memory, n.metadata, n.embedding
+                 FROM edges e JOIN nodes n ON e.from_node = n.id
+                 WHERE e.to_node = ?1 AND (e.relation = ?2 OR ?2 IS NULL)
+                   AND (?3 IS NULL OR (e.owner = ?3 AND n.owner = ?3))",
+            );
+        }
 
-                // Get inbound edges (from -> to)
-                if direction == GraphDirection::Inbound || direction == GraphDirection::Both {
-                    let sql =
-                        "SELECT e.id, e.from_node, e.to_node, e.relation, e.metadata, e.created_at,
-                         n.id, n.memory, n.metadata, n.embedding, n.created_at, n.updated_at
-                         FROM edges e JOIN nodes n ON e.from_node = n.id
-                         WHERE e.to_node = ?1 AND (e.relation = ?2 OR ?2 IS NULL)";
-
-                    let mut stmt = conn.prepare(sql)?;
-                    let rel_dummy: Option<&str> = relation;
-                    let rows = stmt.query_map(rusqlite::params![&id, rel_dummy], |row| {
-                        // Extract all data from row immediately to avoid lifetime issues
-                        let edge = MemoryEdge {
-                            id: row.get(0)?,
-                            from: row.get(1)?,
-                            to: row.get(2)?,
-                            relation: row.get(3)?,
-                            metadata: serde_json::from_str(
-                                &row.get::<_, String>(4).unwrap_or_default(),
-                            )
-                            .unwrap_or_default(),
-                        };
-                        let node = MemoryNode {
-                            id: row.get(6)?,
-                            memory: row.get(7)?,
-                            metadata: serde_json::from_str(
-                                &row.get::<_, String>(8).unwrap_or_default(),
-                            )
-                            .unwrap_or_default(),
-                            embedding: row
-                                .get::<_, Option<Vec<u8>>>(9)
-                                .ok()
-                                .flatten()
-                                .and_then(|b| serde_json::from_slice(&b).ok()),
-                        };
-                        Ok((edge, node, false))
-                    })?;
-
-                    for row in rows {
-                        let (edge, node, _) = row?;
-                        neighbors.push(GraphNeighbor { edge, node });
-                    }
+        let mut neighbors = self.with_read(|conn| {
+            let mut neighbors = Vec::new();
+            for sql in queries {
+                let mut stmt = conn.prepare(sql)?;
+                let rows = stmt.query_map(rusqlite::params![id, relation, user_name], |row| {
+                    Ok((
+                        (
+                            row.get::<_, String>(0)?,
+                            row.get::<_, String>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, String>(3)?,
+                            row.get::<_, String>(4)?,
+                        ),
+                        (
+                            row.get::<_, String>(5)?,
+                            row.get::<_, String>(6)?,
+                            row.get::<_, String>(7)?,
+                            row.get::<_, Option<Vec<u8>>>(8)?,
+                        ),
+                    ))
+                })?;
+                for row in rows {
+                    let ((eid, from, to, rel, emeta), (nid, memory, nmeta, emb)) = row?;
+                    neighbors.push(GraphNeighbor {
+                        edge: self.decode_edge(eid, from, to, rel, emeta)?,
+                        node: self.decode_node(nid, memory, nmeta, emb)?,
+                    });
                 }
-
-                Ok(neighbors)
-            })
-            .map_err(|e| GraphStoreError::Other(e.to_string()))?;
+            }
+            Ok(neighbors)
+        })?;
 
         // Apply limit
-        let mut neighbors = neighbors;
-        if limit > 0 && neighbors.len() > limit as usize {
-            neighbors.truncate(limit as usize);
+        if limit > 0 && neighbors.len() > limit {
+            neighbors.truncate(limit);
         }
 
         Ok(neighbors)
@@ -367,191 +941,392 @@ impl GraphStore for SqliteGraphStore {
         relation: Option<&str>,
         direction: GraphDirection,
         max_depth: usize,
-        _include_deleted: bool,
-        _user_name: Option<&str>,
+        include_deleted: bool,
+        user_name: Option<&str>,
     ) -> Result<Option<GraphPath>, GraphStoreError> {
-        // Simple BFS for shortest path
-        use std::collections::{HashSet, VecDeque};
-
-        let source_id = source_id.to_string();
-        let target_id = target_id.to_string();
-
-        let mut visited: HashSet<String> = HashSet::new();
-        let mut queue: VecDeque<(String, Vec<String>, Vec<MemoryEdge>)> = VecDeque::new();
-        queue.push_back((source_id.clone(), vec![source_id.clone()], vec![]));
-        visited.insert(source_id);
-
-        while let Some((current_id, node_ids, edges)) = queue.pop_front() {
-            if node_ids.len() > max_depth + 1 {
-                continue;
-            }
+        if max_depth == 0 && source_id != target_id {
+            return Ok(None);
+        }
+        if !self.check_path_endpoints(source_id, target_id, include_deleted, user_name)? {
+            return Ok(None);
+        }
+        if source_id == target_id {
+            return Ok(Some(GraphPath {
+                node_ids: vec![source_id.to_string()],
+                edges: Vec::new(),
+            }));
+        }
 
-            if current_id == target_id {
-                return Ok(Some(GraphPath { node_ids, edges }));
-            }
+        // BFS depths of every node within `max_depth` hops. (node, depth) rows are
+        // deduplicated, so the walk is bounded by nodes x depth rather than by path count.
+        let (join, next) = step_clauses(direction);
+        let sql = format!(
+            "WITH RECURSIVE reach(node, depth) AS (
+                 SELECT ?1, 0
+                 UNION
+                 SELECT {next}, r.depth + 1
+                 FROM reach r JOIN edges e ON {join} JOIN nodes n ON n.id = {next}
+                 WHERE r.depth < ?6 AND r.node <> ?2 AND {filter}
+             )
+             SELECT node, MIN(depth) FROM reach GROUP BY node",
+            next = next,
+            join = join,
+            filter = STEP_FILTER,
+        );
+        let (back_join, back_next) = step_clauses(reverse(direction));
+        let back_sql = format!(
+            "SELECT e.id, {prev} FROM edges e JOIN nodes n ON n.id = ?1
+             WHERE {join} AND {filter} ORDER BY e.id",
+            prev = back_next.replace("r.node", "?1"),
+            join = back_join.replace("r.node", "?1"),
+            filter = STEP_FILTER,
+        );
+        let max_depth = i64::try_from(max_depth).unwrap_or(i64::MAX);
+        self.with_read(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let depths: HashMap<String, i64> = stmt
+                .query_map(
+                    rusqlite::params![
+                        source_id,
+                        target_id,
+                        relation,
+                        user_name,
+                        include_deleted,
+                        max_depth
+                    ],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?
+                .collect::<Result<_, _>>()?;
+            let Some(&hops) = depths.get(target_id) else {
+                return Ok(None);
+            };
 
-            // Get neighbors
-            let neighbors = self
-                .get_neighbors(&current_id, relation, direction, 1000, false, None)
-                .await?;
-
-            for neighbor in neighbors {
-                let next_id = neighbor.node.id.clone();
-                if !visited.contains(&next_id) {
-                    visited.insert(next_id.clone());
-                    let mut new_node_ids = node_ids.clone();
-                    new_node_ids.push(next_id.clone());
-                    let mut new_edges = edges.clone();
-                    new_edges.push(neighbor.edge);
-                    queue.push_back((next_id, new_node_ids, new_edges));
-                }
+            // Walk back from the target, one BFS level at a time (smallest edge id first).
+            let mut back = conn.prepare(&back_sql)?;
+            let mut node_ids = vec![target_id.to_string()];
+            let mut edge_ids = Vec::new();
+            let mut current = target_id.to_string();
+            for depth in (0..hops).rev() {
+                let steps: Vec<(String, String)> = back
+                    .query_map(
+                        rusqlite::params![
+                            current,
+                            rusqlite::types::Null,
+                            relation,
+                            user_name,
+                            true
+                        ],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )?
+                    .collect::<Result<_, _>>()?;
+                let (edge_id, prev) = steps
+                    .into_iter()
+                    .find(|(_, prev)| depths.get(prev) == Some(&depth))
+                    .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+                edge_ids.push(edge_id);
+                node_ids.push(prev.clone());
+                current = prev;
             }
-        }
-
-        Ok(None)
+            node_ids.reverse();
+            edge_ids.reverse();
+            let ids: Vec<&str> = edge_ids.iter().map(String::as_str).collect();
+            let mut edges = self.edges_by_ids(conn, &ids)?;
+            let edges = edge_ids
+                .iter()
+                .map(|id| edges.remove(id).ok_or(rusqlite::Error::QueryReturnedNoRows))
+                .collect::<Result<_, _>>()?;
+            Ok(Some(GraphPath { node_ids, edges }))
+        })
     }
 
     async fn find_paths(
         &self,
         source_id: &str,
         target_id: &str,
-        _relation: Option<&str>,
-        _direction: GraphDirection,
+        relation: Option<&str>,
+        direction: GraphDirection,
         max_depth: usize,
         top_k: usize,
-        _include_deleted: bool,
-        _user_name: Option<&str>,
+        include_deleted: bool,
+        user_name: Option<&str>,
     ) -> Result<Vec<GraphPath>, GraphStoreError> {
-        // Simplified implementation: use shortest_path repeatedly
-        let mut paths: Vec<GraphPath> = Vec::new();
+        if top_k == 0 || (max_depth == 0 && source_id != target_id) {
+            return Ok(Vec::new());
+        }
+        if !self.check_path_endpoints(source_id, target_id, include_deleted, user_name)? {
+            return Ok(Vec::new());
+        }
+        if source_id == target_id {
+            return Ok(vec![GraphPath {
+                node_ids: vec![source_id.to_string()],
+                edges: Vec::new(),
+            }]);
+        }
 
-        // For now, just call shortest_path which works
-        if let Some(path) = self
-            .shortest_path(
-                source_id,
-                target_id,
-                None,
-                GraphDirection::Both,
-                max_depth,
-                false,
-                None,
-            )
-            .await?
-        {
-            paths.push(path);
-        }
-
-        // If we need more paths, we'd need a more complex implementation
-        // For SQLite, we just return the first path found
-        if paths.len() < top_k {
-            // Could add more sophisticated path finding here
-        }
-
-        Ok(paths)
+        // `to_target` holds each node's hop distance to the target (a reverse BFS), so the
+        // simple-path walk only extends paths that can still arrive within `max_depth`.
+        // Paths are char(31)-separated id lists; shorter first, then by edge ids. The walk may
+        // run one row past the limit; a leading NULL row reports that it did.
+        let (join, next) = step_clauses(direction);
+        let (back_join, back_next) = step_clauses(reverse(direction));
+        let sql = format!(
+            "WITH RECURSIVE back(node, depth) AS (
+                 SELECT ?2, 0
+                 UNION
+                 SELECT {back_next}, r.depth + 1
+                 FROM back r JOIN edges e ON {back_join} JOIN nodes n ON n.id = {back_next}
+                 WHERE r.depth < ?6 AND {filter}
+             ),
+             to_target(node, dist) AS (SELECT node, MIN(depth) FROM back GROUP BY node),
+             walk(node, depth, nodes, edges) AS (
+                 SELECT ?1, 0, char(31) || ?1 || char(31), char(31)
+                 UNION ALL
+                 SELECT {next}, r.depth + 1, r.nodes || {next} || char(31),
+                        r.edges || e.id || char(31)
+                 FROM walk r JOIN edges e ON {join} JOIN nodes n ON n.id = {next}
+                      JOIN to_target t ON t.node = {next}
+                 WHERE r.node <> ?2 AND r.depth + 1 + t.dist <= ?6
+                   AND instr(r.nodes, char(31) || {next} || char(31)) = 0 AND {filter}
+                 LIMIT {row_limit} + 1
+             )
+             SELECT depth, nodes, edges FROM (
+                 SELECT depth, nodes, edges FROM walk WHERE node = ?2
+                 ORDER BY depth, edges LIMIT ?7
+             )
+             UNION ALL
+             SELECT -1, NULL, NULL WHERE (SELECT COUNT(*) FROM walk) > {row_limit}
+             ORDER BY 1, 3",
+            back_next = back_next,
+            back_join = back_join,
+            next = next,
+            join = join,
+            filter = STEP_FILTER,
+            row_limit = PATH_SEARCH_ROW_LIMIT,
+        );
+        let max_depth = i64::try_from(max_depth).unwrap_or(i64::MAX);
+        let top_k = i64::try_from(top_k).unwrap_or(i64::MAX);
+        self.with_read(|conn| {
+            let mut stmt = conn.prepare(&sql)?;
+            let rows: Vec<(Option<String>, Option<String>)> = stmt
+                .query_map(
+                    rusqlite::params![
+                        source_id,
+                        target_id,
+                        relation,
+                        user_name,
+                        include_deleted,
+                        max_depth,
+                        top_k
+                    ],
+                    |row| Ok((row.get(1)?, row.get(2)?)),
+                )?
+                .collect::<Result<_, _>>()?;
+            if rows.first().is_some_and(|(nodes, _)| nodes.is_none()) {
+                return Err(rusqlite::Error::ToSqlConversionFailure(
+                    format!(
+                        "{} {} partial paths; lower max_depth or filter by relation",
+                        PATH_SEARCH_LIMIT_ERROR, PATH_SEARCH_ROW_LIMIT
+                    )
+                    .into(),
+                ));
+            }
+            let rows: Vec<(String, String)> = rows
+                .into_iter()
+                .filter_map(|(nodes, edges)| nodes.zip(edges))
+                .collect();
+            let split = |list: &str| -> Vec<String> {
+                list.split('\u{1f}')
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            };
+            let paths: Vec<(Vec<String>, Vec<String>)> = rows
+                .iter()
+                .map(|(nodes, edges)| (split(nodes), split(edges)))
+                .collect();
+            let ids: Vec<&str> = paths
+                .iter()
+                .flat_map(|(_, edges)| edges.iter().map(String::as_str))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+            let edges = self.edges_by_ids(conn, &ids)?;
+            paths
+                .into_iter()
+                .map(|(node_ids, edge_ids)| {
+                    let edges = edge_ids
+                        .iter()
+                        .map(|id| {
+                            edges
+                                .get(id)
+                                .cloned()
+                                .ok_or(rusqlite::Error::QueryReturnedNoRows)
+                        })
+                        .collect::<Result<_, _>>()?;
+                    Ok(GraphPath { node_ids, edges })
+                })
+                .collect()
+        })
     }
 
     async fn search_by_embedding(
         &self,
-        _vector: &[f32],
-        _top_k: usize,
-        _user_name: Option<&str>,
+        vector: &[f32],
+        top_k: usize,
+        user_name: Option<&str>,
     ) -> Result<Vec<VecSearchHit>, GraphStoreError> {
-        // SQLite doesn't support vector search natively
-        // For production, use Qdrant or implement approximate nearest neighbor
-        Err(GraphStoreError::Other(
-            "vector search not supported in SQLite backend. Use QdrantVecStore instead."
-                .to_string(),
-        ))
+        // Brute force over the owner's stored embeddings, keeping the best `top_k` in a
+        // min-heap; an empty `user_name` searches every cube like the in-memory store.
+        if top_k == 0 || vector.is_empty() {
+            return Ok(Vec::new());
+        }
+        let owner = user_name.filter(|un| !un.is_empty());
+        let query_norm = dot(vector, vector).sqrt();
+        let mut best: BinaryHeap<Reverse<ScoredId>> = BinaryHeap::with_capacity(top_k + 1);
+        self.with_read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, embedding FROM nodes
+                 WHERE embedding IS NOT NULL AND (?1 IS NULL OR owner = ?1)",
+            )?;
+            let mut rows = stmt.query([owner])?;
+            while let Some(row) = rows.next()? {
+                let id: String = row.get(0)?;
+                let Some(embedding) = self.decode_embedding(&id, row.get(1)?)? else {
+                    continue;
+                };
+                if embedding.len() != vector.len() {
+                    continue;
+                }
+                let norm = dot(&embedding, &embedding).sqrt();
+                let score = if query_norm == 0.0 || norm == 0.0 {
+                    0.0
+                } else {
+                    dot(vector, &embedding) / (query_norm * norm)
+                };
+                best.push(Reverse(ScoredId { score, id }));
+                if best.len() > top_k {
+                    best.pop();
+                }
+            }
+            Ok(())
+        })?;
+        Ok(best
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(hit)| VecSearchHit {
+                id: hit.id,
+                score: hit.score,
+            })
+            .collect())
     }
 
     async fn get_all_memory_items(
         &self,
-        _scope: &str,
-        _user_name: &str,
+        scope: &str,
+        user_name: &str,
         _include_embedding: bool,
     ) -> Result<Vec<MemoryNode>, GraphStoreError> {
-        self.with_conn(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT id, memory, metadata, embedding, created_at, updated_at FROM nodes",
-            )?;
-            let rows = stmt.query_map([], |row| {
-                let metadata_json: String = row.get(2)?;
-                let embedding_blob: Option<Vec<u8>> = row.get(3)?;
-                let embedding = embedding_blob.and_then(|b| serde_json::from_slice(&b).ok());
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    metadata_json,
-                    embedding,
-                ))
-            })?;
+        self.with_read(|conn| {
+            self.read_nodes(
+                conn,
+                "SELECT id, memory, metadata, embedding FROM nodes
+                 WHERE owner = ?1 AND scope = ?2 ORDER BY id",
+                [user_name, scope],
+            )
+        })
+    }
 
-            let mut nodes = Vec::new();
-            for row in rows {
-                let (id, memory, metadata_json, embedding) = row?;
-                let metadata: HashMap<String, serde_json::Value> =
-                    serde_json::from_str(&metadata_json).unwrap_or_default();
-                nodes.push(MemoryNode {
-                    id,
-                    memory,
-                    metadata,
-                    embedding,
-                });
-            }
-            Ok(nodes)
+    async fn latest_in_session(
+        &self,
+        user_name: &str,
+        session_id: &str,
+        exclude_id: &str,
+    ) -> Result<Option<MemoryNode>, GraphStoreError> {
+        if session_id.is_empty() {
+            return Ok(None);
+        }
+        self.with_read(|conn| {
+            Ok(self
+                .read_nodes(
+                    conn,
+                    "SELECT id, memory, metadata, embedding FROM nodes
+                     WHERE owner = ?1 AND session = ?2 AND state != 'tombstone' AND id != ?3
+                     ORDER BY created_at DESC, id DESC LIMIT 1",
+                    [user_name, session_id, exclude_id],
+                )?
+                .pop())
         })
-        .map_err(|e| GraphStoreError::Other(e.to_string()))
     }
 
     async fn update_node(
         &self,
         id: &str,
         fields: &HashMap<String, serde_json::Value>,
-        _user_name: Option<&str>,
+        user_name: Option<&str>,
     ) -> Result<(), GraphStoreError> {
-        let id = id.to_string();
-        let _now = chrono::Utc::now().to_rfc3339();
-
-        // Get existing node
-        let existing = self.get_node(&id, false).await?;
-        let existing =
-            existing.ok_or_else(|| GraphStoreError::Other("node not found".to_string()))?;
-
-        // Merge fields
-        let mut metadata: HashMap<String, serde_json::Value> = existing.metadata;
-        for (k, v) in fields {
-            if k == "memory" {
-                // Handle memory update separately
-                continue;
+        // Updated in place so the embedding and `created_at` survive metadata updates.
+        let updated = self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let Some(mut node) = self
+                .read_nodes(
+                    &tx,
+                    "SELECT id, memory, metadata, embedding FROM nodes WHERE id = ?1",
+                    [id],
+                )?
+                .pop()
+                .filter(|n| user_name.is_none_or(|un| owner_of(&n.metadata) == un))
+            else {
+                return Ok(false);
+            };
+            for (k, v) in fields {
+                if k == "memory" {
+                    node.memory = v.as_str().unwrap_or("").to_string();
+                } else {
+                    node.metadata.insert(k.clone(), v.clone());
+                }
             }
-            metadata.insert(k.clone(), v.clone());
+            let (memory, metadata_json, embedding) = self.node_columns(&tx, &node)?;
+            let (owner, scope, state, session) = node_index_columns(&node.metadata);
+            tx.execute(
+                "UPDATE nodes SET memory = ?1, metadata = ?2, embedding = ?3, updated_at = ?4,
+                 owner = ?5, scope = ?6, state = ?7, session = ?8, author = ?9 WHERE id = ?10",
+                rusqlite::params![
+                    memory,
+                    metadata_json,
+                    embedding,
+                    chrono::Utc::now().to_rfc3339(),
+                    owner,
+                    scope,
+                    state,
+                    session,
+                    author_of(&node.metadata),
+                    id,
+                ],
+            )?;
+            tx.commit()?;
+            Ok(true)
+        })?;
+        if updated {
+            Ok(())
+        } else {
+            Err(GraphStoreError::Other(format!(
+                "node not found or access denied: {}",
+                id
+            )))
         }
-
-        let memory = fields
-            .get("memory")
-            .and_then(|v| v.as_str())
-            .unwrap_or(&existing.memory);
-
-        self.add_node(&id, memory, &metadata, None)
-            .await
-            .map_err(|e| GraphStoreError::Other(e.to_string()))
     }
 
     async fn delete_node(&self, id: &str, _user_name: Option<&str>) -> Result<(), GraphStoreError> {
-        let id = id.to_string();
         self.with_conn(|conn| {
             // Delete edges first
             conn.execute(
                 "DELETE FROM edges WHERE from_node = ?1 OR to_node = ?1",
-                [&id],
+                [id],
             )?;
             // Delete node
-            conn.execute("DELETE FROM nodes WHERE id = ?1", [&id])?;
+            conn.execute("DELETE FROM nodes WHERE id = ?1", [id])?;
             Ok(())
         })
-        .map_err(|e| GraphStoreError::Other(e.to_string()))?;
-
-        Ok(())
     }
 
     async fn delete_edges_by_node(
@@ -559,46 +1334,946 @@ impl GraphStore for SqliteGraphStore {
         id: &str,
         _user_name: Option<&str>,
     ) -> Result<usize, GraphStoreError> {
-        let id = id.to_string();
         self.with_conn(|conn| {
-            let count = conn.execute(
+            conn.execute(
                 "DELETE FROM edges WHERE from_node = ?1 OR to_node = ?1",
-                [&id],
+                [id],
+            )
+        })
+    }
+
+    async fn delete_all_by_user(&self, user_name: &str) -> Result<Vec<String>, GraphStoreError> {
+        self.with_conn(|conn| {
+            let ids = |sql: &str| -> Result<Vec<String>, rusqlite::Error> {
+                let mut stmt = conn.prepare(sql)?;
+                let rows = stmt.query_map([user_name], |row| row.get(0))?;
+                rows.collect()
+            };
+            let node_ids = ids("SELECT id FROM nodes WHERE owner = ?1 ORDER BY id")?;
+            let edge_ids = ids("SELECT id FROM edges WHERE owner = ?1")?;
+
+            let tx = conn.unchecked_transaction()?;
+            for id in &edge_ids {
+                tx.execute("DELETE FROM edges WHERE id = ?1", [id])?;
+            }
+            for id in &node_ids {
+                tx.execute(
+                    "DELETE FROM edges WHERE from_node = ?1 OR to_node = ?1",
+                    [id],
+                )?;
+                tx.execute("DELETE FROM nodes WHERE id = ?1", [id])?;
+            }
+            // Crypto-shred: without its data keys, any copy of the cube's rows (backups,
+            // free pages) stays unreadable.
+            let key_ids = self
+                .keys
+                .as_ref()
+                .map(|k| k.key_ids_for_cube(user_name))
+                .unwrap_or_default();
+            for id in &key_ids {
+                tx.execute("DELETE FROM data_keys WHERE id = ?1", [id])?;
+            }
+            tx.commit()?;
+            if let Some(ref keys) = self.keys {
+                self.retire_keys(keys, &key_ids);
+            }
+            Ok(node_ids)
+        })
+    }
+
+    async fn author_cubes(&self, author: &str) -> Result<Vec<String>, GraphStoreError> {
+        self.with_read(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT owner FROM nodes WHERE author = ?1
+                 UNION SELECT owner FROM edges WHERE author = ?1 ORDER BY 1",
+            )?;
+            let rows = stmt.query_map([author], |row| row.get(0))?;
+            rows.collect()
+        })
+    }
+
+    async fn delete_by_author(
+        &self,
+        user_name: &str,
+        author: &str,
+    ) -> Result<Vec<String>, GraphStoreError> {
+        // The cube keeps its data keys: other users' rows are still encrypted with them.
+        self.with_conn(|conn| {
+            let node_ids: Vec<String> = {
+                let mut stmt = conn
+                    .prepare("SELECT id FROM nodes WHERE owner = ?1 AND author = ?2 ORDER BY id")?;
+                let rows = stmt.query_map([user_name, author], |row| row.get(0))?;
+                rows.collect::<Result<_, _>>()?
+            };
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "DELETE FROM edges WHERE owner = ?1 AND author = ?2",
+                [user_name, author],
             )?;
-            Ok(count)
+            for id in &node_ids {
+                tx.execute(
+                    "DELETE FROM edges WHERE from_node = ?1 OR to_node = ?1",
+                    [id],
+                )?;
+                tx.execute("DELETE FROM nodes WHERE id = ?1", [id])?;
+            }
+            tx.commit()?;
+            Ok(node_ids)
         })
+    }
+}
+
+/// Owner (cube) of a row, from the `user_name` metadata key.
+fn owner_of(metadata: &HashMap<String, serde_json::Value>) -> &str {
+    metadata
+        .get("user_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+/// Extracted `(owner, scope, state, session)` columns of a node, with the in-memory store
+/// defaults.
+fn node_index_columns(metadata: &HashMap<String, serde_json::Value>) -> (&str, &str, &str, &str) {
+    let text = |key: &str, default: &'static str| {
+        metadata
+            .get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or(default)
+    };
+    (
+        owner_of(metadata),
+        text("scope", "LongTermMemory"),
+        text("state", "active"),
+        text("session_id", ""),
+    )
+}
+
+/// Width of the accumulators in `dot`; wide enough for the compiler to emit SIMD adds.
+const LANES: usize = 8;
+
+/// Dot product accumulated in `LANES` independent f32 lanes (vectorizable), summed in f64.
+fn dot(a: &[f32], b: &[f32]) -> f64 {
+    let mut acc = [0f32; LANES];
+    let chunks = a.len() / LANES * LANES;
+    for (x, y) in a[..chunks]
+        .chunks_exact(LANES)
+        .zip(b[..chunks].chunks_exact(LANES))
+    {
+        for lane in 0..LANES {
+            acc[lane] += x[lane] * y[lane];
+        }
+    }
+    let tail: f64 = a[chunks..]
+        .iter()
+        .zip(&b[chunks..])
+        .map(|(x, y)| f64::from(*x) * f64::from(*y))
+        .sum();
+    acc.iter().map(|v| f64::from(*v)).sum::<f64>() + tail
+}
+
+/// Search hit ordered by score, then by id (smaller ids win ties).
+#[derive(PartialEq)]
+struct ScoredId {
+    score: f64,
+    id: String,
+}
+
+impl Eq for ScoredId {}
+
+impl PartialOrd for ScoredId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.id.cmp(&self.id))
+    }
+}
+
+/// Traversal filter on edge `e` reaching node `n`, shared by the path queries:
+/// ?3 relation, ?4 owner, ?5 include_deleted.
+const STEP_FILTER: &str = "(?3 IS NULL OR e.relation = ?3)
+    AND (?4 IS NULL OR (e.owner = ?4 AND n.owner = ?4))
+    AND (?5 OR n.state <> 'tombstone')";
+
+/// Join condition and neighbor expression for one step from `r.node` along edge `e`.
+fn step_clauses(direction: GraphDirection) -> (&'static str, &'static str) {
+    match direction {
+        GraphDirection::Outbound => ("e.from_node = r.node", "e.to_node"),
+        GraphDirection::Inbound => ("e.to_node = r.node", "e.from_node"),
+        GraphDirection::Both => (
+            "(e.from_node = r.node OR e.to_node = r.node)",
+            "(CASE WHEN e.from_node = r.node THEN e.to_node ELSE e.from_node END)",
+        ),
+    }
+}
+
+fn reverse(direction: GraphDirection) -> GraphDirection {
+    match direction {
+        GraphDirection::Outbound => GraphDirection::Inbound,
+        GraphDirection::Inbound => GraphDirection::Outbound,
+        GraphDirection::Both => GraphDirection::Both,
+    }
+}
+
+/// Open a connection in WAL mode that waits on locks instead of failing with `SQLITE_BUSY`.
+fn open_connection(path: &Path) -> Result<rusqlite::Connection, rusqlite::Error> {
+    let conn = rusqlite::Connection::open(path)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.query_row("PRAGMA journal_mode = WAL", [], |row| {
+        row.get::<_, String>(0)
+    })?;
+    conn.execute_batch("PRAGMA synchronous = NORMAL;")?;
+    Ok(conn)
+}
+
+fn open_readers(
+    path: &Path,
+    count: usize,
+) -> Result<Vec<std::sync::Mutex<rusqlite::Connection>>, GraphStoreError> {
+    // Every connection to an in-memory database is a separate database.
+    if path.as_os_str().is_empty() || path == Path::new(":memory:") {
+        return Ok(Vec::new());
+    }
+    (0..count)
+        .map(|_| {
+            let conn = open_connection(path)?;
+            conn.execute_batch("PRAGMA query_only = ON;")?;
+            Ok(std::sync::Mutex::new(conn))
+        })
+        .collect::<Result<_, rusqlite::Error>>()
         .map_err(|e| GraphStoreError::Other(e.to_string()))
+}
+
+/// Add a `TEXT NOT NULL DEFAULT ''` column unless it exists; returns whether it was added.
+fn add_column_if_missing(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+) -> Result<bool, rusqlite::Error> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    if !exists {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} TEXT NOT NULL DEFAULT ''",
+            table, column
+        ))?;
     }
+    Ok(!exists)
 }
 
-#[allow(dead_code)]
-fn parse_edge_row(row: &rusqlite::Row, offset: usize) -> Result<MemoryEdge, rusqlite::Error> {
-    let metadata_json: String = row.get(offset + 4).unwrap_or_default();
-    let metadata: HashMap<String, serde_json::Value> =
-        serde_json::from_str(&metadata_json).unwrap_or_default();
-
-    Ok(MemoryEdge {
-        id: row.get(offset)?,
-        from: row.get(offset + 1)?,
-        to: row.get(offset + 2)?,
-        relation: row.get(offset + 3)?,
-        metadata,
-    })
+/// Stamp the owner into metadata (same key the in-memory store uses).
+fn with_owner(
+    metadata: &HashMap<String, serde_json::Value>,
+    user_name: Option<&str>,
+) -> HashMap<String, serde_json::Value> {
+    let mut meta = metadata.clone();
+    if let Some(un) = user_name {
+        meta.insert(
+            "user_name".to_string(),
+            serde_json::Value::String(un.to_string()),
+        );
+    }
+    meta
 }
 
-#[allow(dead_code)]
-fn parse_node_row(row: &rusqlite::Row, offset: usize) -> Result<MemoryNode, rusqlite::Error> {
-    let metadata_json: String = row.get(offset + 2).unwrap_or_default();
-    let embedding_blob: Option<Vec<u8>> = row.get(offset + 3).ok();
-    let embedding = embedding_blob.and_then(|b| serde_json::from_slice(&b).ok());
-
-    let metadata: HashMap<String, serde_json::Value> =
-        serde_json::from_str(&metadata_json).unwrap_or_default();
-
-    Ok(MemoryNode {
-        id: row.get(offset)?,
-        memory: row.get(offset + 1)?,
-        metadata,
-        embedding,
-    })
+fn load_key_records(conn: &rusqlite::Connection) -> Result<Vec<WrappedKeyRecord>, rusqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT id, cube_id, wrapped_key, master_key_id FROM data_keys ORDER BY id")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(WrappedKeyRecord {
+            key_id: row.get(0)?,
+            cube_id: row.get(1)?,
+            wrapped: row.get(2)?,
+            master_key_id: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn crypto_err(e: EncryptionError) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(e))
+}
+
+fn key_error(e: EncryptionError) -> GraphStoreError {
+    GraphStoreError::Other(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(byte: u8) -> EncryptionConfig {
+        EncryptionConfig {
+            master_key: MasterKey::from_bytes([byte; 32]),
+            encrypt_vectors: true,
+            allow_plaintext: false,
+        }
+    }
+
+    fn raw_memory(path: &Path, id: &str) -> String {
+        let conn = rusqlite::Connection::open(path).unwrap();
+        conn.query_row("SELECT memory FROM nodes WHERE id = ?1", [id], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn encrypted_store_seals_columns_and_survives_rotation() {
+        let path = std::env::temp_dir().join(format!("memos-enc-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        store
+            .add_node("n1", "secret text", &HashMap::new(), Some("u1"))
+            .await
+            .unwrap();
+        assert!(raw_memory(&path, "n1").starts_with("enc:"));
+
+        assert_eq!(
+            store
+                .rotate_master_key(MasterKey::from_bytes([2; 32]))
+                .unwrap(),
+            1
+        );
+        assert_eq!(store.rotate_data_key("u1").unwrap(), 1);
+        drop(store);
+
+        assert!(SqliteGraphStore::with_encryption(&path, config(1)).is_err());
+        let store = SqliteGraphStore::with_encryption(&path, config(2)).unwrap();
+        let node = store.get_node("n1", false).await.unwrap().unwrap();
+        assert_eq!(node.memory, "secret text");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn plaintext_rows_are_read_only_while_migrating() {
+        let path = std::env::temp_dir().join(format!("memos-enc-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::new(&path).unwrap();
+        store
+            .add_node(
+                "n1",
+                "written before encryption",
+                &HashMap::new(),
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        drop(store);
+
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        assert!(store.get_node("n1", false).await.is_err());
+        drop(store);
+        let migrating = EncryptionConfig {
+            allow_plaintext: true,
+            ..config(1)
+        };
+        let store = SqliteGraphStore::with_encryption(&path, migrating).unwrap();
+        assert_eq!(store.rotate_data_key("u1").unwrap(), 1);
+        drop(store);
+
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        assert!(raw_memory(&path, "n1").starts_with("enc:"));
+        let node = store.get_node("n1", false).await.unwrap().unwrap();
+        assert_eq!(node.memory, "written before encryption");
+        remove_db(&path);
+    }
+
+    #[tokio::test]
+    async fn data_key_of_a_rolled_back_write_is_not_reused() {
+        let path = std::env::temp_dir().join(format!("memos-enc-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        let trigger = rusqlite::Connection::open(&path).unwrap();
+        trigger
+            .execute_batch(
+                "CREATE TRIGGER fail BEFORE INSERT ON nodes WHEN NEW.id = 'boom'
+                 BEGIN SELECT RAISE(ABORT, 'forced'); END;",
+            )
+            .unwrap();
+        // The first write for the cube creates its data key, then rolls back.
+        let boom = MemoryNode {
+            id: "boom".to_string(),
+            memory: "lost".to_string(),
+            metadata: HashMap::new(),
+            embedding: None,
+        };
+        assert!(store.add_nodes_batch(&[boom], Some("c1")).await.is_err());
+        assert!(store
+            .keys
+            .as_ref()
+            .unwrap()
+            .key_ids_for_cube("c1")
+            .is_empty());
+        trigger.execute_batch("DROP TRIGGER fail;").unwrap();
+        store
+            .add_node("ok", "kept text", &HashMap::new(), Some("c1"))
+            .await
+            .unwrap();
+        drop(store);
+
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        let node = store.get_node("ok", false).await.unwrap().unwrap();
+        assert_eq!(node.memory, "kept text");
+        remove_db(&path);
+    }
+
+    #[tokio::test]
+    async fn data_key_rotation_keeps_old_keys_until_running_reads_finish() {
+        let path = std::env::temp_dir().join(format!("memos-enc-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        store
+            .add_node("n1", "secret text", &HashMap::new(), Some("u1"))
+            .await
+            .unwrap();
+        let keys = store.keys.as_ref().unwrap();
+        let old = keys.key_ids_for_cube("u1");
+
+        // A read holding a pooled connection may still be decoding rows sealed with the old key.
+        let reader = store.readers[0].lock().unwrap();
+        std::thread::scope(|scope| {
+            let rotation = scope.spawn(|| store.rotate_data_key("u1").unwrap());
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while keys.key_ids_for_cube("u1").len() == old.len() {
+                assert!(
+                    std::time::Instant::now() < deadline,
+                    "new key not in the ring"
+                );
+                std::thread::yield_now();
+            }
+            // The new key is usable before its rows commit; the old one outlives the read.
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            assert!(keys.key_ids_for_cube("u1").starts_with(&old));
+            drop(reader);
+            assert_eq!(rotation.join().unwrap(), 1);
+        });
+        let new = keys.key_ids_for_cube("u1");
+        assert_eq!(new.len(), 1);
+        assert!(!old.contains(&new[0]));
+        let node = store.get_node("n1", false).await.unwrap().unwrap();
+        assert_eq!(node.memory, "secret text");
+        remove_db(&path);
+    }
+
+    #[tokio::test]
+    async fn latest_in_session_skips_other_owners_and_tombstones() {
+        let path = std::env::temp_dir().join(format!("memos-session-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::new(&path).unwrap();
+        let session = |s: &str| HashMap::from([("session_id".to_string(), serde_json::json!(s))]);
+        for (id, owner, s) in [
+            ("a", "u1", "s1"),
+            ("b", "u1", "s1"),
+            ("c", "u1", "s2"),
+            ("d", "u2", "s1"),
+        ] {
+            store
+                .add_node(id, id, &session(s), Some(owner))
+                .await
+                .unwrap();
+        }
+        let latest = |exclude: &'static str| {
+            let store = &store;
+            async move {
+                store
+                    .latest_in_session("u1", "s1", exclude)
+                    .await
+                    .unwrap()
+                    .map(|n| n.id)
+            }
+        };
+        assert_eq!(latest("").await.as_deref(), Some("b"));
+        assert_eq!(latest("b").await.as_deref(), Some("a"));
+        let tombstone = HashMap::from([("state".to_string(), serde_json::json!("tombstone"))]);
+        store.update_node("b", &tombstone, None).await.unwrap();
+        assert_eq!(latest("").await.as_deref(), Some("a"));
+        assert!(store
+            .latest_in_session("u1", "", "")
+            .await
+            .unwrap()
+            .is_none());
+        remove_db(&path);
+    }
+
+    #[tokio::test]
+    async fn edge_updates_are_resealed_and_listed_per_owner() {
+        let path = std::env::temp_dir().join(format!("memos-edge-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        for id in ["a", "b"] {
+            store
+                .add_node(id, id, &HashMap::new(), Some("u1"))
+                .await
+                .unwrap();
+        }
+        store
+            .add_edges_batch(
+                &[MemoryEdge {
+                    id: "e1".to_string(),
+                    from: "a".to_string(),
+                    to: "b".to_string(),
+                    relation: "knows".to_string(),
+                    metadata: HashMap::new(),
+                }],
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+
+        let metadata = HashMap::from([("weight".to_string(), serde_json::json!(0.5))]);
+        assert!(store
+            .update_edge("e1", None, &metadata, Some("u2"))
+            .await
+            .unwrap()
+            .is_none());
+        store
+            .update_edge("e1", Some("likes"), &metadata, Some("u1"))
+            .await
+            .unwrap()
+            .unwrap();
+        let edge = store.get_edge("e1", Some("u1")).await.unwrap().unwrap();
+        assert_eq!(edge.relation, "likes");
+        assert_eq!(edge.metadata["weight"], 0.5);
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let raw: String = conn
+            .query_row("SELECT metadata FROM edges WHERE id = 'e1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(raw.starts_with("enc:"));
+
+        let listed = store
+            .list_edges(
+                "u1",
+                Some("b"),
+                Some("likes"),
+                GraphDirection::Inbound,
+                None,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(store
+            .list_edges("u2", None, None, GraphDirection::Both, None, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(store.delete_edge("e1", Some("u1")).await.unwrap().is_some());
+        assert!(store.get_edge("e1", None).await.unwrap().is_none());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    fn edge(id: &str, from: &str, to: &str, relation: &str) -> MemoryEdge {
+        MemoryEdge {
+            id: id.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            relation: relation.to_string(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn remove_db(path: &Path) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut file = path.as_os_str().to_owned();
+            file.push(suffix);
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    #[tokio::test]
+    async fn paths_and_scopes_are_queried_through_indexed_columns() {
+        let db = std::env::temp_dir().join(format!("memos-cte-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::new(&db).unwrap();
+        let working = HashMap::from([("scope".to_string(), serde_json::json!("WorkingMemory"))]);
+        for id in ["a", "b", "c", "d"] {
+            store
+                .add_node(id, id, &HashMap::new(), Some("u1"))
+                .await
+                .unwrap();
+        }
+        store
+            .add_node("w", "w", &working, Some("u1"))
+            .await
+            .unwrap();
+        store
+            .add_node("x", "x", &HashMap::new(), Some("u2"))
+            .await
+            .unwrap();
+        store
+            .add_edges_batch(
+                &[
+                    edge("e1", "a", "b", "next"),
+                    edge("e2", "b", "d", "next"),
+                    edge("e3", "a", "c", "next"),
+                    edge("e4", "c", "d", "next"),
+                    edge("e5", "d", "a", "back"),
+                    edge("e6", "a", "w", "next"),
+                    edge("e7", "w", "d", "next"),
+                ],
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        store
+            .add_edges_batch(&[edge("e8", "a", "x", "next")], Some("u2"))
+            .await
+            .unwrap();
+
+        let path = store
+            .shortest_path(
+                "a",
+                "d",
+                None,
+                GraphDirection::Outbound,
+                6,
+                false,
+                Some("u1"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.node_ids, ["a", "b", "d"]);
+        assert_eq!(path.edges[1].id, "e2");
+        // Inbound follows edges backwards; `back` goes straight from d to a.
+        let path = store
+            .shortest_path(
+                "d",
+                "a",
+                None,
+                GraphDirection::Inbound,
+                6,
+                false,
+                Some("u1"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.node_ids.len(), 3);
+        let path = store
+            .shortest_path(
+                "d",
+                "a",
+                Some("back"),
+                GraphDirection::Both,
+                6,
+                false,
+                Some("u1"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.edges[0].id, "e5");
+        assert!(store
+            .shortest_path(
+                "a",
+                "d",
+                None,
+                GraphDirection::Outbound,
+                1,
+                false,
+                Some("u1")
+            )
+            .await
+            .unwrap()
+            .is_none());
+        assert!(store
+            .shortest_path(
+                "a",
+                "x",
+                None,
+                GraphDirection::Outbound,
+                6,
+                false,
+                Some("u1")
+            )
+            .await
+            .is_err());
+
+        let paths = store
+            .find_paths(
+                "a",
+                "d",
+                None,
+                GraphDirection::Outbound,
+                6,
+                5,
+                false,
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        let routes: Vec<String> = paths.iter().map(|p| p.node_ids.concat()).collect();
+        assert_eq!(routes, ["abd", "acd", "awd"]);
+
+        // Tombstoned nodes are skipped unless deleted ones are included.
+        let tombstone = HashMap::from([("state".to_string(), serde_json::json!("tombstone"))]);
+        store.update_node("b", &tombstone, None).await.unwrap();
+        let paths = store
+            .find_paths(
+                "a",
+                "d",
+                None,
+                GraphDirection::Outbound,
+                6,
+                5,
+                false,
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        let paths = store
+            .find_paths(
+                "a",
+                "d",
+                None,
+                GraphDirection::Outbound,
+                6,
+                5,
+                true,
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 3);
+
+        let ids = |nodes: Vec<MemoryNode>| nodes.into_iter().map(|n| n.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(store
+                .get_all_memory_items("LongTermMemory", "u1", false)
+                .await
+                .unwrap()),
+            ["a", "b", "c", "d"]
+        );
+        assert_eq!(
+            ids(store
+                .get_all_memory_items("WorkingMemory", "u1", false)
+                .await
+                .unwrap()),
+            ["w"]
+        );
+        assert_eq!(store.delete_all_by_user("u2").await.unwrap(), ["x"]);
+        drop(store);
+        remove_db(&db);
+    }
+
+    #[tokio::test]
+    async fn find_paths_reports_hitting_the_row_limit() {
+        let db = std::env::temp_dir().join(format!("memos-limit-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::new(&db).unwrap();
+        // A complete graph on 14 nodes has well over 100_000 simple paths of up to 6 hops.
+        let ids: Vec<String> = (0..14).map(|i| format!("n{:02}", i)).collect();
+        for id in &ids {
+            store
+                .add_node(id, id, &HashMap::new(), Some("u1"))
+                .await
+                .unwrap();
+        }
+        let mut edges = Vec::new();
+        for from in &ids {
+            for to in ids.iter().filter(|to| *to != from) {
+                edges.push(edge(&format!("{}-{}", from, to), from, to, "next"));
+            }
+        }
+        store.add_edges_batch(&edges, Some("u1")).await.unwrap();
+
+        let search = |max_depth| {
+            store.find_paths(
+                "n00",
+                "n13",
+                None,
+                GraphDirection::Outbound,
+                max_depth,
+                3,
+                false,
+                Some("u1"),
+            )
+        };
+        let paths = search(3).await.unwrap();
+        assert_eq!(paths.len(), 3);
+        assert_eq!(paths[0].node_ids, ["n00", "n13"]);
+        let err = search(6).await.unwrap_err().to_string();
+        assert!(err.contains(PATH_SEARCH_LIMIT_ERROR), "{}", err);
+        drop(store);
+        remove_db(&db);
+    }
+
+    #[tokio::test]
+    async fn databases_without_index_columns_are_backfilled() {
+        let path = std::env::temp_dir().join(format!("memos-mig-{}.db", uuid::Uuid::new_v4()));
+        {
+            let conn = rusqlite::Connection::open(&path).unwrap();
+            conn.execute_batch(
+                r#"
+                CREATE TABLE nodes (id TEXT PRIMARY KEY, memory TEXT NOT NULL,
+                    metadata TEXT NOT NULL, embedding BLOB, created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL);
+                CREATE TABLE edges (id TEXT PRIMARY KEY, from_node TEXT NOT NULL,
+                    to_node TEXT NOT NULL, relation TEXT NOT NULL, metadata TEXT NOT NULL,
+                    created_at TEXT NOT NULL);
+                CREATE INDEX idx_nodes_user ON nodes(metadata);
+                INSERT INTO nodes VALUES ('a', 'A', '{"user_name":"u1","scope":"UserMemory"}',
+                    NULL, '', '');
+                INSERT INTO nodes VALUES ('b', 'B', '{"user_name":"u1"}', NULL, '', '');
+                INSERT INTO edges VALUES ('e1', 'a', 'b', 'r', '{"user_name":"u1"}', '');
+                "#,
+            )
+            .unwrap();
+        }
+        let store = SqliteGraphStore::new(&path)
+            .unwrap()
+            .with_read_connections(1)
+            .unwrap();
+        let items = store
+            .get_all_memory_items("UserMemory", "u1", false)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "a");
+        let path_ab = store
+            .shortest_path(
+                "a",
+                "b",
+                None,
+                GraphDirection::Outbound,
+                3,
+                false,
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        assert!(path_ab.is_some());
+        assert_eq!(
+            store
+                .list_edges("u1", None, None, GraphDirection::Both, None, 10)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        // Rows written before authors were recorded belong to the user the cube is named after.
+        assert_eq!(store.author_cubes("u1").await.unwrap(), ["u1"]);
+        drop(store);
+        remove_db(&path);
+    }
+
+    #[tokio::test]
+    async fn delete_by_author_keeps_other_authors_and_the_cube_key() {
+        let path = std::env::temp_dir().join(format!("memos-author-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        let by =
+            |author: &str| HashMap::from([("author_id".to_string(), serde_json::json!(author))]);
+        for (id, author) in [("a", "x"), ("b", "y"), ("c", "y")] {
+            store
+                .add_node(id, id, &by(author), Some("team"))
+                .await
+                .unwrap();
+        }
+        let edge = |id: &str, from: &str, to: &str, author: &str| MemoryEdge {
+            id: id.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            relation: "r".to_string(),
+            metadata: by(author),
+        };
+        store
+            .add_edges_batch(
+                &[
+                    edge("x_bc", "b", "c", "x"),
+                    edge("y_ab", "a", "b", "y"),
+                    edge("y_bc", "b", "c", "y"),
+                ],
+                Some("team"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(store.author_cubes("x").await.unwrap(), ["team"]);
+
+        assert_eq!(store.delete_by_author("team", "x").await.unwrap(), ["a"]);
+        let edges = store
+            .list_edges("team", None, None, GraphDirection::Both, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            edges.into_iter().map(|e| e.id).collect::<Vec<_>>(),
+            ["y_bc"]
+        );
+        assert!(store.author_cubes("x").await.unwrap().is_empty());
+        drop(store);
+
+        let store = SqliteGraphStore::with_encryption(&path, config(1)).unwrap();
+        let node = store.get_node("b", false).await.unwrap().unwrap();
+        assert_eq!(node.memory, "b");
+        remove_db(&path);
+    }
+
+    #[tokio::test]
+    async fn embeddings_are_searchable_and_survive_updates() {
+        let db = std::env::temp_dir().join(format!("memos-knn-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteGraphStore::with_encryption(&db, config(1)).unwrap();
+        let node = |id: &str, embedding: Vec<f32>| MemoryNode {
+            id: id.to_string(),
+            memory: id.to_string(),
+            metadata: HashMap::new(),
+            embedding: Some(embedding),
+        };
+        // 9 dimensions: one full SIMD chunk plus a tail.
+        let mut axis = vec![0.0f32; 9];
+        axis[8] = 1.0;
+        store
+            .add_nodes_batch(
+                &[
+                    node("tail", axis.clone()),
+                    node("ones", vec![1.0; 9]),
+                    node("zero", vec![0.0; 9]),
+                    node("short", vec![1.0; 3]),
+                ],
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        store
+            .add_nodes_batch(&[node("other", axis.clone())], Some("u2"))
+            .await
+            .unwrap();
+
+        let hits = store
+            .search_by_embedding(&axis, 10, Some("u1"))
+            .await
+            .unwrap();
+        let ids: Vec<&str> = hits.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["tail", "ones", "zero"]);
+        assert!((hits[0].score - 1.0).abs() < 1e-6);
+        assert!((hits[1].score - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(
+            store
+                .search_by_embedding(&axis, 1, Some(""))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let fields = HashMap::from([("access_count".to_string(), serde_json::json!(1))]);
+        store
+            .update_node("tail", &fields, Some("u1"))
+            .await
+            .unwrap();
+        assert!(store
+            .update_node("tail", &fields, Some("u2"))
+            .await
+            .is_err());
+        let hits = store
+            .search_by_embedding(&axis, 1, Some("u1"))
+            .await
+            .unwrap();
+        assert_eq!(hits[0].id, "tail");
+        drop(store);
+        remove_db(&db);
+    }
 }
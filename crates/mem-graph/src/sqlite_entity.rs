@@ -0,0 +1,412 @@
+//! SQLite-backed entity store: entities, name variants, attributes, memory associations and
+//! relations in separate tables, written per entity.
+
+use async_trait::async_trait;
+use mem_types::{
+    Entity, EntityKgSnapshot, EntityMetadata, EntityRelationType, EntityStore, EntityStoreError,
+    EntityType, StoredRelation, Validity,
+};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// SQLite-backed entity store for persistence across restarts.
+pub struct SqliteEntityStore {
+    conn: std::sync::Mutex<rusqlite::Connection>,
+}
+
+impl SqliteEntityStore {
+    /// Open (or create) the entity store at the given path.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, EntityStoreError> {
+        let conn = rusqlite::Connection::open(path).map_err(store_err)?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS entities (
+                id TEXT PRIMARY KEY,
+                cube_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                entity_type TEXT NOT NULL,
+                description TEXT,
+                metadata TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                attribute_history TEXT
+            );
+
+            CREATE TABLE IF NOT EXISTS entity_variants (
+                entity_id TEXT NOT NULL,
+                variant TEXT NOT NULL,
+                PRIMARY KEY (entity_id, variant)
+            );
+
+            CREATE TABLE IF NOT EXISTS entity_attributes (
+                entity_id TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (entity_id, key)
+            );
+
+            CREATE TABLE IF NOT EXISTS entity_memories (
+                entity_id TEXT NOT NULL,
+                memory_id TEXT NOT NULL,
+                PRIMARY KEY (entity_id, memory_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS entity_relations (
+                source_id TEXT NOT NULL,
+                target_id TEXT NOT NULL,
+                relation_type TEXT NOT NULL,
+                validity TEXT,
+                PRIMARY KEY (source_id, target_id, relation_type)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_entities_cube ON entities(cube_id);
+            CREATE INDEX IF NOT EXISTS idx_entity_memories_memory ON entity_memories(memory_id);
+            CREATE INDEX IF NOT EXISTS idx_entity_relations_target ON entity_relations(target_id);
+            "#,
+        )
+        .map_err(store_err)?;
+        // Stores created before temporal validity lack these columns.
+        add_column_if_missing(&conn, "entities", "attribute_history", "TEXT")?;
+        add_column_if_missing(&conn, "entity_relations", "validity", "TEXT")?;
+        Ok(Self {
+            conn: std::sync::Mutex::new(conn),
+        })
+    }
+
+    fn with_conn<T, F>(&self, f: F) -> Result<T, EntityStoreError>
+    where
+        F: FnOnce(&rusqlite::Connection) -> Result<T, rusqlite::Error>,
+    {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|e| EntityStoreError::Other(format!("failed to acquire lock: {}", e)))?;
+        f(&conn).map_err(store_err)
+    }
+}
+
+#[async_trait]
+impl EntityStore for SqliteEntityStore {
+    async fn upsert_entity(
+        &self,
+        entity: &Entity,
+        relations: &[StoredRelation],
+    ) -> Result<(), EntityStoreError> {
+        let entity_type = to_json(&entity.entity_type)?;
+        let metadata = to_json(&entity.metadata)?;
+        let attributes = entity
+            .attributes
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), to_json(v)?)))
+            .collect::<Result<Vec<_>, EntityStoreError>>()?;
+        let attribute_history = to_json(&entity.attribute_history)?;
+        let relations = relations
+            .iter()
+            .map(|r| {
+                Ok((
+                    r.target_id.clone(),
+                    to_json(&r.relation_type)?,
+                    to_json(&r.validity)?,
+                ))
+            })
+            .collect::<Result<Vec<_>, EntityStoreError>>()?;
+
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT OR REPLACE INTO entities (id, cube_id, name, entity_type, description, metadata, version, attribute_history) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                rusqlite::params![
+                    entity.id,
+                    entity.cube_id,
+                    entity.name,
+                    entity_type,
+                    entity.description,
+                    metadata,
+                    entity.version,
+                    attribute_history
+                ],
+            )?;
+            for table in ["entity_variants", "entity_attributes", "entity_memories"] {
+                tx.execute(
+                    &format!("DELETE FROM {} WHERE entity_id = ?1", table),
+                    [&entity.id],
+                )?;
+            }
+            tx.execute(
+                "DELETE FROM entity_relations WHERE source_id = ?1",
+                [&entity.id],
+            )?;
+            for variant in &entity.name_variants {
+                tx.execute(
+                    "INSERT OR IGNORE INTO entity_variants (entity_id, variant) VALUES (?1, ?2)",
+                    [&entity.id, variant],
+                )?;
+            }
+            for (key, value) in &attributes {
+                tx.execute(
+                    "INSERT INTO entity_attributes (entity_id, key, value) VALUES (?1, ?2, ?3)",
+                    [&entity.id, key, value],
+                )?;
+            }
+            for memory_id in &entity.memory_ids {
+                tx.execute(
+                    "INSERT OR IGNORE INTO entity_memories (entity_id, memory_id) VALUES (?1, ?2)",
+                    [&entity.id, memory_id],
+                )?;
+            }
+            for (target_id, relation_type, validity) in &relations {
+                tx.execute(
+                    "INSERT OR IGNORE INTO entity_relations (source_id, target_id, relation_type, validity) VALUES (?1, ?2, ?3, ?4)",
+                    [&entity.id, target_id, relation_type, validity],
+                )?;
+            }
+            tx.commit()
+        })
+    }
+
+    async fn delete_entity(&self, entity_id: &str) -> Result<(), EntityStoreError> {
+        self.with_conn(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute("DELETE FROM entities WHERE id = ?1", [entity_id])?;
+            for table in ["entity_variants", "entity_attributes", "entity_memories"] {
+                tx.execute(
+                    &format!("DELETE FROM {} WHERE entity_id = ?1", table),
+                    [entity_id],
+                )?;
+            }
+            tx.execute(
+                "DELETE FROM entity_relations WHERE source_id = ?1 OR target_id = ?1",
+                [entity_id],
+            )?;
+            tx.commit()
+        })
+    }
+
+    async fn load(&self) -> Result<EntityKgSnapshot, EntityStoreError> {
+        let (rows, variants, attributes, memories, relations) = self.with_conn(|conn| {
+            let rows = query_rows(
+                conn,
+                "SELECT id, cube_id, name, entity_type, description, metadata, version, attribute_history FROM entities ORDER BY id",
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, String>(5)?,
+                        row.get::<_, u32>(6)?,
+                        row.get::<_, Option<String>>(7)?,
+                    ))
+                },
+            )?;
+            let pair = |row: &rusqlite::Row<'_>| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            };
+            let variants = query_rows(
+                conn,
+                "SELECT entity_id, variant FROM entity_variants ORDER BY rowid",
+                pair,
+            )?;
+            let memories = query_rows(
+                conn,
+                "SELECT entity_id, memory_id FROM entity_memories ORDER BY rowid",
+                pair,
+            )?;
+            let attributes = query_rows(
+                conn,
+                "SELECT entity_id, key, value FROM entity_attributes",
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )?;
+            let relations = query_rows(
+                conn,
+                "SELECT source_id, target_id, relation_type, validity FROM entity_relations ORDER BY source_id, target_id",
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )?;
+            Ok((rows, variants, attributes, memories, relations))
+        })?;
+
+        let mut entities: Vec<Entity> = Vec::with_capacity(rows.len());
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (id, cube_id, name, entity_type, description, metadata, version, attribute_history) in
+            rows
+        {
+            let entity_type: EntityType = from_json(&entity_type)?;
+            let metadata: EntityMetadata = from_json(&metadata)?;
+            let attribute_history = match attribute_history {
+                Some(raw) => from_json(&raw)?,
+                None => Vec::new(),
+            };
+            index.insert(id.clone(), entities.len());
+            entities.push(Entity {
+                id,
+                name,
+                entity_type,
+                cube_id,
+                name_variants: Vec::new(),
+                description,
+                memory_ids: Vec::new(),
+                attributes: HashMap::new(),
+                attribute_history,
+                metadata,
+                version,
+            });
+        }
+        for (entity_id, variant) in variants {
+            if let Some(&i) = index.get(&entity_id) {
+                entities[i].name_variants.push(variant);
+            }
+        }
+        for (entity_id, memory_id) in memories {
+            if let Some(&i) = index.get(&entity_id) {
+                entities[i].memory_ids.push(memory_id);
+            }
+        }
+        for (entity_id, key, value) in attributes {
+            if let Some(&i) = index.get(&entity_id) {
+                entities[i].attributes.insert(key, from_json(&value)?);
+            }
+        }
+        let relations = relations
+            .into_iter()
+            .map(|(source_id, target_id, relation_type, validity)| {
+                Ok(StoredRelation {
+                    source_id,
+                    target_id,
+                    relation_type: from_json::<EntityRelationType>(&relation_type)?,
+                    validity: match validity {
+                        Some(raw) => from_json(&raw)?,
+                        None => Validity::default(),
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, EntityStoreError>>()?;
+
+        Ok(EntityKgSnapshot {
+            entities,
+            relations,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+fn query_rows<T, F>(conn: &rusqlite::Connection, sql: &str, f: F) -> Result<Vec<T>, rusqlite::Error>
+where
+    F: FnMut(&rusqlite::Row<'_>) -> Result<T, rusqlite::Error>,
+{
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([], f)?;
+    rows.collect()
+}
+
+fn add_column_if_missing(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    decl: &str,
+) -> Result<(), EntityStoreError> {
+    let columns = query_rows(
+        conn,
+        &format!("SELECT name FROM pragma_table_info('{}')", table),
+        |row| row.get::<_, String>(0),
+    )
+    .map_err(store_err)?;
+    if !columns.iter().any(|c| c == column) {
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, decl
+        ))
+        .map_err(store_err)?;
+    }
+    Ok(())
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, EntityStoreError> {
+    serde_json::to_string(value).map_err(|e| EntityStoreError::Other(e.to_string()))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(raw: &str) -> Result<T, EntityStoreError> {
+    serde_json::from_str(raw).map_err(|e| EntityStoreError::Other(e.to_string()))
+}
+
+fn store_err(e: rusqlite::Error) -> EntityStoreError {
+    EntityStoreError::Other(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn entities_and_relations_round_trip() {
+        let path = std::env::temp_dir().join(format!("memos-entities-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteEntityStore::new(&path).unwrap();
+        let mut alice = Entity::new(
+            "e1".to_string(),
+            "alice".to_string(),
+            EntityType::Person,
+            "m1".to_string(),
+            0.9,
+        );
+        alice.cube_id = "c1".to_string();
+        alice.add_variant("Alice".to_string());
+        alice.add_memory_id("m2".to_string());
+        alice
+            .attributes
+            .insert("role".to_string(), serde_json::json!("engineer"));
+        let mut acme = Entity::new(
+            "e2".to_string(),
+            "acme".to_string(),
+            EntityType::Organization,
+            "m1".to_string(),
+            0.8,
+        );
+        acme.cube_id = "c1".to_string();
+        alice.set_attribute(
+            "title",
+            serde_json::json!("lead"),
+            Validity {
+                valid_from: Some("2024-01-01".to_string()),
+                ..Validity::default()
+            },
+        );
+        let mut works_at = StoredRelation::new("e1", "e2", EntityRelationType::WorksAt);
+        works_at.validity.valid_to = Some("2023-06-30".to_string());
+        works_at.validity.source_memory_ids = vec!["m1".to_string()];
+        store
+            .upsert_entity(&alice, std::slice::from_ref(&works_at))
+            .await
+            .unwrap();
+        store.upsert_entity(&acme, &[]).await.unwrap();
+        drop(store);
+
+        let store = SqliteEntityStore::new(&path).unwrap();
+        let snapshot = store.load().await.unwrap();
+        assert_eq!(snapshot.entities.len(), 2);
+        let loaded = &snapshot.entities[0];
+        assert_eq!(loaded.cube_id, "c1");
+        assert_eq!(loaded.name_variants, vec!["Alice".to_string()]);
+        assert_eq!(loaded.memory_ids, vec!["m1".to_string(), "m2".to_string()]);
+        assert_eq!(loaded.attributes["role"], "engineer");
+        assert_eq!(loaded.attribute_history, alice.attribute_history);
+        assert_eq!(snapshot.relations, vec![works_at]);
+
+        store.delete_entity("e2").await.unwrap();
+        let snapshot = store.load().await.unwrap();
+        assert_eq!(snapshot.entities.len(), 1);
+        assert!(snapshot.relations.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,2 +1,57 @@
 // Re-export trait from mem-types for implementors
 pub use mem_types::GraphStore;
+
+use mem_types::{GraphDirection, MemoryEdge};
+use std::collections::HashMap;
+
+/// Author of a row: the user who wrote it (`author_id` metadata). Rows written before authors
+/// were recorded count as written by the user the cube is named after.
+pub(crate) fn author_of(metadata: &HashMap<String, serde_json::Value>) -> &str {
+    metadata
+        .get("author_id")
+        .or_else(|| metadata.get("user_name"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+/// Apply an `update_edge` change: new relation, metadata merged key by key (`null` removes the
+/// key). The owner and author keys are left alone.
+pub(crate) fn apply_edge_update(
+    edge: &mut MemoryEdge,
+    relation: Option<&str>,
+    metadata: &HashMap<String, serde_json::Value>,
+) {
+    if let Some(relation) = relation {
+        edge.relation = relation.to_string();
+    }
+    for (key, value) in metadata {
+        if key == "user_name" || key == "author_id" {
+            continue;
+        }
+        if value.is_null() {
+            edge.metadata.remove(key);
+        } else {
+            edge.metadata.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Whether a `list_edges` filter keeps the edge.
+pub(crate) fn edge_matches(
+    edge: &MemoryEdge,
+    node_id: Option<&str>,
+    relation: Option<&str>,
+    direction: GraphDirection,
+) -> bool {
+    if relation.is_some_and(|r| r != edge.relation) {
+        return false;
+    }
+    match node_id {
+        None => true,
+        Some(id) => match direction {
+            GraphDirection::Outbound => edge.from == id,
+            GraphDirection::Inbound => edge.to == id,
+            GraphDirection::Both => edge.from == id || edge.to == id,
+        },
+    }
+}
@@ -0,0 +1,424 @@
+//! Weighted path queries: Dijkstra over edge costs read from edge metadata, and Yen's k
+//! shortest simple paths on top of it. Store-agnostic: nodes are expanded with
+//! `GraphStore::get_neighbors`, once per node and query.
+
+use mem_types::{
+    CostTransform, GraphDirection, GraphPath, GraphStore, GraphStoreError, MemoryEdge, MemoryNode,
+};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Parameters of a weighted path query.
+#[derive(Debug, Clone)]
+pub struct WeightedPathQuery<'a> {
+    /// Edge metadata field holding the cost.
+    pub cost_field: &'a str,
+    pub cost_transform: CostTransform,
+    /// Cost of edges without a numeric `cost_field` value.
+    pub default_cost: f64,
+    /// Paths costing more are not returned.
+    pub max_cost: Option<f64>,
+    pub relation: Option<&'a str>,
+    pub direction: GraphDirection,
+    /// Maximum hops per path.
+    pub max_depth: usize,
+    pub include_deleted: bool,
+    /// Only nodes and edges owned by this user/cube are traversed.
+    pub user_name: Option<&'a str>,
+}
+
+impl WeightedPathQuery<'_> {
+    /// Cost of traversing the edge; `None` when the cost is negative or not finite.
+    pub fn edge_cost(&self, edge: &MemoryEdge) -> Option<f64> {
+        let cost = match edge.metadata.get(self.cost_field).and_then(|v| v.as_f64()) {
+            Some(value) => self.cost_transform.apply(value),
+            None => self.default_cost,
+        };
+        (cost.is_finite() && cost >= 0.0).then_some(cost)
+    }
+
+    fn visible(&self, node: &MemoryNode) -> bool {
+        let owner = node.metadata.get("user_name").and_then(|v| v.as_str());
+        let tombstone = node.metadata.get("state").and_then(|v| v.as_str()) == Some("tombstone");
+        self.user_name.is_none_or(|un| owner == Some(un)) && (self.include_deleted || !tombstone)
+    }
+}
+
+/// Up to `top_k` cheapest simple paths from `source_id` to `target_id` with their total cost,
+/// cheapest first (ties: fewer hops). Errors when either end is missing or, with `user_name`,
+/// owned by someone else; empty when no path fits `max_depth` and `max_cost`.
+pub async fn weighted_paths<G: GraphStore + ?Sized>(
+    graph: &G,
+    source_id: &str,
+    target_id: &str,
+    query: &WeightedPathQuery<'_>,
+    top_k: usize,
+) -> Result<Vec<(GraphPath, f64)>, GraphStoreError> {
+    for id in [source_id, target_id] {
+        let node = graph
+            .get_node(id, false)
+            .await?
+            .ok_or_else(|| GraphStoreError::Other(format!("node not found: {}", id)))?;
+        if query
+            .user_name
+            .is_some_and(|un| node.metadata.get("user_name").and_then(|v| v.as_str()) != Some(un))
+        {
+            return Err(GraphStoreError::Other(format!(
+                "node not found or access denied: {}",
+                id
+            )));
+        }
+        if !query.visible(&node) {
+            return Ok(Vec::new());
+        }
+    }
+    if top_k == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut adjacency = Adjacency {
+        graph,
+        query,
+        steps: HashMap::new(),
+    };
+    let no_nodes = HashSet::new();
+    let no_edges = HashSet::new();
+    let Some(first) = adjacency
+        .cheapest(
+            source_id,
+            target_id,
+            &no_nodes,
+            &no_edges,
+            query.max_depth,
+            query.max_cost,
+        )
+        .await?
+    else {
+        return Ok(Vec::new());
+    };
+
+    // Yen: each next path deviates from an accepted one at some spur node, reusing its root.
+    let mut accepted: Vec<(GraphPath, f64)> = vec![first];
+    let mut candidates: Vec<(GraphPath, f64)> = Vec::new();
+    while accepted.len() < top_k {
+        let (last, _) = accepted.last().expect("at least one accepted path");
+        let last = last.clone();
+        for i in 0..last.edges.len() {
+            let spur = &last.node_ids[i];
+            let root_nodes = &last.node_ids[..=i];
+            let root_cost: f64 = last.edges[..i]
+                .iter()
+                .map(|e| query.edge_cost(e).unwrap_or(0.0))
+                .sum();
+            let banned_edges: HashSet<String> = accepted
+                .iter()
+                .filter(|(p, _)| p.node_ids.len() > i + 1 && p.node_ids[..=i] == *root_nodes)
+                .map(|(p, _)| p.edges[i].id.clone())
+                .collect();
+            let banned_nodes: HashSet<String> = root_nodes[..i].iter().cloned().collect();
+            let Some((spur_path, spur_cost)) = adjacency
+                .cheapest(
+                    spur,
+                    target_id,
+                    &banned_nodes,
+                    &banned_edges,
+                    query.max_depth - i,
+                    query.max_cost.map(|max| max - root_cost),
+                )
+                .await?
+            else {
+                continue;
+            };
+            let mut node_ids = root_nodes[..i].to_vec();
+            node_ids.extend(spur_path.node_ids);
+            let mut edges = last.edges[..i].to_vec();
+            edges.extend(spur_path.edges);
+            let path = GraphPath { node_ids, edges };
+            let known = |(p, _): &(GraphPath, f64)| same_path(p, &path);
+            if !accepted.iter().any(known) && !candidates.iter().any(known) {
+                candidates.push((path, root_cost + spur_cost));
+            }
+        }
+        let Some(best) = candidates
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| compare_paths(a, b))
+            .map(|(i, _)| i)
+        else {
+            break;
+        };
+        accepted.push(candidates.swap_remove(best));
+    }
+    Ok(accepted)
+}
+
+fn same_path(a: &GraphPath, b: &GraphPath) -> bool {
+    a.node_ids == b.node_ids
+        && a.edges.len() == b.edges.len()
+        && a.edges.iter().zip(&b.edges).all(|(x, y)| x.id == y.id)
+}
+
+/// Cheaper first, then fewer hops, then by edge ids (deterministic).
+fn compare_paths(a: &(GraphPath, f64), b: &(GraphPath, f64)) -> Ordering {
+    a.1.total_cmp(&b.1)
+        .then_with(|| a.0.edges.len().cmp(&b.0.edges.len()))
+        .then_with(|| {
+            let ids = |p: &GraphPath| p.edges.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+            ids(&a.0).cmp(&ids(&b.0))
+        })
+}
+
+#[derive(Clone)]
+struct Step {
+    to: String,
+    edge: MemoryEdge,
+    cost: f64,
+}
+
+/// Lazily expanded, per-query view of the traversable graph.
+struct Adjacency<'a, G: ?Sized> {
+    graph: &'a G,
+    query: &'a WeightedPathQuery<'a>,
+    steps: HashMap<String, Vec<Step>>,
+}
+
+/// Search label: a node reached through `parent` (label index and edge).
+struct Label {
+    node: String,
+    parent: Option<(usize, MemoryEdge)>,
+}
+
+/// Min-heap entry (reversed ordering on cost, then depth, then node id).
+struct Entry {
+    cost: f64,
+    depth: usize,
+    node: String,
+    label: usize,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.depth.cmp(&self.depth))
+            .then_with(|| other.node.cmp(&self.node))
+            .then_with(|| other.label.cmp(&self.label))
+    }
+}
+
+impl<G: GraphStore + ?Sized> Adjacency<'_, G> {
+    async fn expand(&mut self, node_id: &str) -> Result<Vec<Step>, GraphStoreError> {
+        if let Some(steps) = self.steps.get(node_id) {
+            return Ok(steps.clone());
+        }
+        let neighbors = self
+            .graph
+            .get_neighbors(
+                node_id,
+                self.query.relation,
+                self.query.direction,
+                usize::MAX,
+                false,
+                self.query.user_name,
+            )
+            .await?;
+        let mut steps: Vec<Step> = neighbors
+            .into_iter()
+            .filter(|n| {
+                self.query.visible(&n.node)
+                    && self.query.user_name.is_none_or(|un| {
+                        n.edge.metadata.get("user_name").and_then(|v| v.as_str()) == Some(un)
+                    })
+            })
+            .filter_map(|n| {
+                let cost = self.query.edge_cost(&n.edge)?;
+                Some(Step {
+                    to: n.node.id,
+                    edge: n.edge,
+                    cost,
+                })
+            })
+            .collect();
+        steps.sort_by(|a, b| a.edge.id.cmp(&b.edge.id).then_with(|| a.to.cmp(&b.to)));
+        self.steps.insert(node_id.to_string(), steps.clone());
+        Ok(steps)
+    }
+
+    /// Dijkstra over (node, depth) labels so the hop limit does not hide cheaper long paths.
+    /// A label is dominated by a settled label of the same node with no more hops (settled
+    /// ones are never more expensive), which also keeps paths simple.
+    async fn cheapest(
+        &mut self,
+        source_id: &str,
+        target_id: &str,
+        banned_nodes: &HashSet<String>,
+        banned_edges: &HashSet<String>,
+        max_depth: usize,
+        max_cost: Option<f64>,
+    ) -> Result<Option<(GraphPath, f64)>, GraphStoreError> {
+        if max_cost.is_some_and(|max| max < 0.0) {
+            return Ok(None);
+        }
+        let mut labels = vec![Label {
+            node: source_id.to_string(),
+            parent: None,
+        }];
+        let mut heap = BinaryHeap::from([Entry {
+            cost: 0.0,
+            depth: 0,
+            node: source_id.to_string(),
+            label: 0,
+        }]);
+        let mut settled: HashMap<String, usize> = HashMap::new();
+        while let Some(entry) = heap.pop() {
+            if settled.get(&entry.node).is_some_and(|d| *d <= entry.depth) {
+                continue;
+            }
+            settled.insert(entry.node.clone(), entry.depth);
+            if entry.node == target_id {
+                return Ok(Some((path_to(&labels, entry.label), entry.cost)));
+            }
+            if entry.depth >= max_depth {
+                continue;
+            }
+            for step in self.expand(&entry.node).await? {
+                if banned_nodes.contains(&step.to) || banned_edges.contains(&step.edge.id) {
+                    continue;
+                }
+                let cost = entry.cost + step.cost;
+                if max_cost.is_some_and(|max| cost > max)
+                    || settled.get(&step.to).is_some_and(|d| *d <= entry.depth + 1)
+                {
+                    continue;
+                }
+                labels.push(Label {
+                    node: step.to.clone(),
+                    parent: Some((entry.label, step.edge)),
+                });
+                heap.push(Entry {
+                    cost,
+                    depth: entry.depth + 1,
+                    node: step.to,
+                    label: labels.len() - 1,
+                });
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn path_to(labels: &[Label], mut label: usize) -> GraphPath {
+    let mut node_ids = vec![labels[label].node.clone()];
+    let mut edges = Vec::new();
+    while let Some((parent, ref edge)) = labels[label].parent {
+        edges.push(edge.clone());
+        node_ids.push(labels[parent].node.clone());
+        label = parent;
+    }
+    node_ids.reverse();
+    edges.reverse();
+    GraphPath { node_ids, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryGraphStore;
+
+    async fn diamond() -> InMemoryGraphStore {
+        let store = InMemoryGraphStore::new();
+        for id in ["s", "a", "b", "t"] {
+            store
+                .add_node(id, id, &HashMap::new(), Some("u1"))
+                .await
+                .unwrap();
+        }
+        let edge = |id: &str, from: &str, to: &str, weight: f64| MemoryEdge {
+            id: id.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            relation: "r".to_string(),
+            metadata: HashMap::from([("weight".to_string(), serde_json::json!(weight))]),
+        };
+        store
+            .add_edges_batch(
+                &[
+                    edge("e1", "s", "a", 1.0),
+                    edge("e2", "a", "t", 5.0),
+                    edge("e3", "s", "b", 2.0),
+                    edge("e4", "b", "t", 1.0),
+                    edge("e5", "s", "t", 10.0),
+                    edge("e6", "a", "b", 0.5),
+                ],
+                Some("u1"),
+            )
+            .await
+            .unwrap();
+        store
+    }
+
+    fn query(max_depth: usize, max_cost: Option<f64>) -> WeightedPathQuery<'static> {
+        WeightedPathQuery {
+            cost_field: "weight",
+            cost_transform: CostTransform::Value,
+            default_cost: 1.0,
+            max_cost,
+            relation: None,
+            direction: GraphDirection::Outbound,
+            max_depth,
+            include_deleted: false,
+            user_name: Some("u1"),
+        }
+    }
+
+    fn route(path: &GraphPath) -> String {
+        path.node_ids.join("")
+    }
+
+    #[tokio::test]
+    async fn cheapest_paths_follow_costs_hops_and_bounds() {
+        let store = diamond().await;
+        let paths = weighted_paths(&store, "s", "t", &query(6, None), 4)
+            .await
+            .unwrap();
+        let found: Vec<(String, f64)> = paths.iter().map(|(p, c)| (route(p), *c)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("sabt".to_string(), 2.5),
+                ("sbt".to_string(), 3.0),
+                ("sat".to_string(), 6.0),
+                ("st".to_string(), 10.0),
+            ]
+        );
+
+        // The hop limit keeps the cheapest path that fits, not the cheapest overall.
+        let paths = weighted_paths(&store, "s", "t", &query(2, None), 1)
+            .await
+            .unwrap();
+        assert_eq!(route(&paths[0].0), "sbt");
+        let paths = weighted_paths(&store, "s", "t", &query(6, Some(5.0)), 10)
+            .await
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(weighted_paths(&store, "s", "t", &query(6, Some(2.0)), 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,7 +1,9 @@
-//! Scheduler for async add: submit_add returns job_id, worker runs add, get_status polls.
+//! Scheduler for async jobs: submit_add / submit_erasure / submit_graph_analytics return job_id, worker runs them, get_status polls.
 
+mod erasure;
 mod memory;
 mod trait_;
 
+pub use erasure::{sign_erasure_report, verify_erasure_report, ERASURE_SIGNATURE_ALGORITHM};
 pub use memory::InMemoryScheduler;
 pub use trait_::{Scheduler, SchedulerError};
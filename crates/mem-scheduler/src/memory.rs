@@ -1,9 +1,13 @@
 //! In-memory scheduler: single queue + one worker, job state in a map.
 
+use crate::erasure::sign_erasure_report;
 use crate::{Scheduler, SchedulerError};
 use async_trait::async_trait;
 use chrono::Utc;
-use mem_types::{ApiAddRequest, AuditEvent, AuditEventKind, AuditStore, Job, JobStatus};
+use mem_types::{
+    ApiAddRequest, AuditEvent, AuditEventKind, AuditStore, EraseUserDataRequest, ErasureOutcome,
+    ErasureReport, GraphAnalyticsRequest, Job, JobStatus,
+};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
@@ -12,28 +16,56 @@ use uuid::Uuid;
 struct JobState {
     job: Job,
     owner_user_id: String,
+    /// Cube the job wrote to; used to purge job history on erasure.
+    cube_id: String,
 }
 
-/// In-memory scheduler: queues add requests, one worker calls MemCube::add_memories and updates job status.
+/// Work item sent to the worker.
+enum Work {
+    Add(Box<ApiAddRequest>),
+    Erase(EraseUserDataRequest),
+    Analytics(GraphAnalyticsRequest),
+}
+
+type JobMap = Arc<RwLock<HashMap<String, JobState>>>;
+
+/// In-memory scheduler: queues add, erasure and graph analytics requests; one worker runs them on the MemCube
+/// and updates job status. Jobs run in submission order, so an erasure also covers adds
+/// queued before it.
 pub struct InMemoryScheduler {
-    jobs: Arc<RwLock<HashMap<String, JobState>>>,
-    tx: mpsc::UnboundedSender<(String, ApiAddRequest)>,
+    jobs: JobMap,
+    tx: mpsc::UnboundedSender<(String, Work)>,
 }
 
 impl InMemoryScheduler {
     /// Create scheduler and spawn worker. Worker runs on the given MemCube.
     /// If `audit_store` is provided, an AuditEvent(Add) is appended when an add job completes successfully.
+    /// Erasure reports are signed with a random per-process key; use `with_signing_key` for
+    /// reports that must be verifiable later.
     pub fn new(
         cube: Arc<dyn mem_types::MemCube + Send + Sync>,
         audit_store: Option<Arc<dyn AuditStore + Send + Sync>>,
     ) -> Self {
-        let jobs: Arc<RwLock<HashMap<String, JobState>>> = Arc::new(RwLock::new(HashMap::new()));
-        let (tx, mut rx) = mpsc::unbounded_channel::<(String, ApiAddRequest)>();
+        let key = [
+            Uuid::new_v4().as_bytes().as_slice(),
+            Uuid::new_v4().as_bytes().as_slice(),
+        ]
+        .concat();
+        Self::with_signing_key(cube, audit_store, key)
+    }
+
+    /// Like `new`, but signs erasure reports with `signing_key` (HMAC-SHA256).
+    pub fn with_signing_key(
+        cube: Arc<dyn mem_types::MemCube + Send + Sync>,
+        audit_store: Option<Arc<dyn AuditStore + Send + Sync>>,
+        signing_key: Vec<u8>,
+    ) -> Self {
+        let jobs: JobMap = Arc::new(RwLock::new(HashMap::new()));
+        let (tx, mut rx) = mpsc::unbounded_channel::<(String, Work)>();
 
         let jobs_clone = Arc::clone(&jobs);
-        let audit_store = audit_store.clone();
         tokio::spawn(async move {
-            while let Some((job_id, req)) = rx.recv().await {
+            while let Some((job_id, work)) = rx.recv().await {
                 let now = Utc::now().to_rfc3339();
                 {
                     let mut guard = jobs_clone.write().await;
@@ -42,48 +74,25 @@ impl InMemoryScheduler {
                         s.job.updated_at = now.clone();
                     }
                 }
-                let result = cube.add_memories(&req).await;
-                let now2 = Utc::now().to_rfc3339();
-                let (status, result_summary) = match &result {
-                    Ok(res) => (
-                        JobStatus::Done,
-                        Some(serde_json::json!({ "code": res.code, "message": res.message })),
-                    ),
-                    Err(e) => (
-                        JobStatus::Failed,
-                        Some(serde_json::json!({ "error": e.to_string() })),
-                    ),
-                };
-                if let Ok(ref res) = result {
-                    if let Some(ref store) = audit_store {
-                        let cube_ids = req.writable_cube_ids();
-                        let memory_id = res
-                            .data
-                            .as_ref()
-                            .and_then(|d| d.first())
-                            .and_then(|v| v.get("id"))
-                            .and_then(|v| v.as_str())
-                            .map(String::from);
-                        let event = AuditEvent {
-                            event_id: Uuid::new_v4().to_string(),
-                            kind: AuditEventKind::Add,
-                            memory_id,
-                            user_id: req.user_id.clone(),
-                            cube_id: cube_ids
-                                .first()
-                                .cloned()
-                                .unwrap_or_else(|| req.user_id.clone()),
-                            timestamp: now2.clone(),
-                            input_summary: None,
-                            outcome: Some(format!("code={}", res.code)),
-                        };
-                        let _ = store.append(event).await;
+                let (status, result_summary) = match work {
+                    Work::Add(req) => run_add(cube.as_ref(), audit_store.as_deref(), &req).await,
+                    Work::Erase(req) => {
+                        run_erasure(
+                            cube.as_ref(),
+                            audit_store.as_deref(),
+                            &jobs_clone,
+                            &job_id,
+                            &req,
+                            &signing_key,
+                        )
+                        .await
                     }
-                }
+                    Work::Analytics(req) => run_graph_analytics(cube.as_ref(), &req).await,
+                };
                 let mut guard = jobs_clone.write().await;
                 if let Some(s) = guard.get_mut(&job_id) {
                     s.job.status = status;
-                    s.job.updated_at = now2;
+                    s.job.updated_at = Utc::now().to_rfc3339();
                     s.job.result_summary = result_summary;
                 }
             }
@@ -95,11 +104,13 @@ impl InMemoryScheduler {
     fn now_iso(&self) -> String {
         Utc::now().to_rfc3339()
     }
-}
 
-#[async_trait]
-impl Scheduler for InMemoryScheduler {
-    async fn submit_add(&self, req: ApiAddRequest) -> Result<String, SchedulerError> {
+    async fn enqueue(
+        &self,
+        owner_user_id: String,
+        cube_id: String,
+        work: Work,
+    ) -> Result<String, SchedulerError> {
         let job_id = Uuid::new_v4().to_string();
         let now = self.now_iso();
         let job = Job {
@@ -115,15 +126,174 @@ impl Scheduler for InMemoryScheduler {
                 job_id.clone(),
                 JobState {
                     job,
-                    owner_user_id: req.user_id.clone(),
+                    owner_user_id,
+                    cube_id,
                 },
             );
         }
         self.tx
-            .send((job_id.clone(), req))
+            .send((job_id.clone(), work))
             .map_err(|_| SchedulerError::Other("worker channel closed".to_string()))?;
         Ok(job_id)
     }
+}
+
+async fn run_add(
+    cube: &(dyn mem_types::MemCube + Send + Sync),
+    audit_store: Option<&(dyn AuditStore + Send + Sync)>,
+    req: &ApiAddRequest,
+) -> (JobStatus, Option<serde_json::Value>) {
+    let result = cube.add_memories(req).await;
+    if let (Ok(res), Some(store)) = (&result, audit_store) {
+        let cube_ids = req.writable_cube_ids();
+        let memory_id = res
+            .data
+            .as_ref()
+            .and_then(|d| d.first())
+            .and_then(|v| v.get("id"))
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        let event = AuditEvent {
+            event_id: Uuid::new_v4().to_string(),
+            kind: AuditEventKind::Add,
+            memory_id,
+            user_id: req.user_id.clone(),
+            cube_id: cube_ids
+                .first()
+                .cloned()
+                .unwrap_or_else(|| req.user_id.clone()),
+            timestamp: Utc::now().to_rfc3339(),
+            input_summary: None,
+            outcome: Some(format!("code={}", res.code)),
+        };
+        let _ = store.append(event).await;
+    }
+    match result {
+        Ok(res) => (
+            JobStatus::Done,
+            Some(serde_json::json!({ "code": res.code, "message": res.message })),
+        ),
+        Err(e) => (
+            JobStatus::Failed,
+            Some(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
+async fn run_graph_analytics(
+    cube: &(dyn mem_types::MemCube + Send + Sync),
+    req: &GraphAnalyticsRequest,
+) -> (JobStatus, Option<serde_json::Value>) {
+    match cube.graph_analytics(req).await {
+        Ok(report) => (JobStatus::Done, serde_json::to_value(&report).ok()),
+        Err(e) => (
+            JobStatus::Failed,
+            Some(serde_json::json!({ "error": e.to_string() })),
+        ),
+    }
+}
+
+/// Erase the user's (or the whole cube's) data, purge the matching jobs, redact audit inputs
+/// and return a signed report.
+async fn run_erasure(
+    cube: &(dyn mem_types::MemCube + Send + Sync),
+    audit_store: Option<&(dyn AuditStore + Send + Sync)>,
+    jobs: &JobMap,
+    job_id: &str,
+    req: &EraseUserDataRequest,
+    signing_key: &[u8],
+) -> (JobStatus, Option<serde_json::Value>) {
+    let requested_at = {
+        let guard = jobs.read().await;
+        guard
+            .get(job_id)
+            .map(|s| s.job.created_at.clone())
+            .unwrap_or_default()
+    };
+    let ErasureOutcome { cubes, mut counts } = match cube.erase_user_data(req).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            return (
+                JobStatus::Failed,
+                Some(serde_json::json!({ "error": e.to_string() })),
+            )
+        }
+    };
+
+    {
+        let mut guard = jobs.write().await;
+        let before = guard.len();
+        // Jobs submitted after this erasure are new data, not history.
+        let erased = |s: &JobState| {
+            if req.whole_cube {
+                req.mem_cube_id.as_ref() == Some(&s.cube_id)
+            } else {
+                s.owner_user_id == req.user_id
+                    && req.mem_cube_id.as_ref().is_none_or(|c| c == &s.cube_id)
+            }
+        };
+        guard.retain(|id, s| id == job_id || s.job.created_at > requested_at || !erased(s));
+        counts.jobs = (before - guard.len()) as u64;
+    }
+
+    if let Some(store) = audit_store {
+        let user_id = (!req.whole_cube).then_some(req.user_id.as_str());
+        match store.redact(user_id, req.mem_cube_id.as_deref()).await {
+            Ok(n) => counts.audit_events_redacted = n,
+            Err(e) => {
+                return (
+                    JobStatus::Failed,
+                    Some(serde_json::json!({ "error": e.to_string() })),
+                )
+            }
+        }
+    }
+
+    let mut report = ErasureReport {
+        erasure_id: job_id.to_string(),
+        user_id: req.user_id.clone(),
+        cube_id: req.mem_cube_id.clone(),
+        whole_cube: req.whole_cube,
+        cubes,
+        requested_at,
+        completed_at: Utc::now().to_rfc3339(),
+        counts,
+        algorithm: String::new(),
+        signature: String::new(),
+    };
+    sign_erasure_report(&mut report, signing_key);
+    (JobStatus::Done, serde_json::to_value(&report).ok())
+}
+
+#[async_trait]
+impl Scheduler for InMemoryScheduler {
+    async fn submit_add(&self, req: ApiAddRequest) -> Result<String, SchedulerError> {
+        let cube_id = req
+            .writable_cube_ids()
+            .first()
+            .cloned()
+            .unwrap_or_else(|| req.user_id.clone());
+        self.enqueue(req.user_id.clone(), cube_id, Work::Add(Box::new(req)))
+            .await
+    }
+
+    async fn submit_erasure(&self, req: EraseUserDataRequest) -> Result<String, SchedulerError> {
+        let cube_id = req
+            .mem_cube_id
+            .clone()
+            .unwrap_or_else(|| req.user_id.clone());
+        self.enqueue(req.user_id.clone(), cube_id, Work::Erase(req))
+            .await
+    }
+
+    async fn submit_graph_analytics(
+        &self,
+        req: GraphAnalyticsRequest,
+    ) -> Result<String, SchedulerError> {
+        let cube_id = req.cube_id().to_string();
+        self.enqueue(req.user_id.clone(), cube_id, Work::Analytics(req))
+            .await
+    }
 
     async fn get_status(&self, user_id: &str, job_id: &str) -> Result<Option<Job>, SchedulerError> {
         let guard = self.jobs.read().await;
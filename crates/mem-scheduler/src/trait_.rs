@@ -1,7 +1,7 @@
 //! Scheduler trait: submit add job, get status.
 
 use async_trait::async_trait;
-use mem_types::{ApiAddRequest, Job};
+use mem_types::{ApiAddRequest, EraseUserDataRequest, GraphAnalyticsRequest, Job};
 
 #[derive(Debug, thiserror::Error)]
 pub enum SchedulerError {
@@ -20,6 +20,17 @@ pub trait Scheduler: Send + Sync {
     /// Submit an add request; returns job_id. When async, the actual add runs in a worker.
     async fn submit_add(&self, req: ApiAddRequest) -> Result<String, SchedulerError>;
 
+    /// Submit a right-to-be-forgotten erasure; returns job_id. The finished job's
+    /// `result_summary` holds the signed `ErasureReport`.
+    async fn submit_erasure(&self, req: EraseUserDataRequest) -> Result<String, SchedulerError>;
+
+    /// Submit a graph analytics run; returns job_id. The finished job's `result_summary` holds
+    /// the `GraphAnalyticsReport`.
+    async fn submit_graph_analytics(
+        &self,
+        req: GraphAnalyticsRequest,
+    ) -> Result<String, SchedulerError>;
+
     /// Get current job status by user_id + job_id (task_id).
     /// Returns `Ok(None)` when job is unknown or not owned by the given user.
     async fn get_status(&self, user_id: &str, job_id: &str) -> Result<Option<Job>, SchedulerError>;
@@ -353,6 +353,18 @@ pub struct GraphPathRequest {
     pub max_depth: u32,
     #[serde(default)]
     pub include_deleted: bool,
+    /// Edge metadata field holding the edge cost; when set, paths are ranked by total cost
+    /// (Dijkstra, Yen for several paths) instead of hop count.
+    #[serde(default)]
+    pub cost_field: Option<String>,
+    #[serde(default)]
+    pub cost_transform: CostTransform,
+    /// Cost of edges without a numeric `cost_field` value (default 1).
+    #[serde(default)]
+    pub default_cost: Option<f64>,
+    /// Paths costing more than this are not returned.
+    #[serde(default)]
+    pub max_cost: Option<f64>,
 }
 
 fn default_graph_max_depth() -> u32 {
@@ -365,6 +377,9 @@ pub struct GraphPathData {
     pub hops: u32,
     pub nodes: Vec<MemoryItem>,
     pub edges: Vec<MemoryEdge>,
+    /// Total edge cost, for weighted (`cost_field`) queries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
 }
 
 /// API response for graph path query.
@@ -388,6 +403,18 @@ pub struct GraphPathsRequest {
     pub top_k_paths: u32,
     #[serde(default)]
     pub include_deleted: bool,
+    /// Edge metadata field holding the edge cost; when set, paths are ranked by total cost
+    /// (Dijkstra, Yen for several paths) instead of hop count.
+    #[serde(default)]
+    pub cost_field: Option<String>,
+    #[serde(default)]
+    pub cost_transform: CostTransform,
+    /// Cost of edges without a numeric `cost_field` value (default 1).
+    #[serde(default)]
+    pub default_cost: Option<f64>,
+    /// Paths costing more than this are not returned.
+    #[serde(default)]
+    pub max_cost: Option<f64>,
 }
 
 fn default_graph_top_k_paths() -> u32 {
@@ -397,6 +424,203 @@ fn default_graph_top_k_paths() -> u32 {
 /// API response for multi-path query.
 pub type GraphPathsResponse = BaseResponse<Vec<GraphPathData>>;
 
+/// API request to create an edge between two existing memories of the cube.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdgeAddRequest {
+    pub from: String,
+    pub to: String,
+    pub relation: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// API request addressing one edge (get, delete).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdgeRequest {
+    pub edge_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+}
+
+/// API request to change an edge's relation and/or metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdgeUpdateRequest {
+    pub edge_id: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    #[serde(default)]
+    pub relation: Option<String>,
+    /// Merged into the edge metadata; a `null` value removes the key.
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+/// API response carrying one edge.
+pub type GraphEdgeResponse = BaseResponse<MemoryEdge>;
+
+/// API request to list the cube's edges, optionally those of one memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdgeListRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Only edges of this memory (in `direction`).
+    #[serde(default)]
+    pub memory_id: Option<String>,
+    #[serde(default)]
+    pub relation: Option<String>,
+    #[serde(default = "default_edge_list_direction")]
+    pub direction: GraphDirection,
+    #[serde(default = "default_edge_list_limit")]
+    pub limit: u32,
+    /// Opaque cursor token from previous response for pagination.
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+fn default_edge_list_direction() -> GraphDirection {
+    GraphDirection::Both
+}
+
+fn default_edge_list_limit() -> u32 {
+    50
+}
+
+/// API response payload for edge listing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdgeListData {
+    pub items: Vec<MemoryEdge>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// API response for edge listing.
+pub type GraphEdgeListResponse = BaseResponse<GraphEdgeListData>;
+
+/// API request to analyze the cube's memory graph (PageRank, degree centrality, connected
+/// components, Louvain communities). Runs as a scheduler job; results are written
+/// back to node metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphAnalyticsRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Only edges with this relation are analyzed.
+    #[serde(default)]
+    pub relation: Option<String>,
+    /// PageRank damping factor in (0, 1).
+    #[serde(default = "default_pagerank_damping")]
+    pub damping: f64,
+    /// Iteration cap for PageRank and for each Louvain pass.
+    #[serde(default = "default_analytics_max_iterations")]
+    pub max_iterations: u32,
+    /// Number of top PageRank memories listed in the report.
+    #[serde(default = "default_analytics_top_k")]
+    pub top_k: u32,
+}
+
+fn default_pagerank_damping() -> f64 {
+    0.85
+}
+
+fn default_analytics_max_iterations() -> u32 {
+    100
+}
+
+fn default_analytics_top_k() -> u32 {
+    10
+}
+
+impl GraphAnalyticsRequest {
+    /// Cube being analyzed (defaults to user_id, like other cube-scoped requests).
+    pub fn cube_id(&self) -> &str {
+        self.mem_cube_id.as_deref().unwrap_or(self.user_id.as_str())
+    }
+
+    /// Check the parameters before the job is queued; the error is a client error message.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.user_id.trim().is_empty() {
+            return Err("user_id is required".to_string());
+        }
+        if !(self.damping > 0.0 && self.damping < 1.0) {
+            return Err("damping must be between 0 and 1".to_string());
+        }
+        if self.max_iterations == 0 {
+            return Err("max_iterations must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Analytics of one memory, as written to its metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeAnalytics {
+    pub memory_id: String,
+    /// PageRank over directed edges; sums to 1 over the cube.
+    pub pagerank: f64,
+    /// PageRank divided by the cube's highest PageRank, in [0, 1]; used as a ranking signal.
+    pub centrality: f64,
+    /// Number of distinct neighbors, ignoring direction.
+    pub degree: u64,
+    /// `degree / (nodes - 1)`.
+    pub degree_centrality: f64,
+    /// Weakly connected component; 0 is the largest.
+    pub component_id: u64,
+    /// Louvain community; 0 is the largest.
+    pub community_id: u64,
+}
+
+/// Result summary of a graph analytics job (the job's `result_summary`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphAnalyticsReport {
+    pub cube_id: String,
+    /// Live memories analyzed.
+    pub nodes: u64,
+    /// Edges between live memories.
+    pub edges: u64,
+    pub components: u64,
+    pub communities: u64,
+    /// Sizes of the components, largest first (`component_id` is the index).
+    pub component_sizes: Vec<u64>,
+    /// Sizes of the communities, largest first (`community_id` is the index).
+    pub community_sizes: Vec<u64>,
+    /// PageRank iterations until convergence.
+    pub pagerank_iterations: u32,
+    /// Hub memories: highest PageRank first.
+    pub top_nodes: Vec<NodeAnalytics>,
+    pub completed_at: String,
+}
+
+/// How an edge's `cost_field` value becomes its cost. Edges whose cost comes out negative or
+/// not finite are not traversed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CostTransform {
+    /// The value itself (e.g. `weight`, `distance`).
+    #[default]
+    Value,
+    /// `1 / value`, so larger values are cheaper (e.g. `strength`).
+    Inverse,
+    /// `-ln(value)` for probabilities in (0, 1] (e.g. `confidence`): the cheapest path is the
+    /// most likely one.
+    NegLog,
+}
+
+impl CostTransform {
+    pub fn apply(self, value: f64) -> f64 {
+        match self {
+            CostTransform::Value => value,
+            CostTransform::Inverse => 1.0 / value,
+            CostTransform::NegLog => -value.ln(),
+        }
+    }
+}
+
 /// Traversal direction for graph neighbor query.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
@@ -582,6 +806,9 @@ pub struct SearchEntitiesRequest {
     /// Enable fuzzy matching.
     #[serde(default)]
     pub fuzzy: bool,
+    /// Minimum fuzzy match score (0.0 - 1.0); only used with `fuzzy`.
+    #[serde(default)]
+    pub min_score: Option<f64>,
 }
 
 fn default_entity_limit() -> u32 {
@@ -784,6 +1011,9 @@ pub struct SessionTimelineRequest {
     pub limit: u32,
     #[serde(default)]
     pub include_metadata: bool,
+    /// Opaque cursor token from previous response for pagination.
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 fn default_timeline_limit() -> u32 {
@@ -803,8 +1033,12 @@ pub struct SessionTimelineResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionTimelineData {
     pub session_id: String,
+    /// Memories of this page, oldest first.
     pub memories: Vec<MemoryItem>,
+    /// Memories in the whole session.
     pub total: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 // ============================================================================
@@ -891,6 +1125,12 @@ pub struct ExportRequest {
     /// Export format: json, jsonl
     #[serde(default = "default_export_format")]
     pub format: String,
+    /// Page size; the whole scope is exported when unset.
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Opaque cursor token from previous response for pagination.
+    #[serde(default)]
+    pub cursor: Option<String>,
 }
 
 fn default_export_scope() -> String {
@@ -912,8 +1152,231 @@ pub struct ExportResponse {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportData {
+    /// Memories in this page.
     pub total_memories: u32,
     pub data: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Encoding of a graph export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphExportFormat {
+    /// JSON Graph Format (v2).
+    #[default]
+    #[serde(alias = "jgf")]
+    Json,
+    /// GraphML, e.g. for Gephi or yEd.
+    #[serde(rename = "graphml")]
+    GraphMl,
+    /// Graphviz DOT.
+    Dot,
+}
+
+/// Graph export request: the cube's memories with the edges between them, optionally
+/// restricted to a scope, a session or the subgraph around a root memory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphExportRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    #[serde(default)]
+    pub format: GraphExportFormat,
+    /// Only memories of this scope (`WorkingMemory`, `UserMemory`, `LongTermMemory`).
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// Only memories of this session.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Only the subgraph within `depth` hops of this memory.
+    #[serde(default)]
+    pub root_memory_id: Option<String>,
+    #[serde(default = "default_graph_export_depth")]
+    pub depth: u32,
+    /// Traversal direction from `root_memory_id`.
+    #[serde(default = "default_edge_list_direction")]
+    pub direction: GraphDirection,
+    /// Only memory edges with this relation (also limits the traversal from `root_memory_id`).
+    #[serde(default)]
+    pub relation: Option<String>,
+    /// Add the entities mentioned by the exported memories and the relations between them
+    /// (requires entity extraction).
+    #[serde(default)]
+    pub include_entities: bool,
+    /// Include soft-deleted (tombstone) memories.
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+fn default_graph_export_depth() -> u32 {
+    2
+}
+
+/// Graph export payload: the encoded document and its size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphExportData {
+    pub format: GraphExportFormat,
+    pub total_nodes: u32,
+    pub total_edges: u32,
+    pub data: String,
+}
+
+/// Graph export response.
+pub type GraphExportResponse = BaseResponse<GraphExportData>;
+
+/// Graph pattern query request: a Cypher-like `MATCH ... [WHERE ...] RETURN ... [LIMIT n]`
+/// evaluated over one cube's memories and edges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphQueryRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    pub query: String,
+    /// Row cap, applied on top of the query's own `LIMIT`.
+    #[serde(default = "default_graph_query_limit")]
+    pub limit: u32,
+    /// Let tombstoned memories match.
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+fn default_graph_query_limit() -> u32 {
+    100
+}
+
+/// Graph query payload: column names and one row per match. Nodes are
+/// `{id, memory, metadata}` objects, relationships are edges and variable-length
+/// relationships are arrays of edges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphQueryData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+/// Graph query response.
+pub type GraphQueryResponse = BaseResponse<GraphQueryData>;
+
+/// Consistency check request: compare the cube's graph with its vector and keyword stores.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyCheckRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Re-derive divergent vector and keyword entries from the graph.
+    #[serde(default)]
+    pub repair: bool,
+}
+
+/// Divergence between a cube's stores. The graph is the system of record: live memories
+/// (not tombstoned) should have a vector and a keyword entry, and nothing else should.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    pub cube_id: String,
+    /// Live memories in the graph.
+    pub memories: u64,
+    /// Live memories without a vector.
+    pub missing_vectors: Vec<String>,
+    /// Vectors of the cube without a live memory.
+    pub orphan_vectors: Vec<String>,
+    /// Vectors whose cube or scope payload disagrees with the memory.
+    pub stale_vectors: Vec<String>,
+    /// Live memories missing from the keyword index.
+    pub missing_keywords: Vec<String>,
+    /// Keyword entries of the cube without a live memory.
+    pub orphan_keywords: Vec<String>,
+    /// Memories with a pending write intent; skipped because a write may be in progress.
+    pub in_flight: Vec<String>,
+    /// Stores that cannot list their ids, so orphans (and, for the keyword index, missing
+    /// entries) were not checked: `vector`, `keyword`.
+    pub unchecked: Vec<String>,
+    /// Memories repaired (`repair: true`).
+    pub repaired: u64,
+    pub checked_at: String,
+}
+
+/// Consistency check response.
+pub type ConsistencyCheckResponse = BaseResponse<ConsistencyReport>;
+
+// ============================================================================
+// Feedback DTOs
+// ============================================================================
+
+/// Relevance feedback on search results for a query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Query the results were returned for.
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Memories being judged.
+    pub memory_ids: Vec<String>,
+    pub label: crate::FeedbackLabel,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Feedback response: per-memory accumulated counts.
+pub type FeedbackResponse = BaseResponse<Vec<serde_json::Value>>;
+
+/// Feedback export request (offline evaluation).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackExportRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// ISO8601 timestamp; only events at or after it.
+    #[serde(default)]
+    pub since: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Export format: json, jsonl
+    #[serde(default = "default_export_format")]
+    pub format: String,
+}
+
+/// Feedback export response.
+pub type FeedbackExportResponse = BaseResponse<FeedbackExportData>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackExportData {
+    pub total_events: u32,
+    pub data: String,
+}
+
+// ============================================================================
+// Erasure DTOs
+// ============================================================================
+
+/// Erase what a user wrote (nodes, edges, feedback, sessions, jobs, audit inputs), in every
+/// cube or in one; or, with `whole_cube`, everything stored in one cube whoever wrote it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EraseUserDataRequest {
+    pub user_id: String,
+    /// Only erase in this cube; `None` erases the user's data in every cube they wrote to.
+    #[serde(default)]
+    pub mem_cube_id: Option<String>,
+    /// Erase the whole `mem_cube_id` cube, other users' data included.
+    #[serde(default)]
+    pub whole_cube: bool,
+}
+
+impl EraseUserDataRequest {
+    /// Check the request before the job is queued; the error is a client error message.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.user_id.trim().is_empty() {
+            return Err("user_id is required".to_string());
+        }
+        match self.mem_cube_id.as_deref() {
+            Some(cube) if cube.trim().is_empty() => {
+                Err("mem_cube_id must not be empty".to_string())
+            }
+            None if self.whole_cube => Err("whole_cube requires mem_cube_id".to_string()),
+            _ => Ok(()),
+        }
+    }
 }
 
 // ============================================================================
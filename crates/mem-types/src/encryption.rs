@@ -0,0 +1,498 @@
+//! Envelope encryption for stores that encrypt at rest (feature `encryption`).
+//!
+//! Each cube gets its own AES-256-GCM data key (DEK). DEKs are stored wrapped by a master key
+//! (KEK) loaded from a key file or env var; rotating the master key only rewraps DEKs, rotating
+//! a cube's DEK re-encrypts that cube's rows. Sealed values carry the id of their DEK:
+//!
+//! - text: `enc:<key_id>:<base64(nonce || ciphertext)>`
+//! - blob: `ENC1` || key_id (i64 LE) || nonce || ciphertext
+//!
+//! Values without these markers are rejected, so a row swapped for plaintext cannot pass as
+//! stored data. To enable encryption on an existing database, open it with
+//! `EncryptionConfig::allow_plaintext` until data key rotation has sealed every cube's rows.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+const NONCE_LEN: usize = 12;
+const TEXT_PREFIX: &str = "enc:";
+const BLOB_MAGIC: &[u8; 4] = b"ENC1";
+const WRAP_AAD: &[u8] = b"memos-dek";
+
+/// Env var holding the hex master key.
+pub const MASTER_KEY_ENV: &str = "MEMOS_MASTER_KEY";
+/// Env var holding the path of the master key file.
+pub const MASTER_KEY_FILE_ENV: &str = "MEMOS_MASTER_KEY_FILE";
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptionError {
+    #[error("key error: {0}")]
+    Key(String),
+    #[error("crypto error: {0}")]
+    Crypto(String),
+}
+
+fn seal(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|e| EncryptionError::Crypto(e.to_string()))?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn open(key: &[u8; 32], sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(EncryptionError::Crypto("ciphertext too short".to_string()));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| {
+            EncryptionError::Crypto("decryption failed (wrong key or tampered data)".to_string())
+        })
+}
+
+/// Key-encryption key. Its id (a SHA-256 prefix) is stored next to each wrapped DEK so a
+/// wrong master key is detected on open instead of failing row by row.
+#[derive(Clone)]
+pub struct MasterKey {
+    key: [u8; 32],
+    id: String,
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterKey").field("id", &self.id).finish()
+    }
+}
+
+impl MasterKey {
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        let id = hex::encode(&Sha256::digest(key)[..8]);
+        Self { key, id }
+    }
+
+    /// Parse 64 hex chars.
+    pub fn from_hex(hex_key: &str) -> Result<Self, EncryptionError> {
+        let bytes = hex::decode(hex_key.trim()).map_err(|e| EncryptionError::Key(e.to_string()))?;
+        let key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| EncryptionError::Key("master key must be 32 bytes".to_string()))?;
+        Ok(Self::from_bytes(key))
+    }
+
+    /// Read a key file holding either 32 raw bytes or 64 hex chars.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, EncryptionError> {
+        let bytes = std::fs::read(path.as_ref())
+            .map_err(|e| EncryptionError::Key(format!("{}: {}", path.as_ref().display(), e)))?;
+        match <[u8; 32]>::try_from(bytes.as_slice()) {
+            Ok(key) => Ok(Self::from_bytes(key)),
+            Err(_) => Self::from_hex(&String::from_utf8_lossy(&bytes)),
+        }
+    }
+
+    /// Load from `MEMOS_MASTER_KEY_FILE`, else `MEMOS_MASTER_KEY`. `Ok(None)` when neither is set.
+    pub fn from_env() -> Result<Option<Self>, EncryptionError> {
+        if let Ok(path) = std::env::var(MASTER_KEY_FILE_ENV) {
+            if !path.is_empty() {
+                return Self::from_file(path).map(Some);
+            }
+        }
+        match std::env::var(MASTER_KEY_ENV) {
+            Ok(hex_key) if !hex_key.is_empty() => Self::from_hex(&hex_key).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn wrap(&self, dek: &DataKey) -> Result<Vec<u8>, EncryptionError> {
+        seal(&self.key, &dek.0, WRAP_AAD)
+    }
+
+    pub fn unwrap(&self, wrapped: &[u8]) -> Result<DataKey, EncryptionError> {
+        let raw = open(&self.key, wrapped, WRAP_AAD)?;
+        let key: [u8; 32] = raw
+            .try_into()
+            .map_err(|_| EncryptionError::Key("wrapped data key has wrong length".to_string()))?;
+        Ok(DataKey(key))
+    }
+}
+
+/// Per-cube data-encryption key.
+#[derive(Clone)]
+pub struct DataKey([u8; 32]);
+
+impl DataKey {
+    pub fn generate() -> Self {
+        let key = Aes256Gcm::generate_key(&mut OsRng);
+        let mut raw = [0u8; 32];
+        raw.copy_from_slice(&key);
+        Self(raw)
+    }
+}
+
+/// Encryption settings for a SQLite store.
+#[derive(Debug, Clone)]
+pub struct EncryptionConfig {
+    pub master_key: MasterKey,
+    /// Also encrypt embeddings/vectors (they leak less, but can be inverted to approximate text).
+    pub encrypt_vectors: bool,
+    /// Read values without the sealed marker as plaintext. Only for migrating rows written
+    /// before encryption was enabled; off, such values are an error.
+    pub allow_plaintext: bool,
+}
+
+/// A wrapped DEK as persisted by a store.
+#[derive(Debug, Clone)]
+pub struct WrappedKeyRecord {
+    pub key_id: i64,
+    pub cube_id: String,
+    pub wrapped: Vec<u8>,
+    pub master_key_id: String,
+}
+
+/// Unwrapped DEKs of one store, keyed by the store's key id, plus the active key per cube.
+/// Stores persist `WrappedKeyRecord`s themselves and keep this ring in sync.
+pub struct KeyRing {
+    master: RwLock<MasterKey>,
+    keys: RwLock<HashMap<i64, (String, DataKey)>>,
+    active: RwLock<HashMap<String, i64>>,
+    encrypt_vectors: bool,
+    allow_plaintext: bool,
+}
+
+impl KeyRing {
+    /// Unwrap persisted keys; the highest key id per cube is the active one.
+    pub fn load(
+        config: &EncryptionConfig,
+        records: Vec<WrappedKeyRecord>,
+    ) -> Result<Self, EncryptionError> {
+        let ring = Self {
+            master: RwLock::new(config.master_key.clone()),
+            keys: RwLock::new(HashMap::new()),
+            active: RwLock::new(HashMap::new()),
+            encrypt_vectors: config.encrypt_vectors,
+            allow_plaintext: config.allow_plaintext,
+        };
+        for record in records {
+            if record.master_key_id != config.master_key.id() {
+                return Err(EncryptionError::Key(format!(
+                    "data key {} is wrapped by master key {}, not {}",
+                    record.key_id,
+                    record.master_key_id,
+                    config.master_key.id()
+                )));
+            }
+            let dek = config.master_key.unwrap(&record.wrapped)?;
+            ring.insert(record.key_id, &record.cube_id, dek);
+        }
+        Ok(ring)
+    }
+
+    pub fn encrypt_vectors(&self) -> bool {
+        self.encrypt_vectors
+    }
+
+    pub fn master(&self) -> MasterKey {
+        self.master.read().expect("key ring lock").clone()
+    }
+
+    pub fn set_master(&self, master: MasterKey) {
+        *self.master.write().expect("key ring lock") = master;
+    }
+
+    /// Register a key and make it the cube's active key if it is the newest.
+    pub fn insert(&self, key_id: i64, cube_id: &str, dek: DataKey) {
+        self.keys
+            .write()
+            .expect("key ring lock")
+            .insert(key_id, (cube_id.to_string(), dek));
+        let mut active = self.active.write().expect("key ring lock");
+        let entry = active.entry(cube_id.to_string()).or_insert(key_id);
+        if *entry < key_id {
+            *entry = key_id;
+        }
+    }
+
+    /// Drop keys (after rotation re-encrypted their rows, or when a cube is erased).
+    pub fn remove(&self, key_ids: &[i64]) {
+        let mut keys = self.keys.write().expect("key ring lock");
+        let mut active = self.active.write().expect("key ring lock");
+        for id in key_ids {
+            if let Some((cube, _)) = keys.remove(id) {
+                if active.get(&cube) == Some(id) {
+                    active.remove(&cube);
+                }
+            }
+        }
+        // Re-point cubes that still have older keys.
+        for (id, (cube, _)) in keys.iter() {
+            let entry = active.entry(cube.clone()).or_insert(*id);
+            if *entry < *id {
+                *entry = *id;
+            }
+        }
+    }
+
+    pub fn active_key_id(&self, cube_id: &str) -> Option<i64> {
+        self.active
+            .read()
+            .expect("key ring lock")
+            .get(cube_id)
+            .copied()
+    }
+
+    /// The cube's active key with its id.
+    pub fn active_key(&self, cube_id: &str) -> Option<(i64, DataKey)> {
+        let key_id = self.active_key_id(cube_id)?;
+        self.key(key_id).ok().map(|dek| (key_id, dek))
+    }
+
+    /// Key ids belonging to a cube.
+    pub fn key_ids_for_cube(&self, cube_id: &str) -> Vec<i64> {
+        let mut ids: Vec<i64> = self
+            .keys
+            .read()
+            .expect("key ring lock")
+            .iter()
+            .filter(|(_, (cube, _))| cube == cube_id)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    fn key(&self, key_id: i64) -> Result<DataKey, EncryptionError> {
+        self.keys
+            .read()
+            .expect("key ring lock")
+            .get(&key_id)
+            .map(|(_, k)| k.clone())
+            .ok_or_else(|| EncryptionError::Key(format!("unknown data key {}", key_id)))
+    }
+
+    /// Seal text with the given key: `enc:<key_id>:<base64>`.
+    pub fn seal_text(
+        &self,
+        key_id: i64,
+        plaintext: &str,
+        aad: &str,
+    ) -> Result<String, EncryptionError> {
+        Self::seal_text_with(key_id, &self.key(key_id)?, plaintext, aad)
+    }
+
+    /// Seal text with a key that may not be in the ring yet (created in an open transaction).
+    pub fn seal_text_with(
+        key_id: i64,
+        dek: &DataKey,
+        plaintext: &str,
+        aad: &str,
+    ) -> Result<String, EncryptionError> {
+        let sealed = seal(&dek.0, plaintext.as_bytes(), aad.as_bytes())?;
+        Ok(format!(
+            "{}{}:{}",
+            TEXT_PREFIX,
+            key_id,
+            base64::engine::general_purpose::STANDARD.encode(sealed)
+        ))
+    }
+
+    /// Open a sealed text. Plaintext values are returned unchanged only with `allow_plaintext`.
+    pub fn open_text(&self, value: &str, aad: &str) -> Result<String, EncryptionError> {
+        let Some(key_id) = sealed_text_key_id(value) else {
+            return match self.allow_plaintext {
+                true => Ok(value.to_string()),
+                false => Err(EncryptionError::Crypto("value is not sealed".to_string())),
+            };
+        };
+        let encoded = value
+            .splitn(3, ':')
+            .nth(2)
+            .ok_or_else(|| EncryptionError::Crypto("malformed sealed value".to_string()))?;
+        let sealed = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| EncryptionError::Crypto(e.to_string()))?;
+        let plain = open(&self.key(key_id)?.0, &sealed, aad.as_bytes())?;
+        String::from_utf8(plain).map_err(|e| EncryptionError::Crypto(e.to_string()))
+    }
+
+    /// Seal a blob with the given key.
+    pub fn seal_blob(
+        &self,
+        key_id: i64,
+        plaintext: &[u8],
+        aad: &str,
+    ) -> Result<Vec<u8>, EncryptionError> {
+        Self::seal_blob_with(key_id, &self.key(key_id)?, plaintext, aad)
+    }
+
+    /// Seal a blob with a key that may not be in the ring yet.
+    pub fn seal_blob_with(
+        key_id: i64,
+        dek: &DataKey,
+        plaintext: &[u8],
+        aad: &str,
+    ) -> Result<Vec<u8>, EncryptionError> {
+        let mut out = BLOB_MAGIC.to_vec();
+        out.extend_from_slice(&key_id.to_le_bytes());
+        out.extend_from_slice(&seal(&dek.0, plaintext, aad.as_bytes())?);
+        Ok(out)
+    }
+
+    /// Open a sealed blob. Plaintext blobs are returned unchanged when vectors are not
+    /// encrypted or with `allow_plaintext`.
+    pub fn open_blob(&self, value: &[u8], aad: &str) -> Result<Vec<u8>, EncryptionError> {
+        let Some(key_id) = sealed_blob_key_id(value) else {
+            return match self.allow_plaintext || !self.encrypt_vectors {
+                true => Ok(value.to_vec()),
+                false => Err(EncryptionError::Crypto("value is not sealed".to_string())),
+            };
+        };
+        open(&self.key(key_id)?.0, &value[12..], aad.as_bytes())
+    }
+}
+
+/// Key id of a sealed text value (None for plaintext).
+pub fn sealed_text_key_id(value: &str) -> Option<i64> {
+    let rest = value.strip_prefix(TEXT_PREFIX)?;
+    rest.split(':').next()?.parse().ok()
+}
+
+/// Key id of a sealed blob (None for plaintext).
+pub fn sealed_blob_key_id(value: &[u8]) -> Option<i64> {
+    if value.len() < 12 || &value[..4] != BLOB_MAGIC {
+        return None;
+    }
+    let mut id = [0u8; 8];
+    id.copy_from_slice(&value[4..12]);
+    Some(i64::from_le_bytes(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(master: &MasterKey, records: Vec<WrappedKeyRecord>, allow_plaintext: bool) -> KeyRing {
+        let config = EncryptionConfig {
+            master_key: master.clone(),
+            encrypt_vectors: true,
+            allow_plaintext,
+        };
+        KeyRing::load(&config, records).unwrap()
+    }
+
+    fn record(master: &MasterKey, key_id: i64, dek: &DataKey) -> WrappedKeyRecord {
+        WrappedKeyRecord {
+            key_id,
+            cube_id: "c1".to_string(),
+            wrapped: master.wrap(dek).unwrap(),
+            master_key_id: master.id().to_string(),
+        }
+    }
+
+    #[test]
+    fn sealed_values_round_trip_and_are_bound_to_their_aad() {
+        let master = MasterKey::from_bytes([1; 32]);
+        let dek = DataKey::generate();
+        let keys = ring(&master, vec![record(&master, 7, &dek)], false);
+
+        let text = keys.seal_text(7, "secret", "nodes.memory:a").unwrap();
+        assert_eq!(sealed_text_key_id(&text), Some(7));
+        assert!(!text.contains("secret"));
+        assert_eq!(keys.open_text(&text, "nodes.memory:a").unwrap(), "secret");
+        assert!(keys.open_text(&text, "nodes.memory:b").is_err());
+
+        let blob = keys.seal_blob(7, b"[0.5]", "nodes.embedding:a").unwrap();
+        assert_eq!(sealed_blob_key_id(&blob), Some(7));
+        assert_eq!(
+            keys.open_blob(&blob, "nodes.embedding:a").unwrap(),
+            b"[0.5]"
+        );
+        assert!(keys.open_blob(&blob, "nodes.embedding:b").is_err());
+    }
+
+    #[test]
+    fn unknown_key_ids_and_tampered_ciphertext_fail() {
+        let master = MasterKey::from_bytes([1; 32]);
+        let dek = DataKey::generate();
+        let keys = ring(&master, vec![record(&master, 7, &dek)], false);
+
+        let foreign = KeyRing::seal_text_with(8, &dek, "secret", "aad").unwrap();
+        assert!(keys.open_text(&foreign, "aad").is_err());
+
+        let text = keys.seal_text(7, "secret", "aad").unwrap();
+        let (prefix, encoded) = text.rsplit_once(':').unwrap();
+        let mut sealed = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        let tampered = format!(
+            "{}:{}",
+            prefix,
+            base64::engine::general_purpose::STANDARD.encode(sealed)
+        );
+        assert!(keys.open_text(&tampered, "aad").is_err());
+
+        let mut blob = keys.seal_blob(7, b"[0.5]", "aad").unwrap();
+        blob[12] ^= 1;
+        assert!(keys.open_blob(&blob, "aad").is_err());
+    }
+
+    #[test]
+    fn rewrapped_keys_open_only_with_the_new_master() {
+        let old = MasterKey::from_bytes([1; 32]);
+        let new = MasterKey::from_bytes([2; 32]);
+        let dek = DataKey::generate();
+        let keys = ring(&old, vec![record(&old, 7, &dek)], false);
+        let text = keys.seal_text(7, "secret", "aad").unwrap();
+
+        // Master rotation rewraps the data key; row data is untouched.
+        let unwrapped = old.unwrap(&record(&old, 7, &dek).wrapped).unwrap();
+        let records = vec![record(&new, 7, &unwrapped)];
+        let config = EncryptionConfig {
+            master_key: old,
+            encrypt_vectors: true,
+            allow_plaintext: false,
+        };
+        assert!(KeyRing::load(&config, records.clone()).is_err());
+        let keys = ring(&new, records, false);
+        assert_eq!(keys.open_text(&text, "aad").unwrap(), "secret");
+    }
+
+    #[test]
+    fn plaintext_is_rejected_unless_migrating() {
+        let master = MasterKey::from_bytes([1; 32]);
+        let keys = ring(&master, Vec::new(), false);
+        assert!(keys.open_text("plain", "aad").is_err());
+        assert!(keys.open_blob(b"[0.5]", "aad").is_err());
+
+        let migrating = ring(&master, Vec::new(), true);
+        assert_eq!(migrating.open_text("plain", "aad").unwrap(), "plain");
+        assert_eq!(migrating.open_blob(b"[0.5]", "aad").unwrap(), b"[0.5]");
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Provides structures for extracted entities, their relations, and metadata.
 
+use crate::dto::{GraphDirection, MemoryItem};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::str::FromStr;
@@ -116,6 +117,12 @@ pub struct EntityMetadata {
     pub source_memory_id: String,
     /// Confidence score from NER extraction (0.0 - 1.0).
     pub confidence: f64,
+    /// Memories already folded into the entity's description (its profile summary).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub description_memory_ids: Vec<String>,
+    /// When the description was last generated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_updated_at: Option<String>,
 }
 
 impl EntityMetadata {
@@ -128,6 +135,8 @@ impl EntityMetadata {
             occurrence_count: 1,
             source_memory_id,
             confidence,
+            description_memory_ids: Vec::new(),
+            description_updated_at: None,
         }
     }
 }
@@ -141,6 +150,9 @@ pub struct Entity {
     pub name: String,
     /// The type of this entity.
     pub entity_type: EntityType,
+    /// Cube (tenant) this entity belongs to; names are unique per cube only.
+    #[serde(default)]
+    pub cube_id: String,
     /// Alternative names/variants for fuzzy matching.
     #[serde(default)]
     pub name_variants: Vec<String>,
@@ -150,9 +162,12 @@ pub struct Entity {
     /// IDs of memories associated with this entity.
     #[serde(default)]
     pub memory_ids: Vec<String>,
-    /// Key-value attributes/properties of this entity.
+    /// Key-value attributes/properties of this entity (current values).
     #[serde(default)]
     pub attributes: HashMap<String, serde_json::Value>,
+    /// Every value an attribute has had, with when it held; see [`Entity::set_attribute`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attribute_history: Vec<AttributeFact>,
     /// Additional metadata.
     pub metadata: EntityMetadata,
     /// Version number for optimistic locking.
@@ -172,10 +187,12 @@ impl Entity {
             id,
             name,
             entity_type,
+            cube_id: String::new(),
             name_variants: Vec::new(),
             description: None,
             memory_ids: vec![source_memory_id.clone()],
             attributes: HashMap::new(),
+            attribute_history: Vec::new(),
             metadata: EntityMetadata::new(source_memory_id, confidence),
             version: 0,
         }
@@ -201,6 +218,164 @@ impl Entity {
         self.version += 1;
         self.metadata.last_updated = chrono::Utc::now().to_rfc3339();
     }
+
+    /// Record a value of an attribute. A different value closes the open fact of the key
+    /// (at the new fact's `valid_from`, default now) instead of overwriting it; the same value
+    /// only adds evidence. `attributes` keeps the values that are still open.
+    pub fn set_attribute(&mut self, key: &str, value: serde_json::Value, mut validity: Validity) {
+        let now = chrono::Utc::now().to_rfc3339();
+        if validity.valid_from.is_none() {
+            validity.valid_from = Some(now);
+        }
+        // Values set before history was kept become an open-ended fact of their own.
+        if let Some(current) = self.attributes.get(key) {
+            if !self.attribute_history.iter().any(|f| f.key == key) {
+                self.attribute_history.push(AttributeFact {
+                    key: key.to_string(),
+                    value: current.clone(),
+                    validity: Validity::default(),
+                });
+            }
+        }
+        if let Some(open) = self
+            .attribute_history
+            .iter_mut()
+            .find(|f| f.key == key && f.validity.valid_to.is_none() && f.value == value)
+        {
+            open.validity.absorb(&validity);
+        } else {
+            for fact in self
+                .attribute_history
+                .iter_mut()
+                .filter(|f| f.key == key && f.validity.valid_to.is_none())
+            {
+                fact.validity.valid_to = validity.valid_from.clone();
+            }
+            self.attribute_history.push(AttributeFact {
+                key: key.to_string(),
+                value,
+                validity,
+            });
+        }
+        match self
+            .attribute_history
+            .iter()
+            .rev()
+            .find(|f| f.key == key && f.validity.valid_to.is_none())
+        {
+            Some(open) => {
+                self.attributes.insert(key.to_string(), open.value.clone());
+            }
+            None => {
+                self.attributes.remove(key);
+            }
+        }
+        self.increment_version();
+    }
+
+    /// Attribute values that held at `at` (latest `valid_from` wins); attributes without
+    /// history count as always valid.
+    pub fn attributes_as_of(
+        &self,
+        at: &chrono::DateTime<chrono::Utc>,
+    ) -> HashMap<String, serde_json::Value> {
+        let mut out: HashMap<String, serde_json::Value> = self
+            .attributes
+            .iter()
+            .filter(|(key, _)| !self.attribute_history.iter().any(|f| &f.key == *key))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        let mut held: Vec<&AttributeFact> = self
+            .attribute_history
+            .iter()
+            .filter(|f| f.validity.holds_at(at))
+            .collect();
+        held.sort_by_key(|f| f.validity.valid_from.as_deref().and_then(parse_timestamp));
+        for fact in held {
+            out.insert(fact.key.clone(), fact.value.clone());
+        }
+        out
+    }
+}
+
+/// One value of an entity attribute and when it held.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeFact {
+    pub key: String,
+    pub value: serde_json::Value,
+    #[serde(flatten)]
+    pub validity: Validity,
+}
+
+/// When a fact (a relation or an attribute value) holds and what supports it. Bounds are
+/// RFC 3339 timestamps or `YYYY-MM-DD` dates; a missing bound is open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Validity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<String>,
+    /// Memories the fact was extracted from or asserted by.
+    #[serde(default)]
+    pub source_memory_ids: Vec<String>,
+    #[serde(default = "default_validity_confidence")]
+    pub confidence: f64,
+}
+
+fn default_validity_confidence() -> f64 {
+    1.0
+}
+
+impl Default for Validity {
+    fn default() -> Self {
+        Self {
+            valid_from: None,
+            valid_to: None,
+            source_memory_ids: Vec::new(),
+            confidence: default_validity_confidence(),
+        }
+    }
+}
+
+impl Validity {
+    /// Whether the fact held at `at`: `valid_from <= at < valid_to`. Unparsable bounds are
+    /// treated as open.
+    pub fn holds_at(&self, at: &chrono::DateTime<chrono::Utc>) -> bool {
+        let from = self.valid_from.as_deref().and_then(parse_timestamp);
+        let to = self.valid_to.as_deref().and_then(parse_timestamp);
+        from.is_none_or(|from| from <= *at) && to.is_none_or(|to| *at < to)
+    }
+
+    /// Fold another assertion of the same fact into this one: evidence is unioned, the higher
+    /// confidence and the earlier start are kept, and an explicit end from `other` applies.
+    pub fn absorb(&mut self, other: &Validity) {
+        for memory_id in &other.source_memory_ids {
+            if !self.source_memory_ids.contains(memory_id) {
+                self.source_memory_ids.push(memory_id.clone());
+            }
+        }
+        self.confidence = self.confidence.max(other.confidence);
+        let start = |v: &Option<String>| v.as_deref().and_then(parse_timestamp);
+        if let (Some(mine), Some(theirs)) = (start(&self.valid_from), start(&other.valid_from)) {
+            if theirs < mine {
+                self.valid_from = other.valid_from.clone();
+            }
+        }
+        if other.valid_to.is_some() {
+            self.valid_to = other.valid_to.clone();
+        }
+    }
+}
+
+/// Parse an RFC 3339 timestamp or a `YYYY-MM-DD` date (midnight UTC).
+pub fn parse_timestamp(raw: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(ts) = chrono::DateTime::parse_from_rfc3339(raw) {
+        return Some(ts.with_timezone(&chrono::Utc));
+    }
+    chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
 }
 
 /// A lightweight reference to an entity within a memory.
@@ -569,3 +744,184 @@ impl Default for RerankConfig {
         }
     }
 }
+
+/// Statistics about an entity knowledge graph.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntityKgStats {
+    pub total_entities: usize,
+    pub total_relations: usize,
+    pub type_counts: HashMap<String, u32>,
+}
+
+/// A relation between two stored entities (by id).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredRelation {
+    pub source_id: String,
+    pub target_id: String,
+    pub relation_type: EntityRelationType,
+    #[serde(flatten)]
+    pub validity: Validity,
+}
+
+impl StoredRelation {
+    /// A relation without temporal bounds or evidence.
+    pub fn new(
+        source_id: impl Into<String>,
+        target_id: impl Into<String>,
+        relation_type: EntityRelationType,
+    ) -> Self {
+        Self {
+            source_id: source_id.into(),
+            target_id: target_id.into(),
+            relation_type,
+            validity: Validity::default(),
+        }
+    }
+}
+
+/// Snapshot of an entity knowledge graph for serialization and persistence.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntityKgSnapshot {
+    pub entities: Vec<Entity>,
+    pub relations: Vec<StoredRelation>,
+    pub timestamp: String,
+}
+
+/// A proposed merge of two entities of one cube that likely denote the same thing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityMergeCandidate {
+    /// Entity to keep (the more frequently mentioned one).
+    pub keep_id: String,
+    pub keep_name: String,
+    /// Entity that would be folded into `keep_id`.
+    pub merge_id: String,
+    pub merge_name: String,
+    pub entity_type: EntityType,
+    /// Combined score (0.0 - 1.0).
+    pub confidence: f64,
+    /// Name similarity: embedding cosine or token/initial match, whichever is higher.
+    pub name_similarity: f64,
+    /// Overlap of co-mentioned and related entities.
+    pub context_similarity: f64,
+}
+
+/// What to move out of an entity into a new one when splitting it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntitySplit {
+    /// Name of the new entity; must not already exist in the cube.
+    pub name: String,
+    /// Type of the new entity (defaults to the source entity's type).
+    #[serde(default)]
+    pub entity_type: Option<EntityType>,
+    /// Memories that mention the new entity instead of the source.
+    #[serde(default)]
+    pub memory_ids: Vec<String>,
+    /// Name variants to move to the new entity.
+    #[serde(default)]
+    pub variants: Vec<String>,
+    /// Targets of outgoing relations to move to the new entity.
+    #[serde(default)]
+    pub relation_targets: Vec<String>,
+}
+
+/// Options for multi-hop traversal of entity relations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityTraversal {
+    /// Follow outgoing relations, incoming ones, or both.
+    #[serde(default)]
+    pub direction: GraphDirection,
+    /// Only follow relations of these types (all types when absent or empty).
+    #[serde(default)]
+    pub relation_types: Option<Vec<EntityRelationType>>,
+    /// Maximum number of hops from the start entity.
+    #[serde(default = "default_traversal_depth")]
+    pub max_depth: u32,
+    /// Upper bound on the entities visited, start included.
+    #[serde(default = "default_traversal_max_entities")]
+    pub max_entities: u32,
+    /// Only follow relations that held at this time (RFC 3339 or `YYYY-MM-DD`).
+    #[serde(default)]
+    pub as_of: Option<String>,
+}
+
+fn default_traversal_depth() -> u32 {
+    2
+}
+
+fn default_traversal_max_entities() -> u32 {
+    100
+}
+
+impl Default for EntityTraversal {
+    fn default() -> Self {
+        Self {
+            direction: GraphDirection::default(),
+            relation_types: None,
+            max_depth: default_traversal_depth(),
+            max_entities: default_traversal_max_entities(),
+            as_of: None,
+        }
+    }
+}
+
+impl EntityTraversal {
+    /// Whether relations of this type may be followed.
+    pub fn allows(&self, relation_type: &EntityRelationType) -> bool {
+        self.relation_types
+            .as_ref()
+            .filter(|types| !types.is_empty())
+            .is_none_or(|types| types.contains(relation_type))
+    }
+
+    /// Whether a relation may be followed: allowed type and, with `as_of`, valid at that time.
+    pub fn follows(&self, relation_type: &EntityRelationType, validity: &Validity) -> bool {
+        self.allows(relation_type)
+            && self
+                .as_of
+                .as_deref()
+                .and_then(parse_timestamp)
+                .is_none_or(|at| validity.holds_at(&at))
+    }
+}
+
+/// A path between two entities; `relations[i]` links `entities[i]` and `entities[i + 1]`
+/// (as stored, so it may point backwards when traversing inbound).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityPath {
+    pub entities: Vec<Entity>,
+    pub relations: Vec<StoredRelation>,
+}
+
+/// An entity of a subgraph with its hop distance from the center and the memories mentioning it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitySubgraphNode {
+    pub entity: Entity,
+    pub depth: u32,
+    #[serde(default)]
+    pub memories: Vec<MemoryItem>,
+}
+
+/// What is known about an entity: the entity (its `description` is the profile summary) and the
+/// linked memories, most recently linked first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityProfile {
+    pub entity: Entity,
+    #[serde(default)]
+    pub memories: Vec<MemoryItem>,
+    /// Linked memories not yet folded into the description.
+    #[serde(default)]
+    pub pending_memory_ids: Vec<String>,
+    /// True when this call regenerated the description.
+    #[serde(default)]
+    pub refreshed: bool,
+}
+
+/// Entity-centric subgraph: entities within `max_depth` hops of the center (center first, by
+/// distance) and the relations among them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitySubgraph {
+    pub nodes: Vec<EntitySubgraphNode>,
+    pub relations: Vec<StoredRelation>,
+    /// True when `max_entities` stopped the traversal before `max_depth` was exhausted.
+    pub truncated: bool,
+}
@@ -3,6 +3,8 @@
 //! Request/response DTOs align with MemOS `product_models.py` for JSON compatibility.
 
 mod dto;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 mod entity;
 mod job;
 mod lifecycle;
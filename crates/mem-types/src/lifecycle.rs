@@ -37,6 +37,14 @@ pub enum AuditEventKind {
     Update,
     Forget,
     Search,
+    Feedback,
+    Erase,
+    #[serde(rename = "edge_add")]
+    EdgeAdd,
+    #[serde(rename = "edge_update")]
+    EdgeUpdate,
+    #[serde(rename = "edge_delete")]
+    EdgeDelete,
 }
 
 /// One audit event (for governance and debugging).
@@ -65,3 +73,233 @@ pub struct AuditListOptions {
     pub limit: Option<u32>,
     pub offset: Option<u32>,
 }
+
+/// What to do with PII (emails, phone numbers) found in memory text on ingest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PiiAction {
+    /// Store verbatim; record detected kinds in `metadata.pii_kinds`.
+    #[default]
+    Detect,
+    /// Replace each value with a placeholder such as `[EMAIL]`.
+    Mask,
+    /// Replace each value with a keyed hash, e.g. `[EMAIL:3f2a9c01b7de]`.
+    Hash,
+    /// Refuse the memory (400 / batch failure).
+    Reject,
+}
+
+/// Per-cube PII policy applied before embedding.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PiiPolicy {
+    pub action: PiiAction,
+    /// For mask/hash: keep the original text AES-256-GCM encrypted in `metadata.pii_original`.
+    #[serde(default)]
+    pub encrypt_original: bool,
+}
+
+/// Number of records removed per store by an erasure.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErasureCounts {
+    pub memories: u64,
+    pub vectors: u64,
+    pub keyword_docs: u64,
+    pub sessions: u64,
+    pub entities: u64,
+    pub feedback_events: u64,
+    pub jobs: u64,
+    pub audit_events_redacted: u64,
+}
+
+/// What an erasure removed from the cube's stores.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErasureOutcome {
+    /// Cubes the erasure covered, sorted.
+    pub cubes: Vec<String>,
+    pub counts: ErasureCounts,
+}
+
+/// Completion report of an erasure job, signed so it can be handed to the data subject.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErasureReport {
+    pub erasure_id: String,
+    pub user_id: String,
+    /// Cube named by the request; `None` when the user was erased from every cube.
+    pub cube_id: Option<String>,
+    pub whole_cube: bool,
+    /// Cubes the erasure covered.
+    pub cubes: Vec<String>,
+    pub requested_at: String,
+    pub completed_at: String,
+    pub counts: ErasureCounts,
+    /// Signature algorithm, e.g. `HMAC-SHA256`.
+    pub algorithm: String,
+    /// Hex signature over the report with `signature` set to an empty string.
+    pub signature: String,
+}
+
+/// Relevance judgement for a memory returned by search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackLabel {
+    Helpful,
+    Wrong,
+}
+
+/// One feedback signal (relevance feedback or a feedback add), kept for offline evaluation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackEvent {
+    pub event_id: String,
+    pub user_id: String,
+    pub cube_id: String,
+    /// Memory the feedback is about.
+    pub memory_id: String,
+    /// Relevance label; `None` for annotations from feedback adds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<FeedbackLabel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Memory created by an `is_feedback` add, if the signal came from one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feedback_memory_id: Option<String>,
+    pub timestamp: String,
+}
+
+/// Multi-store write recorded in the intent log before any store is touched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntentOp {
+    /// New memory; an unfinished add is rolled back (removed from every store).
+    Add,
+    /// Text, metadata or scope change; replayed by re-deriving the vector and keyword
+    /// entries from the graph node.
+    Update,
+    /// Soft or hard delete; replayed like `Update` (a missing or tombstoned node has no
+    /// vector or keyword entry).
+    Forget,
+    /// A committed memory whose vector or keyword entry could not be written; replayed like
+    /// `Update`.
+    Repair,
+}
+
+/// One pending multi-store write. The graph is the system of record: replay makes the vector
+/// and keyword stores agree with it (or removes an unfinished add everywhere).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WriteIntent {
+    pub intent_id: String,
+    pub op: IntentOp,
+    pub memory_id: String,
+    pub cube_id: String,
+    pub created_at: String,
+}
+
+/// Options for listing feedback events.
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackListOptions {
+    pub user_id: Option<String>,
+    pub cube_id: Option<String>,
+    /// ISO8601 timestamp; return events with timestamp >= since.
+    pub since: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Usage signals tracked per memory in `MemoryNode.metadata`
+/// (`importance`, `access_count`, `last_accessed_at`, `feedback_helpful`, `feedback_wrong`,
+/// and `centrality` written by graph analytics).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemorySignals {
+    /// Importance in [0, 1]; set on add, adjustable via update.
+    pub importance: f64,
+    /// Number of times the memory was returned by search or get.
+    pub access_count: u64,
+    /// ISO8601 timestamp of the last retrieval.
+    pub last_accessed_at: Option<String>,
+    /// Times the memory was marked helpful.
+    pub helpful_count: u64,
+    /// Times the memory was marked wrong or corrected by a feedback add.
+    pub wrong_count: u64,
+    /// PageRank relative to the cube's top memory, in [0, 1]; 0 until graph analytics ran.
+    pub centrality: f64,
+}
+
+impl MemorySignals {
+    pub const DEFAULT_IMPORTANCE: f64 = 0.5;
+    /// Half-life (days) of the recency factor in `retention_score`.
+    pub const RETENTION_HALF_LIFE_DAYS: f64 = 30.0;
+
+    /// Read signals from node metadata, falling back to defaults for missing keys.
+    pub fn from_metadata(metadata: &std::collections::HashMap<String, serde_json::Value>) -> Self {
+        Self {
+            importance: metadata
+                .get("importance")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(Self::DEFAULT_IMPORTANCE)
+                .clamp(0.0, 1.0),
+            access_count: metadata
+                .get("access_count")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            last_accessed_at: metadata
+                .get("last_accessed_at")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            helpful_count: metadata
+                .get("feedback_helpful")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            wrong_count: metadata
+                .get("feedback_wrong")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            centrality: metadata
+                .get("centrality")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0)
+                .clamp(0.0, 1.0),
+        }
+    }
+
+    /// Parse an importance value; only numbers in [0, 1] are accepted.
+    pub fn parse_importance(value: &serde_json::Value) -> Option<f64> {
+        value.as_f64().filter(|v| (0.0..=1.0).contains(v))
+    }
+
+    /// Feedback boost in (-0.3, 0.3): smoothed balance of helpful vs wrong judgements.
+    pub fn feedback_boost(&self) -> f64 {
+        let helpful = self.helpful_count as f64;
+        let wrong = self.wrong_count as f64;
+        0.3 * (helpful - wrong) / (helpful + wrong + 2.0)
+    }
+
+    /// Multiplicative ranking boost: around 1.0 for a default, never-accessed memory;
+    /// importance moves it by up to ±10%, usage adds at most another 10%,
+    /// relevance feedback up to ±30%, and graph centrality up to 5%.
+    pub fn ranking_boost(&self) -> f64 {
+        let importance = (self.importance - Self::DEFAULT_IMPORTANCE) * 0.2;
+        let usage = ((1.0 + self.access_count as f64).ln() * 0.02).min(0.1);
+        1.0 + importance + usage + self.feedback_boost() + 0.05 * self.centrality
+    }
+
+    /// Retention score in [0, 1]: importance and usage, decayed by time since last access
+    /// (or `created_at`). Consolidation reads memories in this order.
+    pub fn retention_score(
+        &self,
+        created_at: Option<&str>,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> f64 {
+        let last_touch = self
+            .last_accessed_at
+            .as_deref()
+            .or(created_at)
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&chrono::Utc));
+        let age_days = last_touch
+            .map(|t| (now - t).num_seconds().max(0) as f64 / 86_400.0)
+            .unwrap_or(0.0);
+        let recency = 0.5f64.powf(age_days / Self::RETENTION_HALF_LIFE_DAYS);
+        let usage = 1.0 - 1.0 / (1.0 + self.access_count as f64);
+        (0.5 * self.importance + 0.2 * usage + 0.3 * recency).clamp(0.0, 1.0)
+    }
+}
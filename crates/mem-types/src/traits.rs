@@ -2,7 +2,9 @@
 
 use crate::{
     ApiAddRequest, ApiHybridSearchRequest, ApiSearchRequest, AuditEvent, AuditListOptions,
-    ForgetMemoryRequest, ForgetMemoryResponse, GetMemoryRequest, GetMemoryResponse, GraphDirection,
+    FeedbackEvent, FeedbackListOptions, ForgetMemoryRequest, ForgetMemoryResponse,
+    GetMemoryRequest, GetMemoryResponse, GraphDirection, GraphEdgeAddRequest, GraphEdgeListRequest,
+    GraphEdgeListResponse, GraphEdgeRequest, GraphEdgeResponse, GraphEdgeUpdateRequest,
     GraphNeighbor, GraphNeighborsRequest, GraphNeighborsResponse, GraphPath, GraphPathRequest,
     GraphPathResponse, GraphPathsRequest, GraphPathsResponse, HybridSearchResponse, MemoryEdge,
     MemoryNode, MemoryResponse, SearchResponse, UpdateMemoryRequest, UpdateMemoryResponse,
@@ -43,6 +45,44 @@ pub trait GraphStore: Send + Sync {
         user_name: Option<&str>,
     ) -> Result<(), GraphStoreError>;
 
+    /// Get one edge by id. With `user_name`, edges owned by another user/cube are `None`.
+    async fn get_edge(
+        &self,
+        id: &str,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError>;
+
+    /// Change an edge's relation and/or merge `metadata` into its metadata (a `null` value
+    /// removes the key; the owner cannot be changed). Returns the updated edge, or `None` when
+    /// it does not exist or, with `user_name`, belongs to another user/cube.
+    async fn update_edge(
+        &self,
+        id: &str,
+        relation: Option<&str>,
+        metadata: &HashMap<String, serde_json::Value>,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError>;
+
+    /// Delete one edge. Returns the deleted edge, or `None` when it does not exist or, with
+    /// `user_name`, belongs to another user/cube.
+    async fn delete_edge(
+        &self,
+        id: &str,
+        user_name: Option<&str>,
+    ) -> Result<Option<MemoryEdge>, GraphStoreError>;
+
+    /// Edges owned by `user_name` ordered by id, starting after edge id `after`; optionally only
+    /// the edges of `node_id` in `direction`, and only those with `relation`.
+    async fn list_edges(
+        &self,
+        user_name: &str,
+        node_id: Option<&str>,
+        relation: Option<&str>,
+        direction: GraphDirection,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<MemoryEdge>, GraphStoreError>;
+
     /// Get one node by id.
     async fn get_node(
         &self,
@@ -111,6 +151,15 @@ pub trait GraphStore: Send + Sync {
         include_embedding: bool,
     ) -> Result<Vec<MemoryNode>, GraphStoreError>;
 
+    /// The live (non-tombstoned) memory of `user_name` with `session_id` metadata added last,
+    /// other than `exclude_id`.
+    async fn latest_in_session(
+        &self,
+        user_name: &str,
+        session_id: &str,
+        exclude_id: &str,
+    ) -> Result<Option<MemoryNode>, GraphStoreError>;
+
     /// Update fields of an existing node (memory and/or metadata).
     async fn update_node(
         &self,
@@ -129,6 +178,22 @@ pub trait GraphStore: Send + Sync {
         id: &str,
         user_name: Option<&str>,
     ) -> Result<usize, GraphStoreError>;
+
+    /// Delete every node and edge owned by `user_name` (tombstones included).
+    /// Returns the ids of the deleted nodes.
+    async fn delete_all_by_user(&self, user_name: &str) -> Result<Vec<String>, GraphStoreError>;
+
+    /// Cubes holding nodes or edges written by `author` (`author_id` metadata; rows without
+    /// one count as written by the user the cube is named after). Sorted.
+    async fn author_cubes(&self, author: &str) -> Result<Vec<String>, GraphStoreError>;
+
+    /// Delete the nodes and edges `author` wrote in `user_name`'s cube (tombstones included),
+    /// and every edge touching those nodes. Returns the ids of the deleted nodes.
+    async fn delete_by_author(
+        &self,
+        user_name: &str,
+        author: &str,
+    ) -> Result<Vec<String>, GraphStoreError>;
 }
 
 /// Vector store abstraction (subset of MemOS BaseVecDB).
@@ -166,6 +231,17 @@ pub trait VecStore: Send + Sync {
         items: &[VecStoreItem],
         collection: Option<&str>,
     ) -> Result<(), VecStoreError>;
+
+    /// Ids of the items whose `mem_cube_id` payload is `cube_id` (for consistency checks).
+    async fn list_ids(
+        &self,
+        _cube_id: &str,
+        _collection: Option<&str>,
+    ) -> Result<Vec<String>, VecStoreError> {
+        Err(VecStoreError::Other(
+            "listing ids not supported".to_string(),
+        ))
+    }
 }
 
 /// Item for vector store (id, vector, payload).
@@ -209,6 +285,13 @@ pub trait KeywordStore: Send + Sync {
         user_name: Option<&str>,
         filter: Option<&HashMap<String, serde_json::Value>>,
     ) -> Result<Vec<KeywordSearchHit>, KeywordStoreError>;
+
+    /// Ids of the documents indexed for the user/cube (for consistency checks).
+    async fn list_ids(&self, _user_name: Option<&str>) -> Result<Vec<String>, KeywordStoreError> {
+        Err(KeywordStoreError::Other(
+            "listing ids not supported".to_string(),
+        ))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -296,6 +379,56 @@ pub trait MemCube: Send + Sync {
         req: &GraphPathsRequest,
     ) -> Result<GraphPathsResponse, MemCubeError>;
 
+    /// Create an edge between two memories of the cube.
+    async fn add_edge(&self, req: &GraphEdgeAddRequest) -> Result<GraphEdgeResponse, MemCubeError>;
+
+    /// Get one edge of the cube.
+    async fn get_edge(&self, req: &GraphEdgeRequest) -> Result<GraphEdgeResponse, MemCubeError>;
+
+    /// Change an edge's relation and/or metadata.
+    async fn update_edge(
+        &self,
+        req: &GraphEdgeUpdateRequest,
+    ) -> Result<GraphEdgeResponse, MemCubeError>;
+
+    /// Delete one edge; the response carries the deleted edge.
+    async fn delete_edge(&self, req: &GraphEdgeRequest) -> Result<GraphEdgeResponse, MemCubeError>;
+
+    /// List the cube's edges, ordered by id.
+    async fn list_edges(
+        &self,
+        req: &GraphEdgeListRequest,
+    ) -> Result<GraphEdgeListResponse, MemCubeError>;
+
+    /// Analyze the cube's memory graph and write PageRank, centrality, component and community
+    /// ids back to node metadata. Usually run as a scheduler job.
+    async fn graph_analytics(
+        &self,
+        _req: &crate::dto::GraphAnalyticsRequest,
+    ) -> Result<crate::dto::GraphAnalyticsReport, MemCubeError> {
+        Err(MemCubeError::Other(
+            "graph analytics not supported".to_string(),
+        ))
+    }
+
+    /// Compare the cube's graph, vector and keyword stores and optionally repair divergence.
+    async fn check_consistency(
+        &self,
+        _req: &crate::dto::ConsistencyCheckRequest,
+    ) -> Result<crate::dto::ConsistencyCheckResponse, MemCubeError> {
+        Err(MemCubeError::Other(
+            "consistency checks not supported".to_string(),
+        ))
+    }
+
+    /// Run a Cypher-like pattern query over the cube's memory graph.
+    async fn graph_query(
+        &self,
+        _req: &crate::dto::GraphQueryRequest,
+    ) -> Result<crate::dto::GraphQueryResponse, MemCubeError> {
+        Err(MemCubeError::Other("graph query not supported".to_string()))
+    }
+
     /// Hybrid search (vector + optional graph + optional keyword). Default: not supported.
     async fn hybrid_search(
         &self,
@@ -329,6 +462,12 @@ pub trait MemCube: Send + Sync {
         req: &crate::dto::ExportRequest,
     ) -> Result<crate::dto::ExportResponse, MemCubeError>;
 
+    /// Export the cube's memory graph (nodes and edges) as JSON Graph Format, GraphML or DOT.
+    async fn export_graph(
+        &self,
+        req: &crate::dto::GraphExportRequest,
+    ) -> Result<crate::dto::GraphExportResponse, MemCubeError>;
+
     // ============================================================================
     // Session Management (P1-3)
     // ============================================================================
@@ -373,6 +512,214 @@ pub trait MemCube: Send + Sync {
         &self,
         req: &crate::dto::SummarizeRequest,
     ) -> Result<crate::dto::SummarizeResponse, MemCubeError>;
+
+    // ============================================================================
+    // Feedback
+    // ============================================================================
+
+    /// Record relevance feedback (helpful / wrong) for memories returned by a query.
+    async fn submit_feedback(
+        &self,
+        _req: &crate::dto::FeedbackRequest,
+    ) -> Result<crate::dto::FeedbackResponse, MemCubeError> {
+        Err(MemCubeError::Other("feedback not supported".to_string()))
+    }
+
+    /// Export accumulated feedback events for offline evaluation.
+    async fn export_feedback(
+        &self,
+        _req: &crate::dto::FeedbackExportRequest,
+    ) -> Result<crate::dto::FeedbackExportResponse, MemCubeError> {
+        Err(MemCubeError::Other("feedback not supported".to_string()))
+    }
+
+    // ============================================================================
+    // Entities (implemented by entity-aware cubes; every call is scoped to one cube)
+    // ============================================================================
+
+    /// Search the cube's entities by name, optionally restricted to one type.
+    async fn search_entities(
+        &self,
+        _cube_id: &str,
+        _query: &str,
+        _entity_type: Option<crate::entity::EntityType>,
+        _limit: u32,
+    ) -> Result<Vec<crate::entity::Entity>, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Typo-tolerant search over names and variants of the cube's entities. Returns hits with
+    /// their match score (0.0 - 1.0), best first; `min_score` defaults to the graph's threshold.
+    async fn fuzzy_search_entities(
+        &self,
+        _cube_id: &str,
+        _query: &str,
+        _entity_type: Option<crate::entity::EntityType>,
+        _min_score: Option<f64>,
+        _limit: u32,
+    ) -> Result<Vec<(crate::entity::Entity, f64)>, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Get one entity of the cube by id.
+    async fn get_entity(
+        &self,
+        _cube_id: &str,
+        _entity_id: &str,
+    ) -> Result<Option<crate::entity::Entity>, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Page through entities of a type (first seen first). Returns the page and the cursor
+    /// of the next page.
+    async fn list_entities_by_type(
+        &self,
+        _cube_id: &str,
+        _entity_type: crate::entity::EntityType,
+        _limit: u32,
+        _cursor: Option<&str>,
+    ) -> Result<(Vec<crate::entity::Entity>, Option<String>), MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Outgoing relations of `entity_id` with their target entities, optionally filtered by
+    /// relation type and restricted to relations that held at `as_of`.
+    async fn get_entity_relations(
+        &self,
+        _cube_id: &str,
+        _entity_id: &str,
+        _relation_type: Option<crate::entity::EntityRelationType>,
+        _as_of: Option<&chrono::DateTime<chrono::Utc>>,
+        _limit: u32,
+    ) -> Result<Vec<(crate::entity::StoredRelation, crate::entity::Entity)>, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Assert a relation between two entities of the cube. Re-asserting an existing relation
+    /// folds the new evidence and bounds into it; a `valid_to` ends it.
+    async fn upsert_entity_relation(
+        &self,
+        _cube_id: &str,
+        _relation: &crate::entity::StoredRelation,
+    ) -> Result<crate::entity::StoredRelation, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Record attribute values of an entity of the cube; replaced values are kept as history.
+    async fn set_entity_attributes(
+        &self,
+        _cube_id: &str,
+        _entity_id: &str,
+        _attributes: &std::collections::HashMap<String, serde_json::Value>,
+        _validity: &crate::entity::Validity,
+    ) -> Result<crate::entity::Entity, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Entities of the cube mentioned by a memory.
+    async fn get_memory_entities(
+        &self,
+        _cube_id: &str,
+        _memory_id: &str,
+    ) -> Result<Vec<crate::entity::Entity>, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Entity knowledge graph statistics of the cube.
+    async fn entity_stats(
+        &self,
+        _cube_id: &str,
+    ) -> Result<crate::entity::EntityKgStats, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Search memories through the entities they mention.
+    async fn search_by_entity(
+        &self,
+        _req: &crate::dto::EntityAwareSearchRequest,
+    ) -> Result<SearchResponse, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Propose merges of entities of the cube that likely denote the same thing.
+    async fn propose_entity_merges(
+        &self,
+        _cube_id: &str,
+        _min_confidence: Option<f64>,
+        _limit: usize,
+    ) -> Result<Vec<crate::entity::EntityMergeCandidate>, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Fold `merge_id` into `keep_id`, rewriting relations and memory associations.
+    async fn merge_entities(
+        &self,
+        _cube_id: &str,
+        _keep_id: &str,
+        _merge_id: &str,
+    ) -> Result<crate::entity::Entity, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Move part of an entity into a new entity; returns the new entity.
+    async fn split_entity(
+        &self,
+        _cube_id: &str,
+        _entity_id: &str,
+        _split: &crate::entity::EntitySplit,
+    ) -> Result<crate::entity::Entity, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Shortest relation path between two entities of the cube; `None` when not connected
+    /// within the traversal limits.
+    async fn entity_path(
+        &self,
+        _cube_id: &str,
+        _source_id: &str,
+        _target_id: &str,
+        _traversal: &crate::entity::EntityTraversal,
+    ) -> Result<Option<crate::entity::EntityPath>, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Subgraph around an entity of the cube, each entity with up to `memories_per_entity`
+    /// of the memories mentioning it.
+    async fn entity_subgraph(
+        &self,
+        _cube_id: &str,
+        _entity_id: &str,
+        _traversal: &crate::entity::EntityTraversal,
+        _memories_per_entity: u32,
+    ) -> Result<crate::entity::EntitySubgraph, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    /// Profile of an entity of the cube: its LLM-written description and up to `memory_limit`
+    /// linked memories. `refresh` folds memories linked since the last refresh into the
+    /// description (`None` = the cube's default).
+    async fn entity_profile(
+        &self,
+        _cube_id: &str,
+        _entity_id: &str,
+        _refresh: Option<bool>,
+        _memory_limit: u32,
+    ) -> Result<crate::entity::EntityProfile, MemCubeError> {
+        Err(MemCubeError::Other("entities not supported".to_string()))
+    }
+
+    // ============================================================================
+    // Erasure
+    // ============================================================================
+
+    /// Erase a user's data (or a whole cube) from every store the cube owns.
+    /// Usually run as a scheduler job, which also purges job history and redacts audit events.
+    async fn erase_user_data(
+        &self,
+        _req: &crate::dto::EraseUserDataRequest,
+    ) -> Result<crate::lifecycle::ErasureOutcome, MemCubeError> {
+        Err(MemCubeError::Other("erasure not supported".to_string()))
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -403,6 +750,15 @@ pub trait AuditStore: Send + Sync {
 
     /// List events with optional filters and limit/offset. Newest first.
     async fn list(&self, opts: &AuditListOptions) -> Result<Vec<AuditEvent>, AuditStoreError>;
+
+    /// Redact `input_summary` of the events matching both filters: those of `user_id` when
+    /// given, in `cube_id` when given (erasure requests; no filter redacts nothing).
+    /// Returns the number of redacted events.
+    async fn redact(
+        &self,
+        user_id: Option<&str>,
+        cube_id: Option<&str>,
+    ) -> Result<u64, AuditStoreError>;
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -411,6 +767,78 @@ pub enum AuditStoreError {
     Other(String),
 }
 
+/// Feedback event store: append-only log of relevance judgements and feedback adds.
+#[async_trait]
+pub trait FeedbackStore: Send + Sync {
+    /// Append one feedback event.
+    async fn append(&self, event: FeedbackEvent) -> Result<(), FeedbackStoreError>;
+
+    /// List events with optional filters and limit. Oldest first.
+    async fn list(
+        &self,
+        opts: &FeedbackListOptions,
+    ) -> Result<Vec<FeedbackEvent>, FeedbackStoreError>;
+
+    /// Delete the events matching both filters: those of `user_id` when given, in `cube_id`
+    /// when given (no filter deletes nothing). Returns the number of deleted events.
+    async fn purge(
+        &self,
+        user_id: Option<&str>,
+        cube_id: Option<&str>,
+    ) -> Result<u64, FeedbackStoreError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FeedbackStoreError {
+    #[error("feedback store error: {0}")]
+    Other(String),
+}
+
+/// Write-ahead log of multi-store writes (outbox). An intent is begun before the first store
+/// write and completed once every store agrees; intents still pending after a crash are
+/// replayed by `NaiveMemCube::recover_pending_writes`.
+#[async_trait]
+pub trait IntentLog: Send + Sync {
+    /// Durably record an intent.
+    async fn begin(&self, intent: &crate::WriteIntent) -> Result<(), IntentLogError>;
+
+    /// Mark an intent done; completing an unknown intent is a no-op.
+    async fn complete(&self, intent_id: &str) -> Result<(), IntentLogError>;
+
+    /// Intents begun but not completed, oldest first.
+    async fn pending(&self) -> Result<Vec<crate::WriteIntent>, IntentLogError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IntentLogError {
+    #[error("intent log error: {0}")]
+    Other(String),
+}
+
+/// Durable storage for the entity knowledge graph. Writes are per entity: an upsert replaces
+/// the entity's row, variants, attributes, memory associations and outgoing relations.
+#[async_trait]
+pub trait EntityStore: Send + Sync {
+    /// Insert or replace one entity together with its outgoing relations.
+    async fn upsert_entity(
+        &self,
+        entity: &crate::entity::Entity,
+        relations: &[crate::entity::StoredRelation],
+    ) -> Result<(), EntityStoreError>;
+
+    /// Delete one entity, its associations and every relation from or to it.
+    async fn delete_entity(&self, entity_id: &str) -> Result<(), EntityStoreError>;
+
+    /// Load everything (used at startup to rebuild the in-memory graph).
+    async fn load(&self) -> Result<crate::entity::EntityKgSnapshot, EntityStoreError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EntityStoreError {
+    #[error("entity store error: {0}")]
+    Other(String),
+}
+
 /// Session: represents a conversation session.
 #[derive(Debug, Clone)]
 pub struct Session {
@@ -450,7 +878,9 @@ pub trait SessionStore: Send + Sync {
         user_id: &str,
     ) -> Result<Option<Session>, SessionError>;
 
-    /// List sessions for a user.
+    /// List sessions for a user. `cursor` is the `next_cursor` of the previous page; it should be
+    /// an opaque keyset token (e.g. the last `(created_at, session_id)`) rather than an offset,
+    /// so pages stay stable while sessions are created.
     async fn list_sessions(
         &self,
         user_id: &str,
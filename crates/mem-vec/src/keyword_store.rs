@@ -168,4 +168,15 @@ impl KeywordStore for InMemoryKeywordStore {
             .unwrap_or_default();
         Ok(hits)
     }
+
+    async fn list_ids(&self, user_name: Option<&str>) -> Result<Vec<String>, KeywordStoreError> {
+        let key = Self::user_key(user_name);
+        let guard = self.by_user.read().await;
+        let mut ids: Vec<String> = guard
+            .get(&key)
+            .map(|idx| idx.doc_length.keys().cloned().collect())
+            .unwrap_or_default();
+        ids.sort();
+        Ok(ids)
+    }
 }
@@ -144,4 +144,26 @@ impl VecStore for InMemoryVecStore {
         }
         Ok(())
     }
+
+    async fn list_ids(
+        &self,
+        cube_id: &str,
+        collection: Option<&str>,
+    ) -> Result<Vec<String>, VecStoreError> {
+        let coll = self.coll(collection);
+        let guard = self.store.read().await;
+        let mut ids: Vec<String> = guard
+            .get(&coll)
+            .map(|m| {
+                m.values()
+                    .filter(|i| {
+                        i.payload.get("mem_cube_id").and_then(|v| v.as_str()) == Some(cube_id)
+                    })
+                    .map(|i| i.id.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        ids.sort();
+        Ok(ids)
+    }
 }
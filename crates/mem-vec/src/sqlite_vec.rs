@@ -1,20 +1,45 @@
 //! SQLite-backed vector store implementation (P0: persistence).
 //! Note: Vector search is not natively supported in SQLite.
 //! For production use with vector search, use QdrantVecStore.
+//!
+//! With `with_encryption`, payloads (and optionally vectors) are sealed with per-cube data
+//! keys (cube = payload `mem_cube_id`); see `mem_types::encryption`.
 
 use crate::{VecSearchHit, VecStore, VecStoreError, VecStoreItem};
 use async_trait::async_trait;
+use mem_types::encryption::{
+    DataKey, EncryptionConfig, EncryptionError, KeyRing, MasterKey, WrappedKeyRecord,
+};
 use std::collections::HashMap;
 use std::path::Path;
 
 /// SQLite-backed vector store for persistence (without native vector search).
 pub struct SqliteVecStore {
     conn: std::sync::Mutex<rusqlite::Connection>,
+    /// Data keys when encryption at rest is enabled.
+    keys: Option<KeyRing>,
+    /// Data keys created by the write in progress; they join `keys` only once it commits.
+    new_keys: std::sync::Mutex<Vec<(i64, String, DataKey)>>,
 }
 
 impl SqliteVecStore {
     /// Create a new SQLite vector store at the given path.
     pub fn new(path: impl AsRef<Path>) -> Result<Self, VecStoreError> {
+        Self::open(path, None)
+    }
+
+    /// Create a store that encrypts payloads (and vectors, if configured) at rest.
+    pub fn with_encryption(
+        path: impl AsRef<Path>,
+        config: EncryptionConfig,
+    ) -> Result<Self, VecStoreError> {
+        Self::open(path, Some(config))
+    }
+
+    fn open(
+        path: impl AsRef<Path>,
+        encryption: Option<EncryptionConfig>,
+    ) -> Result<Self, VecStoreError> {
         let conn =
             rusqlite::Connection::open(path).map_err(|e| VecStoreError::Other(e.to_string()))?;
 
@@ -32,15 +57,188 @@ impl SqliteVecStore {
 
             CREATE INDEX IF NOT EXISTS idx_vectors_collection ON vectors(collection);
             CREATE INDEX IF NOT EXISTS idx_vectors_id ON vectors(id);
+
+            CREATE TABLE IF NOT EXISTS data_keys (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cube_id TEXT NOT NULL,
+                wrapped_key BLOB NOT NULL,
+                master_key_id TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
             "#,
         )
         .map_err(|e| VecStoreError::Other(e.to_string()))?;
 
+        let keys = match encryption {
+            Some(config) => {
+                let records =
+                    load_key_records(&conn).map_err(|e| VecStoreError::Other(e.to_string()))?;
+                Some(
+                    KeyRing::load(&config, records)
+                        .map_err(|e| VecStoreError::Other(e.to_string()))?,
+                )
+            }
+            None => None,
+        };
+
         Ok(Self {
             conn: std::sync::Mutex::new(conn),
+            keys,
+            new_keys: std::sync::Mutex::new(Vec::new()),
         })
     }
 
+    /// Rewrap every data key with `new_master`. Returns the number of rewrapped keys.
+    pub fn rotate_master_key(&self, new_master: MasterKey) -> Result<usize, VecStoreError> {
+        let keys = self.key_ring()?;
+        self.with_conn(|conn| {
+            let old_master = keys.master();
+            let records = load_key_records(conn)?;
+            let tx = conn.unchecked_transaction()?;
+            for record in &records {
+                let dek = old_master.unwrap(&record.wrapped).map_err(crypto_err)?;
+                let wrapped = new_master.wrap(&dek).map_err(crypto_err)?;
+                tx.execute(
+                    "UPDATE data_keys SET wrapped_key = ?1, master_key_id = ?2 WHERE id = ?3",
+                    rusqlite::params![wrapped, new_master.id(), record.key_id],
+                )?;
+            }
+            tx.commit()?;
+            keys.set_master(new_master);
+            Ok(records.len())
+        })
+    }
+
+    /// Give `cube_id` a fresh data key, re-encrypt its rows and destroy the old keys.
+    /// Returns the number of re-encrypted rows.
+    pub fn rotate_data_key(&self, cube_id: &str) -> Result<usize, VecStoreError> {
+        let keys = self.key_ring()?;
+        self.with_conn(|conn| {
+            let old_ids = keys.key_ids_for_cube(cube_id);
+            let tx = conn.unchecked_transaction()?;
+            let new_key = create_data_key(&tx, keys, cube_id)?;
+            let rows = self.read_items(&tx, "SELECT id, vector, payload FROM vectors", [])?;
+            let mut rotated = 0usize;
+            for item in rows {
+                if cube_of(&item.payload) != cube_id {
+                    continue;
+                }
+                let (vector, payload) = self.encode_item(Some(&new_key), &item)?;
+                tx.execute(
+                    "UPDATE vectors SET vector = ?1, payload = ?2 WHERE id = ?3",
+                    rusqlite::params![vector, payload, item.id],
+                )?;
+                rotated += 1;
+            }
+            for id in &old_ids {
+                tx.execute("DELETE FROM data_keys WHERE id = ?1", [id])?;
+            }
+            tx.commit()?;
+            let (new_id, dek) = new_key;
+            keys.insert(new_id, cube_id, dek);
+            keys.remove(&old_ids);
+            Ok(rotated)
+        })
+    }
+
+    fn key_ring(&self) -> Result<&KeyRing, VecStoreError> {
+        self.keys
+            .as_ref()
+            .ok_or_else(|| VecStoreError::Other("encryption is not enabled".to_string()))
+    }
+
+    /// Active key for the cube, creating a data key on first use. A created key is staged in
+    /// `new_keys` until the write commits (see `with_conn`).
+    fn key_for_cube(
+        &self,
+        conn: &rusqlite::Connection,
+        cube_id: &str,
+    ) -> Result<Option<(i64, DataKey)>, rusqlite::Error> {
+        let Some(ref keys) = self.keys else {
+            return Ok(None);
+        };
+        let mut new_keys = self.new_keys.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((id, _, dek)) = new_keys.iter().find(|(_, cube, _)| cube == cube_id) {
+            return Ok(Some((*id, dek.clone())));
+        }
+        if let Some(key) = keys.active_key(cube_id) {
+            return Ok(Some(key));
+        }
+        let (id, dek) = create_data_key(conn, keys, cube_id)?;
+        new_keys.push((id, cube_id.to_string(), dek.clone()));
+        Ok(Some((id, dek)))
+    }
+
+    /// Serialize (and seal, when `key` is set) the vector and payload columns.
+    fn encode_item(
+        &self,
+        key: Option<&(i64, DataKey)>,
+        item: &VecStoreItem,
+    ) -> Result<(Vec<u8>, String), rusqlite::Error> {
+        let vector_blob = serde_json::to_vec(&item.vector)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let payload_json = serde_json::to_string(&item.payload)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+        let (Some(keys), Some((key_id, dek))) = (self.keys.as_ref(), key) else {
+            return Ok((vector_blob, payload_json));
+        };
+        let vector_blob = if keys.encrypt_vectors() {
+            KeyRing::seal_blob_with(
+                *key_id,
+                dek,
+                &vector_blob,
+                &format!("vectors.vector:{}", item.id),
+            )
+            .map_err(crypto_err)?
+        } else {
+            vector_blob
+        };
+        let payload_json = KeyRing::seal_text_with(
+            *key_id,
+            dek,
+            &payload_json,
+            &format!("vectors.payload:{}", item.id),
+        )
+        .map_err(crypto_err)?;
+        Ok((vector_blob, payload_json))
+    }
+
+    /// Run a query selecting `id, vector, payload` and decode (open) the rows.
+    fn read_items<P: rusqlite::Params>(
+        &self,
+        conn: &rusqlite::Connection,
+        sql: &str,
+        params: P,
+    ) -> Result<Vec<VecStoreItem>, rusqlite::Error> {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Vec<u8>>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        let mut items = Vec::new();
+        for row in rows {
+            let (id, vector_blob, payload_json) = row?;
+            let (vector_blob, payload_json) = match self.keys {
+                Some(ref keys) => (
+                    keys.open_blob(&vector_blob, &format!("vectors.vector:{}", id))
+                        .map_err(crypto_err)?,
+                    keys.open_text(&payload_json, &format!("vectors.payload:{}", id))
+                        .map_err(crypto_err)?,
+                ),
+                None => (vector_blob, payload_json),
+            };
+            items.push(VecStoreItem {
+                id,
+                vector: serde_json::from_slice(&vector_blob).unwrap_or_default(),
+                payload: serde_json::from_str(&payload_json).unwrap_or_default(),
+            });
+        }
+        Ok(items)
+    }
+
     fn with_conn<T, F>(&self, f: F) -> Result<T, VecStoreError>
     where
         F: FnOnce(&rusqlite::Connection) -> Result<T, rusqlite::Error>,
@@ -49,7 +247,16 @@ impl SqliteVecStore {
             .conn
             .lock()
             .map_err(|e| VecStoreError::Other(format!("failed to acquire lock: {}", e)))?;
-        f(&conn).map_err(|e| VecStoreError::Other(e.to_string()))
+        let result = f(&conn);
+        // Keys created by a write that rolled back were never saved: forget them.
+        let new_keys =
+            std::mem::take(&mut *self.new_keys.lock().unwrap_or_else(|e| e.into_inner()));
+        if let (Ok(_), Some(keys)) = (&result, &self.keys) {
+            for (id, cube_id, dek) in new_keys {
+                keys.insert(id, &cube_id, dek);
+            }
+        }
+        result.map_err(|e| VecStoreError::Other(e.to_string()))
     }
 }
 
@@ -66,14 +273,8 @@ impl VecStore for SqliteVecStore {
         self.with_conn(|conn| {
             let tx = conn.unchecked_transaction()?;
             for item in items {
-                let vector_blob =
-                    serde_json::to_vec(&item.vector).map_err(|e| {
-                        rusqlite::Error::ToSqlConversionFailure(Box::new(e))
-                    })?;
-                let payload_json =
-                    serde_json::to_string(&item.payload).map_err(|e| {
-                        rusqlite::Error::ToSqlConversionFailure(Box::new(e))
-                    })?;
+                let key = self.key_for_cube(&tx, cube_of(&item.payload))?;
+                let (vector_blob, payload_json) = self.encode_item(key.as_ref(), item)?;
 
                 tx.execute(
                     "INSERT OR REPLACE INTO vectors (id, vector, payload, collection, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
@@ -113,35 +314,15 @@ impl VecStore for SqliteVecStore {
 
         let placeholders: Vec<String> = ids.iter().map(|_| "?".to_string()).collect();
         let sql = format!(
-            "SELECT id, vector, payload, collection, created_at, updated_at FROM vectors WHERE id IN ({})",
+            "SELECT id, vector, payload FROM vectors WHERE id IN ({})",
             placeholders.join(",")
         );
 
         self.with_conn(|conn| {
-            let mut stmt = conn.prepare(&sql)?;
             let params: Vec<&dyn rusqlite::ToSql> =
                 ids.iter().map(|s| s as &dyn rusqlite::ToSql).collect();
-            let rows = stmt.query_map(params.as_slice(), |row| {
-                let vector_blob: Vec<u8> = row.get(1)?;
-                let payload_json: String = row.get(2)?;
-                Ok((row.get::<_, String>(0)?, vector_blob, payload_json))
-            })?;
-
-            let mut items = Vec::new();
-            for row in rows {
-                let (id, vector_blob, payload_json) = row?;
-                let vector: Vec<f32> = serde_json::from_slice(&vector_blob).unwrap_or_default();
-                let payload: HashMap<String, serde_json::Value> =
-                    serde_json::from_str(&payload_json).unwrap_or_default();
-                items.push(VecStoreItem {
-                    id,
-                    vector,
-                    payload,
-                });
-            }
-            Ok(items)
+            self.read_items(conn, &sql, params.as_slice())
         })
-        .map_err(|e| VecStoreError::Other(e.to_string()))
     }
 
     async fn delete(&self, ids: &[String], collection: Option<&str>) -> Result<(), VecStoreError> {
@@ -179,4 +360,68 @@ impl VecStore for SqliteVecStore {
         // SQLite's INSERT OR REPLACE handles upsert
         self.add(items, collection).await
     }
+
+    async fn list_ids(
+        &self,
+        cube_id: &str,
+        collection: Option<&str>,
+    ) -> Result<Vec<String>, VecStoreError> {
+        let coll = collection.unwrap_or("default");
+        // The cube lives in the (possibly sealed) payload, so rows are opened and filtered here.
+        let items = self.with_conn(|conn| {
+            self.read_items(
+                conn,
+                "SELECT id, vector, payload FROM vectors WHERE collection = ?1 ORDER BY id",
+                rusqlite::params![coll],
+            )
+        })?;
+        Ok(items
+            .into_iter()
+            .filter(|i| cube_of(&i.payload) == cube_id)
+            .map(|i| i.id)
+            .collect())
+    }
+}
+
+/// Cube a vector belongs to (payload `mem_cube_id`).
+fn cube_of(payload: &HashMap<String, serde_json::Value>) -> &str {
+    payload
+        .get("mem_cube_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+fn load_key_records(conn: &rusqlite::Connection) -> Result<Vec<WrappedKeyRecord>, rusqlite::Error> {
+    let mut stmt =
+        conn.prepare("SELECT id, cube_id, wrapped_key, master_key_id FROM data_keys ORDER BY id")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(WrappedKeyRecord {
+            key_id: row.get(0)?,
+            cube_id: row.get(1)?,
+            wrapped: row.get(2)?,
+            master_key_id: row.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+/// Generate, wrap and persist a new data key for the cube. The caller adds it to the ring
+/// (where it becomes the active key) once the transaction commits.
+fn create_data_key(
+    conn: &rusqlite::Connection,
+    keys: &KeyRing,
+    cube_id: &str,
+) -> Result<(i64, DataKey), rusqlite::Error> {
+    let master = keys.master();
+    let dek = DataKey::generate();
+    let wrapped = master.wrap(&dek).map_err(crypto_err)?;
+    conn.execute(
+        "INSERT INTO data_keys (cube_id, wrapped_key, master_key_id, created_at) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![cube_id, wrapped, master.id(), chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok((conn.last_insert_rowid(), dek))
+}
+
+fn crypto_err(e: EncryptionError) -> rusqlite::Error {
+    rusqlite::Error::ToSqlConversionFailure(Box::new(e))
 }